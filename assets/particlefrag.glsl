@@ -0,0 +1,16 @@
+#version 450 core
+in vec2 TexCoord;
+in float Alpha;
+
+uniform sampler2D ourTexture;
+
+out vec4 FragColor;
+
+void main()
+{
+    vec4 texColor = texture(ourTexture, TexCoord);
+    if (texColor.a < 0.1 || Alpha <= 0.0) {
+        discard;
+    }
+    FragColor = vec4(texColor.rgb, texColor.a * Alpha);
+}
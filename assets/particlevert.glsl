@@ -0,0 +1,16 @@
+#version 450 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec2 uv;
+layout (location = 2) in float alpha;
+
+uniform mat4 mvp;
+
+out vec2 TexCoord;
+out float Alpha;
+
+void main()
+{
+    gl_Position = mvp * vec4(position, 1.0);
+    TexCoord = uv;
+    Alpha = alpha;
+}
@@ -8,6 +8,7 @@ use std::fs::File;
 use voxelland::windowandkey::{uncapkb, WindowAndKeyContext};
 
 use voxelland::game::{Game, DECIDEDSPORMP, SHOULDRUN};
+use voxelland::headlessclient::run_headless_client;
 
 
 
@@ -53,7 +54,15 @@ fn main() {
         }
     }));
 
-    
+    //--headless-client <addr> plays as a scripted bot with no window, for load testing a server
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--headless-client") {
+        match args.get(i + 1) {
+            Some(address) => run_headless_client(address.clone()),
+            None => eprintln!("Usage: --headless-client <address:port>"),
+        }
+        return ();
+    }
 
     let mut wak_context = WindowAndKeyContext::new("Distant Garden", 1280, 720);
 
@@ -107,19 +116,25 @@ fn main() {
         }
     }
 
-    match handle.join() {
-        Ok(_) => {
-            wak_context.game.as_mut().unwrap().loadedworld.store(true, std::sync::atomic::Ordering::Relaxed);
-        }
-        Err(_e) => {
+    let joined = handle.join().is_ok();
+
+    wak_context.game.as_mut().unwrap().start_world();
 
+    //Keep the loading screen up until the chunks around the player are in
+    while wak_context.game.as_ref().unwrap().loading_progress() < 1.0 {
+        if !wak_context.window.read().should_close() {
+            wak_context.run();
+        } else {
+            return ();
         }
     }
+
+    if joined {
+        wak_context.game.as_mut().unwrap().loadedworld.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
     wak_context.game.as_mut().unwrap().vars.menu_open = false;
-    
-    wak_context.game.as_mut().unwrap().start_world();
     wak_context.game.as_mut().unwrap().set_mouse_focused(true);
-    wak_context.game.as_mut().unwrap().window.write().set_cursor_mode(glfw::CursorMode::Disabled);
+    wak_context.game.as_mut().unwrap().set_cursor_mode(glfw::CursorMode::Disabled);
     unsafe {
         uncapkb.store(true, std::sync::atomic::Ordering::Relaxed);
     }
@@ -128,5 +143,7 @@ fn main() {
         wak_context.run();
     }
 
+    wak_context.game.as_mut().unwrap().save_singleplayer();
+
     unsafe { SHOULDRUN = false; }
 }
@@ -3,6 +3,7 @@ use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use rusqlite::{params, Connection};
 
+use voxelland::blockedits::{EditBatch, EditRateLimiter, SAVE_BATCH_INTERVAL};
 use voxelland::hud::SlotIndexType;
 use voxelland::inventory::{self, ChestInventory, Inventory};
 use std::collections::HashMap;
@@ -15,11 +16,12 @@ use std::sync::{Arc};
 use parking_lot::{Mutex, RwLock};
 
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 use glam::Vec3;
 use voxelland::chunk::ChunkSystem;
-use voxelland::game::{Game, CURRSEED, ROWLENGTH, SONGINDEX, STARTINGITEMS, WEATHERTYPE};
+use voxelland::planetinfo::PLANET_TYPES;
+use voxelland::game::{Game, CURRSEED, PLAYER_HIT_DAMAGE, ROWLENGTH, SONGINDEX, STARTINGITEMS, TIME_SYNC_INTERVAL, WEATHERTYPE};
 use voxelland::vec::{self, IVec3};
 use voxelland::server_types::{self, *};
 use dashmap::DashMap;
@@ -48,7 +50,8 @@ pub struct Client {
     errorstrikes: i8,
     saveposcounter: i32,
     ready_for_player_messages: bool,
-    sendmobcounter: i32
+    sendmobcounter: i32,
+    edits: EditRateLimiter
 }
 
 
@@ -61,9 +64,11 @@ fn handle_client(
     shutupmobmsgs: &Arc<AtomicBool>,
     nsmes: &Arc<Mutex<Vec<Nsme>>>,
     _wl: &Arc<Mutex<u8>>,
-    tod: &Arc<Mutex<f32>>,
     queued_sql: &Arc<SegQueue<QueuedSqlType>>,
     chest_reg: &Arc<DashMap<vec::IVec3, ChestInventory>>,
+    mobhits: &Arc<SegQueue<(Uuid, Message)>>,
+    accepting_edits: &Arc<RwLock<bool>>,
+    planet_swapped: &Arc<AtomicBool>,
 ) {
     let mut buffer;
     unsafe {
@@ -127,6 +132,15 @@ fn handle_client(
                 };
         
                 match message.message_type {
+                    MessageType::SetDayLength => {
+                        //Only the server console gets to change the day
+                        message.message_type = MessageType::None;
+                    }
+                    MessageType::MobHit => {
+                        //The main loop applies it and tells everyone what happened
+                        mobhits.push((client_id, message.clone()));
+                        message.message_type = MessageType::None;
+                    }
                     MessageType::ShutUpMobMsgs => {
                         shutupmobmsgs.store(true, std::sync::atomic::Ordering::Relaxed);
                     }
@@ -287,16 +301,6 @@ fn handle_client(
                             }
                         }
 
-                        let mut timeupdate = Message::new(MessageType::TimeUpdate, Vec3::ZERO, unsafe { WEATHERTYPE }, unsafe { SONGINDEX } as u32);
-                        //println!("Songindex: {}", unsafe { SONGINDEX });
-                        let t = *tod.lock();
-                        timeupdate.infof = t;
-    
-                        {
-                            let mut mystream = stream.lock();
-                            mystream.write_all(&bincode::serialize(&timeupdate).unwrap());
-                        }
-
 
                         //thread::sleep(Duration::from_millis(10));
 
@@ -351,11 +355,30 @@ fn handle_client(
                         println!("Recvd block set");
                         let spot = IVec3::new(message.x as i32, message.y as i32, message.z as i32);
                         let block = message.info;
-        
-                        let csys = csys.write();
-                        csys.set_block(spot, block, true);
-                        let currseed = unsafe { CURRSEED.load(Ordering::Relaxed) };
-                        queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot, block));
+
+                        //Held until the edit is queued, so the sql thread's last drain can't miss it
+                        let accepting = accepting_edits.read();
+                        let allowed = *accepting && match clients.lock().get_mut(&client_id) {
+                            Some(client) => client.edits.allow(Instant::now()),
+                            None => false,
+                        };
+
+                        if allowed {
+                            let csys = csys.write();
+                            csys.set_block_no_queue(spot, block, true);
+                            let currseed = unsafe { CURRSEED.load(Ordering::Relaxed) };
+                            queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot, block));
+                        } else {
+                            //Tell only the sender what's really there so they can undo their guess
+                            println!("Rejected block set, {}", if *accepting { "too many edits" } else { "the server is stopping" });
+                            message.info = csys.read().blockat(spot);
+                            message.bo = true;
+                            {
+                                let mut mystream = stream.lock();
+                                let _ = mystream.write_all(&bincode::serialize(&message).unwrap());
+                            }
+                            message.message_type = MessageType::None;
+                        }
                     }
                     MessageType::MultiBlockSet => {
                         println!("Recvd multi block set");
@@ -365,14 +388,37 @@ fn handle_client(
         
                         let block = message.info;
                         let block2 = message.info2;
-        
-                        let csys = csys.write();
-                        csys.set_block(spot, block, true);
-                        csys.set_block(spot2, block2, true);
-        
-                        let currseed = unsafe { CURRSEED.load(Ordering::Relaxed) };
-                        queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot, block));
-                        queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot2, block2));
+
+                        //Same limiter as a single block set, a door is one edit
+                        let accepting = accepting_edits.read();
+                        let allowed = *accepting && match clients.lock().get_mut(&client_id) {
+                            Some(client) => client.edits.allow(Instant::now()),
+                            None => false,
+                        };
+
+                        if allowed {
+                            let csys = csys.write();
+                            csys.set_block_no_queue(spot, block, true);
+                            csys.set_block_no_queue(spot2, block2, true);
+
+                            let currseed = unsafe { CURRSEED.load(Ordering::Relaxed) };
+                            queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot, block));
+                            queued_sql.push(QueuedSqlType::UserDataMap(currseed, spot2, block2));
+                        } else {
+                            //The sender gets both spots back as they really are
+                            println!("Rejected multi block set, {}", if *accepting { "too many edits" } else { "the server is stopping" });
+                            {
+                                let csys = csys.read();
+                                message.info = csys.blockat(spot);
+                                message.info2 = csys.blockat(spot2);
+                            }
+                            message.bo = true;
+                            {
+                                let mut mystream = stream.lock();
+                                let _ = mystream.write_all(&bincode::serialize(&message).unwrap());
+                            }
+                            message.message_type = MessageType::None;
+                        }
                     }
                     MessageType::RequestTakeoff => {
                         println!("Recvd req takeoff");
@@ -381,7 +427,9 @@ fn handle_client(
                         let mut csys = csys.write();
         
                         let pt = csys.planet_type.clone();
-                        csys.reset(0, newseed, (pt + 1) as usize % 2);
+                        csys.reset(0, newseed, (pt + 1) as usize % PLANET_TYPES as usize);
+                        //The game lives on the main thread, it picks the new planet's gravity up before its next update
+                        planet_swapped.store(true, Ordering::Relaxed);
                         csys.save_current_world_to_file(format!("world/{}", newseed));
                         mobspawnqueued.store(true, std::sync::atomic::Ordering::Relaxed);
                     }
@@ -461,6 +509,14 @@ fn handle_client(
             knowncams.remove(&client_id);
             let mut locked_clients = clients.lock();
             locked_clients.remove(&client_id);
+
+            //Everyone still here drops this player's avatar
+            let mut byemsg = Message::new(MessageType::PlayerDisconnect, Vec3::ZERO, 0.0, 0);
+            byemsg.goose = client_id.as_u64_pair();
+            let serial = bincode::serialize(&byemsg).unwrap();
+            for (_, client) in locked_clients.iter() {
+                let _ = client.stream.lock().write_all(&serial);
+            }
             break;
         }
 
@@ -478,22 +534,11 @@ fn main() {
         PACKET_SIZE = bincode::serialized_size(&Message::new(MessageType::RequestSeed, Vec3::new(0.0, 0.0, 0.0), 0.0, 0)).unwrap() as usize;
     }
 
-    let width = 10;
-    let height = 10;
-
-    #[cfg(target_feature="glfw")]
-    let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
-    #[cfg(target_feature="glfw")]
-    let (mut window, _events) = glfw
-        .create_window(width, height, "VoxellandServer", glfw::WindowMode::Windowed)
-        .expect("Failed to create GLFW window.");
-    #[cfg(target_feature="glfw")]
-    gl::load_with(|s| window.get_proc_address(s) as *const _);
-
     let initialseed: u32 = 92927777;
     
 
-    let gameh = Game::new(false, true, &Arc::new(AtomicBool::new(false)), &Arc::new(Mutex::new(None)));
+    //No window or GL context, the server runs fine over ssh with no display
+    let gameh = Game::new_headless(&Arc::new(AtomicBool::new(false)), &Arc::new(Mutex::new(None)));
 
     while !gameh.is_finished() {
         thread::sleep(Duration::from_millis(100));
@@ -553,8 +598,6 @@ fn main() {
     let shutupmobmsgs = Arc::new(AtomicBool::new(false));
 
 
-    let todclone = gamewrite.timeofday.clone();
-
     drop(gamewrite);
 
     listener.set_nonblocking(true);
@@ -759,38 +802,136 @@ fn main() {
         }
     }
 
+    //One connection and one transaction for every block edited in a world since the last batch
+    fn save_block_batch(seed: u32, edits: &[(IVec3, u32)]) {
+        let table_name = format!("userdatamap_{}", seed);
+        println!("Saving {} block edits to table {}", edits.len(), table_name);
+        let mut retries = 0;
 
-    let _sqlthread = thread::spawn(move || {
-        let queued_sql = qs.clone();
         loop {
-            match queued_sql.pop() {
-                Some(sql) => {
-                    
-                        handlesql(&sql);
-                        let mut morestuff = true;
-                        while morestuff {
-                            match queued_sql.pop() {
-                                Some(sql) => {
-                                    handlesql(&sql);
-                                }
-                                None => {
-                                    morestuff = false;
-                                }
-                            }
-                        }
-                        
-                    
-                    
+            let result = (|| -> rusqlite::Result<()> {
+                let mut conn = Connection::open("db")?;
+                conn.execute(
+                    &format!(
+                        "CREATE TABLE IF NOT EXISTS {} (
+                            x INTEGER,
+                            y INTEGER,
+                            z INTEGER,
+                            value INTEGER,
+                            PRIMARY KEY (x, y, z)
+                        )",
+                        table_name
+                    ),
+                    (),
+                )?;
+                let tx = conn.transaction()?;
+                {
+                    let mut stmt = tx.prepare(&format!(
+                        "INSERT OR REPLACE INTO {} (x, y, z, value) VALUES (?, ?, ?, ?)",
+                        table_name
+                    ))?;
+                    for (spot, block) in edits {
+                        stmt.execute(params![spot.x, spot.y, spot.z, block])?;
+                    }
                 }
-                None => {
-                    thread::sleep(Duration::from_secs(1));
+                tx.commit()
+            })();
+
+            match result {
+                Ok(()) => return,
+                Err(_e) => {
+                    println!("Sqlite failure, retrying..");
+                    retries += 1;
+                    thread::sleep(Duration::from_millis(100));
                 }
-                
             }
+            if retries > 30 {
+                panic!("Retried an operation more than 30 times. Aborting.");
+            }
+        }
+    }
+
+
+    let mobhit_queue: Arc<SegQueue<(Uuid, Message)>> = Arc::new(SegQueue::new());
+
+    //Console commands: "daylength <seconds>", 0 freezes the cycle
+    let daylength_queue: Arc<SegQueue<f32>> = Arc::new(SegQueue::new());
+    let dlq = daylength_queue.clone();
+
+    //"stop" or Ctrl-C, the main loop stops taking players and the sql thread writes out what's left
+    let shutting_down = Arc::new(AtomicBool::new(false));
+    let sd = shutting_down.clone();
+    let _consolethread = thread::spawn(move || {
+        let stdin = std::io::stdin();
+        let mut line = String::new();
+        while stdin.read_line(&mut line).unwrap_or(0) > 0 {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("daylength") => match parts.next().and_then(|s| s.parse::<f32>().ok()) {
+                    Some(length) => dlq.push(length),
+                    None => println!("Usage: daylength <seconds>"),
+                },
+                Some("stop") => sd.store(true, Ordering::Relaxed),
+                _ => {}
+            }
+            line.clear();
+        }
+    });
+
+    //Once the sql thread closes this no more block edits get queued, it waits for any being queued right then
+    let accepting_edits = Arc::new(RwLock::new(true));
+
+    let planet_swapped = Arc::new(AtomicBool::new(false));
+
+    //The first Ctrl-C saves and stops, the next one (or one after "stop") quits on the spot
+    let sd = shutting_down.clone();
+    let _signalthread = thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        while runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+            if sd.swap(true, Ordering::Relaxed) {
+                println!("Quitting without waiting for the save");
+                std::process::exit(1);
+            }
+            println!("Stopping, Ctrl-C again to quit right away");
         }
     });
 
+    //Clients only queue their changes, block edits get gathered up and written every SAVE_BATCH_INTERVAL
+    let sd = shutting_down.clone();
+    let ae = accepting_edits.clone();
+    let sqlthread = thread::spawn(move || {
+        let queued_sql = qs.clone();
+        let mut batch = EditBatch::new();
+        loop {
+            //Read before draining, so everything queued before the stop still gets written
+            let stopping = sd.load(Ordering::Relaxed);
+            if stopping {
+                *ae.write() = false;
+            }
+
+            while let Some(sql) = queued_sql.pop() {
+                match sql {
+                    QueuedSqlType::UserDataMap(seed, spot, block) => batch.push(seed, spot, block),
+                    other => handlesql(&other),
+                }
+            }
+            for (seed, edits) in batch.take() {
+                save_block_batch(seed, &edits);
+            }
+
+            if stopping {
+                break;
+            }
+            thread::sleep(SAVE_BATCH_INTERVAL);
+        }
+    });
+
+    let mut last_time_sync = Instant::now();
+
     loop {
+        if shutting_down.load(Ordering::Relaxed) {
+            break;
+        }
 
 
         
@@ -899,7 +1040,8 @@ fn main() {
                                             },
                                             saveposcounter: 0,
                                             ready_for_player_messages: false,
-                                            sendmobcounter: 0
+                                            sendmobcounter: 0,
+                                            edits: EditRateLimiter::new(Instant::now())
                                         },
                                     );
                                     gotlock = true;
@@ -923,13 +1065,14 @@ fn main() {
                         let nsme_clone = Arc::clone(&nsme_bare_arc);
                         let wl_clone = Arc::clone(&writelock);
 
-                        let todclone = todclone.clone();
-
                         let queued_sql = qs2.clone();
                         let chestreg = chestreg.clone();
+                        let mobhits = mobhit_queue.clone();
+                        let accepting_edits = accepting_edits.clone();
+                        let planet_swapped = planet_swapped.clone();
                         println!("About to spawn thread");
                         thread::spawn(move || {
-                            handle_client(client_id, clients_ref_clone, &csysarc_clone, &knowncams_clone, &msq_clone, &su_clone, &nsme_clone, &wl_clone, &todclone, &queued_sql, &chestreg);
+                            handle_client(client_id, clients_ref_clone, &csysarc_clone, &knowncams_clone, &msq_clone, &su_clone, &nsme_clone, &wl_clone, &queued_sql, &chestreg, &mobhits, &accepting_edits, &planet_swapped);
                         });
                         println!("Spawned thread");
 
@@ -955,12 +1098,74 @@ fn main() {
 
 
         //println!("Running this");
-        #[cfg(feature = "glfw")]
-        glfw.poll_events();
 
 
+        while let Some(length) = daylength_queue.pop() {
+            gamearc.write().set_day_length(length);
+
+            let mut msg = Message::new(MessageType::SetDayLength, Vec3::ZERO, 0.0, 0);
+            msg.infof = length;
+            let serial = bincode::serialize(&msg).unwrap();
+            for (_, client) in clients.lock().iter() {
+                let _ = client.stream.lock().write_all(&serial);
+            }
+            println!("Day length set to {}", length);
+        }
+
+        while let Some((attacker, hit)) = mobhit_queue.pop() {
+            //The damage is ours to decide, and only someone standing near the mob can have hit it
+            let mobpos = nsme.get(&hit.info).map(|m| m.position);
+            let in_reach = match (knowncams.get(&attacker), mobpos) {
+                (Some(cam), Some(mobpos)) => Game::mob_hit_in_reach(*cam, mobpos),
+                _ => false,
+            };
+            if !in_reach {
+                info!("Ignoring a hit on mob {} from {} who isn't close enough", hit.info, attacker);
+                continue;
+            }
+            let dir = Vec3::new(hit.x, hit.y, hit.z);
+            let result = gamearc.write().damage_mob(hit.info, PLAYER_HIT_DAMAGE, dir);
+            if let Some((pos, model_index, health)) = result {
+                let mut msg = Message::new(MessageType::MobHit, pos, 0.0, hit.info);
+                msg.info2 = model_index as u32;
+                msg.infof = health as f32;
+                msg.goose = hit.goose;
+                let serial = bincode::serialize(&msg).unwrap();
+                for (_, client) in clients.lock().iter() {
+                    let _ = client.stream.lock().write_all(&serial);
+                }
+            }
+        }
+
+        if planet_swapped.swap(false, Ordering::Relaxed) {
+            let mut game = gamearc.write();
+            let gravity = game.chunksys.read().gravity;
+            game.vars.gravity = gravity;
+            println!("New planet, gravity is {}", gravity);
+        }
+
         gamearc.write().update();
 
+        //Everyone's sky follows this clock, so send it on a steady beat
+        if last_time_sync.elapsed().as_secs_f32() >= TIME_SYNC_INTERVAL {
+            last_time_sync = Instant::now();
+            let (t, daylength) = {
+                let g = gamearc.read();
+                let t = *g.timeofday.lock();
+                (t, if g.day_frozen { 0.0 } else { g.daylength })
+            };
+            //Day length as a float in x, a frozen clock sends 0
+            let mut timeupdate = Message::new(MessageType::TimeUpdate, Vec3::ZERO, unsafe { WEATHERTYPE }, unsafe { SONGINDEX } as u32);
+            timeupdate.infof = t;
+            timeupdate.x = daylength;
+            let serial = bincode::serialize(&timeupdate).unwrap();
+            for (_, client) in clients.lock().iter() {
+                if client.ready_for_player_messages {
+                    let _ = client.stream.lock().write_all(&serial);
+                }
+            }
+        }
+
         //println!("Ran update");
 
         // let mut nblock = nsme_bare_arc.lock();
@@ -1037,4 +1242,8 @@ fn main() {
     
     
     }
-}
\ No newline at end of file
+
+    println!("Stopping, saving the last block edits");
+    let _ = sqlthread.join();
+    println!("Saved, bye");
+}
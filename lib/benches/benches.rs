@@ -19,6 +19,30 @@ fn criterion_benchmark(c: &mut Criterion) {
     let csys = ChunkSystem::new(8, 1, 0, false);
 
     c.bench_function("rebuild 20 chunks", |b| b.iter(|| csys.rebuild_index(black_box(20), false, false)));
+
+    //The first rebuild fills the column cache, the second should barely touch the noise
+    c.bench_function("rebuild the same chunk twice", |b| b.iter(|| {
+        csys.natural_columns.clear();
+        csys.rebuild_index(black_box(21), false, false);
+        csys.rebuild_index(black_box(21), false, false);
+    }));
+
+    let headless = ChunkSystem::new(8, 1, 0, true);
+    c.bench_function("sample 15x15 columns cold", |b| b.iter(|| {
+        headless.natural_columns.clear();
+        for x in 0..15 {
+            for z in 0..15 {
+                black_box(headless.natural_column(x, z));
+            }
+        }
+    }));
+    c.bench_function("sample 15x15 columns warm", |b| b.iter(|| {
+        for x in 0..15 {
+            for z in 0..15 {
+                black_box(headless.natural_column(x, z));
+            }
+        }
+    }));
 }
 
 
@@ -0,0 +1,83 @@
+use crate::chunk::Biome;
+use crate::planetinfo::Planets;
+
+//Seconds for a bed to fade all the way in or out
+pub const AMBIENT_FADE_SECONDS: f32 = 3.0;
+//Solid blocks over the camera before the surface wind gives way to the cave
+pub const UNDERGROUND_COVER: i32 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AmbientCommand {
+    //Start the bed looping, silent, the fade brings it up
+    Start(String),
+    Stop(String),
+}
+
+//Deep enough under the terrain (not just under a roof) to count as a cave
+pub fn is_underground(camera_y: f32, highest_solid: Option<i32>) -> bool {
+    highest_solid.is_some_and(|top| top as f32 >= camera_y + UNDERGROUND_COVER as f32)
+}
+
+//Which looping bed fits where the camera is
+pub fn ambient_bed(planet: u32, biome: Biome, underground: bool) -> &'static str {
+    if underground {
+        return "assets/sfx/cavedrips.mp3";
+    }
+    if Planets::is_hostile(planet) {
+        return "assets/sfx/alienhum.mp3";
+    }
+    match (planet, biome) {
+        (2, _) | (_, Biome::Snow) => "assets/sfx/coldwind.mp3",
+        (_, Biome::Desert) => "assets/sfx/desertwind.mp3",
+        _ => "assets/sfx/wind.mp3",
+    }
+}
+
+//Fades the wanted bed in and everything else out, so walking into a cave swaps them smoothly
+#[derive(Default)]
+pub struct AmbientMixer {
+    pub target: Option<String>,
+    pub beds: Vec<(String, f32)>,
+}
+
+impl AmbientMixer {
+    pub fn new() -> AmbientMixer {
+        AmbientMixer { target: None, beds: Vec::new() }
+    }
+
+    pub fn set_target(&mut self, target: Option<String>) {
+        self.target = target;
+    }
+
+    pub fn advance(&mut self, delta_time: f32) -> Vec<AmbientCommand> {
+        let mut cmds = Vec::new();
+        if let Some(target) = &self.target {
+            if !self.beds.iter().any(|(bed, _)| bed == target) {
+                self.beds.push((target.clone(), 0.0));
+                cmds.push(AmbientCommand::Start(target.clone()));
+            }
+        }
+        let step = delta_time / AMBIENT_FADE_SECONDS;
+        for (bed, vol) in self.beds.iter_mut() {
+            if Some(&*bed) == self.target.as_ref() {
+                *vol = (*vol + step).min(1.0);
+            } else {
+                *vol = (*vol - step).max(0.0);
+            }
+        }
+        let target = self.target.clone();
+        self.beds.retain(|(bed, vol)| {
+            if *vol <= 0.0 && Some(bed) != target.as_ref() {
+                cmds.push(AmbientCommand::Stop(bed.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        cmds
+    }
+
+    pub fn volume(&self, bed: &str) -> f32 {
+        self.beds.iter().find(|(b, _)| b == bed).map(|(_, vol)| *vol).unwrap_or_default()
+    }
+}
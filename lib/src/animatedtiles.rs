@@ -0,0 +1,195 @@
+use std::{fs, path::Path};
+
+use glam::IVec4;
+use noise::{NoiseFn, Perlin};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::textureface::{AtlasLayout, CELL_PX};
+
+const CHANS: usize = 4;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TileEffect {
+    //Copies each listed tile over this one in turn
+    #[default]
+    Frames,
+    //Shifts the tile up a pixel, wrapping the top row round (conveyor belts)
+    Scroll,
+    //Redraws the tile as moving noise (water)
+    Ripple,
+}
+
+//How an animated tile is written in animatedtiles.json, tiles not listed are never touched
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimatedTile {
+    #[serde(default)]
+    pub name: String,
+    pub tile: (u32, u32),
+    #[serde(default)]
+    pub effect: TileEffect,
+    #[serde(default)]
+    pub frames: Vec<(u32, u32)>,
+    //Steps per second
+    pub fps: f32,
+}
+
+impl AnimatedTile {
+    //Entries pointing off the sheet (say, made for a bigger atlas) are left alone
+    pub fn fits(&self, layout: &AtlasLayout) -> bool {
+        let inside = |t: &(u32, u32)| t.0 < layout.tiles_per_row() && t.1 < layout.tiles_per_column();
+        inside(&self.tile) && self.frames.iter().all(inside)
+    }
+}
+
+pub fn parse_animated_tiles(json: &str) -> Result<Vec<AnimatedTile>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+//animatedtiles.json next to the executable replaces the built in list
+pub static ANIMATED_TILES: Lazy<Vec<AnimatedTile>> = Lazy::new(|| {
+    let path = Path::new("animatedtiles.json");
+    if path.exists() {
+        match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|json| parse_animated_tiles(&json).map_err(|e| e.to_string())) {
+            Ok(tiles) => {
+                info!("Loaded {} animated tiles from animatedtiles.json", tiles.len());
+                return tiles;
+            }
+            Err(e) => {
+                info!("Couldn't load animatedtiles.json, using built in animated tiles: {}", e);
+            }
+        }
+    }
+    parse_animated_tiles(include_str!("animatedtiles.json")).unwrap()
+});
+
+pub struct TileAnimator {
+    pub tiles: Vec<AnimatedTile>,
+    timers: Vec<f32>,
+    pub frames: Vec<usize>,
+    //What a tile looked like before it was first drawn over, for frame lists that include the tile itself
+    originals: Vec<Vec<u8>>,
+    perlin: Perlin,
+}
+
+impl TileAnimator {
+    pub fn new(tiles: Vec<AnimatedTile>) -> TileAnimator {
+        let count = tiles.len();
+        TileAnimator {
+            tiles,
+            timers: vec![0.0; count],
+            frames: vec![0; count],
+            originals: vec![Vec::new(); count],
+            perlin: Perlin::new(0),
+        }
+    }
+
+    //Moves every animation along and redraws only the tiles whose step came up, true if any pixels changed
+    pub fn advance(&mut self, delta_time: f32, pix: &mut [u8], layout: &AtlasLayout) -> bool {
+        let mut changed = false;
+        for i in 0..self.tiles.len() {
+            let tile = &self.tiles[i];
+            if tile.fps <= 0.0 || (tile.effect == TileEffect::Frames && tile.frames.is_empty()) || !tile.fits(layout) {
+                continue;
+            }
+            let step = 1.0 / tile.fps;
+            self.timers[i] += delta_time;
+            if self.timers[i] < step {
+                continue;
+            }
+            let steps = (self.timers[i] / step).floor();
+            self.timers[i] -= steps * step;
+            self.frames[i] = self.frames[i].wrapping_add(steps as usize);
+
+            let tile = self.tiles[i].clone();
+            match tile.effect {
+                TileEffect::Frames => {
+                    if self.originals[i].is_empty() && tile.frames.contains(&tile.tile) {
+                        self.originals[i] = read_cell(pix, layout, tile.tile);
+                    }
+                    let from = tile.frames[self.frames[i] % tile.frames.len()];
+                    if from == tile.tile {
+                        write_cell(pix, layout, tile.tile, &self.originals[i]);
+                    } else {
+                        copy_cell(pix, layout, from, tile.tile);
+                    }
+                }
+                TileEffect::Scroll => {
+                    for _ in 0..(steps as usize).min(CELL_PX as usize) {
+                        scroll_cell(pix, layout, tile.tile);
+                    }
+                }
+                TileEffect::Ripple => {
+                    ripple_cell(pix, layout, tile.tile, &self.perlin, self.frames[i] as f64 / tile.fps as f64);
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+}
+
+fn cell_index(layout: &AtlasLayout, tile: (u32, u32), x: usize, y: usize) -> usize {
+    let (startx, starty) = layout.cell_origin_px(tile.0, tile.1);
+    ((starty + y) * layout.width as usize + startx + x) * CHANS
+}
+
+pub fn read_cell(pix: &[u8], layout: &AtlasLayout, tile: (u32, u32)) -> Vec<u8> {
+    let rowbytes = CELL_PX as usize * CHANS;
+    let mut out = Vec::with_capacity(rowbytes * CELL_PX as usize);
+    for y in 0..CELL_PX as usize {
+        let start = cell_index(layout, tile, 0, y);
+        out.extend_from_slice(&pix[start..start + rowbytes]);
+    }
+    out
+}
+
+pub fn write_cell(pix: &mut [u8], layout: &AtlasLayout, tile: (u32, u32), cell: &[u8]) {
+    let rowbytes = CELL_PX as usize * CHANS;
+    for (y, row) in cell.chunks(rowbytes).enumerate() {
+        let start = cell_index(layout, tile, 0, y);
+        pix[start..start + rowbytes].copy_from_slice(row);
+    }
+}
+
+pub fn copy_cell(pix: &mut [u8], layout: &AtlasLayout, from: (u32, u32), to: (u32, u32)) {
+    if from == to {
+        return;
+    }
+    let rowbytes = CELL_PX as usize * CHANS;
+    for y in 0..CELL_PX as usize {
+        let src = cell_index(layout, from, 0, y);
+        let dest = cell_index(layout, to, 0, y);
+        pix.copy_within(src..src + rowbytes, dest);
+    }
+}
+
+pub fn scroll_cell(pix: &mut [u8], layout: &AtlasLayout, tile: (u32, u32)) {
+    let rowbytes = CELL_PX as usize * CHANS;
+    let top = cell_index(layout, tile, 0, 0);
+    let firstrow = pix[top..top + rowbytes].to_vec();
+    for y in 0..CELL_PX as usize - 1 {
+        let src = cell_index(layout, tile, 0, y + 1);
+        let dest = cell_index(layout, tile, 0, y);
+        pix.copy_within(src..src + rowbytes, dest);
+    }
+    let bottom = cell_index(layout, tile, 0, CELL_PX as usize - 1);
+    pix[bottom..bottom + rowbytes].copy_from_slice(&firstrow);
+}
+
+pub fn ripple_cell(pix: &mut [u8], layout: &AtlasLayout, tile: (u32, u32), perlin: &Perlin, time: f64) {
+    let base_color = IVec4::new(0, 45, 100, 140);
+    for y in 0..CELL_PX as usize {
+        for x in 0..CELL_PX as usize {
+            let i = cell_index(layout, tile, x, y);
+            let added_noise = (perlin.get([x as f64 / 4.0, time * 2.0 % 100.0, y as f64 / 4.0]) * 70.0).max(-10.0) as i32;
+
+            pix[i] = (base_color.x + added_noise).clamp(0, 254) as u8;
+            pix[i + 1] = (base_color.y + added_noise).clamp(0, 254) as u8;
+            pix[i + 2] = (base_color.z + added_noise).clamp(0, 254) as u8;
+            pix[i + 3] = base_color.w.clamp(0, 254) as u8;
+        }
+    }
+}
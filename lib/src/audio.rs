@@ -1,8 +1,8 @@
-use std::{collections::HashMap, fs::File, io::{BufReader, Cursor, Read}, thread};
+use std::{collections::{HashMap, HashSet}, fs::File, io::{BufReader, Cursor, Read}, thread};
 use glam::Vec3;
 use lockfree::queue::Queue;
 use once_cell::sync::Lazy;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, SpatialSink};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source, SpatialSink};
 use tracing::info;
 
 
@@ -14,6 +14,7 @@ pub static mut FUNC_QUEUE: Lazy<Queue<FuncQueue>> = Lazy::new(|| Queue::new());
 
 enum FuncQueue {
     play_in_head(String),
+    loop_in_head(String),
     play(String, Vec3, Vec3, f32)
 }
 
@@ -69,6 +70,9 @@ pub fn spawn_audio_thread() {
                             FuncQueue::play_in_head(f) => {
                                 AUDIOPLAYER._play_in_head(f);
                             },
+                            FuncQueue::loop_in_head(f) => {
+                                AUDIOPLAYER._start_in_head(f, true);
+                            },
                             FuncQueue::play(id, pos, vel, vol) => {
                                 AUDIOPLAYER._play(id, &pos, &vel, vol)
                             },
@@ -92,9 +96,16 @@ pub struct AudioPlayer {
     pub sounds: HashMap<String, Vec<u8>>,
     pub sinks: HashMap<String, SoundSink>,
     pub headsinks: HashMap<String, Sink>,
-    pub serieslist: HashMap<String, SoundSeries>
+    pub serieslist: HashMap<String, SoundSeries>,
+    pub music: HashSet<String>,
+    //Fade scale per head sound (music crossfades, ambient beds), the volume sliders go on top
+    pub head_scale: HashMap<String, f32>,
+    pub muffled: bool
 }
 
+//Volume scale applied to everything while the camera is underwater
+pub static MUFFLE_FACTOR: f32 = 0.3;
+
 impl AudioPlayer {
     pub fn new() -> Result<Self, AudioError> {
 
@@ -106,7 +117,10 @@ impl AudioPlayer {
             sounds: HashMap::new(),
             sinks: HashMap::new(),
             headsinks: HashMap::new(),
-            serieslist: HashMap::new()
+            serieslist: HashMap::new(),
+            music: HashSet::new(),
+            head_scale: HashMap::new(),
+            muffled: false
         })
 
     }
@@ -115,6 +129,87 @@ impl AudioPlayer {
 
     }
 
+    pub fn muffle_mult(&self) -> f32 {
+        if self.muffled { MUFFLE_FACTOR } else { 1.0 }
+    }
+
+    pub fn set_muffled(&mut self, muffled: bool) {
+        if self.muffled == muffled {
+            return;
+        }
+        let scale = if muffled { MUFFLE_FACTOR } else { 1.0 / MUFFLE_FACTOR };
+        self.muffled = muffled;
+        for sink in self.sinks.values() {
+            sink.sink.set_volume(sink.sink.volume() * scale);
+        }
+        for sink in self.headsinks.values() {
+            sink.set_volume(sink.volume() * scale);
+        }
+    }
+
+    pub fn music_gain(&self) -> f32 {
+        unsafe { MISCSETTINGS.music_vol * MISCSETTINGS.master_vol }
+    }
+
+    pub fn sfx_gain(&self) -> f32 {
+        unsafe { MISCSETTINGS.sound_vol * MISCSETTINGS.master_vol }
+    }
+
+    //Volume a head sink should sit at for its category
+    fn head_volume(&self, id: &String) -> f32 {
+        let gain = if self.music.contains(id) { self.music_gain() } else { 0.5 * self.sfx_gain() };
+        gain * self.head_scale.get(id).copied().unwrap_or(1.0) * self.muffle_mult()
+    }
+
+    pub fn set_head_scale(&mut self, id: &str, scale: f32) {
+        self.head_scale.insert(id.to_string(), scale);
+        if let Some(sink) = self.headsinks.get(id) {
+            sink.set_volume(self.head_volume(&id.to_string()));
+        }
+    }
+
+    pub fn set_master_volume(&mut self, vol: f32) {
+        let old = self.sfx_gain();
+        unsafe { MISCSETTINGS.master_vol = vol.clamp(0.0, 1.0) };
+        self.apply_volumes(old);
+    }
+
+    pub fn set_music_volume(&mut self, vol: f32) {
+        let old = self.sfx_gain();
+        unsafe { MISCSETTINGS.music_vol = vol.clamp(0.0, 1.0) };
+        self.apply_volumes(old);
+    }
+
+    pub fn set_sfx_volume(&mut self, vol: f32) {
+        let old = self.sfx_gain();
+        unsafe { MISCSETTINGS.sound_vol = vol.clamp(0.0, 1.0) };
+        self.apply_volumes(old);
+    }
+
+    //Push the current gains onto sinks that are already playing
+    fn apply_volumes(&mut self, old_sfx_gain: f32) {
+        for (id, sink) in self.headsinks.iter() {
+            sink.set_volume(self.head_volume(id));
+        }
+        let new_sfx_gain = self.sfx_gain();
+        for sink in self.sinks.values() {
+            if new_sfx_gain == 0.0 || old_sfx_gain == 0.0 {
+                sink.sink.set_volume(0.0);
+            } else {
+                sink.sink.set_volume(sink.sink.volume() * new_sfx_gain / old_sfx_gain);
+            }
+        }
+    }
+
+    pub fn preload_music(&mut self, id: &'static str) -> Result<(), AudioError> {
+        self.preload_music_path(id.to_string())
+    }
+
+    pub fn preload_music_path(&mut self, path: String) -> Result<(), AudioError> {
+        self.music.insert(path.clone());
+        self._preload(path.clone(), path)
+    }
+
     pub fn preload(&mut self, id: &'static str, file_path: &'static str) -> Result<(), AudioError> {
         self._preload(id.to_string(), file_path.to_string())
     }
@@ -171,6 +266,32 @@ impl AudioPlayer {
         unsafe { FUNC_QUEUE.push(FuncQueue::play_in_head(id.to_string())) };
     }
 
+    pub fn play_music(&mut self, id: String) {
+        unsafe { FUNC_QUEUE.push(FuncQueue::play_in_head(id)) };
+    }
+
+    //Keeps repeating until stopped, for ambient beds
+    pub fn play_looping_in_head(&mut self, id: String) {
+        unsafe { FUNC_QUEUE.push(FuncQueue::loop_in_head(id)) };
+    }
+
+    pub fn pause_head_sound(&mut self, id: &str) {
+        if let Some(sink) = self.headsinks.get(id) {
+            sink.pause();
+        }
+    }
+
+    pub fn resume_head_sound(&mut self, id: &str) {
+        if let Some(sink) = self.headsinks.get(id) {
+            sink.play();
+        }
+    }
+
+    //True once a head sound has played out (or never started)
+    pub fn head_sound_finished(&self, id: &str) -> bool {
+        self.headsinks.get(id).map_or(true, |sink| sink.empty())
+    }
+
     pub fn stop_head_sound(&mut self, id: String) {
         match self.headsinks.get(&id.to_string()) {
             Some(sink) => {
@@ -183,6 +304,10 @@ impl AudioPlayer {
     }
 
     pub fn _play_in_head(&mut self, id: String) {
+        self._start_in_head(id, false);
+    }
+
+    pub fn _start_in_head(&mut self, id: String, looping: bool) {
         let mut needtopreload = false;
         match self.sounds.get(&id.to_string()) {
             Some(sound) => {
@@ -198,8 +323,13 @@ impl AudioPlayer {
 
                         sink.stop();
         
-                        sink.append(source);
-                        sink.set_volume(0.5);
+                        if looping {
+                            sink.append(source.repeat_infinite());
+                        } else {
+                            sink.append(source);
+                        }
+                        sink.play();
+                        sink.set_volume(self.head_volume(&id));
                     },
                     None => {
                         println!("There was a sound but no sink. This shouldn't happen");
@@ -217,7 +347,7 @@ impl AudioPlayer {
         if needtopreload {
             match self._preload(id.clone(), id.clone()) {
                 Ok(_) => {
-                    self._play_in_head(id.clone());
+                    self._start_in_head(id.clone(), looping);
                 }
                 Err(e) => {
                     println!("Couldn't play or preload {}", id);
@@ -249,7 +379,7 @@ impl AudioPlayer {
     pub fn _play(&mut self, id: String, pos: &Vec3, vel: &Vec3, vol: f32) {
         let vol = vol * 5.0;
 
-        let vol = vol * unsafe { MISCSETTINGS.sound_vol };
+        let vol = vol * self.sfx_gain() * self.muffle_mult();
         let mut needtopreload = false;
         match self.sounds.get(&id.to_string()) {
             Some(sound) => {
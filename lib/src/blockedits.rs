@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use glam::Vec3;
+
+use crate::vec::IVec3;
+
+//An edit the server hasn't echoed back by now gets rolled back
+pub const PENDING_EDIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+//Server side cap on block sets per client
+pub const MAX_EDITS_PER_WINDOW: u32 = 20;
+pub const EDIT_WINDOW: Duration = Duration::from_secs(1);
+
+//How long the server lets block edits pile up before writing them out together
+pub const SAVE_BATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+//A block set applied locally before the server confirmed it
+#[derive(Clone, Debug)]
+pub struct PendingEdit {
+    pub seq: u32,
+    pub spot: IVec3,
+    pub previous: u32,
+    pub predicted: u32,
+    pub sent_at: Instant,
+    //Item to hand back if the edit gets undone, 0 for none
+    pub refund: u32,
+    //Held back until the server agrees the block really broke
+    pub drops: Vec<(u32, u32)>,
+    pub drop_at: Vec3,
+}
+
+impl PendingEdit {
+    pub fn new(spot: IVec3, previous: u32, predicted: u32, now: Instant) -> PendingEdit {
+        PendingEdit {
+            seq: 0,
+            spot,
+            previous,
+            predicted,
+            sent_at: now,
+            refund: 0,
+            drops: Vec::new(),
+            drop_at: Vec3::ZERO,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PendingEdits {
+    next_seq: u32,
+    pub edits: Vec<PendingEdit>,
+}
+
+impl PendingEdits {
+    pub fn new() -> PendingEdits {
+        PendingEdits { next_seq: 0, edits: Vec::new() }
+    }
+
+    //Seq 0 is never handed out, it marks untagged block sets on the wire
+    pub fn push(&mut self, mut edit: PendingEdit) -> u32 {
+        self.next_seq = self.next_seq.wrapping_add(1).max(1);
+        edit.seq = self.next_seq;
+        self.edits.push(edit);
+        self.next_seq
+    }
+
+    pub fn take(&mut self, seq: u32) -> Option<PendingEdit> {
+        let index = self.edits.iter().position(|e| e.seq == seq)?;
+        Some(self.edits.remove(index))
+    }
+
+    pub fn take_expired(&mut self, now: Instant, timeout: Duration) -> Vec<PendingEdit> {
+        let (expired, waiting) = self
+            .edits
+            .drain(..)
+            .partition(|e| now.saturating_duration_since(e.sent_at) >= timeout);
+        self.edits = waiting;
+        expired
+    }
+}
+
+//Fixed window counter, anything past the cap inside one window is refused
+pub struct EditRateLimiter {
+    window_start: Instant,
+    count: u32,
+}
+
+impl EditRateLimiter {
+    pub fn new(now: Instant) -> EditRateLimiter {
+        EditRateLimiter { window_start: now, count: 0 }
+    }
+
+    pub fn allow(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.window_start) >= EDIT_WINDOW {
+            self.window_start = now;
+            self.count = 0;
+        }
+        if self.count >= MAX_EDITS_PER_WINDOW {
+            return false;
+        }
+        self.count += 1;
+        true
+    }
+}
+
+//Block edits waiting to be saved, a spot changed twice before a write only gets written once
+#[derive(Default)]
+pub struct EditBatch {
+    edits: HashMap<u32, HashMap<IVec3, u32>>,
+}
+
+impl EditBatch {
+    pub fn new() -> EditBatch {
+        EditBatch { edits: HashMap::new() }
+    }
+
+    pub fn push(&mut self, seed: u32, spot: IVec3, block: u32) {
+        self.edits.entry(seed).or_default().insert(spot, block);
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.values().map(|e| e.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //Everything waiting, grouped by the world it belongs to
+    pub fn take(&mut self) -> Vec<(u32, Vec<(IVec3, u32)>)> {
+        self.edits
+            .drain()
+            .map(|(seed, edits)| (seed, edits.into_iter().collect()))
+            .collect()
+    }
+}
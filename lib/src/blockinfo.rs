@@ -1,6 +1,10 @@
-use crate::{chunk::LightColor, cube::CubeSide};
+use crate::{chunk::LightColor, cube::CubeSide, vec};
 
 pub const BLOCK_DIRECTION_BITS: u32 = 0b0000_0000_0000_0011_0000_0000_0000_0000;
+//How many steps flowing water is from its source, 0 is a source block
+pub const WATER_LEVEL_BITS: u32 = 0b0000_0000_1110_0000_0000_0000_0000_0000;
+//Which way a log runs: 0 upright, 1 along x, 2 along z
+pub const BLOCK_AXIS_BITS: u32 = 0b0000_0011_0000_0000_0000_0000_0000_0000;
 pub struct Blocks {}
 
 
@@ -248,6 +252,10 @@ impl Blocks {
         return &TEXS[id as usize][SIDES[side as usize]];
     }
 
+    pub fn is_unbreakable(id: u32) -> bool {
+        return id == 15;
+    }
+
     pub fn is_overwritable(id: u32) -> bool {
         static OV: [u32; 2] = [
             0, 2
@@ -260,6 +268,10 @@ impl Blocks {
         ];
         return TRANSPARENTS.contains(&id);
     }
+    //Solid and see-through nowhere, the mesher hides any face against one of these
+    pub fn is_opaque(id: u32) -> bool {
+        return id != 0 && !Blocks::is_transparent(id) && !Blocks::is_semi_transparent(id);
+    }
     pub fn is_climbable(id: u32) -> bool {
         static CLIMBABLES: [u32; 2] = [
             20, 22
@@ -295,6 +307,54 @@ impl Blocks {
         0b0000_0000_0000_0000_1111_1111_1111_1111
     }
 
+    pub fn get_water_level(input: u32) -> u32 {
+        return (input & WATER_LEVEL_BITS) >> 21;
+    }
+
+    pub fn set_water_level(input: &mut u32, level: u32) {
+        let bits = level << 21;
+        *input = (*input) & !WATER_LEVEL_BITS;
+        *input |= bits & WATER_LEVEL_BITS;
+    }
+
+    pub fn get_axis_bits(input: u32) -> u32 {
+        return (input & BLOCK_AXIS_BITS) >> 24;
+    }
+
+    pub fn set_axis_bits(input: &mut u32, axis: u32) {
+        let bits = axis << 24;
+        *input = (*input) & !BLOCK_AXIS_BITS;
+        *input |= bits & BLOCK_AXIS_BITS;
+    }
+
+    //Blocks that lie along the face they were placed against
+    pub fn is_axis_oriented(id: u32) -> bool {
+        static AXIS_ORIENTED: [u32; 3] = [
+            6, 12, 43
+        ];
+        return AXIS_ORIENTED.contains(&id);
+    }
+
+    pub fn axis_from_normal(normal: vec::IVec3) -> u32 {
+        if normal.x != 0 {
+            1
+        } else if normal.z != 0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    //Which of the block's own textures shows on a world side, once it's turned onto its axis
+    pub fn axis_side(side: CubeSide, axis: u32) -> CubeSide {
+        match (axis, side) {
+            (1, CubeSide::LEFT) | (2, CubeSide::BACK) => CubeSide::BOTTOM,
+            (1, CubeSide::RIGHT) | (2, CubeSide::FRONT) => CubeSide::TOP,
+            (1, CubeSide::BOTTOM) | (1, CubeSide::TOP) | (2, CubeSide::BOTTOM) | (2, CubeSide::TOP) => CubeSide::LEFT,
+            _ => side,
+        }
+    }
+
     pub fn get_direction_bits(input: u32) -> u32 {
         return (input & BLOCK_DIRECTION_BITS) >> 16;
     }
@@ -309,6 +369,14 @@ impl Blocks {
     pub fn block_flag_bits() -> u32 {
         0b1111_1111_1111_1111_0000_0000_0000_0000
     }
+    //Slot counts are drawn with two glyphs, so nothing stacks past 99
+    pub fn get_max_stack(id: u32) -> u32 {
+        match id {
+            37 | 38 | 39 => 1,
+            32 | 33 => 16,
+            _ => 99
+        }
+    }
     pub fn get_food_stats(id: u32) -> (i32, i32) {
         match id {
             _ => {
@@ -318,36 +386,31 @@ impl Blocks {
     }
     pub fn get_walk_series(id: u32) -> &'static str {
         match id {
-            3 | 48 => {
+            3 | 22 | 23 => {
                 "grassstepseries"
             }
+            48 => {
+                "snowstepseries"
+            }
             34 => {
                 "mulchstepseries"
             }
-            7 => {
-                "plantplaceseries"
-            }
-            11 => {
-                "plantplaceseries"
+            7 | 11 | 44 => {
+                "leavesstepseries"
             }
             1 => {
                 "sandstepseries"
             }
-            6 => {
+            6 | 10 | 12 | 19 | 21 | 31 | 43 => {
                 "woodstepseries"
             }
-            4 => {
+            4 | 14 => {
                 "dirtstepseries"
             }
-            10 => {
-                "woodstepseries"
-            }
-            22 => {
-                "grassstepseries"
-            }
             2 => {
                 "waterstepseries"
             }
+            //Anything hard we don't have a sound for
             _ => {
                 "stonestepseries"
             }
@@ -2,7 +2,61 @@ use gl::types::{GLsizeiptr, GLuint, GLvoid};
 use glam::{Mat4, Vec3};
 
 use crate::shader::Shader;
+use crate::vec::IVec3;
+
+//Time spent breaking the block under the crosshair, started over whenever the target changes
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BreakProgress {
+    pub target: Option<IVec3>,
+    pub block: u32,
+    pub time: f32,
+    //Creative's wait before the next block, kept across targets since the next block is a new one
+    pub cooldown: f32,
+}
+
+impl BreakProgress {
+    pub const fn new() -> BreakProgress {
+        BreakProgress { target: None, block: 0, time: 0.0, cooldown: 0.0 }
+    }
+
+    //A different spot, or the same spot holding a different block, starts over
+    pub fn aim(&mut self, target: Option<IVec3>, block: u32) {
+        if self.target != target || self.block != block {
+            self.target = target;
+            self.block = block;
+            self.time = 0.0;
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.time = 0.0;
+        self.cooldown = 0.0;
+    }
+
+    pub fn progress(&self, break_time: f32) -> f32 {
+        (self.time / break_time).clamp(0.0, 1.0)
+    }
 
+    //Adds held time, true once the block is done, which also starts the count over
+    pub fn hold(&mut self, delta_time: f32, break_time: f32) -> bool {
+        self.time += delta_time;
+        if self.progress(break_time) >= 1.0 {
+            self.time = 0.0;
+            return true;
+        }
+        false
+    }
+
+    //Creative breaking, true on the first frame held and then every interval after
+    pub fn hold_repeating(&mut self, delta_time: f32, interval: f32) -> bool {
+        self.cooldown -= delta_time;
+        if self.cooldown <= 0.0 {
+            self.cooldown = interval;
+            return true;
+        }
+        false
+    }
+}
 
 
 
@@ -16,11 +70,13 @@ pub struct BlockOverlay {
 
 impl BlockOverlay {
     pub fn new(texture: GLuint) -> BlockOverlay {
-        let shader = Shader::new("assets/bovert.glsl", "assets/bofrag.glsl");
+        let shader = Shader::new("assets/bovert.glsl", "assets/bofrag.glsl").unwrap_or_else(Shader::failed);
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::BindVertexArray(shader.vao);
-            gl::BindTextureUnit(0, texture);
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::BindVertexArray(shader.vao);
+                gl::BindTextureUnit(0, texture);
+            }
         }
         let mut vbo: GLuint = 0;
 
@@ -77,23 +133,25 @@ impl BlockOverlay {
     
         ];
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::CreateBuffers(1, &mut vbo);
-            gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
-            faces.as_ptr() as *const GLvoid,
-            gl::STATIC_DRAW);
-
-            gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
-            gl::EnableVertexArrayAttrib(shader.vao, 0);
-            gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
-            gl::VertexArrayAttribBinding(shader.vao, 0, 0);
-
-            gl::EnableVertexArrayAttrib(shader.vao, 1);
-            gl::VertexArrayAttribFormat(shader.vao, 1, 1, gl::FLOAT, gl::FALSE, 3 * std::mem::size_of::<f32>() as u32);
-            gl::VertexArrayAttribBinding(shader.vao, 1, 0);
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::CreateBuffers(1, &mut vbo);
+                gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                faces.as_ptr() as *const GLvoid,
+                gl::STATIC_DRAW);
+
+                gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
+                gl::EnableVertexArrayAttrib(shader.vao, 0);
+                gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
+                gl::VertexArrayAttribBinding(shader.vao, 0, 0);
+
+                gl::EnableVertexArrayAttrib(shader.vao, 1);
+                gl::VertexArrayAttribFormat(shader.vao, 1, 1, gl::FLOAT, gl::FALSE, 3 * std::mem::size_of::<f32>() as u32);
+                gl::VertexArrayAttribBinding(shader.vao, 1, 0);
 
             
             
+            }
         }
         BlockOverlay {
             shader,
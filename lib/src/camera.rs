@@ -1,5 +1,5 @@
-use crate::game::{ControlsState, MOVING, SPRINTING};
-use glam::{Mat4, Vec3};
+use crate::game::{ControlsState, MOVING};
+use glam::{Mat4, Vec3, Vec4};
 
 #[derive(Clone, Default)]
 pub struct Camera {
@@ -18,11 +18,21 @@ pub struct Camera {
     pub mvp: Mat4,
 
     pub velocity: Vec3,
+    //Only moves the view, position stays the physics position
+    pub bob_offset: Vec3,
 
     pub far: f32,
     pub near: f32,
+    //Framebuffer width over height, kept in sync with window resizes
+    pub aspect: f32,
 }
 
+pub const DEFAULT_ASPECT: f32 = 1280.0 / 720.0;
+
+//Close enough that hugging a wall at max fov doesn't clip it, far enough to keep depth precision at the far plane
+pub const NEAR_PLANE: f32 = 0.02;
+pub const MIN_FAR_PLANE: f32 = 64.0;
+
 impl Camera {
     pub fn new() -> Camera {
         let direction = Vec3::new(0.0, 0.0, 1.0);
@@ -30,11 +40,11 @@ impl Camera {
         let right = Vec3::new(0.0, 1.0, 0.0).cross(direction).normalize();
         let fov: f32 = 80.0;
         let far = 560.0;
-        let near = 0.001;
+        let near = NEAR_PLANE;
         let up = direction.cross(right);
 
         let model = Mat4::IDENTITY;
-        let projection = Mat4::perspective_rh_gl(fov.to_radians(), 1280.0 / 720.0, near, far);
+        let projection = Mat4::perspective_rh_gl(fov.to_radians(), DEFAULT_ASPECT, near, far);
         let view = Mat4::look_at_rh(position, position + direction, up);
         Camera {
             yaw: 0.0,
@@ -49,22 +59,82 @@ impl Camera {
             view,
             mvp: projection * model * view,
             velocity: Vec3::new(0.0, 0.0, 0.0),
+            bob_offset: Vec3::ZERO,
             far,
             near,
+            aspect: DEFAULT_ASPECT,
         }
     }
     pub fn update_fov(&mut self, value: f32) {
         self.fov = value.clamp(50.0, 160.0);
-        self.projection =
-            Mat4::perspective_rh_gl(self.fov.to_radians(), 1280.0 / 720.0, self.near, self.far);
+        self.update_projection();
+    }
+    //Minimizing reports a 0 by 0 framebuffer, keep the old aspect instead of dividing by zero
+    pub fn set_aspect(&mut self, width: i32, height: i32) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        self.aspect = width as f32 / height as f32;
+        self.update_projection();
+    }
+    pub fn set_far(&mut self, far: f32) {
+        self.far = far.max(MIN_FAR_PLANE);
+        self.update_projection();
+    }
+    //Distance to the furthest loaded block: the corner chunk of the loaded square, from the top of the world
+    pub fn far_for_render_distance(radius: u8, chunk_width: i32, chunk_height: i32) -> f32 {
+        let horizontal = (radius as f32 + 1.0) * chunk_width as f32 * std::f32::consts::SQRT_2;
+        let vertical = chunk_height as f32;
+        (horizontal * horizontal + vertical * vertical).sqrt().max(MIN_FAR_PLANE)
+    }
+    fn update_projection(&mut self) {
+        let aspect = if self.aspect > 0.0 { self.aspect } else { DEFAULT_ASPECT };
+        self.projection = Mat4::perspective_rh_gl(self.fov.to_radians(), aspect, self.near, self.far);
         self.recalculate();
     }
     pub fn recalculate(&mut self) {
         self.right = Vec3::new(0.0, 1.0, 0.0).cross(self.direction).normalize();
         self.up = self.direction.cross(self.right);
-        self.view = Mat4::look_at_rh(self.position, self.position + self.direction, self.up);
+        let eye = self.position + self.bob_offset;
+        self.view = Mat4::look_at_rh(eye, eye + self.direction, self.up);
         self.mvp = self.projection * self.view * self.model;
     }
+    //Left, right, bottom, top, near, far planes pulled out of the mvp, normals pointing inward
+    pub fn frustum_planes(&self) -> [Vec4; 6] {
+        let r0 = self.mvp.row(0);
+        let r1 = self.mvp.row(1);
+        let r2 = self.mvp.row(2);
+        let r3 = self.mvp.row(3);
+
+        let mut planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2];
+        for plane in planes.iter_mut() {
+            let len = plane.truncate().length();
+            if len > 0.0 {
+                *plane /= len;
+            }
+        }
+        planes
+    }
+    pub fn aabb_in_frustum(planes: &[Vec4; 6], min: Vec3, max: Vec3) -> bool {
+        for plane in planes {
+            //The corner furthest along the plane normal, if even that is behind it the whole box is out
+            let corner = Vec3::new(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+            if plane.truncate().dot(corner) + plane.w < 0.0 {
+                return false;
+            }
+        }
+        true
+    }
+    //Sways side to side once per stride and dips at each footfall, so twice per stride
+    pub fn head_bob_offset(phase: f32, amplitude: f32, right: Vec3) -> Vec3 {
+        let lateral = phase.sin() * 0.035 * amplitude;
+        let vertical = -(1.0 - (phase * 2.0).cos()) * 0.5 * 0.06 * amplitude;
+        right * lateral + Vec3::new(0.0, vertical, 0.0)
+    }
     pub fn respond_to_controls(
         &mut self,
         cs: &ControlsState,
@@ -72,12 +142,7 @@ impl Camera {
         speed_mult: f32,
     ) -> Vec3 {
 
-        let mut xz_speed_mult = 2.2;
-        unsafe {
-            if SPRINTING  {
-                xz_speed_mult = 2.74;
-            }
-        }
+        let xz_speed_mult = 2.2;
         
         let mut moving = false;
 
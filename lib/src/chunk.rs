@@ -7,13 +7,27 @@ use std::io::BufReader;
 use std::path::Path;
 
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
 
 
 pub static CHUNKPOSDEFAULT: i32 = 999999;
 
+pub const MIN_RADIUS: u8 = 4;
+pub const MAX_RADIUS: u8 = 20;
+
+//Chunks within this many chunks of the player are meshed at full resolution, then 2x out to LOD_HALF_DISTANCE and 4x past it
+pub const LOD_FULL_DISTANCE: i32 = 5;
+pub const LOD_HALF_DISTANCE: i32 = 10;
+
+//How far water runs sideways from a source, it has to fit in WATER_LEVEL_BITS
+pub const WATER_SPREAD: u32 = 7;
+//Seconds between water flow steps
+pub const FLUID_TICK: f32 = 0.25;
+//Chunks are whole columns, so burial is checked in slices this tall up the column
+pub const OCCLUSION_SECTION: i32 = 16;
+
 use gl::types::GLuint;
 use glam::Vec2;
 use glam::Vec3;
@@ -23,14 +37,16 @@ use once_cell::sync::Lazy;
 use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
+use rayon::prelude::*;
 use rusqlite::params;
 use rusqlite::Connection;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc};
 
 use parking_lot::{Mutex, RwLock};
 
-use noise::{NoiseFn, Perlin};
+use noise::{NoiseFn, Perlin, Seedable};
 
 
 use crate::camera::Camera;
@@ -43,9 +59,11 @@ use crate::cube::CubeSide;
 use crate::game::AUDIOPLAYER;
 
 use crate::game::CURRSEED;
+use crate::game::PLAYERCHUNKPOS;
 
 use crate::packedvertex::PackedVertex;
 use crate::planetinfo::Planets;
+use crate::glcheck::gl_check;
 use crate::shader::Shader;
 use crate::specialblocks::chest::ChestInfo;
 use crate::specialblocks::conveyor::ConveyorInfo;
@@ -55,8 +73,8 @@ use crate::specialblocks::ladder::LadderInfo;
 use crate::specialblocks::tallgrass::TallGrassInfo;
 use crate::specialblocks::torch::TorchInfo;
 use crate::textureface::TextureFace;
-use crate::textureface::ONE_OVER_16;
-use crate::textureface::TEXTURE_WIDTH;
+use crate::textureface::atlas;
+use crate::droptables::{break_series, place_series};
 use crate::vec::IVec3;
 use crate::vec::{self, IVec2};
 
@@ -155,13 +173,7 @@ impl ChunkGeo {
             gl::CreateBuffers(1, &mut vbo8rgb);
             gl::CreateBuffers(1, &mut tvbo8rgb);
 
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!(
-                    "OpenGL Error after creating chunk system buffers: {}",
-                    error
-                );
-            }
+            gl_check("creating chunk system buffers");
         }
 
         ChunkGeo {
@@ -211,6 +223,22 @@ impl ChunkGeo {
         self.data8rgb.lock().clear();
         self.tdata8rgb.lock().clear();
     }
+    //Like clear but hands the memory back too, for a chunk that won't be meshed again soon
+    pub fn release(&self) {
+        *self.data32.lock() = Vec::new();
+        *self.data8.lock() = Vec::new();
+        *self.tdata32.lock() = Vec::new();
+        *self.tdata8.lock() = Vec::new();
+
+        *self.vdata.lock() = Vec::new();
+        *self.uvdata.lock() = Vec::new();
+
+        *self.wvdata.lock() = Vec::new();
+        *self.wuvdata.lock() = Vec::new();
+
+        *self.data8rgb.lock() = Vec::new();
+        *self.tdata8rgb.lock() = Vec::new();
+    }
     pub fn solids(&self) -> (&Mutex<Vec<u32>>, &Mutex<Vec<u8>>, &Mutex<Vec<u16>>) {
         return (&self.data32, &self.data8, &self.data8rgb);
     }
@@ -224,11 +252,125 @@ pub struct ChunkFacade {
     pub geo_index: usize,
     pub used: bool,
     pub pos: vec::IVec2,
+    //Blocks per mesh cell this slot was last built at, 1 is full resolution
+    pub lod: i32,
 }
 
 static CW: i32 = 15;
 static CH: i32 = 256;
 
+//PackedVertex gives x/z 4 bits and y 8 bits within a chunk, so these can't go any higher
+pub static MAX_CHUNK_WIDTH: i32 = 15;
+pub static MAX_CHUNK_HEIGHT: i32 = 256;
+//A block's top face sits one above it and y only has 8 bits, so the top layer of a full height column stays sky
+pub static MAX_BLOCK_Y: i32 = 254;
+
+static CAVE_THRESHOLD: f64 = 0.5;
+
+//Chunks per side of one region file
+pub static REGION_SIZE: i32 = 16;
+
+//(index within the chunk, block) for every spot that isn't plain noise terrain, keyed by chunk coordinate within a region file
+type RegionData = HashMap<(i32, i32), Vec<(u32, u32)>>;
+
+//Region files kept in memory after the first read, so loading a chunk doesn't parse its whole region again
+pub const REGION_CACHE_MAX: usize = 32;
+
+pub struct CachedRegion {
+    pub data: RegionData,
+    //Saved chunks not written out yet, flush_regions writes the whole region once
+    pub dirty: bool,
+    last_used: u64,
+}
+
+#[derive(Default)]
+pub struct RegionCache {
+    pub regions: HashMap<String, CachedRegion>,
+    tick: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OreSpec {
+    pub block_id: u32,
+    pub min_y: i32,
+    pub max_y: i32,
+    //One vein per this many 3x3x3 cells of stone in the band
+    pub rarity: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Snow,
+}
+
+impl Biome {
+    pub fn surface_block(&self) -> u32 {
+        match self {
+            Biome::Plains => 3,
+            Biome::Desert => 1,
+            Biome::Snow => 48,
+        }
+    }
+    //Indexes match the voxel model list loaded in Game::new
+    pub fn allows_model(&self, index: u32) -> bool {
+        match self {
+            Biome::Plains => true,
+            //Only rocks (and the other planet's models), nothing leafy
+            Biome::Desert => !(index <= 2 || (5..=13).contains(&index)),
+            //No bushes, bamboo, tall grass or rubber trees in the snow
+            Biome::Snow => !(index == 0 || (8..=13).contains(&index)),
+        }
+    }
+}
+
+impl OreSpec {
+    pub fn default_list() -> Vec<OreSpec> {
+        vec![
+            OreSpec { block_id: 35, min_y: 1, max_y: 70, rarity: 40 },
+            OreSpec { block_id: 13, min_y: 1, max_y: 30, rarity: 90 },
+        ]
+    }
+
+    pub fn for_planet(dim_id: u32) -> Vec<OreSpec> {
+        if Planets::is_hostile(dim_id) {
+            vec![
+                OreSpec { block_id: 35, min_y: 1, max_y: 70, rarity: 40 },
+                OreSpec { block_id: 17, min_y: 1, max_y: 50, rarity: 70 },
+            ]
+        } else {
+            Self::default_list()
+        }
+    }
+}
+
+//Natural terrain of one x/z column as (start y, block) runs, so rebuilds don't redo the noise
+pub struct NaturalColumn {
+    runs: Vec<(i32, u32)>,
+}
+
+impl NaturalColumn {
+    pub fn block_at(&self, y: i32) -> u32 {
+        let run = self.runs.partition_point(|(start, _)| *start <= y);
+        if run == 0 {
+            return 0;
+        }
+        self.runs[run - 1].1
+    }
+}
+
+//Packed vertices for a chunk meshed at a coarser lod, opaque and transparent
+#[derive(Default)]
+pub struct LodMesh {
+    pub data32: Vec<u32>,
+    pub data8: Vec<u8>,
+    pub data8rgb: Vec<u16>,
+    pub tdata32: Vec<u32>,
+    pub tdata8: Vec<u8>,
+    pub tdata8rgb: Vec<u16>,
+}
+
 pub struct ReadyMesh {
     pub geo_index: usize,
     pub newpos: vec::IVec2,
@@ -304,6 +446,33 @@ pub struct ChunkSystem {
     pub lightmap: Arc<Mutex<HashMap<vec::IVec3, LightSegment>>>,
 
     pub generated_chunks: Arc<DashMap<vec::IVec2, bool>>,
+
+    //Scales how tightly the cave tunnels wind, higher is more frequent/narrower
+    pub cave_frequency: f64,
+    //How hard things fall here, players, mobs and drops all read it
+    pub gravity: f32,
+    pub ore_specs: Arc<Vec<OreSpec>>,
+    //Octaves of detail on the rolling hills, more is rougher/more mountainous
+    pub terrain_octaves: u32,
+
+    pub chunk_width: i32,
+    pub chunk_height: i32,
+
+    pub natural_columns: DashMap<vec::IVec2, Arc<NaturalColumn>>,
+    pub column_cache_hits: AtomicUsize,
+    pub column_cache_misses: AtomicUsize,
+
+    //Counts player block edits so autosave can tell whether there's anything new to write
+    pub user_edits: AtomicUsize,
+
+    pub fluid_updates: lockfree::queue::Queue<vec::IVec3>,
+    pub last_fluid_tick: Mutex<Instant>,
+
+    //Folder of the world being played, its region files live under it. None in multiplayer, the server has the world
+    pub world_path: RwLock<Option<String>>,
+    //Sqlite file with the user edits. A save slot keeps its own, the server and multiplayer use the shared "db"
+    pub db_path: RwLock<String>,
+    pub region_cache: Mutex<RegionCache>,
 }
 
 impl ChunkSystem {
@@ -311,7 +480,7 @@ impl ChunkSystem {
         let seed = unsafe {CURRSEED.load(std::sync::atomic::Ordering::Relaxed)};
         let table_name = format!("userdatamap_{}", seed);
 
-        let conn = Connection::open("db").unwrap();
+        let conn = Connection::open(&*self.db_path.read()).unwrap();
 
         // Insert userdatamap entries
         let mut stmt = conn
@@ -329,7 +498,7 @@ impl ChunkSystem {
         let seed = unsafe {CURRSEED.load(std::sync::atomic::Ordering::Relaxed)};
         let table_name = format!("userdatamap_{}", seed);
 
-        let conn = Connection::open("db").unwrap();
+        let conn = Connection::open(&*self.db_path.read()).unwrap();
 
         conn.execute(
             &format!(
@@ -364,6 +533,7 @@ impl ChunkSystem {
         }
 
         fs::create_dir_all(&path).unwrap();
+        *self.world_path.write() = Some(path.clone());
 
         // let mut file = File::create(path.clone() + "/udm").unwrap();
         // for entry in self.userdatamap.iter() {
@@ -375,14 +545,180 @@ impl ChunkSystem {
 
         let mut file = File::create(path.clone() + "/pt").unwrap();
         writeln!(file, "{}", self.planet_type).unwrap();
+
+        let mut edited: HashSet<vec::IVec2> = HashSet::new();
+        for entry in self.userdatamap.iter() {
+            edited.insert(self.spot_to_chunk_pos(entry.key()));
+        }
+        for cpos in edited {
+            self.save_chunk(cpos);
+        }
+        self.flush_regions();
+    }
+
+
+
+
+    //Under the world's own folder, one subfolder per planet it has been to. None when there's no world folder
+    pub fn region_file_path(&self, cpos: &vec::IVec2) -> Option<String> {
+        let seed = unsafe {CURRSEED.load(std::sync::atomic::Ordering::Relaxed)};
+        self.world_path.read().as_ref().map(|world| {
+            format!(
+                "{}/regions/{}/r.{}.{}.bin",
+                world,
+                seed,
+                cpos.x.div_euclid(REGION_SIZE),
+                cpos.y.div_euclid(REGION_SIZE)
+            )
+        })
+    }
+
+    //Reads the region into the cache if it isn't there yet, then hands it to f.
+    //A full cache drops the least recently used region, writing it out first if it has unsaved chunks
+    fn with_region<R>(&self, path: &String, f: impl FnOnce(&mut CachedRegion) -> R) -> R {
+        let mut cache = self.region_cache.lock();
+        cache.tick += 1;
+        let tick = cache.tick;
+        if !cache.regions.contains_key(path) {
+            if cache.regions.len() >= REGION_CACHE_MAX {
+                let oldest = cache.regions.iter().min_by_key(|(_, region)| region.last_used).map(|(p, _)| p.clone()).unwrap();
+                let region = cache.regions.remove(&oldest).unwrap();
+                if region.dirty {
+                    Self::write_region(&oldest, &region.data);
+                }
+            }
+            cache.regions.insert(path.clone(), CachedRegion { data: Self::read_region(path), dirty: false, last_used: tick });
+        }
+        let region = cache.regions.get_mut(path).unwrap();
+        region.last_used = tick;
+        f(region)
     }
 
+    fn write_region(path: &String, data: &RegionData) {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, bincode::serialize(data).unwrap()).unwrap();
+    }
+
+    //Writes every region with chunks saved since the last flush, once each
+    pub fn flush_regions(&self) {
+        let mut cache = self.region_cache.lock();
+        for (path, region) in cache.regions.iter_mut() {
+            if region.dirty {
+                Self::write_region(path, &region.data);
+                region.dirty = false;
+            }
+        }
+    }
+
+    fn read_region(path: &String) -> RegionData {
+        match fs::read(path) {
+            Ok(bytes) => match bincode::deserialize::<RegionData>(&bytes) {
+                Ok(region) => region,
+                Err(e) => {
+                    info!("Couldn't read region file {}: {}", path, e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    //Puts the chunk's edits and stamped structures in its cached region, flush_regions writes it out.
+    //Plain noise terrain is left out, load_chunk gets it back from the seed
+    pub fn save_chunk(&self, cpos: vec::IVec2) {
+        let path = match self.region_file_path(&cpos) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let mut changed: Vec<(u32, u32)> = Vec::new();
+        let mut index = 0;
+        for x in 0..self.chunk_width {
+            for z in 0..self.chunk_width {
+                for y in 0..self.chunk_height {
+                    let spot = vec::IVec3::new(cpos.x * self.chunk_width + x, y, cpos.y * self.chunk_width + z);
+                    let stored = match self.userdatamap.get(&spot) {
+                        Some(block) => Some(*block),
+                        None => self.nonuserdatamap.get(&spot).map(|block| *block),
+                    };
+                    if let Some(block) = stored {
+                        if self.is_in_world(spot) && block != self.natural_blockat(spot) {
+                            changed.push((index, block));
+                        }
+                    }
+                    index += 1;
+                }
+            }
+        }
+
+        self.with_region(&path, |region| {
+            region.data.insert((cpos.x, cpos.y), changed);
+            region.dirty = true;
+        });
+    }
+
+    //Returns false if this chunk was never saved, so the caller should generate it from noise instead
+    pub fn load_chunk(&self, cpos: vec::IVec2) -> bool {
+        let path = match self.region_file_path(&cpos) {
+            Some(path) => path,
+            None => return false,
+        };
+        if !self.region_cache.lock().regions.contains_key(&path) && !Path::new(&path).exists() {
+            return false;
+        }
+
+        let changed = match self.with_region(&path, |region| region.data.get(&(cpos.x, cpos.y)).cloned()) {
+            Some(changed) => changed,
+            None => return false,
+        };
+
+        let column = self.chunk_height as u32;
+        for (index, block) in changed {
+            let x = (index / column) as i32 / self.chunk_width;
+            let z = (index / column) as i32 % self.chunk_width;
+            let y = (index % column) as i32;
+            if x >= self.chunk_width {
+                info!("Region data for chunk {} {} doesn't fit this world", cpos.x, cpos.y);
+                break;
+            }
+            let spot = vec::IVec3::new(cpos.x * self.chunk_width + x, y, cpos.y * self.chunk_width + z);
+            if !self.userdatamap.contains_key(&spot) {
+                self.nonuserdatamap.insert(spot, block);
+            }
+        }
+
+        self.generated_chunks.insert(cpos, true);
+        true
+    }
 
+    //Everything natural terrain depends on, so two chunk systems given the same pair generate the same blocks
+    pub fn apply_world_params(&mut self, seed: u32, planet_type: u8) {
+        *(self.perlin.write()) = Perlin::new(seed);
+        unsafe {CURRSEED.store(seed, std::sync::atomic::Ordering::Relaxed)};
+        self.set_planet_type(planet_type);
+    }
 
+    //Multiplayer: terrain comes only from the server's seed and planet type, edits from the db it sent.
+    //Never goes through the local world files, which would hand back this client's own planet type
+    pub fn load_server_world(&mut self, seed: u32, planet_type: u8) {
+        self.userdatamap.clear();
+        self.nonuserdatamap.clear();
+        *self.world_path.write() = None;
+        *self.db_path.write() = "db".to_string();
+        self.flush_regions();
+        *self.region_cache.lock() = RegionCache::default();
+        self.apply_world_params(seed, planet_type);
+        self.load_udm_from_db();
+    }
 
     pub fn load_world_from_file(&mut self, path: String) {
         self.userdatamap.clear();
         self.nonuserdatamap.clear();
+        *self.world_path.write() = Some(path.clone());
+        self.flush_regions();
+        *self.region_cache.lock() = RegionCache::default();
 
         match File::open(format!("{}/udm", path.clone())) {
             Ok(_) => {}
@@ -392,17 +728,6 @@ impl ChunkSystem {
             }
         }
 
-        let conn = Connection::open("db").unwrap();
-
-        conn.execute_batch(
-            "
-            PRAGMA synchronous = OFF;
-            PRAGMA journal_mode = WAL;
-            PRAGMA cache_size = 10000;
-        ",
-        )
-        .unwrap();
-
         // let file = File::open(format!("{}/udm", path)).unwrap();
         // let reader = BufReader::new(file);
 
@@ -428,6 +753,7 @@ impl ChunkSystem {
                     let s = seed.parse::<u32>().unwrap();
                     info!("Seed Is {}", s);
                     *(self.perlin.write()) = Perlin::new(s);
+                    self.natural_columns.clear();
 
                     unsafe {CURRSEED.store(s, std::sync::atomic::Ordering::Relaxed)}
 
@@ -437,6 +763,65 @@ impl ChunkSystem {
             info!("Seed2 doesnt exist");
         }
 
+        self.load_udm_from_db();
+
+        let file = File::open(format!("{}/pt", path)).unwrap();
+        let reader = BufReader::new(file);
+
+        for line in reader.lines() {
+            let line = line.unwrap();
+            let mut parts = line.splitn(2, ' ');
+            if let Some(pt) = parts.next() {
+                self.set_planet_type(pt.parse::<u8>().unwrap());
+            }
+        }
+    }
+
+    //Save slots from before each had its own db kept their edits in the shared one, copy this seed's over once
+    pub fn adopt_shared_edits(&self, shared_db: &str, seed: u32) -> rusqlite::Result<usize> {
+        let table_name = format!("userdatamap_{}", seed);
+        let conn = Connection::open(&*self.db_path.read())?;
+        conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    x INTEGER,
+                    y INTEGER,
+                    z INTEGER,
+                    value INTEGER,
+                    PRIMARY KEY (x, y, z)
+                )",
+                table_name
+            ),
+            (),
+        )?;
+        conn.execute("ATTACH DATABASE ?1 AS shared", params![shared_db])?;
+        let exists: bool = conn.query_row(
+            "SELECT count(*) > 0 FROM shared.sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table_name],
+            |row| row.get(0),
+        )?;
+        let copied = if exists {
+            conn.execute(&format!("INSERT OR REPLACE INTO main.{0} SELECT x, y, z, value FROM shared.{0}", table_name), ())?
+        } else {
+            0
+        };
+        conn.execute("DETACH DATABASE shared", ())?;
+        Ok(copied)
+    }
+
+    //User edits for the current seed out of the db table
+    pub fn load_udm_from_db(&self) {
+        let conn = Connection::open(&*self.db_path.read()).unwrap();
+
+        conn.execute_batch(
+            "
+            PRAGMA synchronous = OFF;
+            PRAGMA journal_mode = WAL;
+            PRAGMA cache_size = 10000;
+        ",
+        )
+        .unwrap();
+
         let seed = unsafe {CURRSEED.load(std::sync::atomic::Ordering::Relaxed)};
         let table_name = format!("userdatamap_{}", seed);
         info!("LOADING FROM TABLENAME {}", table_name);
@@ -474,25 +859,158 @@ impl ChunkSystem {
             let (key, value): (vec::IVec3, u32) = entry.unwrap();
             self.userdatamap.insert(key, value);
         }
+    }
 
-        let file = File::open(format!("{}/pt", path)).unwrap();
-        let reader = BufReader::new(file);
+    //Each planet type shapes its terrain differently, so this swaps the noise settings and drops cached columns too
+    pub fn set_planet_type(&mut self, planet_type: u8) {
+        self.planet_type = planet_type;
+        self.terrain_octaves = Planets::get_terrain_octaves(planet_type as u32);
+        self.cave_frequency = Planets::get_cave_frequency(planet_type as u32);
+        self.gravity = Planets::get_gravity(planet_type as u32);
+        self.ore_specs = Arc::new(OreSpec::for_planet(planet_type as u32));
+        self.natural_columns.clear();
+    }
 
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let mut parts = line.splitn(2, ' ');
-            if let Some(pt) = parts.next() {
-                self.planet_type = pt.parse::<u8>().unwrap();
+    //The one definition of what you can stand on and bump into
+    pub fn is_solid(&self, spot: vec::IVec3) -> bool {
+        let block = self.blockat(spot) & Blocks::block_id_bits();
+        let isntwater = block != 2;
+        let isnttallgrass = block != 23;
+
+        return isntwater && isnttallgrass && block != 0
+            || self.justcollisionmap.contains_key(&spot);
+    }
+
+    //Y of the topmost solid block in a column, None if the column is empty
+    pub fn highest_solid_y(&self, x: i32, z: i32) -> Option<i32> {
+        (0..self.chunk_height).rev().find(|y| self.is_solid(vec::IVec3::new(x, *y, z)))
+    }
+
+    fn water_level_at(&self, spot: vec::IVec3) -> Option<u32> {
+        let bits = self.blockat(spot);
+        if bits & Blocks::block_id_bits() == 2 {
+            Some(Blocks::get_water_level(bits))
+        } else {
+            None
+        }
+    }
+
+    //Queues a spot and its neighbours for the fluid pass if any water is involved
+    fn queue_fluid_check(&self, spot: vec::IVec3) {
+        let near_water = self.water_level_at(spot).is_some()
+            || Cube::get_neighbors().iter().any(|n| self.water_level_at(spot + *n).is_some());
+        if near_water {
+            self.fluid_updates.push(spot);
+            for n in Cube::get_neighbors() {
+                self.fluid_updates.push(spot + *n);
+            }
+        }
+    }
+
+    //Works out what the water at spot should do next, only reading the world so every spot sees the same state
+    fn fluid_changes_at(&self, spot: vec::IVec3, changes: &mut HashMap<vec::IVec3, u32>) {
+        static SIDEWAYS: [vec::IVec3; 4] = [
+            vec::IVec3 { x: -1, y: 0, z: 0 },
+            vec::IVec3 { x: 1, y: 0, z: 0 },
+            vec::IVec3 { x: 0, y: 0, z: -1 },
+            vec::IVec3 { x: 0, y: 0, z: 1 },
+        ];
+        fn water(level: u32) -> u32 {
+            let mut bits = 2;
+            Blocks::set_water_level(&mut bits, level);
+            bits
+        }
+        //Two streams running into the same gap, the stronger one wins
+        fn fill(changes: &mut HashMap<vec::IVec3, u32>, target: vec::IVec3, level: u32) {
+            let entry = changes.entry(target).or_insert(water(level));
+            if level < Blocks::get_water_level(*entry) {
+                *entry = water(level);
             }
         }
+
+        let mut level = match self.water_level_at(spot) {
+            Some(level) => level,
+            None => return,
+        };
+
+        if level > 0 {
+            //Flowing water needs water above it or a shallower neighbour feeding it
+            let fed = if self.water_level_at(spot + vec::IVec3::new(0, 1, 0)).is_some() {
+                Some(1)
+            } else {
+                SIDEWAYS.iter().filter_map(|n| self.water_level_at(spot + *n)).min().map(|l| l + 1)
+            };
+            match fed {
+                Some(fedlevel) if fedlevel <= WATER_SPREAD => {
+                    if fedlevel != level {
+                        fill(changes, spot, fedlevel);
+                        level = fedlevel;
+                    }
+                }
+                _ => {
+                    changes.insert(spot, 0);
+                    return;
+                }
+            }
+        }
+
+        let below = spot + vec::IVec3::new(0, -1, 0);
+        if below.y >= 0 && self.blockat(below) & Blocks::block_id_bits() == 0 {
+            fill(changes, below, 1);
+        } else if level < WATER_SPREAD {
+            for n in SIDEWAYS.iter() {
+                let side = spot + *n;
+                if self.blockat(side) & Blocks::block_id_bits() == 0 {
+                    fill(changes, side, level + 1);
+                }
+            }
+        }
+    }
+
+    //One step of water flow for everything queued since the last step
+    pub fn step_fluids(&self) {
+        let mut spots = HashSet::new();
+        while let Some(spot) = self.fluid_updates.pop() {
+            spots.insert(spot);
+        }
+
+        let mut changes = HashMap::new();
+        for spot in spots {
+            self.fluid_changes_at(spot, &mut changes);
+        }
+
+        for (spot, bits) in changes {
+            self.set_fluid_block(spot, bits);
+        }
     }
 
-    pub fn collision_predicate(&self, vec: vec::IVec3) -> bool {
-        let isntwater = (self.blockat(vec.clone()) & Blocks::block_id_bits()) != 2;
-        let isnttallgrass = (self.blockat(vec.clone()) & Blocks::block_id_bits()) != 23;
+    //Water saves with the world like an edit does, but the player didn't do it so it doesn't count towards an autosave
+    pub fn set_fluid_block(&self, spot: vec::IVec3, bits: u32) {
+        if !self.is_in_world(spot) {
+            return;
+        }
+        self.userdatamap.insert(spot, bits);
+        self.queue_fluid_check(spot);
+        if !self.headless {
+            let mut neighbs: HashSet<vec::IVec2> = HashSet::new();
+            for i in Cube::get_neighbors() {
+                neighbs.insert(self.spot_to_chunk_pos(&(spot + *i)));
+            }
+            for i in neighbs {
+                self.queue_rerender_with_key(i, true, false);
+            }
+        }
+    }
 
-        return isntwater && isnttallgrass && self.blockat(vec.clone()) != 0
-            || self.justcollisionmap.contains_key(&vec);
+    pub fn update_fluids(&self) {
+        {
+            let mut last = self.last_fluid_tick.lock();
+            if last.elapsed().as_secs_f32() < FLUID_TICK {
+                return;
+            }
+            *last = Instant::now();
+        }
+        self.step_fluids();
     }
 
     pub fn start_with_seed(_seed: u32) {}
@@ -509,6 +1027,12 @@ impl ChunkSystem {
         let udm = self.userdatamap.clone();
         let nudm = self.nonuserdatamap.clone();
         let per = self.perlin.clone();
+        let cavefreq = self.cave_frequency;
+        let orespecs = self.ore_specs.clone();
+        let octaves = self.terrain_octaves;
+        let planettype = self.planet_type;
+        let cw = self.chunk_width;
+        let ch = self.chunk_height;
         let cam = cam.clone();
 
         
@@ -541,19 +1065,19 @@ impl ChunkSystem {
 
 
 
-                                for i in 0..CW {
-                                    for k in 0..CW {
+                                for i in 0..cw {
+                                    for k in 0..cw {
                                         let hit_block = false;
-                                        for j in (0..CH).rev() {
+                                        for j in (0..ch).rev() {
 
                                             let spot = vec::IVec3 {
-                                                x: ((c.pos.x)  * CW) + i,
+                                                x: ((c.pos.x)  * cw) + i,
                                                 y: j,
-                                                z: (c.pos.y * CW) + k,
+                                                z: (c.pos.y * cw) + k,
                                             };
 
 
-                                            let combined = Self::_blockat(&nudm, &udm, &per.read(), spot);
+                                            let combined = Self::_blockat(&nudm, &udm, &per.read(), cavefreq, &orespecs, octaves, planettype, spot);
                                             let block = combined & Blocks::block_id_bits();
                                             let flags = combined & Blocks::block_flag_bits();
                                             unsafe {
@@ -593,7 +1117,8 @@ impl ChunkSystem {
         
     }
 
-    pub fn exit(&mut self) {
+    //Forgets every loaded chunk and its mesh, the world's data stays
+    pub fn clear_loaded(&mut self) {
         if !self.headless {
             for cg in &self.geobank {
                 #[cfg(feature = "glfw")]
@@ -621,6 +1146,10 @@ impl ChunkSystem {
         while let Some(_) = self.gen_rebuild_requests.pop() {}
         while let Some(_) = self.background_rebuild_requests.pop() {}
         info!("After that whole popping thing");
+    }
+
+    pub fn exit(&mut self) {
+        self.clear_loaded();
         self.userdatamap.clear();
         self.nonuserdatamap.clear();
         self.justcollisionmap.clear();
@@ -629,11 +1158,14 @@ impl ChunkSystem {
 
     pub fn reset(&mut self, radius: u8, seed: u32, noisetype: usize) {
         info!("Start of reset func");
+        //Slots from the last world would otherwise pile up under the new ones and keep their spots taken
+        self.clear_loaded();
 
         self.radius = radius;
         *(self.perlin.write()) = Perlin::new(seed);
+        self.natural_columns.clear();
         self.voxel_models = None;
-        self.planet_type = noisetype as u8;
+        self.set_planet_type(noisetype as u8);
         unsafe {CURRSEED.store(seed, std::sync::atomic::Ordering::Relaxed)};
 
         info!("After setting currentseed");
@@ -648,6 +1180,7 @@ impl ChunkSystem {
                             x: CHUNKPOSDEFAULT,
                             y: CHUNKPOSDEFAULT,
                         },
+                        lod: 1,
                     })));
 
                     self.geobank.push(Arc::new(ChunkGeo::new()));
@@ -663,12 +1196,121 @@ impl ChunkSystem {
         info!("After making new chunk stuff");
     }
 
+    //Beyond twice the radius (manhattan, in chunks), so never a spot the loaded square still wants
+    pub fn is_distant(&self, cpos: vec::IVec2, center: vec::IVec2) -> bool {
+        (cpos.x - center.x).abs() + (cpos.y - center.y).abs() > self.radius as i32 * 2
+    }
+
+    //How much of the square the chunk thread keeps around center is loaded, 0 to 1
+    pub fn loaded_fraction(&self, center: vec::IVec2) -> f32 {
+        let r = self.radius as i32;
+        if r == 0 {
+            return 1.0;
+        }
+        let mut loaded = 0;
+        for i in -r..r {
+            for k in -r..r {
+                if self.takencare.contains_key(&vec::IVec2::new(center.x + i, center.y + k)) {
+                    loaded += 1;
+                }
+            }
+        }
+        loaded as f32 / (4 * r * r) as f32
+    }
+
+    //Drops a loaded chunk, its mesh data is freed and its spot forgotten so walking back regenerates it
+    pub fn unload_index(&self, index: usize) {
+        let mut cf = self.chunks[index].lock();
+        if !cf.used {
+            return;
+        }
+        self.takencare.remove(&cf.pos);
+        cf.used = false;
+        cf.pos = IVec2 {
+            x: CHUNKPOSDEFAULT,
+            y: CHUNKPOSDEFAULT,
+        };
+        let geo = &self.geobank[cf.geo_index];
+        geo.pos.lock().clone_from(&cf.pos);
+        geo.release();
+        if let Some(mem) = self.chunk_memories.lock().memories.get_mut(cf.geo_index) {
+            mem.used = false;
+        }
+    }
+
+    //Change the render distance while a world is running. Growing allocates more
+    //chunk slots, shrinking frees every chunk outside the new square so it can be reused.
+    pub fn set_radius(&mut self, radius: u8, center: vec::IVec2) {
+        let radius = radius.clamp(MIN_RADIUS, MAX_RADIUS);
+        if radius == self.radius {
+            return;
+        }
+        info!("Changing render distance from {} to {}", self.radius, radius);
+        self.radius = radius;
+
+        if self.headless {
+            return;
+        }
+
+        let side = radius as usize * 2 + 5;
+        while self.chunks.len() < side * side {
+            self.chunks.push(Arc::new(Mutex::new(ChunkFacade {
+                geo_index: self.geobank.len(),
+                used: false,
+                pos: IVec2 {
+                    x: CHUNKPOSDEFAULT,
+                    y: CHUNKPOSDEFAULT,
+                },
+                lod: 1,
+            })));
+
+            self.geobank.push(Arc::new(ChunkGeo::new()));
+            self.chunk_memories
+                .lock()
+                .memories
+                .push(ChunkMemory::new(&self.geobank[self.geobank.len() - 1]));
+        }
+
+        let r = radius as i32;
+        for index in 0..self.chunks.len() {
+            let pos = self.chunks[index].lock().pos;
+            if pos.x == CHUNKPOSDEFAULT {
+                continue;
+            }
+            let dx = pos.x - center.x;
+            let dz = pos.y - center.y;
+            if dx < -r || dx >= r || dz < -r || dz >= r {
+                self.unload_index(index);
+            }
+        }
+    }
+
     pub fn new(
         radius: u8,
         seed: u32,
         noisetype: usize,
         headless: bool
     ) -> ChunkSystem {
+        Self::new_with_dimensions(radius, seed, noisetype, headless, CW, CH)
+    }
+
+    pub fn new_with_dimensions(
+        radius: u8,
+        seed: u32,
+        noisetype: usize,
+        headless: bool,
+        chunk_width: i32,
+        chunk_height: i32
+    ) -> ChunkSystem {
+        let clamped_width = chunk_width.clamp(1, MAX_CHUNK_WIDTH);
+        let clamped_height = chunk_height.clamp(1, MAX_CHUNK_HEIGHT);
+        if clamped_width != chunk_width || clamped_height != chunk_height {
+            info!(
+                "Chunk size {}x{} doesn't fit the vertex packing, using {}x{}",
+                chunk_width, chunk_height, clamped_width, clamped_height
+            );
+        }
+
         let mut cs = ChunkSystem {
             chunks: Vec::new(),
             geobank: Vec::new(),
@@ -693,6 +1335,21 @@ impl ChunkSystem {
             hashadinitiallightpass: Arc::new(Mutex::new(HashMap::new())),
             lightmap: Arc::new(Mutex::new(HashMap::new())),
             generated_chunks: Arc::new(DashMap::new()),
+            cave_frequency: Planets::get_cave_frequency(noisetype as u32),
+            gravity: Planets::get_gravity(noisetype as u32),
+            ore_specs: Arc::new(OreSpec::for_planet(noisetype as u32)),
+            terrain_octaves: Planets::get_terrain_octaves(noisetype as u32),
+            chunk_width: clamped_width,
+            chunk_height: clamped_height,
+            natural_columns: DashMap::new(),
+            column_cache_hits: AtomicUsize::new(0),
+            column_cache_misses: AtomicUsize::new(0),
+            user_edits: AtomicUsize::new(0),
+            fluid_updates: lockfree::queue::Queue::new(),
+            last_fluid_tick: Mutex::new(Instant::now()),
+            world_path: RwLock::new(None),
+            db_path: RwLock::new("db".to_string()),
+            region_cache: Mutex::new(RegionCache::default()),
         };
 
         // let directory_path = "assets/voxelmodels/";
@@ -716,6 +1373,7 @@ impl ChunkSystem {
                             x: CHUNKPOSDEFAULT,
                             y: CHUNKPOSDEFAULT,
                         },
+                        lod: 1,
                     })));
 
                     cs.geobank.push(Arc::new(ChunkGeo::new()));
@@ -732,10 +1390,10 @@ impl ChunkSystem {
 
         cs
     }
-    pub fn spot_to_chunk_pos(spot: &vec::IVec3) -> vec::IVec2 {
+    pub fn spot_to_chunk_pos(&self, spot: &vec::IVec3) -> vec::IVec2 {
         return vec::IVec2 {
-            x: (spot.x as f32 / CW as f32).floor() as i32,
-            y: (spot.z as f32 / CW as f32).floor() as i32,
+            x: (spot.x as f32 / self.chunk_width as f32).floor() as i32,
+            y: (spot.z as f32 / self.chunk_width as f32).floor() as i32,
         };
     }
     pub fn initial_rebuild_on_main_thread(
@@ -748,15 +1406,15 @@ impl ChunkSystem {
         //     gl::UseProgram(shader.shader_id);
         // }
 
+        let csys = csys.read();
+
         let user_cpos = IVec2 {
-            x: (campos.x / CW as f32).floor() as i32,
-            y: (campos.z / CW as f32).floor() as i32,
+            x: (campos.x / csys.chunk_width as f32).floor() as i32,
+            y: (campos.z / csys.chunk_width as f32).floor() as i32,
         };
 
         let mut neededspots = Vec::new();
 
-        let csys = csys.read();
-
         for i in -(csys.radius as i32)..(csys.radius as i32) {
             for k in -(csys.radius as i32)..(csys.radius as i32) {
                 let this_spot = IVec2 {
@@ -847,7 +1505,7 @@ impl ChunkSystem {
         }
     }
     pub fn queue_rerender(&self, spot: vec::IVec3, user_power: bool, light: bool) {
-        let chunk_key = &Self::spot_to_chunk_pos(&spot);
+        let chunk_key = &self.spot_to_chunk_pos(&spot);
         match self.takencare.get(chunk_key) {
             Some(cf) => {
                 self.queue_geoindex_rerender(cf.geo_index, user_power, light);
@@ -890,7 +1548,7 @@ impl ChunkSystem {
                 match self.lightmap.lock().get(&(*i + spot)) {
                     Some(k) => {
                         for ray in &k.rays {
-                            let chunkofthisraysorigin = ChunkSystem::spot_to_chunk_pos(&ray.origin);
+                            let chunkofthisraysorigin = self.spot_to_chunk_pos(&ray.origin);
                             // match self.takencare.get(&chunkofthisraysorigin) {
                             //     Some(chunk) => {
                             //         implicated.insert(chunk.geo_index);
@@ -916,7 +1574,7 @@ impl ChunkSystem {
 
             for i in Cube::get_neighbors() {
                 let thisspot = spot + *i;
-                neighbs.insert(ChunkSystem::spot_to_chunk_pos(&thisspot));
+                neighbs.insert(self.spot_to_chunk_pos(&thisspot));
             }
             for i in neighbs {
                 let here = i;
@@ -962,7 +1620,7 @@ impl ChunkSystem {
                 match self.lightmap.lock().get(&(*i + spot)) {
                     Some(k) => {
                         for ray in &k.rays {
-                            let chunkofthisraysorigin = ChunkSystem::spot_to_chunk_pos(&ray.origin);
+                            let chunkofthisraysorigin = self.spot_to_chunk_pos(&ray.origin);
                             // match self.takencare.get(&chunkofthisraysorigin) {
                             //     Some(chunk) => {
                             //         implicated.insert(chunk.geo_index);
@@ -988,7 +1646,7 @@ impl ChunkSystem {
 
             for i in Cube::get_neighbors() {
                 let thisspot = spot + *i;
-                neighbs.insert(ChunkSystem::spot_to_chunk_pos(&thisspot));
+                neighbs.insert(self.spot_to_chunk_pos(&thisspot));
             }
             for i in neighbs {
                 let here = i;
@@ -1003,62 +1661,74 @@ impl ChunkSystem {
 
 
 
-    pub fn set_block(&self, spot: vec::IVec3, block: u32, user_power: bool) {
+    //Between the bedrock floor and the top of the columns, the only place an edit would ever show up
+    pub fn is_in_world(&self, spot: vec::IVec3) -> bool {
+        spot.y > 0 && spot.y < self.chunk_height && spot.y <= MAX_BLOCK_Y
+    }
+
+    //Just stores the block (user edits count towards the next save), no sound, water or mesh work follows.
+    //What the server uses, it has nothing to draw and doesn't run water
+    pub fn set_block_no_queue(&self, spot: vec::IVec3, block: u32, user_power: bool) {
+        if !self.is_in_world(spot) {
+            return;
+        }
         match user_power {
             true => {
                 //info!("Has user power, set block to {block}");
                 self.userdatamap.insert(spot, block);
+                self.user_edits.fetch_add(1, Ordering::Relaxed);
             }
             false => {
                 //info!("Non user power");
                 self.nonuserdatamap.insert(spot, block);
             }
         }
-        if !self.headless {
-            if block == 0 {
-                let wastherebits = self.blockat(spot) & Blocks::block_id_bits();
-                #[cfg(feature = "audio")]
-unsafe {
-    let _ = AUDIOPLAYER.play_next_in_series(
-                                Blocks::get_place_series(wastherebits),
-                                &Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32),
-                                &Vec3::ZERO,
-                                0.5,
-                            );
-}
-       
-                            
+    }
 
+    //What an edit at spot sounds like. Has to be asked before the edit is stored, a break leaves only air to go by
+    pub fn edit_sound_series(&self, spot: vec::IVec3, block: u32) -> &'static str {
+        if block == 0 {
+            break_series(self.blockat(spot) & Blocks::block_id_bits())
+        } else {
+            place_series(block & Blocks::block_id_bits())
+        }
+    }
 
-            } else {
-                #[cfg(feature = "audio")]
+    pub fn set_block(&self, spot: vec::IVec3, block: u32, user_power: bool) {
+        if !self.is_in_world(spot) {
+            return;
+        }
+        #[cfg(feature = "audio")]
+        let series = self.edit_sound_series(spot, block);
+        self.set_block_no_queue(spot, block, user_power);
+        #[cfg(feature = "audio")]
+        if !self.headless {
             unsafe {
-                 let _ = AUDIOPLAYER.play_next_in_series(
-                                Blocks::get_place_series(block & Blocks::block_id_bits()),
-                                &Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32),
-                                &Vec3::ZERO,
-                                0.5,
-                            );
-            }
-                           
-
+                let _ = AUDIOPLAYER.play_next_in_series(
+                    series,
+                    &Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32),
+                    &Vec3::ZERO,
+                    0.5,
+                );
             }
         }
+        self.queue_fluid_check(spot);
     }
 
     pub fn set_block_no_sound(&self, spot: vec::IVec3, block: u32, user_power: bool) {
-        match user_power {
-            true => {
-                //info!("Has user power, set block to {block}");
-                self.userdatamap.insert(spot, block);
-            }
-            false => {
-                //info!("Non user power");
-                self.nonuserdatamap.insert(spot, block);
-            }
+        if !self.is_in_world(spot) {
+            return;
         }
+        self.set_block_no_queue(spot, block, user_power);
+        self.queue_fluid_check(spot);
     }
     pub fn move_and_rebuild(&self, index: usize, cpos: vec::IVec2) {
+        let (ind, light) = self.move_index(index, cpos);
+        self.rebuild_index(ind, false, light);
+    }
+
+    //Moves (loading or generating) a chunk slot to cpos without meshing it, returns the slot to rebuild and whether it needs a light pass
+    pub fn move_index(&self, index: usize, cpos: vec::IVec2) -> (usize, bool) {
         //info!("MBeing asked to move and rebuild to {} {}", cpos.x, cpos.y);
         let tc = self.takencare.clone();
 
@@ -1087,7 +1757,9 @@ unsafe {
             //info!("Chunkgeoarc pos set to {} {}", lo.x, lo.y);
 
             //#[cfg(feature="structures")]
-            self.generate_chunk(&lo);
+            if !self.load_chunk(*lo) {
+                self.generate_chunk(&lo);
+            }
 
             let hashadlock = self.hashadinitiallightpass.lock();
             let mut light = false;
@@ -1095,7 +1767,7 @@ unsafe {
                 light = true;
             }
             drop(hashadlock);
-            self.rebuild_index(index, false, light);
+            (index, light)
         } else {
             info!("This path");
             let ind = tc.get(&cpos).unwrap().geo_index;
@@ -1106,8 +1778,27 @@ unsafe {
                 light = true;
             }
             drop(hashadlock);
-            self.rebuild_index(ind, false, light);
+            (ind, light)
+        }
+    }
+
+    pub fn mesh_workers() -> usize {
+        rayon::current_num_threads().max(1)
+    }
+
+    //Meshes (index, light) jobs on the rayon pool, each slot only once per batch since rebuild_index holds that slot's locks
+    pub fn rebuild_indices_parallel(&self, jobs: Vec<(usize, bool)>, user_power: bool) {
+        let mut merged: Vec<(usize, bool)> = Vec::new();
+        for (index, light) in jobs {
+            match merged.iter_mut().find(|job| job.0 == index) {
+                Some(job) => job.1 |= light,
+                None => merged.push((index, light)),
+            }
         }
+
+        merged.par_iter().for_each(|(index, light)| {
+            self.rebuild_index(*index, user_power, *light);
+        });
     }
 
     pub fn depropagate_light_origin(&self, origin: vec::IVec3, imp: &mut HashSet<vec::IVec2>) {
@@ -1122,9 +1813,9 @@ unsafe {
         while !stack.is_empty() {
             let spot = stack.pop().unwrap();
 
-            let chunkcoordoforigin = Self::spot_to_chunk_pos(&origin);
+            let chunkcoordoforigin = self.spot_to_chunk_pos(&origin);
 
-            let chunkcoordhere = Self::spot_to_chunk_pos(&spot);
+            let chunkcoordhere = self.spot_to_chunk_pos(&spot);
 
             if chunkcoordoforigin != chunkcoordhere {
                 imp.insert(chunkcoordhere);
@@ -1223,8 +1914,8 @@ unsafe {
                 drop(inner_light_seg);
             }
             else {
-                let chunkcoordoforigin = Self::spot_to_chunk_pos(&origin);
-                let chunkcoordhere = Self::spot_to_chunk_pos(&n.1);
+                let chunkcoordoforigin = self.spot_to_chunk_pos(&origin);
+                let chunkcoordhere = self.spot_to_chunk_pos(&n.1);
 
                 //info!("Chunk coord of origin: {:?}", chunkcoordoforigin);
                 //info!("Chunk coord here: {:?}", chunkcoordhere);
@@ -1352,15 +2043,15 @@ unsafe {
         let lmarc = self.lightmap.clone();
 
 
-        for x in 0..CW {
-            for z in 0..CW {
-                for y in 0..CH {
-                    let blockcoord = IVec3::new(pos.x * CW + x, y, pos.y * CW + z);
+        for x in 0..self.chunk_width {
+            for z in 0..self.chunk_width {
+                for y in 0..self.chunk_height {
+                    let blockcoord = IVec3::new(pos.x * self.chunk_width + x, y, pos.y * self.chunk_width + z);
                     let lmlock = lmarc.lock();
                     match lmlock.get(&blockcoord) {
                         Some(k) => {
                             for ray in &k.rays {
-                                let chunkcoord_of_origin = Self::spot_to_chunk_pos(&ray.origin);
+                                let chunkcoord_of_origin = self.spot_to_chunk_pos(&ray.origin);
 
                                 if chunkcoord_of_origin == pos {
                                     let originweremoving = ray.origin;
@@ -1412,11 +2103,289 @@ unsafe {
         //info!("Got to end of lightpass");
     }
 
+    //Highest block in a world column that stops sunlight, or -1 if nothing does
+    pub fn sky_top(&self, x: i32, z: i32, memo: &mut HashMap<vec::IVec3, u32>) -> i32 {
+        for y in (0..self.chunk_height).rev() {
+            let block = self.blockatmemo(vec::IVec3::new(x, y, z), memo) & Blocks::block_id_bits();
+            if block != 0 && !Blocks::is_transparent(block) && !Blocks::is_semi_transparent(block) {
+                return y;
+            }
+        }
+        -1
+    }
+
+    //Sunlight reaching spot: full when open to the sky, some next to an open column, dim otherwise
+    pub fn sky_light_at(&self, spot: vec::IVec3, skytops: &mut HashMap<vec::IVec2, i32>, memo: &mut HashMap<vec::IVec3, u32>) -> u8 {
+        let mut open = |x: i32, z: i32, memo: &mut HashMap<vec::IVec3, u32>| {
+            let top = *skytops.entry(vec::IVec2::new(x, z)).or_insert_with(|| self.sky_top(x, z, memo));
+            spot.y > top
+        };
+
+        if open(spot.x, spot.z, memo) {
+            return 15;
+        }
+        if open(spot.x + 1, spot.z, memo) || open(spot.x - 1, spot.z, memo)
+            || open(spot.x, spot.z + 1, memo) || open(spot.x, spot.z - 1, memo) {
+            return 10;
+        }
+        4
+    }
+
+    //Smooth lighting for one face corner from the four blocks touching it on the open side, the face neighbour and its three amb_spots.
+    //Gives the occlusion level (0-3, both sides blocked is full), and the sky and block light averaged over the ones light can get through
+    pub fn smooth_corner_light(
+        &self,
+        spot: vec::IVec3,
+        neigh: vec::IVec3,
+        amb_spots: &[vec::IVec3; 3],
+        memo: &mut HashMap<vec::IVec3, u32>,
+        skytops: &mut HashMap<vec::IVec2, i32>,
+    ) -> (usize, u8, LightColor) {
+        let mut sides = 0;
+        let mut corner = false;
+        let mut open: Vec<vec::IVec3> = vec![spot + neigh];
+        let mut corner_spot = None;
+
+        for amb in amb_spots {
+            let off = *amb - neigh;
+            let is_corner = off.x != 0 && off.y != 0 || off.x != 0 && off.z != 0 || off.y != 0 && off.z != 0;
+            let blocked = self.blockatmemo(*amb + spot, memo) != 0;
+            if is_corner {
+                corner = blocked;
+                corner_spot = Some(*amb + spot);
+            } else if blocked {
+                sides += 1;
+            } else {
+                open.push(*amb + spot);
+            }
+        }
+
+        let occlusion = if sides == 2 { 3 } else { sides + corner as usize };
+        //Light can't reach round a corner that both sides close off
+        if let Some(c) = corner_spot {
+            if !corner && sides < 2 {
+                open.push(c);
+            }
+        }
+
+        let mut sky: u16 = 0;
+        for o in &open {
+            sky += self.sky_light_at(*o, skytops, memo) as u16;
+        }
+
+        let lmlock = self.lightmap.lock();
+        let mut block = LightColor::ZERO;
+        for o in &open {
+            if let Some(seg) = lmlock.get(o) {
+                block += seg.sum();
+            }
+        }
+        drop(lmlock);
+
+        let n = open.len() as u16;
+        (occlusion, (sky / n) as u8, block / n)
+    }
+
+    //Every block on the outside of this slice of the column is opaque, so nothing inside it can be seen from outside
+    pub fn section_sealed(&self, cpos: vec::IVec2, section: i32, memo: &mut HashMap<vec::IVec3, u32>) -> bool {
+        let bottom = section * OCCLUSION_SECTION;
+        let top = (bottom + OCCLUSION_SECTION).min(self.chunk_height) - 1;
+        let last = self.chunk_width - 1;
+        if top - bottom < 2 {
+            return false;
+        }
+        for j in bottom..=top {
+            for i in 0..self.chunk_width {
+                for k in 0..self.chunk_width {
+                    if j != bottom && j != top && i != 0 && i != last && k != 0 && k != last {
+                        continue;
+                    }
+                    let spot = vec::IVec3::new(cpos.x * self.chunk_width + i, j, cpos.y * self.chunk_width + k);
+                    if !Blocks::is_opaque(self.blockatmemo(spot, memo) & Blocks::block_id_bits()) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn sealed_sections(&self, cpos: vec::IVec2, memo: &mut HashMap<vec::IVec3, u32>) -> Vec<bool> {
+        (0..(self.chunk_height + OCCLUSION_SECTION - 1) / OCCLUSION_SECTION)
+            .map(|section| self.section_sealed(cpos, section, memo))
+            .collect()
+    }
+
+    //Inside a sealed section of this column (not on its shell), so only visible from in there
+    pub fn is_buried(&self, cpos: vec::IVec2, spot: vec::IVec3, sealed: &[bool]) -> bool {
+        let i = spot.x - cpos.x * self.chunk_width;
+        let k = spot.z - cpos.y * self.chunk_width;
+        if i <= 0 || k <= 0 || i >= self.chunk_width - 1 || k >= self.chunk_width - 1 || spot.y < 0 {
+            return false;
+        }
+        let section = spot.y / OCCLUSION_SECTION;
+        let bottom = section * OCCLUSION_SECTION;
+        let top = (bottom + OCCLUSION_SECTION).min(self.chunk_height) - 1;
+        spot.y > bottom && spot.y < top && sealed.get(section as usize).copied().unwrap_or(false)
+    }
+
+    pub fn lod_for(&self, cpos: vec::IVec2) -> i32 {
+        let (px, pz) = unsafe {
+            (
+                PLAYERCHUNKPOS.0.load(Ordering::Relaxed),
+                PLAYERCHUNKPOS.1.load(Ordering::Relaxed),
+            )
+        };
+        let dist = (cpos.x - px).abs().max((cpos.y - pz).abs());
+        if dist <= LOD_FULL_DISTANCE {
+            1
+        } else if dist <= LOD_HALF_DISTANCE {
+            2
+        } else {
+            4
+        }
+    }
+
+    //The block a whole lod cell is drawn as: its highest opaque block, else water if it has any, else air
+    fn lod_cell(&self, origin: vec::IVec3, size: vec::IVec3, memo: &mut HashMap<vec::IVec3, u32>) -> u32 {
+        let mut water = 0;
+        for y in (origin.y..origin.y + size.y).rev() {
+            for x in origin.x..origin.x + size.x {
+                for z in origin.z..origin.z + size.z {
+                    let combined = self.blockatmemo(vec::IVec3::new(x, y, z), memo);
+                    let block = combined & Blocks::block_id_bits();
+                    if block == 0 {
+                        continue;
+                    }
+                    if !Blocks::is_transparent(block) && !Blocks::is_semi_transparent(block) {
+                        return combined;
+                    }
+                    if block == 2 && water == 0 {
+                        water = combined;
+                    }
+                }
+            }
+        }
+        water
+    }
+
+    //Meshes a chunk from lod x lod x lod cells, no special blocks, ambient occlusion or block light, it's only for far away
+    pub fn lod_mesh(&self, cpos: vec::IVec2, lod: i32) -> LodMesh {
+        let mut mesh = LodMesh::default();
+        let mut memo: HashMap<vec::IVec3, u32> = HashMap::new();
+        let mut skytops: HashMap<vec::IVec2, i32> = HashMap::new();
+
+        let base = vec::IVec3::new(cpos.x * self.chunk_width, 0, cpos.y * self.chunk_width);
+
+        for i in (0..self.chunk_width).step_by(lod as usize) {
+            for k in (0..self.chunk_width).step_by(lod as usize) {
+                for j in (0..self.chunk_height).step_by(lod as usize) {
+                    let size = vec::IVec3::new(
+                        lod.min(self.chunk_width - i),
+                        lod.min(self.chunk_height - j),
+                        lod.min(self.chunk_width - k),
+                    );
+                    let origin = base + vec::IVec3::new(i, j, k);
+
+                    let combined = self.lod_cell(origin, size, &mut memo);
+                    let block = combined & Blocks::block_id_bits();
+                    if block == 0 {
+                        continue;
+                    }
+                    let flags = combined & Blocks::block_flag_bits();
+                    let water = Blocks::is_transparent(block) || Blocks::is_semi_transparent(block);
+
+                    for (indie, neigh) in Cube::get_neighbors().iter().enumerate() {
+                        let cubeside = CubeSide::from_primitive(indie);
+
+                        //Step past this cell's far edge going up an axis, a whole cell back going down it
+                        let step = |n: i32, s: i32| if n > 0 { s } else { n * lod };
+                        let neighorigin = origin + vec::IVec3::new(step(neigh.x, size.x), step(neigh.y, size.y), step(neigh.z, size.z));
+                        let neigh_block = self.lod_cell(neighorigin, vec::IVec3::new(lod, lod, lod), &mut memo)
+                            & Blocks::block_id_bits();
+
+                        let show = if water {
+                            neigh_block == 0
+                        } else {
+                            neigh_block == 0 || Blocks::is_transparent(neigh_block) || Blocks::is_semi_transparent(neigh_block)
+                        };
+                        if !show {
+                            continue;
+                        }
+
+                        let skylighthere = self.sky_light_at(neighorigin, &mut skytops, &mut memo);
+                        let texcoord = Blocks::get_tex_coords(block, Blocks::axis_side(cubeside, Blocks::get_axis_bits(flags)));
+                        let side = Cube::get_side(cubeside);
+
+                        let mut packed32: [u32; 6] = [0, 0, 0, 0, 0, 0];
+                        let mut packed8: [u8; 6] = [0, 0, 0, 0, 0, 0];
+                        for (ind, v) in side.chunks(4).enumerate() {
+                            let adjusted_light: i32 = v[3] as i32 - (15 - skylighthere as i32) / 2;
+
+                            let pack = PackedVertex::pack(
+                                (i + v[0] as i32 * size.x) as u8,
+                                (j + v[1] as i32 * size.y).min(255) as u8,
+                                (k + v[2] as i32 * size.z) as u8,
+                                ind as u8,
+                                adjusted_light.clamp(0, 15) as u8,
+                                0,
+                                texcoord.0,
+                                texcoord.1,
+                            );
+                            packed32[ind] = PackedVertex::with_sky_light(pack.0, skylighthere);
+                            packed8[ind] = pack.1;
+                        }
+
+                        if water {
+                            mesh.tdata32.extend_from_slice(packed32.as_slice());
+                            mesh.tdata8.extend_from_slice(packed8.as_slice());
+                            mesh.tdata8rgb.extend_from_slice(&[0; 6]);
+                        } else {
+                            mesh.data32.extend_from_slice(packed32.as_slice());
+                            mesh.data8.extend_from_slice(packed8.as_slice());
+                            mesh.data8rgb.extend_from_slice(&[0; 6]);
+                        }
+                    }
+                }
+            }
+        }
+        mesh
+    }
+
+    fn publish_mesh(&self, index: usize, user_power: bool, chunklock: ChunkFacade, lengths: (i32, i32, i32, i32)) {
+        let rm = ReadyMesh::new(
+            index,
+            &chunklock.pos,
+            lengths.0,
+            lengths.1,
+            lengths.2,
+            lengths.3
+        );
+        let ugqarc = self.finished_user_geo_queue.clone();
+        let gqarc = self.finished_geo_queue.clone();
+
+        match user_power {
+            true => {
+                ugqarc.push(rm);
+            }
+            false => {
+                gqarc.push(rm);
+            }
+        }
+
+        let tc = self.takencare.clone();
+
+        if !tc.contains_key(&chunklock.pos) {
+            tc.insert(chunklock.pos, chunklock);
+        }
+    }
+
     pub fn rebuild_index(&self, index: usize, user_power: bool, light: bool) {
         //info!("Rebuilding!");
         let chunkarc = self.chunks[index].clone();
         let mut chunklock = chunkarc.lock();
         chunklock.used = true;
+        chunklock.lod = self.lod_for(chunklock.pos);
 
         let chunklock = chunklock.clone();
 
@@ -1451,17 +2420,32 @@ unsafe {
         let mut data8rgb = geobankarc.data8rgb.lock();
         let mut tdata8rgb = geobankarc.tdata8rgb.lock();
 
+        if chunklock.lod > 1 {
+            let mesh = self.lod_mesh(chunklock.pos, chunklock.lod);
+            data32.extend_from_slice(&mesh.data32);
+            data8.extend_from_slice(&mesh.data8);
+            data8rgb.extend_from_slice(&mesh.data8rgb);
+            tdata32.extend_from_slice(&mesh.tdata32);
+            tdata8.extend_from_slice(&mesh.tdata8);
+            tdata8rgb.extend_from_slice(&mesh.tdata8rgb);
+
+            self.publish_mesh(index, user_power, chunklock, (data32.len() as i32, tdata32.len() as i32, 0, 0));
+            return;
+        }
+
         let mut weatherstoptops: HashMap<vec::IVec2, i32> = HashMap::new();
-        let mut tops: HashMap<vec::IVec2, i32> = HashMap::new();
+        let mut skytops: HashMap<vec::IVec2, i32> = HashMap::new();
 
-        for i in 0..CW {
-            for k in 0..CW {
-                let mut hit_block = false;
-                for j in (0..CH).rev() {
+        //Caves sealed off inside the column get no faces, breaking into one rebuilds this chunk and brings them back
+        let sealed = self.sealed_sections(chunklock.pos, &mut memo);
+
+        for i in 0..self.chunk_width {
+            for k in 0..self.chunk_width {
+                for j in (0..self.chunk_height).rev() {
                     let spot = vec::IVec3 {
-                        x: (chunklock.pos.x * CW) + i,
+                        x: (chunklock.pos.x * self.chunk_width) + i,
                         y: j,
-                        z: (chunklock.pos.y * CW) + k,
+                        z: (chunklock.pos.y * self.chunk_width) + k,
                     };
                     let combined = self.blockatmemo(spot, &mut memo);
                     let block = combined & Blocks::block_id_bits();
@@ -1513,7 +2497,7 @@ unsafe {
                     //                 tdata8.extend_from_slice(packed8.as_slice());
                     //     }
                     // }
-                    if block != 0 {
+                    if block != 0 && !self.is_buried(chunklock.pos, spot, &sealed) {
 
 
                         let isgrass = if block == 3 {
@@ -1816,55 +2800,30 @@ unsafe {
                                         && neigh_block != 2
                                         && Blocks::is_transparent(neigh_block);
 
-                                    let lmlock = self.lightmap.lock();
-
-                                    let blocklighthere = match lmlock.get(&neighspot) {
-                                        Some(k) => k.sum(),
-                                        None => LightColor::ZERO,
-                                    };
-
-                                    // if blocklighthere != 0 {
-                                    //     info!("Block light here: {}", blocklighthere);
-                                    // }
-                                    drop(lmlock);
-
-                                    hit_block = match tops.get(&vec::IVec2 {
-                                        x: i + neigh.x,
-                                        y: k + neigh.z,
-                                    }) {
-                                        Some(t) => *t > j + neigh.y,
-                                        None => false,
-                                    };
-
-                                    if neigh_block == 0
+                                    if (neigh_block == 0
                                         || neigh_semi_trans
-                                        || water_bordering_transparent
+                                        || water_bordering_transparent)
+                                        && !self.is_buried(chunklock.pos, neighspot, &sealed)
                                     {
                                         let side = Cube::get_side(cubeside);
                                         let mut packed32: [u32; 6] = [0, 0, 0, 0, 0, 0];
                                         let mut packed8: [u8; 6] = [0, 0, 0, 0, 0, 0];
                                         let mut packed8rgb: [u16; 6] = [0, 0, 0, 0, 0, 0];
 
-                                        let texcoord = Blocks::get_tex_coords(block, cubeside);
+                                        let texcoord = Blocks::get_tex_coords(block, Blocks::axis_side(cubeside, Blocks::get_axis_bits(flags)));
                                         for (ind, v) in side.chunks(4).enumerate() {
                                             static AMB_CHANGES: [u8; 4] = [0, 3, 6, 10];
 
                                             let amb_spots: &[vec::IVec3; 3] =
                                                 Cube::get_amb_occul_spots(cubeside, ind as u8);
 
-                                            let amb_change = amb_spots
-                                                .iter()
-                                                .map(|vec| self.blockatmemo(*vec + spot, &mut memo))
-                                                .filter(|&result| result != 0)
-                                                .count();
+                                            let (amb_change, skylighthere, blocklighthere) = self
+                                                .smooth_corner_light(spot, *neigh, amb_spots, &mut memo, &mut skytops);
 
                                             let base_light: i32 =
                                                 v[3] as i32 - AMB_CHANGES[amb_change] as i32; // Perform calculations as i32
-                                            let adjusted_light: i32 = if hit_block {
-                                                base_light - 3
-                                            } else {
-                                                base_light
-                                            };
+                                            let adjusted_light: i32 =
+                                                base_light - (15 - skylighthere as i32) / 2;
                                             let clamped_light: u8 =
                                                 adjusted_light.clamp(0, 15) as u8; // Clamp in i32 context, then cast to u8
 
@@ -1885,7 +2844,7 @@ unsafe {
                                                 blocklighthere.z,
                                             );
 
-                                            packed32[ind] = pack.0;
+                                            packed32[ind] = PackedVertex::with_sky_light(pack.0, skylighthere);
                                             packed8[ind] = pack.1;
                                             packed8rgb[ind] = packedcolor;
                                         }
@@ -1893,14 +2852,6 @@ unsafe {
                                         tdata32.extend_from_slice(packed32.as_slice());
                                         tdata8.extend_from_slice(packed8.as_slice());
                                         tdata8rgb.extend_from_slice(packed8rgb.as_slice());
-                                    } else {
-                                        tops.insert(
-                                            vec::IVec2 {
-                                                x: i + neigh.x,
-                                                y: k + neigh.z,
-                                            },
-                                            j + neigh.y,
-                                        );
                                     }
                                 }
                             } else {
@@ -1913,52 +2864,28 @@ unsafe {
                                     let neighbor_transparent = Blocks::is_transparent(neigh_block)
                                         || Blocks::is_semi_transparent(neigh_block);
 
-                                    hit_block = match tops.get(&vec::IVec2 {
-                                        x: i + neigh.x,
-                                        y: k + neigh.z,
-                                    }) {
-                                        Some(t) => *t > j + neigh.y,
-                                        None => false,
-                                    };
-
-                                    let lmlock = self.lightmap.lock();
-
-                                    let blocklighthere = match lmlock.get(&neighspot) {
-                                        Some(k) => k.sum(),
-                                        None => LightColor::ZERO,
-                                    };
-                                    // if blocklighthere != 0 {
-                                    //     info!("Block light here: {}", blocklighthere);
-                                    // }
-
-                                    drop(lmlock);
-
-                                    if neigh_block == 0 || neighbor_transparent {
+                                    if (neigh_block == 0 || neighbor_transparent)
+                                        && !self.is_buried(chunklock.pos, neighspot, &sealed)
+                                    {
                                         let side = Cube::get_side(cubeside);
                                         let mut packed32: [u32; 6] = [0, 0, 0, 0, 0, 0];
                                         let mut packed8: [u8; 6] = [0, 0, 0, 0, 0, 0];
                                         let mut packed8rgb: [u16; 6] = [0, 0, 0, 0, 0, 0];
 
-                                        let texcoord = Blocks::get_tex_coords(block, cubeside);
+                                        let texcoord = Blocks::get_tex_coords(block, Blocks::axis_side(cubeside, Blocks::get_axis_bits(flags)));
                                         for (ind, v) in side.chunks(4).enumerate() {
                                             static AMB_CHANGES: [u8; 4] = [0, 3, 6, 10];
 
                                             let amb_spots: &[vec::IVec3; 3] =
                                                 Cube::get_amb_occul_spots(cubeside, ind as u8);
 
-                                            let amb_change = amb_spots
-                                                .iter()
-                                                .map(|vec| self.blockatmemo(*vec + spot, &mut memo))
-                                                .filter(|&result| result != 0)
-                                                .count();
+                                            let (amb_change, skylighthere, blocklighthere) = self
+                                                .smooth_corner_light(spot, *neigh, amb_spots, &mut memo, &mut skytops);
 
                                             let base_light: i32 =
                                                 v[3] as i32 - AMB_CHANGES[amb_change] as i32; // Perform calculations as i32
-                                            let adjusted_light: i32 = if hit_block {
-                                                base_light - 3
-                                            } else {
-                                                base_light
-                                            };
+                                            let adjusted_light: i32 =
+                                                base_light - (15 - skylighthere as i32) / 2;
                                             let clamped_light: u8 =
                                                 adjusted_light.clamp(0, 15) as u8; // Clamp in i32 context, then cast to u8
 
@@ -1978,7 +2905,7 @@ unsafe {
                                                 blocklighthere.z,
                                             );
 
-                                            packed32[ind] = pack.0;
+                                            packed32[ind] = PackedVertex::with_sky_light(pack.0, skylighthere);
                                             packed8[ind] = pack.1;
                                             packed8rgb[ind] = packedcolor;
                                         }
@@ -1987,23 +2914,6 @@ unsafe {
                                         data8.extend_from_slice(packed8.as_slice());
                                         data8rgb.extend_from_slice(packed8rgb.as_slice());
 
-                                        if Blocks::is_semi_transparent(neigh_block) {
-                                            tops.insert(
-                                                vec::IVec2 {
-                                                    x: i + neigh.x,
-                                                    y: k + neigh.z,
-                                                },
-                                                j + neigh.y,
-                                            );
-                                        }
-                                    } else {
-                                        tops.insert(
-                                            vec::IVec2 {
-                                                x: i + neigh.x,
-                                                y: k + neigh.z,
-                                            },
-                                            j + neigh.y,
-                                        );
                                     }
                                 }
                             }
@@ -2026,7 +2936,7 @@ unsafe {
                     }
                 };
                 
-                if ((i * CW) + k) % 17 == 0 && topy < 115 {
+                if ((i * self.chunk_width) + k) % 17 == 0 && topy < 115 {
                     
 
                     let mut rng = StdRng::from_entropy();
@@ -2037,16 +2947,16 @@ unsafe {
 
                     //spot xz top
                     let spoint: IVec3 = vec::IVec3 {
-                        x: (chunklock.pos.x * CW) + i,
+                        x: (chunklock.pos.x * self.chunk_width) + i,
                         y: topy,
-                        z: (chunklock.pos.y * CW) + k,
+                        z: (chunklock.pos.y * self.chunk_width) + k,
                     };
 
                     //spot xz top
                     let spo = Vec3 {
-                        x: (chunklock.pos.x * CW) as f32 + i as f32+ xzoff.x,
+                        x: (chunklock.pos.x * self.chunk_width) as f32 + i as f32+ xzoff.x,
                         y: topy as f32,
-                        z: (chunklock.pos.y * CW) as f32 + k as f32 + xzoff.y,
+                        z: (chunklock.pos.y * self.chunk_width) as f32 + k as f32 + xzoff.y,
                     };
 
 
@@ -2080,6 +2990,7 @@ unsafe {
                     
 
                     let face = TextureFace::new(15, 0);
+                    let (cellu, tilev) = (atlas().cell_u(), atlas().tile_v());
 
                     
 
@@ -2128,31 +3039,31 @@ unsafe {
                     
                     wuvdata.extend_from_slice(&[
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bry + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly  - TEXTURE_WIDTH * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
-                        face.blx, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bry + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly  - tilev * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly   - tilev * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
+                        face.blx, face.bly   - tilev * (115.0 - spo.y as f32)   + randyoffset, 0.0, 0.0,
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
 
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bry + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly  - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.blx, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bry + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly  - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.blx, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
 
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bry + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly  - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.blx, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bry + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly  - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.blx, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
 
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bry + randyoffset, 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly  - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.brx + ONE_OVER_16 * 2.0, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
-                        face.blx, face.bly   - TEXTURE_WIDTH * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bry + randyoffset, 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly  - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.brx + cellu * 2.0, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
+                        face.blx, face.bly   - tilev * (115.0 - spo.y as f32)  + randyoffset , 0.0, 0.0,
                         face.blx, face.bly + randyoffset, 0.0, 0.0,
                     ]);
 
@@ -2192,31 +3103,12 @@ unsafe {
             }
         }
 
-        let rm = ReadyMesh::new(
+        self.publish_mesh(
             index,
-            &chunklock.pos,
-            data32.len() as i32,
-            tdata32.len() as i32,
-            vdata.len() as i32,
-            wvdata.len() as i32
+            user_power,
+            chunklock,
+            (data32.len() as i32, tdata32.len() as i32, vdata.len() as i32, wvdata.len() as i32),
         );
-        let ugqarc = self.finished_user_geo_queue.clone();
-        let gqarc = self.finished_geo_queue.clone();
-
-        match user_power {
-            true => {
-                ugqarc.push(rm);
-            }
-            false => {
-                gqarc.push(rm);
-            }
-        }
-
-        let tc = self.takencare.clone();
-
-        if !tc.contains_key(&chunklock.pos) {
-            tc.insert(chunklock.pos, chunklock);
-        }
     }
 
     pub fn stamp_here(
@@ -2250,7 +3142,7 @@ unsafe {
                     v.point.y as i32 - (size.y / 2) as i32,
                 );
 
-                let c_pos = ChunkSystem::spot_to_chunk_pos(&(*spot + rearr_point));
+                let c_pos = self.spot_to_chunk_pos(&(*spot + rearr_point));
                 implicated_chunks.insert(c_pos);
                 self.set_block_no_sound(
                     IVec3::new(
@@ -2275,6 +3167,39 @@ unsafe {
         }
     }
 
+    //The blocks between two corners (either order, both included) as a .vox model, air left empty.
+    //.vox models top out at 256 a side so anything past that from min is cut off, and block flags like facing aren't kept.
+    pub fn export_region(&self, a: vec::IVec3, b: vec::IVec3) -> vox_format::types::Model {
+        let min = vec::IVec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = vec::IVec3::new(
+            a.x.max(b.x).min(min.x + 255),
+            a.y.max(b.y).min(min.y + 255),
+            a.z.max(b.z).min(min.z + 255),
+        );
+        let mut voxels = Vec::new();
+        for x in min.x..=max.x {
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    let block = self.blockat(vec::IVec3::new(x, y, z)) & Blocks::block_id_bits();
+                    if block == 0 || block > 255 {
+                        continue;
+                    }
+                    //World y up is .vox z up, points are 0-255 stored in an i8
+                    let point = [(x - min.x) as u8 as i8, (z - min.z) as u8 as i8, (y - min.y) as u8 as i8];
+                    voxels.push(vox_format::types::Voxel::new(point, block as u8));
+                }
+            }
+        }
+        vox_format::types::Model {
+            size: vox_format::types::Vector::new(
+                (max.x - min.x + 1) as u32,
+                (max.z - min.z + 1) as u32,
+                (max.y - min.y + 1) as u32,
+            ),
+            voxels,
+        }
+    }
+
     pub fn generate_chunk(&self, cpos: &vec::IVec2) {
         // Seed for the RNG.
         let seed: [u8; 32] = [
@@ -2332,12 +3257,17 @@ unsafe {
 
         //let mut index = 0;
 
-        for x in 0..CW {
-            for z in 0..CW {
-                for y in (0..CH - 40).rev() {
-                    let coord = IVec3::new(cpos.x * CW + x, y, cpos.y * CW + z);
+        for x in 0..self.chunk_width {
+            for z in 0..self.chunk_width {
+                let biome = self.biome_at(IVec2 {
+                    x: cpos.x * self.chunk_width + x,
+                    y: cpos.y * self.chunk_width + z,
+                });
+                for y in (0..self.chunk_height - 40).rev() {
+                    let coord = IVec3::new(cpos.x * self.chunk_width + x, y, cpos.y * self.chunk_width + z);
                     //if index == spot {
-                    if dim_floors.contains(&self.natural_blockat(coord)) {
+                    let here = self.natural_blockat(coord);
+                    if dim_floors.contains(&here) || here == biome.surface_block() {
                         let featnoise = self.feature_noise(IVec2 {
                             x: coord.x * 20,
                             y: coord.z * 20,
@@ -2349,6 +3279,7 @@ unsafe {
                             if item <= dim_range.1 as u32
                                 && item >= dim_range.0 as u32
                                 && item2 >= 127 as u32
+                                && biome.allows_model(item)
                             {
                                 self.stamp_here(
                                     &coord,
@@ -2387,6 +3318,25 @@ unsafe {
         a * (1.0 - t) + b * t
     }
 
+    pub fn biome_at(&self, spot: vec::IVec2) -> Biome {
+        return Self::_biome_at(&self.perlin.read(), spot);
+    }
+
+    pub fn _biome_at(perlin: &Perlin, spot: vec::IVec2) -> Biome {
+        if Self::_biome_noise(perlin, spot) > 0.0 {
+            return Biome::Desert;
+        }
+        let cold = Self::_biome_noise(perlin, IVec2 {
+            x: spot.x + 10000,
+            y: spot.y - 10000,
+        });
+        if cold > 0.2 {
+            Biome::Snow
+        } else {
+            Biome::Plains
+        }
+    }
+
     pub fn biome_noise(&self, spot: vec::IVec2) -> f64 {
         return Self::_biome_noise(&self.perlin.read(), spot);
     }
@@ -2465,11 +3415,58 @@ unsafe {
         noise1
     }
 
+    pub fn cave_density(&self, spot: vec::IVec3) -> f64 {
+        return Self::_cave_density(&self.perlin.read(), spot, self.cave_frequency);
+    }
+
+    //Two offset noise fields; where both are near zero at once we get long connected tunnels instead of isolated pockets
+    pub fn _cave_density(perlin: &Perlin, spot: vec::IVec3, cave_frequency: f64) -> f64 {
+        const XZDIVISOR1: f64 = 45.35;
+        const YDIVISOR1: f64 = 25.35;
+
+        let x = spot.x as f64 * cave_frequency;
+        let y = spot.y as f64 * cave_frequency;
+        let z = spot.z as f64 * cave_frequency;
+
+        let noise1 = perlin.get([x / XZDIVISOR1, y / YDIVISOR1, z / XZDIVISOR1]);
+        let noise2 = perlin.get([
+            (x + 3000.0) / XZDIVISOR1,
+            (y - 1000.0) / YDIVISOR1,
+            (z + 3000.0) / XZDIVISOR1,
+        ]);
+
+        let tunnel = 1.0 - (noise1 * noise1 + noise2 * noise2) * 40.0;
+
+        f64::max(tunnel, Self::_cave_noise(perlin, spot) - 0.1)
+    }
+
     pub fn noise_func(&self, spot: vec::IVec3) -> f64 {
-        return Self::_noise_func(&self.perlin.read(), spot);
+        return Self::_noise_func(&self.perlin.read(), self.terrain_octaves, spot);
+    }
+
+    pub fn fbm(&self, x: f64, z: f64, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        return Self::_fbm(&self.perlin.read(), x, z, octaves, lacunarity, gain);
+    }
+
+    //Sums octaves of 2D perlin, each offset so they don't line up at the origin. Normalized back into -1..1
+    pub fn _fbm(perlin: &Perlin, x: f64, z: f64, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut total_amplitude = 0.0;
+
+        for octave in 0..octaves.max(1) {
+            let offset = octave as f64 * 137.31;
+            sum += perlin.get([x * frequency + offset, z * frequency - offset]) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= gain;
+            frequency *= lacunarity;
+        }
+
+        sum / total_amplitude
     }
 
-    pub fn _noise_func(perlin: &Perlin, spot: vec::IVec3) -> f64 {
+    pub fn _noise_func(perlin: &Perlin, terrain_octaves: u32, spot: vec::IVec3) -> f64 {
 
         let per = perlin;
 
@@ -2489,8 +3486,7 @@ unsafe {
             ]) * 5.0
                 - f64::max(
                     y as f64 / 1.7
-                        + per
-                            .get([spot.x as f64 / 65.0, spot.z as f64 / 65.0])
+                        + Self::_fbm(per, spot.x as f64 / 65.0, spot.z as f64 / 65.0, terrain_octaves, 2.0, 0.5)
                             * 10.0,
                     0.0,
                 ),
@@ -2624,7 +3620,7 @@ unsafe {
         return match memo.get(&spot) {
             Some(b) => *b,
             None => {
-                let b = self.blockat(spot);
+                let b = self.cached_blockat(spot);
                 memo.insert(spot, b);
                 b
             }
@@ -2638,18 +3634,93 @@ unsafe {
         //     return b;
         // }
     }
+    //Same as blockat, but natural terrain comes from the column cache, which is only kept for the client's mesher
+    pub fn cached_blockat(&self, spot: vec::IVec3) -> u32 {
+        if self.headless || spot.y <= 0 || spot.y >= self.chunk_height || spot.y > MAX_BLOCK_Y {
+            return self.blockat(spot);
+        }
+
+        match self.userdatamap.get(&spot) {
+            Some(id) => {
+                return *id;
+            }
+            None => {}
+        }
+
+        match self.nonuserdatamap.get(&spot) {
+            Some(id) => {
+                return *id;
+            }
+            None => {}
+        }
+
+        self.natural_column(spot.x, spot.z).block_at(spot.y)
+    }
+
+    pub fn natural_column(&self, x: i32, z: i32) -> Arc<NaturalColumn> {
+        let key = vec::IVec2::new(x, z);
+        if let Some(column) = self.natural_columns.get(&key) {
+            self.column_cache_hits.fetch_add(1, Ordering::Relaxed);
+            return column.clone();
+        }
+        self.column_cache_misses.fetch_add(1, Ordering::Relaxed);
+
+        let per = self.perlin.read();
+        let mut runs: Vec<(i32, u32)> = Vec::new();
+        for y in 0..self.chunk_height {
+            let id = Self::_natural_blockat(&per, self.cave_frequency, &self.ore_specs, self.terrain_octaves, self.planet_type, vec::IVec3::new(x, y, z));
+            if runs.last().map_or(true, |(_, last)| *last != id) {
+                runs.push((y, id));
+            }
+        }
+        drop(per);
+
+        let column = Arc::new(NaturalColumn { runs });
+        self.natural_columns.insert(key, column.clone());
+        column
+    }
+
+    //Drops cached columns that aren't in or bordering a loaded chunk
+    pub fn trim_column_cache(&self) {
+        let cw = self.chunk_width;
+        self.natural_columns.retain(|col, _| {
+            let cpos = vec::IVec2::new(col.x.div_euclid(cw), col.y.div_euclid(cw));
+            self.takencare.contains_key(&cpos)
+        });
+    }
+
+    //Column cache (hits, misses) since the last call
+    pub fn take_column_cache_stats(&self) -> (usize, usize) {
+        (
+            self.column_cache_hits.swap(0, Ordering::Relaxed),
+            self.column_cache_misses.swap(0, Ordering::Relaxed),
+        )
+    }
+
     pub fn blockat(&self, spot: vec::IVec3) -> u32 {
-        Self::_blockat(&self.nonuserdatamap.clone(), &self.userdatamap.clone(), &self.perlin.read(), spot)
+        //Open sky above the columns, the mesher's 0..chunk_height is all there is
+        if spot.y >= self.chunk_height || spot.y > MAX_BLOCK_Y {
+            return 0;
+        }
+        Self::_blockat(&self.nonuserdatamap.clone(), &self.userdatamap.clone(), &self.perlin.read(), self.cave_frequency, &self.ore_specs, self.terrain_octaves, self.planet_type, spot)
     }
-    pub fn _blockat(nonuserdatamap: &Arc<DashMap<IVec3, u32>>, userdatamap: &Arc<DashMap<IVec3, u32>>, perlin: &Perlin, spot: vec::IVec3) -> u32 {
+    pub fn _blockat(nonuserdatamap: &Arc<DashMap<IVec3, u32>>, userdatamap: &Arc<DashMap<IVec3, u32>>, perlin: &Perlin, cave_frequency: f64, ore_specs: &[OreSpec], terrain_octaves: u32, planet_type: u8, spot: vec::IVec3) -> u32 {
         // if self.headless {
-        //     if self.generated_chunks.contains_key(&ChunkSystem::spot_to_chunk_pos(&spot)) {
+        //     if self.generated_chunks.contains_key(&self.spot_to_chunk_pos(&spot)) {
 
         //     } else {
-        //         self.generate_chunk(&ChunkSystem::spot_to_chunk_pos(&spot))
+        //         self.generate_chunk(&self.spot_to_chunk_pos(&spot))
         //     }
         // }
 
+        //The world has a floor nothing can dig or stamp through, and nothing above what a column can mesh
+        if spot.y <= 0 {
+            return 15;
+        }
+        if spot.y > MAX_BLOCK_Y {
+            return 0;
+        }
+
         match userdatamap.get(&spot) {
             Some(id) => {
                 return *id;
@@ -2661,21 +3732,55 @@ unsafe {
             Some(id) => {
                 return *id;
             }
-            None => return Self::_natural_blockat(perlin, spot),
+            None => return Self::_natural_blockat(perlin, cave_frequency, ore_specs, terrain_octaves, planet_type, spot),
         }
     }
 
     pub fn natural_blockat(&self, spot: vec::IVec3) -> u32 {
-        return Self::_natural_blockat(&self.perlin.read(), spot);
+        return Self::_natural_blockat(&self.perlin.read(), self.cave_frequency, &self.ore_specs, self.terrain_octaves, self.planet_type, spot);
     }
 
-    pub fn _natural_blockat(perlin: &Perlin, spot: vec::IVec3) -> u32 {
+    //Cheap integer hash so ore placement only depends on the seed and the coordinate
+    fn hash_spot(seed: u32, spot: vec::IVec3, salt: u32) -> u32 {
+        let mut h = (spot.x as u32).wrapping_mul(0x8DA6_B343)
+            ^ (spot.y as u32).wrapping_mul(0xD816_3841)
+            ^ (spot.z as u32).wrapping_mul(0xCB1A_B31F)
+            ^ seed.wrapping_mul(0x9E37_79B9)
+            ^ salt.wrapping_mul(0x85EB_CA6B);
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x7FEB_352D);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x846C_A68B);
+        h ^= h >> 16;
+        h
+    }
+
+    pub fn _ore_at(seed: u32, ore_specs: &[OreSpec], spot: vec::IVec3) -> Option<u32> {
+        let cell = vec::IVec3::new(spot.x.div_euclid(3), spot.y.div_euclid(3), spot.z.div_euclid(3));
+
+        for spec in ore_specs {
+            if spot.y < spec.min_y || spot.y > spec.max_y || spec.rarity == 0 {
+                continue;
+            }
+            if Self::hash_spot(seed, cell, spec.block_id) % spec.rarity == 0
+                && Self::hash_spot(seed, spot, spec.block_id) % 2 == 0
+            {
+                return Some(spec.block_id);
+            }
+        }
+        None
+    }
+
+    pub fn _natural_blockat(perlin: &Perlin, cave_frequency: f64, ore_specs: &[OreSpec], terrain_octaves: u32, planet_type: u8, spot: vec::IVec3) -> u32 {
 
 
         let per = perlin;
-        if spot.y == 0 {
+        if spot.y <= 0 {
             return 15;
         }
+        if spot.y > MAX_BLOCK_Y {
+            return 0;
+        }
 
         
         let ret = match 0 {
@@ -2694,35 +3799,40 @@ unsafe {
             _ => {
                 static WL: f32 = 30.0;
 
-                let biomenum = Self::_biome_noise(per, IVec2 {
+                let biome = Self::_biome_at(per, IVec2 {
                     x: spot.x,
                     y: spot.z,
                 });
-                let biomenum2 = Self::_biome_noise(per, IVec2 {
-                    x: spot.x * 20 + 5000,
-                    y: spot.z * 20 + 5000,
-                });
 
                 let mut underdirt = 5;
-                let mut surface = 3;
+                let mut surface = biome.surface_block();
                 let mut undersurface = 4;
-                let mut liquid = 2;
+                let liquid = 2;
                 let mut beach = 1;
 
-                if biomenum > 0.0 {
-                    underdirt = 1;
-                    surface = 1;
-                    undersurface = 1;
-                    liquid = 2;
-                    beach = 1;
-                } else {
-                    if biomenum2 > 0.0 {
-                        surface = 34;
+                match biome {
+                    Biome::Desert => {
+                        underdirt = 1;
+                        undersurface = 1;
                     }
+                    Biome::Plains => {
+                        let biomenum2 = Self::_biome_noise(per, IVec2 {
+                            x: spot.x * 20 + 5000,
+                            y: spot.z * 20 + 5000,
+                        });
+                        if biomenum2 > 0.0 {
+                            surface = 34;
+                        }
+                    }
+                    Biome::Snow => {}
                 }
 
-                if Self::_noise_func(per, spot) > 10.0 {
-                    if Self::_noise_func(per, spot + vec::IVec3 { x: 0, y: 10, z: 0 }) > 10.0 {
+                if let Some(palette) = Planets::get_terrain_palette(planet_type as u32) {
+                    (surface, undersurface, beach) = palette;
+                }
+
+                if Self::_noise_func(per, terrain_octaves, spot) > 10.0 {
+                    if Self::_noise_func(per, terrain_octaves, spot + vec::IVec3 { x: 0, y: 10, z: 0 }) > 10.0 {
                         if Self::_ore_noise(per, spot) > 1.0 {
                             35
                         } else {
@@ -2732,9 +3842,9 @@ unsafe {
 
                         let beachnoise = per.get([spot.y as f64/7.5, spot.z as f64/7.5, spot.x as f64/7.5]);
                         if spot.y > (WL + beachnoise as f32) as i32
-                        || Self::_noise_func(per, spot + vec::IVec3 { x: 0, y: 5, z: 0 }) > 10.0
+                        || Self::_noise_func(per, terrain_octaves, spot + vec::IVec3 { x: 0, y: 5, z: 0 }) > 10.0
                         {
-                            if Self::_noise_func(per, spot + vec::IVec3 { x: 0, y: 1, z: 0 }) < 10.0 {
+                            if Self::_noise_func(per, terrain_octaves, spot + vec::IVec3 { x: 0, y: 1, z: 0 }) < 10.0 {
                                 surface
                             } else {
                                 undersurface
@@ -2756,11 +3866,19 @@ unsafe {
                 }
             }
         };
-        if ret != 2 {
-            if Self::_cave_noise(per, spot) > 0.5 {
+        if ret != 0 && ret != 2 {
+            //Only carve where the terrain is still solid a few blocks up, so the surface never gets holes punched in it
+            if Self::_cave_density(per, spot, cave_frequency) > CAVE_THRESHOLD
+                && Self::_noise_func(per, terrain_octaves, spot + vec::IVec3 { x: 0, y: 4, z: 0 }) > 10.0
+            {
                 return 0;
             }
         }
+        if ret == 5 {
+            if let Some(ore) = Self::_ore_at(per.seed(), ore_specs, spot) {
+                return ore;
+            }
+        }
         ret
     }
 }
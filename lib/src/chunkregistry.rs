@@ -1,6 +1,7 @@
 
 
 use gl::types::GLuint;
+use glam::Vec3;
 
 use crate::{chunk::ChunkGeo, vec};
 
@@ -27,7 +28,11 @@ pub struct ChunkMemory {
     pub vlength: i32,
     pub wvlength: i32,
 
-    pub pos: vec::IVec2
+    pub pos: vec::IVec2,
+
+    //World-space box around the chunk column, for frustum culling
+    pub bounds_min: Vec3,
+    pub bounds_max: Vec3,
 }
 
 impl ChunkMemory {
@@ -51,12 +56,21 @@ impl ChunkMemory {
             tlength: 0,
             vlength: 0,
             wvlength: 0,
-            pos: *geo.pos.lock()
+            pos: *geo.pos.lock(),
+            bounds_min: Vec3::ZERO,
+            bounds_max: Vec3::ZERO,
         };
         //info!("ChunkMemory: {} {} {} {} {} {} {} {} {}", cm.used, cm.vbo8, cm.vbo32, cm.tvbo8, cm.tvbo32, cm.length, cm.tlength, cm.pos.x, cm.pos.y);
 
         cm
     }
+
+    pub fn set_pos(&mut self, pos: vec::IVec2, chunk_width: i32, chunk_height: i32) {
+        self.pos = pos;
+        self.bounds_min = Vec3::new((pos.x * chunk_width) as f32, 0.0, (pos.y * chunk_width) as f32);
+        self.bounds_max = self.bounds_min
+            + Vec3::new(chunk_width as f32, chunk_height as f32, chunk_width as f32);
+    }
 }
 
 pub struct ChunkRegistry {
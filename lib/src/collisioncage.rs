@@ -3,6 +3,9 @@ use glam::{Vec3};
 use crate::vec;
 use num_enum::FromPrimitive;
 
+//Longest move resolved in one go, anything longer gets split so it can't skip through a block
+pub const MAX_COLLISION_STEP: f32 = 0.5;
+
 #[derive(Debug, Clone, Copy, FromPrimitive, PartialEq)]
 #[repr(usize)]
 pub enum Side {
@@ -88,6 +91,39 @@ impl BoundBox {
             || self.max_corner.z < other.min_corner.z
             || self.min_corner.z > other.max_corner.z);
     }
+    //Whether a block at spot would end up inside this box, just touching a face doesn't count
+    pub fn overlaps_block(&self, spot: vec::IVec3) -> bool {
+        let margin = 0.001;
+        let lo = Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32);
+        let hi = lo + Vec3::ONE;
+        self.max_corner.x - margin > lo.x
+            && self.min_corner.x + margin < hi.x
+            && self.max_corner.y - margin > lo.y
+            && self.min_corner.y + margin < hi.y
+            && self.max_corner.z - margin > lo.z
+            && self.min_corner.z + margin < hi.z
+    }
+    //Smallest sideways shove that takes self out of other, along x or z
+    pub fn push_out_xz(&self, other: &BoundBox) -> Option<Vec3> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let x_overlap = f32::min(
+            self.max_corner.x - other.min_corner.x,
+            other.max_corner.x - self.min_corner.x,
+        );
+        let z_overlap = f32::min(
+            self.max_corner.z - other.min_corner.z,
+            other.max_corner.z - self.min_corner.z,
+        );
+        if x_overlap < z_overlap {
+            let sign = if self.center.x < other.center.x { -1.0 } else { 1.0 };
+            Some(Vec3::new(x_overlap * sign, 0.0, 0.0))
+        } else {
+            let sign = if self.center.z < other.center.z { -1.0 } else { 1.0 };
+            Some(Vec3::new(0.0, 0.0, z_overlap * sign))
+        }
+    }
     pub fn get_penetration(&self, other: &BoundBox) -> f32 {
         if !self.intersects(other) {
             return 0.0;
@@ -245,6 +281,50 @@ impl CollCage {
         // Return the smoothed Y position by adding an offset to the dominant axis height
         return actualpos.y.floor() + dominant_axis;
     }
+    //Moves from start to end in steps of at most MAX_COLLISION_STEP, re-centering the cage and
+    //pushing out of solid blocks after each one. Returns where it ended up and every side hit on the way.
+    pub fn sweep(
+        &mut self,
+        bound_box: &mut BoundBox,
+        start: Vec3,
+        end: Vec3,
+        extents: (f32, f32),
+        box_offset: Vec3,
+        cage_offset: Vec3,
+    ) -> (Vec3, Vec<Side>) {
+        let delta = end - start;
+        let steps = (delta.length() / MAX_COLLISION_STEP).ceil().max(1.0) as i32;
+        let step = delta / steps as f32;
+
+        let mut pos = start;
+        let mut hit: Vec<Side> = Vec::new();
+
+        for i in 0..steps {
+            //The first step uses the readings already taken around start
+            if i > 0 {
+                self.update_readings(pos + cage_offset);
+            }
+
+            let mut proposed = pos + step;
+            bound_box.set_center(proposed + box_offset, extents.0, extents.1);
+            self.update_colliding(bound_box);
+
+            let mut corr_made: Vec<Vec3> = Vec::new();
+            for side in &self.colliding {
+                let normal = self.normals[*side as usize];
+                if !corr_made.contains(&normal) {
+                    proposed += normal * self.penetrations[*side as usize];
+                    corr_made.push(normal);
+                }
+                if !hit.contains(side) {
+                    hit.push(*side);
+                }
+            }
+            pos = proposed;
+        }
+
+        (pos, hit)
+    }
     pub fn update_readings(&mut self, pos: Vec3) {
         self.update_position(pos);
         self.update_solidity();
@@ -5,7 +5,6 @@ use gl::types::{GLuint, GLvoid};
 use glam::{Mat4, Vec3};
 use glfw::ffi::glfwGetTime;
 use lockfree::queue::Queue;
-use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use tracing::info;
 use crate::{camera::Camera, chunk::ChunkSystem, collisioncage::{BoundBox, CollCage, Side}, game::Game, server_types::Message, shader::Shader, vec};
@@ -14,6 +13,11 @@ use crate::inventory::Inventory;
 
 
 
+//Drops inside this start sliding toward the player
+pub const MAGNET_RADIUS: f32 = 4.0;
+//Drops inside this go into the inventory
+pub const PICKUP_RADIUS: f32 = 1.0;
+
 pub struct Drop {
     position: Vec3,
     block_id: u32,
@@ -23,7 +27,11 @@ pub struct Drop {
     velocity: Vec3,
     bound_box: BoundBox,
     to_be_deleted: bool,
-    amount: u32
+    amount: u32,
+    //Seconds before the player can pull this back in, so thrown items don't boomerang
+    pickup_delay: f32,
+    //Seconds since it was spawned
+    age: f32
 }
 
 impl Drop {
@@ -32,7 +40,7 @@ impl Drop {
         let solid_pred: Box<dyn Fn(vec::IVec3) -> bool  + Send + Sync> = {
             let csys_arc = Arc::clone(&csys);
             Box::new(move |v: vec::IVec3| {
-                return csys_arc.read().collision_predicate(v);
+                return csys_arc.read().is_solid(v);
             })
         };
 
@@ -45,7 +53,9 @@ impl Drop {
             velocity: Vec3::new(0.0, 0.0, 0.0),
             bound_box: BoundBox::new(position),
             to_be_deleted: false,
-            amount: amt
+            amount: amt,
+            pickup_delay: 0.0,
+            age: 0.0
         }
     }
 }
@@ -62,84 +72,91 @@ pub struct Drops {
     pub inv: Arc<RwLock<Inventory>>,
 
     pub in_multiplayer: bool,
-    pub needtosend: Arc<Queue<Message>>
+    pub needtosend: Arc<Queue<Message>>,
+
+    //Drops older than this disappear
+    pub despawn_seconds: f32,
+    //Past this many the oldest drop is removed to make room
+    pub max_drops: usize
 }
 
 impl Drops {
 
     pub fn new(texture: GLuint, cam: &Arc<Mutex<Camera>>, csys: &Arc<RwLock<ChunkSystem>>, inv: &Arc<RwLock<Inventory>>, in_m: bool, needtosend: &Arc<Queue<Message>>) -> Drops {
 
-        let shader = Shader::new("assets/dropvert.glsl", "assets/dropfrag.glsl");
+        let shader = Shader::new("assets/dropvert.glsl", "assets/dropfrag.glsl").unwrap_or_else(Shader::failed);
         let mut vbo: GLuint = 0;
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::BindVertexArray(shader.vao);
-            gl::CreateBuffers(1, &mut vbo);
-            gl::BindTextureUnit(0, texture);
-            let faces: [f32; 144] = [
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::BindVertexArray(shader.vao);
+                gl::CreateBuffers(1, &mut vbo);
+                gl::BindTextureUnit(0, texture);
+                let faces: [f32; 144] = [
 
             
-            -0.5, -0.5, 0.5, 0.0,
-            -0.5, -0.5, -0.5, 1.0,
-            -0.5, 0.5, -0.5, 2.0,
+                -0.5, -0.5, 0.5, 0.0,
+                -0.5, -0.5, -0.5, 1.0,
+                -0.5, 0.5, -0.5, 2.0,
     
-            -0.5, 0.5, -0.5, 2.0,
-            -0.5, 0.5, 0.5, 3.0,
-            -0.5, -0.5, 0.5, 0.0,
+                -0.5, 0.5, -0.5, 2.0,
+                -0.5, 0.5, 0.5, 3.0,
+                -0.5, -0.5, 0.5, 0.0,
     
-                    0.5, -0.5, -0.5, 0.0,
-                    0.5, -0.5, 0.5, 1.0,
-                    0.5, 0.5, 0.5,2.0,
+                        0.5, -0.5, -0.5, 0.0,
+                        0.5, -0.5, 0.5, 1.0,
+                        0.5, 0.5, 0.5,2.0,
     
-                    0.5, 0.5, 0.5, 2.0,
-                    0.5, 0.5, -0.5, 3.0,
-                    0.5, -0.5, -0.5,0.0,
+                        0.5, 0.5, 0.5, 2.0,
+                        0.5, 0.5, -0.5, 3.0,
+                        0.5, -0.5, -0.5,0.0,
     
-            0.5, -0.5, 0.5, 0.0,
-            -0.5, -0.5, 0.5,1.0,
-            -0.5, 0.5, 0.5, 2.0,
+                0.5, -0.5, 0.5, 0.0,
+                -0.5, -0.5, 0.5,1.0,
+                -0.5, 0.5, 0.5, 2.0,
     
-            -0.5, 0.5, 0.5, 2.0,
-            0.5, 0.5, 0.5,  3.0,
-            0.5, -0.5, 0.5, 0.0,
+                -0.5, 0.5, 0.5, 2.0,
+                0.5, 0.5, 0.5,  3.0,
+                0.5, -0.5, 0.5, 0.0,
     
-                    -0.5, -0.5, -0.5, 0.0,
-                    0.5, -0.5, -0.5,1.0,
-                    0.5, 0.5, -0.5,2.0,
+                        -0.5, -0.5, -0.5, 0.0,
+                        0.5, -0.5, -0.5,1.0,
+                        0.5, 0.5, -0.5,2.0,
     
-                    0.5, 0.5, -0.5, 2.0,
-                    -0.5, 0.5, -0.5, 3.0,
-                    -0.5, -0.5, -0.5,0.0,
+                        0.5, 0.5, -0.5, 2.0,
+                        -0.5, 0.5, -0.5, 3.0,
+                        -0.5, -0.5, -0.5,0.0,
     
-            -0.5, 0.5, -0.5, 0.0,
-            0.5, 0.5, -0.5,1.0,
-            0.5, 0.5, 0.5,2.0,
+                -0.5, 0.5, -0.5, 0.0,
+                0.5, 0.5, -0.5,1.0,
+                0.5, 0.5, 0.5,2.0,
     
-            0.5, 0.5, 0.5, 2.0,
-            -0.5, 0.5, 0.5, 3.0,
-            -0.5, 0.5, -0.5,0.0,
+                0.5, 0.5, 0.5, 2.0,
+                -0.5, 0.5, 0.5, 3.0,
+                -0.5, 0.5, -0.5,0.0,
     
-                    0.5, -0.5, -0.5, 0.0,
-                    -0.5, -0.5, -0.5,1.0,
-                    -0.5, -0.5, 0.5,2.0,
+                        0.5, -0.5, -0.5, 0.0,
+                        -0.5, -0.5, -0.5,1.0,
+                        -0.5, -0.5, 0.5,2.0,
     
-                    -0.5, -0.5, 0.5, 2.0,
-                    0.5, -0.5, 0.5, 3.0,
-                    0.5, -0.5, -0.5,0.0,
+                        -0.5, -0.5, 0.5, 2.0,
+                        0.5, -0.5, 0.5, 3.0,
+                        0.5, -0.5, -0.5,0.0,
         
-            ];
+                ];
 
-            gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as isize, faces.as_ptr() as *const GLvoid, gl::STATIC_DRAW);
+                gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as isize, faces.as_ptr() as *const GLvoid, gl::STATIC_DRAW);
             
-            gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
-            gl::EnableVertexArrayAttrib(shader.vao, 0);
-            gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
-            gl::VertexArrayAttribBinding(shader.vao, 0, 0);
+                gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
+                gl::EnableVertexArrayAttrib(shader.vao, 0);
+                gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
+                gl::VertexArrayAttribBinding(shader.vao, 0, 0);
 
-            gl::EnableVertexArrayAttrib(shader.vao, 1);
-            gl::VertexArrayAttribFormat(shader.vao, 1, 1, gl::FLOAT, gl::FALSE, 3 * std::mem::size_of::<f32>() as u32);
-            gl::VertexArrayAttribBinding(shader.vao, 1, 0);
+                gl::EnableVertexArrayAttrib(shader.vao, 1);
+                gl::VertexArrayAttribFormat(shader.vao, 1, 1, gl::FLOAT, gl::FALSE, 3 * std::mem::size_of::<f32>() as u32);
+                gl::VertexArrayAttribBinding(shader.vao, 1, 0);
 
+            }
         }
         Drops {
             shader,
@@ -149,20 +166,29 @@ impl Drops {
             csys: csys.clone(),
             inv: inv.clone(),
             in_multiplayer: in_m,
-            needtosend: needtosend.clone()
+            needtosend: needtosend.clone(),
+            despawn_seconds: 300.0,
+            max_drops: 512
         }
     }
 
-    pub fn add_drop(&mut self, pos: Vec3, block_id: u32, amt: u32) {
-        let mut block_id = block_id;
-        if block_id == 7 {
-            let mut rng = StdRng::from_entropy();
-            if rng.gen_range(0..100) < 3 {
-                block_id = 32;
-            }
+    fn push_drop(&mut self, drop: Drop) {
+        self.drops.push(drop);
+        if self.drops.len() > self.max_drops {
+            let excess = self.drops.len() - self.max_drops;
+            self.drops.drain(0..excess);
         }
+    }
+
+    pub fn add_drop(&mut self, pos: Vec3, block_id: u32, amt: u32) {
         let drop = Drop::new(block_id, pos, &self.csys, amt);
-        self.drops.push(drop);
+        self.push_drop(drop);
+    }
+    pub fn add_thrown_drop(&mut self, pos: Vec3, block_id: u32, amt: u32, velocity: Vec3) {
+        let mut drop = Drop::new(block_id, pos, &self.csys, amt);
+        drop.velocity = velocity;
+        drop.pickup_delay = 1.5;
+        self.push_drop(drop);
     }
     pub fn update_and_draw_drops(&mut self, delta_time: &f32, mvp: &Mat4) {
         self.update_drops(delta_time);
@@ -199,13 +225,18 @@ impl Drops {
 
     pub fn update_drops(&mut self, delta_time: &f32) {
         let mut to_remove_indices = Vec::new();
+        let grav = self.csys.read().gravity;
         for (index, drop) in self.drops.iter_mut().enumerate() {
+            drop.age += *delta_time;
+            if drop.age > self.despawn_seconds {
+                to_remove_indices.push(index);
+                continue;
+            }
+
             if !drop.coll_cage.solid.contains(&Side::FLOOR) {
                 drop.grounded = false;
             }
 
-            const GRAV: f32 = 9.8;
-
             if !drop.grounded  {
                 drop.time_falling_scalar = (drop.time_falling_scalar + delta_time * 5.0).min(3.0);
             } else {
@@ -214,7 +245,7 @@ impl Drops {
     
             if !drop.grounded {
                 drop.velocity +=
-                    Vec3::new(0.0, -GRAV * drop.time_falling_scalar * delta_time, 0.0);
+                    Vec3::new(0.0, -grav * drop.time_falling_scalar * delta_time, 0.0);
             }
     
 
@@ -222,20 +253,29 @@ impl Drops {
             let cc_center = drop.position;
             drop.coll_cage.update_readings(cc_center);
             
+            drop.pickup_delay = (drop.pickup_delay - *delta_time).max(0.0);
+
             let campos = self.cam.lock().position - Vec3::new(0.0, 1.0, 0.0);
-            if (drop.position).distance(campos) < 4.0 {
+
+            //Multiplayer pickups go one at a time through the server
+            let needed = if self.in_multiplayer { 1 } else { drop.amount };
+            let fits = self.inv.read().room_for(drop.block_id) >= needed;
+
+            if drop.pickup_delay > 0.0 || !fits {
+
+            } else if (drop.position).distance(campos) < MAGNET_RADIUS {
                 let diff = campos - drop.position;
 
                 let diffmag = campos.distance(drop.position);
 
                 let diffnorm = diff.normalize();
 
-                let pull = diffnorm * (1.0 - (diffmag / 4.0));
+                let pull = diffnorm * (1.0 - (diffmag / MAGNET_RADIUS));
 
                 drop.velocity += pull * *delta_time * 10.0;
             }
 
-            if (drop.position).distance(campos) < 1.0 {
+            if drop.pickup_delay <= 0.0 && fits && (drop.position).distance(campos) < PICKUP_RADIUS {
                 match Game::add_to_inventory(&self.inv, drop.block_id, drop.amount, self.in_multiplayer, &self.needtosend) {
                     Ok(_t) => {
                         to_remove_indices.push(index);
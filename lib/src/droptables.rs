@@ -0,0 +1,109 @@
+use std::{collections::{HashMap, HashSet}, fs, path::Path};
+
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::blockinfo::Blocks;
+
+//One possible drop, rolled on its own against the others
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DropEntry {
+    pub item: u32,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    #[serde(default = "default_chance")]
+    pub chance: f32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+fn default_chance() -> f32 {
+    1.0
+}
+
+//How a block is written in blocks.json, blocks left out (or without drops) just drop themselves
+#[derive(Deserialize)]
+pub struct BlockDef {
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub drops: Option<Vec<DropEntry>>,
+    //Breaking one breaks everything of the same id connected to it
+    #[serde(default)]
+    pub flood_break: bool,
+    //Sound series names, blocks without them fall back to Blocks::get_place_series
+    #[serde(default)]
+    pub break_sound: Option<String>,
+    #[serde(default)]
+    pub place_sound: Option<String>,
+}
+
+pub type DropTables = HashMap<u32, Vec<DropEntry>>;
+
+pub fn parse_block_defs(json: &str) -> Result<Vec<BlockDef>, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+pub fn parse_drop_tables(json: &str) -> Result<DropTables, serde_json::Error> {
+    Ok(drop_tables_of(parse_block_defs(json)?.iter()))
+}
+
+fn drop_tables_of<'a>(defs: impl Iterator<Item = &'a BlockDef>) -> DropTables {
+    defs.filter_map(|def| def.drops.clone().map(|drops| (def.id, drops))).collect()
+}
+
+//blocks.json next to the executable replaces the built in definitions
+pub static BLOCK_DEFS: Lazy<Vec<BlockDef>> = Lazy::new(|| {
+    let path = Path::new("blocks.json");
+    if path.exists() {
+        match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|json| parse_block_defs(&json).map_err(|e| e.to_string())) {
+            Ok(defs) => {
+                info!("Loaded {} block definitions from blocks.json", defs.len());
+                return defs;
+            }
+            Err(e) => {
+                info!("Couldn't load blocks.json, using built in block definitions: {}", e);
+            }
+        }
+    }
+    parse_block_defs(include_str!("blocks.json")).unwrap()
+});
+
+pub static DROP_TABLES: Lazy<DropTables> = Lazy::new(|| drop_tables_of(BLOCK_DEFS.iter()));
+
+pub static FLOOD_BREAK_BLOCKS: Lazy<HashSet<u32>> =
+    Lazy::new(|| BLOCK_DEFS.iter().filter(|def| def.flood_break).map(|def| def.id).collect());
+
+pub fn flood_breaks(block: u32) -> bool {
+    FLOOD_BREAK_BLOCKS.contains(&block)
+}
+
+pub fn sound_series_in(defs: &[BlockDef], block: u32, breaking: bool) -> Option<&str> {
+    let def = defs.iter().find(|def| def.id == block)?;
+    if breaking { def.break_sound.as_deref() } else { def.place_sound.as_deref() }
+}
+
+pub fn break_series(block: u32) -> &'static str {
+    sound_series_in(&BLOCK_DEFS, block, true).unwrap_or_else(|| Blocks::get_place_series(block))
+}
+
+pub fn place_series(block: u32) -> &'static str {
+    sound_series_in(&BLOCK_DEFS, block, false).unwrap_or_else(|| Blocks::get_place_series(block))
+}
+
+//(item, count) pairs to spawn for breaking block, empty means it drops nothing
+pub fn roll_drops(tables: &DropTables, block: u32, rng: &mut impl Rng) -> Vec<(u32, u32)> {
+    match tables.get(&block) {
+        Some(entries) => entries
+            .iter()
+            .filter(|entry| entry.count > 0 && (entry.chance >= 1.0 || rng.gen::<f32>() < entry.chance))
+            .map(|entry| (entry.item, entry.count))
+            .collect(),
+        None => vec![(block, 1)],
+    }
+}
@@ -1,6 +1,8 @@
+use std::cell::RefCell;
 use std::cmp::max;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::f32::consts::{self};
+use std::fs;
 use std::io::Write;
 
 use atomic_float::AtomicF32;
@@ -13,7 +15,7 @@ use gl::types::{GLenum, GLsizei, GLsizeiptr, GLuint, GLvoid};
 use glam::{Mat4, Vec2, Vec3, Vec4};
 use glfw::ffi::glfwGetTime;
 use glfw::{Action, Key, MouseButton, PWindow};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use lockfree::queue::Queue;
 use rand::rngs::StdRng;
@@ -21,7 +23,7 @@ use rand::{Rng, SeedableRng};
 use rusqlite::{params, Connection};
 use uuid::Uuid;
 
-use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI8, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 use std::sync::{Arc};
 
 use parking_lot::{deadlock, Mutex, RwLock};
@@ -31,37 +33,47 @@ use std::thread::{self, JoinHandle};
 #[cfg(feature = "audio")]
 use crate::audio::{spawn_audio_thread, AudioPlayer};
 
+use crate::blockedits::{EditRateLimiter, PendingEdit, PendingEdits, PENDING_EDIT_TIMEOUT};
 use crate::blockinfo::Blocks;
-use crate::blockoverlay::BlockOverlay;
+use crate::blockoverlay::{BlockOverlay, BreakProgress};
 use crate::chunk::{ChunkFacade, ChunkSystem, AUTOMATA_QUEUED_CHANGES};
 
 use crate::camera::Camera;
 use crate::collisioncage::*;
 use crate::cube::Cube;
 use crate::drops::Drops;
+use crate::animatedtiles::{TileAnimator, ANIMATED_TILES};
+use crate::ambience::AmbientMixer;
+#[cfg(feature = "audio")]
+use crate::ambience::{ambient_bed, is_underground, AmbientCommand};
+use crate::music::{mood_volume, scan_music_dir, MusicCommand, Playlist, COMBAT_LINGER_SECONDS};
+use crate::droptables::{flood_breaks, roll_drops, DROP_TABLES};
+use crate::particles::Particles;
 use crate::fader::Fader;
 use crate::glyphface::GlyphFace;
 use crate::guisystem::GuiSystem;
 use crate::hud::{Hud, HudElement, SlotIndexType};
 use crate::inventory::*;
 
-use crate::modelentity::ModelEntity;
+use crate::modelentity::{received_yaw, ModelEntity};
 use crate::network::NetworkConnector;
-use crate::planetinfo::Planets;
-use crate::playerposition::PlayerPosition;
+use crate::planetinfo::{Planets, HOME_GRAVITY, PLANET_TYPES};
+use crate::playerposition::{PlayerPosition, PlayerState, PlayerVec};
+use crate::saveslots::{CHOSEN_SAVE_SLOT, SAVES_DIR};
 use crate::raycast::*;
-use crate::recipes::{Recipe, RecipeEntry, RECIPES};
+use crate::recipes::{try_craft, RecipeEntry, RECIPES};
 use crate::selectcube::SelectCube;
 use crate::server_types::{Message, MessageType};
+use crate::glcheck::{gl_check, report_gl_failure, startup_failure_message};
 use crate::shader::Shader;
 use crate::specialblocks::door::{self, DoorInfo};
 use crate::statics::{MISCSETTINGS, MY_MULTIPLAYER_UUID, SAVE_MISC};
 use crate::texture::Texture;
-use crate::textureface::{TextureFace};
-use crate::tools::{get_block_material, get_tools_target_material, Material};
+use crate::textureface::{set_atlas_size, TextureFace};
+use crate::tools::get_tool_speed;
 use crate::vec::{self, IVec2, IVec3};
-use crate::voxmodel::JVoxModel;
-use crate::windowandkey::uncapkb;
+use crate::voxmodel::{JVoxModel, VOX_COLOR_BLOCKS};
+use crate::windowandkey::{uncapkb, WINDOWHEIGHT, WINDOWWIDTH};
 use crate::worldgeometry::WorldGeometry;
 
 
@@ -82,7 +94,6 @@ pub static mut FREEFALLING: bool = false;
 
 
 pub static mut STAMINA: i32 = 0;
-pub static mut UPDATE_THE_BLOCK_OVERLAY: bool = false;
 
 pub static mut WINDED: bool = false;
 pub static mut WINDEDTIMER: f32 = 0.0;
@@ -104,6 +115,15 @@ pub const WEATHERINTERVAL: f32 = 120.0;
 pub static mut TRAMPOLINE: bool = false;
 pub static mut TRAMP_TIMER: f32 = 0.0;
 
+pub const MIN_FOV: f32 = 50.0;
+pub const MAX_FOV: f32 = 110.0;
+
+pub static PLAYER_HIT_DAMAGE: i32 = 4;
+//How far the attack ray reaches, the server allows some more since its copy of the player and mob is a little behind
+pub static MOB_HIT_REACH: f32 = 10.0;
+pub static MOB_HIT_LEEWAY: f32 = 4.0;
+pub static MOB_KNOCKBACK: f32 = 6.0;
+
 pub static QUEUE_THESE: Lazy<Queue<IVec2>> = Lazy::new(|| Queue::new());
 
 pub static mut ON_CONVEYORS: bool = false;
@@ -113,6 +133,56 @@ pub static mut ROOFOVERHEAD: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(fal
 
 pub static mut HEADLESS: bool = false;
 
+thread_local! {
+    //Headless for this thread only, so a test can check the headless paths without flipping HEADLESS under every other test
+    static HEADLESS_HERE: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+//Keeps this thread headless until it's dropped
+pub struct HeadlessGuard {
+    was: bool,
+}
+
+impl HeadlessGuard {
+    pub fn enter() -> HeadlessGuard {
+        HeadlessGuard { was: HEADLESS_HERE.with(|h| h.replace(true)) }
+    }
+}
+
+impl Drop for HeadlessGuard {
+    fn drop(&mut self) {
+        HEADLESS_HERE.with(|h| h.set(self.was));
+    }
+}
+
+//Anything that talks to GL calls this first, the server and headless clients must never get that far
+pub fn debug_assert_gl_allowed(what: &str) {
+    debug_assert!(gl_allowed(), "{} reached GL while headless", what);
+}
+
+//Seconds since the last call, for when there's no glfw clock to ask
+fn instant_delta_time() -> f32 {
+    unsafe {
+        static mut PREVTIME: Lazy<Instant> = Lazy::new(|| Instant::now());
+        let delta_time = match Instant::now().checked_duration_since(*PREVTIME) {
+            Some(time) => {
+                time
+            }
+            None => {
+                Duration::from_secs_f32(0.0)
+            }
+        };
+        (*PREVTIME) = Instant::now();
+        delta_time.as_secs_f32().min(0.05)
+    }
+}
+
+//For the parts that get built either way, a glfw build can still be running as the server
+pub fn gl_allowed() -> bool {
+    let headless_here = HEADLESS_HERE.with(|h| h.get());
+    unsafe { !HEADLESS && !headless_here }
+}
+
 pub const SPRINTFOV: f32 = 83.0;
 pub const FALLFOV: f32 = 93.0;
 
@@ -160,7 +230,7 @@ pub static mut SONGS: [&'static str; 11] = [
     "assets/music/empythree.mp3",
 ];
 
-pub static mut SONGTIMER: f32 = 0.0;
+//Longest a track plays before the playlist crossfades to the next
 pub static mut SONGINTERVAL: f32 = 300.0;
 pub static mut SONGINDEX: usize = 0;
 
@@ -195,9 +265,105 @@ pub struct Skin {
 pub struct Node {
     pub transform: Mat4,
     pub children: Vec<usize>,
+    pub mesh: Option<usize>,
+    pub skin: Option<usize>,
+}
+
+//How far the planet drops away under the ship before we switch worlds, and where the new one starts coming back up from
+pub static SHIP_FLYAWAY_HEIGHT: f32 = 300.0;
+
+//Time between blocks going while break is held down in creative
+pub const CREATIVE_BREAK_REPEAT: f32 = 0.2;
+
+//How far away blocks can be looked at, broken and placed
+pub const SURVIVAL_REACH: f32 = 10.0;
+pub const CREATIVE_REACH: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    Survival,
+    //Placing never uses anything up, blocks break at once, and you fly
+    Creative,
+}
+
+impl GameMode {
+    pub fn toggled(self) -> GameMode {
+        match self {
+            GameMode::Survival => GameMode::Creative,
+            GameMode::Creative => GameMode::Survival,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Survival => "Survival",
+            GameMode::Creative => "Creative",
+        }
+    }
+
+    pub fn consumes_items(self) -> bool {
+        self == GameMode::Survival
+    }
+
+    //The server doesn't know about creative, it would hand out free items and let you reach past what it checks
+    pub fn allowed(self, in_multiplayer: bool) -> bool {
+        !(in_multiplayer && self == GameMode::Creative)
+    }
+
+    //Stamping places a whole model for free, so it's a creative thing, which in turn is singleplayer only
+    pub fn can_stamp_prefabs(self) -> bool {
+        self == GameMode::Creative
+    }
+
+    pub fn reach(self) -> f32 {
+        match self {
+            GameMode::Survival => SURVIVAL_REACH,
+            GameMode::Creative => CREATIVE_REACH,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShipState {
+    Grounded,
+    Ascending,
+    //Reached the flyaway height, the next step loads the new world
+    Transitioning,
+    Descending,
 }
 
-static REQUIRED_SHIP_FLYAWAY_HEIGHT: f32 = 0.0;
+impl ShipState {
+    pub fn taken_off(&self) -> bool {
+        *self != ShipState::Grounded
+    }
+
+    //Moves the planet offset one frame along, returns the next state, the new offset and whether to load the new world now
+    pub fn step(self, planet_y_offset: f32, delta_time: f32) -> (ShipState, f32, bool) {
+        //Faster the further from the ground, but never slower than half a block a second
+        let speed = (-planet_y_offset).clamp(0.5, 100.0);
+
+        match self {
+            ShipState::Grounded => (ShipState::Grounded, planet_y_offset, false),
+            ShipState::Ascending => {
+                let offset = (planet_y_offset - delta_time * speed).max(-SHIP_FLYAWAY_HEIGHT);
+                if offset <= -SHIP_FLYAWAY_HEIGHT {
+                    (ShipState::Transitioning, offset, false)
+                } else {
+                    (ShipState::Ascending, offset, false)
+                }
+            }
+            ShipState::Transitioning => (ShipState::Descending, -SHIP_FLYAWAY_HEIGHT, true),
+            ShipState::Descending => {
+                let offset = (planet_y_offset + delta_time * speed).min(0.0);
+                if offset >= 0.0 {
+                    (ShipState::Grounded, 0.0, false)
+                } else {
+                    (ShipState::Descending, offset, false)
+                }
+            }
+        }
+    }
+}
 
 pub struct ControlsState {
     pub left: bool,
@@ -208,6 +374,7 @@ pub struct ControlsState {
     pub lookingleft: bool,
     pub lookingright: bool,
     pub shift: bool,
+    pub sprint: bool,
 }
 
 impl ControlsState {
@@ -221,6 +388,7 @@ impl ControlsState {
             lookingleft: false,
             lookingright: false,
             shift: false,
+            sprint: false,
         }
     }
     pub fn clear(&mut self) {
@@ -231,6 +399,7 @@ impl ControlsState {
         self.up = false;
         self.lookingleft = false;
         self.lookingright = false;
+        self.sprint = false;
     }
 }
 
@@ -245,19 +414,37 @@ pub struct GameVariables {
     pub hostile_world: bool,
     pub hostile_world_sky_color: Vec4,
     pub hostile_world_sky_bottom: Vec4,
-    pub ship_going_up: bool,
-    pub ship_going_down: bool,
+    pub ship_state: ShipState,
+    //Current planet's gravity, picked up whenever the world changes
+    pub gravity: f32,
     pub break_time: f32,
     pub near_ship: bool,
-    pub ship_taken_off: bool,
-    pub on_new_world: bool,
     pub in_multiplayer: bool,
     pub menu_open: bool,
     pub main_menu: bool,
     pub in_climbable: bool,
     pub walkbobtimer: f32,
-
-    pub time_tfs_at_3: f32
+    pub head_bob_phase: f32,
+    pub head_bob_last_pos: Vec3,
+    pub invert_y: bool,
+    pub mouse_smoothing: f32,
+    //Running average of the yaw/pitch deltas used when smoothing is on
+    pub smoothed_look: (f32, f32),
+
+    //Highest point since last standing on something, for fall damage
+    pub fall_peak_y: f32,
+    //Where we last stood on something, a death in the void drops the inventory back up here
+    pub last_ground: Option<Vec3>,
+    pub fly: bool,
+    pub noclip: bool,
+    pub sprint_multiplier: f32,
+    pub show_debug: bool,
+    //Chunks drawn as triangle edges with their boundary boxes, for checking meshing and culling
+    pub wireframe: bool,
+    //Saved player state gets applied on the first world start only, not after flying to a new planet
+    pub player_state_restored: bool,
+    //Waiting on the respawn button, input is ignored. The death menu pauses singleplayer like any other menu, a server keeps going
+    pub dead: bool
 }
 
 pub enum VisionType {
@@ -267,6 +454,10 @@ pub enum VisionType {
 
 pub static mut GLCHUNKS: bool = true;
 
+//How many chunks the last frame drew vs skipped for being outside the view
+pub static mut CHUNKS_DRAWN: usize = 0;
+pub static mut CHUNKS_CULLED: usize = 0;
+
 
 
 
@@ -288,6 +479,19 @@ pub struct PlayerCamSnapshot {
 }
 
 impl PlayerCam {
+    pub fn store(&self, pos: Vec3, dir: Vec3, yaw: f32, pitch: f32) {
+        self.pos.0.store(pos.x, Ordering::Relaxed);
+        self.pos.1.store(pos.y, Ordering::Relaxed);
+        self.pos.2.store(pos.z, Ordering::Relaxed);
+
+        self.dir.0.store(dir.x, Ordering::Relaxed);
+        self.dir.1.store(dir.y, Ordering::Relaxed);
+        self.dir.2.store(dir.z, Ordering::Relaxed);
+
+        self.yaw.store(yaw, Ordering::Relaxed);
+        self.pitch.store(pitch, Ordering::Relaxed);
+    }
+
     pub fn snapshot(&self) -> PlayerCamSnapshot {
         let pos = (self.pos.0.load(Ordering::Relaxed), self.pos.1.load(Ordering::Relaxed), self.pos.2.load(Ordering::Relaxed));
         let dir = (self.dir.0.load(Ordering::Relaxed), self.dir.1.load(Ordering::Relaxed), self.dir.2.load(Ordering::Relaxed));
@@ -312,6 +516,46 @@ pub static mut PLAYERPOS: Lazy<PlayerCam> = Lazy::new(|| {
     }
 });
 
+//The chunk thread and its run flag. stop only comes back once the thread has finished its pass and exited,
+//so whatever comes next (a reset, a respawn) never overlaps a rebuild
+#[derive(Default)]
+pub struct ChunkThread {
+    pub run: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl ChunkThread {
+    pub fn new() -> ChunkThread {
+        ChunkThread { run: Arc::new(AtomicBool::new(false)), handle: None }
+    }
+
+    pub fn start<F: FnOnce(Arc<AtomicBool>) + Send + 'static>(&mut self, body: F) {
+        self.stop();
+        self.run.store(true, Ordering::Release);
+        let run = self.run.clone();
+        self.handle = Some(thread::spawn(move || body(run)));
+    }
+
+    //False if the thread died on its own, there's nothing left running either way
+    pub fn stop(&mut self) -> bool {
+        self.run.store(false, Ordering::Release);
+        match self.handle.take() {
+            Some(handle) => match handle.join() {
+                Ok(_) => true,
+                Err(_) => {
+                    info!("The chunk thread had panicked before it was stopped");
+                    false
+                }
+            },
+            None => true,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().map_or(false, |h| !h.is_finished())
+    }
+}
+
 pub struct Game {
     pub chunksys: Arc<RwLock<ChunkSystem>>,
     pub shader0: Shader,
@@ -321,8 +565,7 @@ pub struct Game {
     pub cloudshader: Shader,
     pub starshader: Shader,
     pub camera: Arc<Mutex<Camera>>,
-    pub run_chunk_thread: Arc<AtomicBool>,
-    pub chunk_thread: Option<thread::JoinHandle<()>>,
+    pub chunk_thread: ChunkThread,
     pub vars: GameVariables,
     pub controls: ControlsState,
     pub faders: Arc<RwLock<Vec<Fader>>>,
@@ -352,21 +595,25 @@ pub struct Game {
     pub static_model_entities: Vec<ModelEntity>,
     pub non_static_model_entities: Arc<DashMap<u32, ModelEntity>>,
     pub select_cube: SelectCube,
+    pub chunk_border: SelectCube,
     #[cfg(feature = "glfw")]
     pub block_overlay: BlockOverlay,
     pub ship_pos: Vec3,
     pub planet_y_offset: f32,
+    //None when headless, the server can have glfw compiled in without ever opening a window
     #[cfg(feature = "glfw")]
-    pub window: Arc<RwLock<PWindow>>,
+    pub window: Option<Arc<RwLock<PWindow>>>,
     #[cfg(feature = "glfw")]
     pub guisys: GuiSystem,
     #[cfg(feature = "glfw")]
     pub hud: Hud,
     #[cfg(feature = "glfw")]
     pub drops: Drops,
+    #[cfg(feature = "glfw")]
+    pub particles: Particles,
     pub inventory: Arc<RwLock<Inventory>>,
     pub animations: Vec<Vec<Animation>>,
-    pub skins: Vec<Skin>,
+    pub skins: Vec<Vec<Skin>>,
     pub nodes: Vec<Vec<Node>>,
     pub current_time: f32,
     pub netconn: NetworkConnector,
@@ -377,7 +624,10 @@ pub struct Game {
     pub my_uuid: Arc<RwLock<Option<Uuid>>>,
     pub ambient_bright_mult: f32,
     pub daylength: f32,
+    pub day_frozen: bool,
     pub timeofday: Arc<Mutex<f32>>,
+    //Latest time of day from the server, multiplayer clocks follow this instead of running on their own
+    pub server_timeofday: Option<f32>,
     pub sunrise_factor: f32,
     pub sunset_factor: f32,
     pub visions_timer: f32,
@@ -396,16 +646,86 @@ pub struct Game {
 
     pub mouse_slot: (u32, u32),
     pub needtosend: Arc<Queue<Message>>,
+    pub pending_edits: PendingEdits,
+    pub playlist: Playlist,
+    pub ambience: AmbientMixer,
+    //Counts down from the last hit given or taken, music stays low while it's running
+    pub combat_timer: f32,
 
-    pub health: Arc<AtomicI8>,
+    pub health: Arc<AtomicF32>,
     pub crafting_open: bool,
     pub stamina: Arc<AtomicI32>,
     pub weathertype: f32,
     pub chest_registry: Arc<DashMap<vec::IVec3, ChestInventory>>,
+
+    //Singleplayer saves at most this many seconds after an edit, sooner once building pauses
+    pub autosave_interval: f32,
+    pub autosave_timer: f32,
+    pub autosave_quiet_timer: f32,
+    pub autosave_seen_edits: usize,
+    pub autosave_saved_edits: usize,
+    pub autosave_thread: Option<thread::JoinHandle<()>>,
+    pub saving_indicator_timer: f32,
+    pub saved_indicator_timer: f32,
+    pub game_mode: GameMode,
+    //Block reach, follows the game mode but can be tuned on its own
+    pub reach: f32,
+    //Block sets waiting to go to the server (felled trees), a few at a time so its edit limit doesn't refuse them.
+    //Each carries the drops to hand out once the server agrees
+    pub edit_queue: VecDeque<(IVec3, u32, Vec<(u32, u32)>, Vec3)>,
+    pub edit_limiter: EditRateLimiter,
+    //Corners of the region to export, marked from the prefab menu
+    pub prefab_corners: [Option<IVec3>; 2],
+}
+
+//Extra .vox prefabs go in here, next to the built in models in the stamp menu
+pub const PREFABS_DIR: &str = "prefabs";
+
+pub fn prefab_files(dir: &str) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<std::path::PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().map_or(false, |e| e.eq_ignore_ascii_case("vox")))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
 }
 
+//Hotbar slots, also the width of an inventory row. Chests hold 4 rows and serde only does arrays up to 32, so 8 is the most it can be.
 pub const ROWLENGTH: i32 = 8;
 
+//The hotbar slot after scrolling by change, wrapping round either end
+pub fn wrap_hotbar_slot(slot: usize, change: i32) -> usize {
+    (slot as i32 + change).rem_euclid(ROWLENGTH) as usize
+}
+
+//Number keys 1 up to the hotbar length pick a slot directly
+pub fn hotbar_slot_for_key(key: Key) -> Option<usize> {
+    let keys = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+    keys.iter().take(ROWLENGTH as usize).position(|k| *k == key)
+}
+
+pub const AUTOSAVE_INTERVAL: f32 = 60.0;
+pub const AUTOSAVE_DEBOUNCE: f32 = 5.0;
+
+//Bedrock is at y 0, anything this far under it has fallen out of the world
+pub const VOID_DEATH_Y: f32 = -16.0;
+
+//Most blocks one flood break can take, so a huge connected mass doesn't go all at once
+pub const FLOOD_BREAK_MAX: usize = 256;
+
+//Seconds between the server's TimeUpdate broadcasts
+pub const TIME_SYNC_INTERVAL: f32 = 0.5;
+//How quickly a client's clock closes the gap to the server's, per second
+pub const TIME_SYNC_RATE: f32 = 2.0;
+//Gaps bigger than this (just joined, day length changed) snap instead of racing the sun across the sky
+pub const TIME_SNAP_SECONDS: f32 = 30.0;
+
+pub const PLAYER_STATE_PATH: &str = "saves/playerstate";
+
 
 enum FaderNames {
     FovFader = 0,
@@ -440,6 +760,14 @@ impl Game {
         Self::newold(&None, connectonstart, headless, addressentered, address)
     }
 
+    //Same call with or without the glfw feature, for the server. Nothing here touches GL.
+    pub fn new_headless(
+        addressentered: &Arc<AtomicBool>,
+        address: &Arc<Mutex<Option<String>>>,
+    ) -> JoinHandle<Game> {
+        Self::newold(&None, false, true, addressentered, address)
+    }
+
     pub fn newold(
         window: &Option<Arc<RwLock<PWindow>>>,
         connectonstart: bool,
@@ -447,11 +775,24 @@ impl Game {
         addressentered: &Arc<AtomicBool>,
         address: &Arc<Mutex<Option<String>>>,
     ) -> JoinHandle<Game> {
+        //A glfw build runs headless too when the workspace is built together, no window means no GL either way
+        let headless = headless || window.is_none();
         unsafe {
             if headless {
                 HEADLESS = true;
             }
         }
+        //Missing or broken assets are gathered up and shown together instead of panicking on the first one
+        let startup_errors = RefCell::new(Vec::new());
+        let shader = |vert: &str, frag: &str| {
+            if headless {
+                return Shader::empty();
+            }
+            Shader::new(vert, frag).unwrap_or_else(|e| {
+                startup_errors.borrow_mut().push(e);
+                Shader::empty()
+            })
+        };
         unsafe {
             SHOULDRUN = true;
         }
@@ -464,11 +805,14 @@ impl Game {
             }
         }
 
-        let oldshader = Shader::new("assets/oldvert.glsl", "assets/oldfrag.glsl");
-        let shader0 = Shader::new("assets/vert.glsl", "assets/frag.glsl");
-        let skyshader = Shader::new("assets/skyvert.glsl", "assets/skyfrag.glsl");
+        let oldshader = shader("assets/oldvert.glsl", "assets/oldfrag.glsl");
+        let shader0 = shader("assets/vert.glsl", "assets/frag.glsl");
+        let skyshader = shader("assets/skyvert.glsl", "assets/skyfrag.glsl");
         let faders: RwLock<Vec<Fader>> = RwLock::new(Vec::new());
         let cam = Arc::new(Mutex::new(Camera::new()));
+        unsafe {
+            cam.lock().set_aspect(WINDOWWIDTH, WINDOWHEIGHT);
+        }
 
         let stamina = Arc::new(AtomicI32::new(100));
 
@@ -477,32 +821,57 @@ impl Game {
             spawn_audio_thread();
         }
 
+        let basefov = if headless { 80.0 } else { unsafe { MISCSETTINGS.fov } };
         faders.write().extend(vec![
-            Fader::new(83.0, 80.0, 30.0, false), //FOV fader for moving
+            Fader::new(basefov + 3.0, basefov, 30.0, false), //FOV fader for moving
             Fader::new(1.0, 0.0, 5.0, false),    //"Visions" fader for overlay
         ]);
 
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::BindVertexArray(shader0.vao);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after binding vertex array: {}", error);
+        if !headless {
+            unsafe {
+                gl::BindVertexArray(shader0.vao);
+                gl_check("binding vertex array");
             }
         }
 
         #[cfg(feature = "glfw")]
-        let tex = Texture::new("assets/world.png").unwrap();
+        let texture = |path: &'static str| {
+            if headless {
+                return Texture::empty();
+            }
+            Texture::new(path).unwrap_or_else(|e| {
+                startup_errors.borrow_mut().push(e);
+                Texture::empty()
+            })
+        };
+        #[cfg(feature = "glfw")]
+        let mut tex = texture("assets/world.png");
         #[cfg(feature = "glfw")]
-        let weathertex = Texture::new("assets/weather.png").unwrap();
+        let weathertex = texture("assets/weather.png");
+        if let Some(message) = startup_failure_message(&startup_errors.borrow()) {
+            report_gl_failure(message);
+        }
 
         #[cfg(feature = "glfw")]
-        {
+        if !headless {
+            //Tile and glyph UVs all come from the size of the sheet actually loaded
+            set_atlas_size(tex.size.0, tex.size.1);
+            tex.animator = TileAnimator::new(ANIMATED_TILES.clone());
             tex.add_to_unit(0);
 
             weathertex.add_to_unit(2);
         }
-        let randseed = if !headless {
+        let chosen_slot = if !headless && unsafe { SINGLEPLAYER } {
+            CHOSEN_SAVE_SLOT.lock().clone()
+        } else {
+            None
+        };
+
+        let randseed = if let Some(slot) = &chosen_slot {
+            info!("Loading save slot {} with seed {}", slot.name, slot.seed);
+            slot.seed
+        } else if !headless {
             let mut rng = StdRng::from_entropy();
 
             let randseed: u32 = rng.gen_range(0..72731273);
@@ -514,7 +883,24 @@ impl Game {
             0
         };
 
-        let mut csys = ChunkSystem::new(10, randseed, 0, headless);
+        let mut csys = ChunkSystem::new(10, randseed, chosen_slot.as_ref().map_or(0, |s| s.planet_type as usize), headless);
+        if let Some(slot) = &chosen_slot {
+            unsafe { CURRSEED.store(slot.seed, Ordering::Relaxed) };
+            let db_path = slot.db_path(SAVES_DIR);
+            //Only a slot that was saved before it had its own db, a new one must not pick up another world's edits
+            let saved_before_own_db = !std::path::Path::new(&db_path).exists()
+                && std::path::Path::new(&format!("{}/pt", slot.path(SAVES_DIR))).exists();
+            let _ = fs::create_dir_all(slot.path(SAVES_DIR));
+            *csys.db_path.write() = db_path;
+            if saved_before_own_db && std::path::Path::new("db").exists() {
+                match csys.adopt_shared_edits("db", slot.seed) {
+                    Ok(n) => info!("Moved {} edits for seed {} into slot {}", n, slot.seed, slot.folder),
+                    Err(e) => info!("Couldn't move old edits into slot {}: {}", slot.folder, e),
+                }
+            }
+            csys.load_world_from_file(slot.path(SAVES_DIR));
+            unsafe { CURRSEED.store(slot.seed, Ordering::Relaxed) };
+        }
         let voxel_models = vec![
             JVoxModel::new("assets/voxelmodels/bush.vox"),
             JVoxModel::new("assets/voxelmodels/tree1.vox"),
@@ -566,11 +952,11 @@ impl Game {
                     && isnttorch
                     && isntbamboo
                     && isnttallgrass
-                    && csys_arc.read().collision_predicate(v);
+                    && csys_arc.read().is_solid(v);
             })
         };
 
-        let health = Arc::new(AtomicI8::new(20));
+        let health = Arc::new(AtomicF32::new(20.0));
 
         let cam_clone = cam.clone();
         let csysclone = chunksys.clone();
@@ -646,7 +1032,7 @@ impl Game {
 
         #[cfg(feature = "glfw")]
         let mut hud = Hud::new(
-            &window.as_ref().unwrap().clone(),
+            window.as_ref(),
             tex.id,
             health.clone(),
             stamina.clone(),
@@ -673,7 +1059,7 @@ impl Game {
                         SlotIndexType::None => SlotIndexType::None,
                     };
                     let invrowel = HudElement::new(
-                        Vec2::new(-(0.10 * 3.5) + i as f32 * 0.10, yoffset - y as f32 * rh),
+                        Vec2::new(-(0.10 * (rowlength - 1) as f32 / 2.0) + i as f32 * 0.10, yoffset - y as f32 * rh),
                         Vec2::new(0.15, 0.15),
                         [
                             tf.blx, tf.bly, tf.brx, tf.bry, tf.trx, tf.tr_y, tf.trx, tf.tr_y,
@@ -698,7 +1084,7 @@ impl Game {
                         SlotIndexType::None => SlotIndexType::None,
                     };
                     let invrowel = HudElement::new(
-                        Vec2::new(-(0.10 * 3.5) + i as f32 * 0.10, yoffset - y as f32 * rh),
+                        Vec2::new(-(0.10 * (rowlength - 1) as f32 / 2.0) + i as f32 * 0.10, yoffset - y as f32 * rh),
                         Vec2::new(0.10, 0.10),
                         [
                             tf.blx, tf.bly, tf.brx, tf.bry, tf.trx, tf.tr_y, tf.trx, tf.tr_y,
@@ -753,7 +1139,7 @@ impl Game {
 
                     let invrowel = HudElement::new(
                         Vec2::new(
-                            -(0.10 * 3.5) + 0.01 + i as f32 * 0.10,
+                            -(0.10 * (rowlength - 1) as f32 / 2.0) + 0.01 + i as f32 * 0.10,
                             yoffset - y as f32 * rh - 0.03,
                         ),
                         Vec2::new(0.05, 0.05),
@@ -767,7 +1153,7 @@ impl Game {
 
                     let invrowel = HudElement::new(
                         Vec2::new(
-                            -(0.10 * 3.5) + 0.02 + i as f32 * 0.10,
+                            -(0.10 * (rowlength - 1) as f32 / 2.0) + 0.02 + i as f32 * 0.10,
                             yoffset - y as f32 * rh - 0.03,
                         ),
                         Vec2::new(0.05, 0.05),
@@ -888,22 +1274,33 @@ impl Game {
             SONGINDEX = (SONGINDEX + rng.gen_range(1..SONGS.len())) % SONGS.len();
         }
 
+        //Whatever is in assets/music, the built in list if that folder can't be read
+        let mut tracks = scan_music_dir("assets/music");
+        if tracks.is_empty() {
+            tracks = unsafe { SONGS.iter().map(|s| s.to_string()).collect() };
+        }
+        let mut playlist = Playlist::new(tracks, StdRng::from_entropy().gen());
+        playlist.paused = unsafe { MISCSETTINGS.music_paused };
+        playlist.max_track_seconds = unsafe { SONGINTERVAL };
+
         #[cfg(feature = "glfw")]
-        let window = &window.as_ref().unwrap().clone();
+        let window = window.clone();
 
         let chest_registry = Arc::new(DashMap::new());
+        if let Some(slot) = &chosen_slot {
+            Game::static_load_chests_from_file(slot.seed, &chest_registry);
+        }
 
         let mut g = Game {
             chunksys: chunksys.clone(),
             shader0,
             oldshader,
             skyshader,
-            modelshader: Shader::new("assets/mvert.glsl", "assets/mfrag.glsl"),
-            cloudshader: Shader::new("assets/cloudsvert.glsl", "assets/cloudsfrag.glsl"),
-            starshader: Shader::new("assets/starsvert.glsl", "assets/starsfrag.glsl"),
+            modelshader: shader("assets/mvert.glsl", "assets/mfrag.glsl"),
+            cloudshader: shader("assets/cloudsvert.glsl", "assets/cloudsfrag.glsl"),
+            starshader: shader("assets/starsvert.glsl", "assets/starsfrag.glsl"),
             camera: cam.clone(),
-            run_chunk_thread: Arc::new(AtomicBool::new(true)),
-            chunk_thread: None,
+            chunk_thread: ChunkThread::new(),
             vars: GameVariables {
                 first_mouse: true,
                 mouse_focused: false,
@@ -915,19 +1312,30 @@ impl Game {
                 hostile_world: false,
                 hostile_world_sky_color: Vec4::new(0.0, 0.0, 0.0, 1.0),
                 hostile_world_sky_bottom: Vec4::new(1.0, 0.0, 0.0, 1.0),
-                ship_going_up: false,
-                ship_going_down: false,
+                ship_state: ShipState::Grounded,
+                gravity: chunksys.read().gravity,
                 break_time: 0.0,
                 near_ship: false,
-                ship_taken_off: false,
-                on_new_world: true,
                 in_multiplayer: connectonstart, //For now,
                 menu_open: false,
                 main_menu: false,
                 in_climbable: false,
                 walkbobtimer: 0.0,
-
-                time_tfs_at_3: 0.0
+                head_bob_phase: 0.0,
+                head_bob_last_pos: Vec3::ZERO,
+                invert_y: unsafe { MISCSETTINGS.invert_y },
+                mouse_smoothing: unsafe { MISCSETTINGS.mouse_smoothing },
+                smoothed_look: (0.0, 0.0),
+
+                fall_peak_y: 0.0,
+                last_ground: None,
+                fly: false,
+                noclip: false,
+                sprint_multiplier: 1.25,
+                show_debug: false,
+                wireframe: false,
+                player_state_restored: false,
+                dead: false
             },
             controls: ControlsState::new(),
             faders: Arc::new(faders),
@@ -953,6 +1361,11 @@ impl Game {
             static_model_entities: Vec::new(),
             non_static_model_entities: nsme.clone(),
             select_cube: SelectCube::new(),
+            chunk_border: {
+                let cs = chunksys.read();
+                let (w, h) = (cs.chunk_width as f32, cs.chunk_height as f32);
+                SelectCube::new_box(Vec3::splat(-0.5), Vec3::new(w - 0.5, h - 0.5, w - 0.5))
+            },
             #[cfg(feature = "glfw")]
             block_overlay: BlockOverlay::new(tex.id),
             ship_pos: Vec3::new(0.0, 0.0, 0.0),
@@ -960,7 +1373,7 @@ impl Game {
             #[cfg(feature = "glfw")]
             window: window.clone(),
             #[cfg(feature = "glfw")]
-            guisys: GuiSystem::new(&window.clone(), &tex),
+            guisys: GuiSystem::new(window.as_ref(), &tex),
             #[cfg(feature = "glfw")]
             hud,
             #[cfg(feature = "glfw")]
@@ -972,6 +1385,8 @@ impl Game {
                 connectonstart,
                 &needtosend.clone(),
             ),
+            #[cfg(feature = "glfw")]
+            particles: Particles::new(tex.id),
             inventory: inv,
             animations: Vec::new(),
             skins: Vec::new(),
@@ -995,8 +1410,10 @@ impl Game {
             known_cameras: kc,
             my_uuid,
             ambient_bright_mult: 1.0,
-            daylength: 900.0,
+            daylength: if headless { 900.0 } else { unsafe { MISCSETTINGS.day_length } },
+            day_frozen: false,
             timeofday: Arc::new(Mutex::new(250.0)),
+            server_timeofday: None,
             sunrise_factor: 0.0,
             sunset_factor: 0.0,
             visions_timer: 0.0,
@@ -1016,11 +1433,28 @@ impl Game {
             player_model_entities: pme,
             mouse_slot: (0, 0),
             needtosend,
+            pending_edits: PendingEdits::new(),
+            playlist,
+            ambience: AmbientMixer::new(),
+            combat_timer: 0.0,
             health,
             crafting_open: false,
             stamina,
             weathertype: 0.0,
-            chest_registry
+            chest_registry,
+            autosave_interval: AUTOSAVE_INTERVAL,
+            autosave_timer: 0.0,
+            autosave_quiet_timer: 0.0,
+            autosave_seen_edits: 0,
+            autosave_saved_edits: 0,
+            autosave_thread: None,
+            saving_indicator_timer: 0.0,
+            saved_indicator_timer: 0.0,
+            game_mode: GameMode::Survival,
+            reach: GameMode::Survival.reach(),
+            edit_queue: VecDeque::new(),
+            edit_limiter: EditRateLimiter::new(Instant::now()),
+            prefab_corners: [None, None],
         };
         #[cfg(feature = "glfw")]
         if !headless {
@@ -1042,15 +1476,17 @@ impl Game {
         let _aeclone = g.addressentered.clone();
         let _aclone = g.address.clone();
 
-        unsafe {
-            for string in SONGS {
-                #[cfg(feature = "audio")]
-                AUDIOPLAYER.preload(string, string);
+        #[cfg(feature = "audio")]
+        if !headless {
+            for track in g.playlist.tracks.clone() {
+                unsafe {
+                    let _ = AUDIOPLAYER.preload_music_path(track);
+                }
             }
         }
 
         #[cfg(feature = "audio")]
-        {
+        if !headless {
             unsafe {
                 AUDIOPLAYER.preload_series(
                     "grassstepseries",
@@ -1164,6 +1600,26 @@ impl Game {
                     ],
                 );
 
+                AUDIOPLAYER.preload_series(
+                    "leavesstepseries",
+                    vec![
+                        "assets/sfx/plantplace1.mp3",
+                        "assets/sfx/plantplace2.mp3",
+                        "assets/sfx/plantplace3.mp3",
+                    ],
+                );
+
+                AUDIOPLAYER.preload_series(
+                    "snowstepseries",
+                    vec![
+                        "assets/sfx/sandstep1.mp3",
+                        "assets/sfx/sandstep2.mp3",
+                        "assets/sfx/sandstep3.mp3",
+                        "assets/sfx/sandstep4.mp3",
+                        "assets/sfx/sandstep5.mp3",
+                    ],
+                );
+
                 AUDIOPLAYER.preload_series(
                     "glassplaceseries",
                     vec![
@@ -1460,26 +1916,79 @@ impl Game {
                 if self.vars.in_multiplayer {
                     self.netconn
                         .send(&Message::new(MessageType::Disconnect, Vec3::ZERO, 0.0, 0))
+                } else {
+                    self.save_singleplayer();
                 }
                 #[cfg(feature = "glfw")]
-                self.window.write().set_should_close(true);
+                if let Some(window) = &self.window {
+                    window.write().set_should_close(true);
+                }
             }
             "closemenu" => {
+                //The death menu only goes away by respawning
+                if self.vars.dead {
+                    return;
+                }
                 self.vars.menu_open = false;
                 #[cfg(feature = "glfw")]
-                self.window
-                    .write()
-          
-                    .set_cursor_mode(glfw::CursorMode::Disabled);
+                self.set_cursor_mode(glfw::CursorMode::Disabled);
                 self.set_mouse_focused(true);
             }
+            "toggleinverty" => {
+                self.vars.invert_y = !self.vars.invert_y;
+                unsafe {
+                    MISCSETTINGS.invert_y = self.vars.invert_y;
+                }
+                SAVE_MISC();
+                self.button_command("settingsmenu".into());
+            }
+            "togglemusic" => {
+                let cmds = self.playlist.toggle_pause();
+                Self::run_music_commands(cmds);
+                unsafe {
+                    MISCSETTINGS.music_paused = self.playlist.paused;
+                }
+                SAVE_MISC();
+                self.button_command("settingsmenu".into());
+            }
+            "skiptrack" => {
+                let cmds = self.playlist.skip();
+                Self::run_music_commands(cmds);
+                unsafe {
+                    MISCSETTINGS.music_paused = self.playlist.paused;
+                }
+                SAVE_MISC();
+                self.button_command("settingsmenu".into());
+            }
+            "respawn" => {
+                self.respawn();
+            }
+            "togglegamemode" => {
+                self.set_game_mode(self.game_mode.toggled());
+                self.button_command("escapemenu".into());
+            }
+            "saveworld" => {
+                self.save_world();
+                self.button_command("escapemenu".into());
+            }
             "escapemenu" => {
+                //Key releases don't reach the game while the menu is up, so let go of everything now
+                self.controls.clear();
                 self.currentbuttons = vec![
                     ("Close Menu".to_string(), "closemenu".to_string()),
                     ("Recipe Book".to_string(), "recipemenu".to_string()),
-                    ("Settings".to_string(), "settingsmenu".to_string()),
-                    ("Quit Game".to_string(), "quittomainmenu".to_string()),
                 ];
+                if !self.vars.in_multiplayer {
+                    self.currentbuttons.push((format!("Game Mode: {}", self.game_mode.name()), "togglegamemode".to_string()));
+                }
+                if self.game_mode.can_stamp_prefabs() {
+                    self.currentbuttons.push(("Stamp Prefab".to_string(), "prefabmenu".to_string()));
+                }
+                if !self.vars.in_multiplayer {
+                    self.currentbuttons.push(("Save World".to_string(), "saveworld".to_string()));
+                }
+                self.currentbuttons.push(("Settings".to_string(), "settingsmenu".to_string()));
+                self.currentbuttons.push(("Quit Game".to_string(), "quittomainmenu".to_string()));
                 self.vars.menu_open = true;
             }
             "settingsmenu" => {
@@ -1492,6 +2001,21 @@ impl Game {
                     ("SliderMouse Sensitivity".to_string(), "test".to_string()),
                     ("SliderMusic Volume".to_string(), "music".to_string()),
                     ("SliderSounds Volume".to_string(), "sounds".to_string()),
+                    ("SliderRender Distance".to_string(), "renderdistance".to_string()),
+                    ("SliderField of View".to_string(), "fov".to_string()),
+                    ("SliderDay Length".to_string(), "daylength".to_string()),
+                    ("SliderMaster Volume".to_string(), "mastervolume".to_string()),
+                    ("SliderHead Bob".to_string(), "headbob".to_string()),
+                    ("SliderMouse Smoothing".to_string(), "mousesmoothing".to_string()),
+                    (
+                        format!("Invert Mouse Y: {}", if self.vars.invert_y { "On" } else { "Off" }),
+                        "toggleinverty".to_string(),
+                    ),
+                    (
+                        format!("Music: {}", if self.playlist.paused { "Paused" } else { "Playing" }),
+                        "togglemusic".to_string(),
+                    ),
+                    ("Skip Track".to_string(), "skiptrack".to_string()),
                 ];
                 self.vars.menu_open = true;
             }
@@ -1511,17 +2035,18 @@ impl Game {
                 ];
 
                 unsafe {
-                    for (key, action) in MISCSETTINGS.keybinds.iter_mut() {
-                        self.currentbuttons.push((
-                            action.clone(), format!("{:?}", key)
-                        ));
-                    }
-
-                    for (key, action) in MISCSETTINGS.mousebinds.iter_mut() {
-                        self.currentbuttons.push((
-                            action.clone(), format!("{:?}", key)
-                        ));
-                    }
+                    //Sorted by action so the list doesn't reshuffle after every rebind
+                    let mut keys: Vec<(String, String)> = MISCSETTINGS.keybinds.iter()
+                        .map(|(key, action)| (action.clone(), format!("{:?}", key)))
+                        .collect();
+                    keys.sort();
+                    self.currentbuttons.extend(keys);
+
+                    let mut buttons: Vec<(String, String)> = MISCSETTINGS.mousebinds.iter()
+                        .map(|(key, action)| (action.clone(), key.clone()))
+                        .collect();
+                    buttons.sort();
+                    self.currentbuttons.extend(buttons);
                 }
                 self.vars.menu_open = true;
 
@@ -1579,11 +2104,157 @@ impl Game {
                 self.vars.menu_open = true;
                 
             }
+            "prefabmenu" => {
+                self.currentbuttons = vec![(
+                    "Back to Previous Menu".to_string(),
+                    "escapemenu".to_string(),
+                )];
+                for (corner, label) in self.prefab_corners.iter().zip(["A", "B"]) {
+                    let at = corner.map_or("not set".to_string(), |c| format!("{} {} {}", c.x, c.y, c.z));
+                    self.currentbuttons.push((
+                        format!("Mark Corner {} at Looked At Block ({})", label, at),
+                        format!("markcorner {}", label),
+                    ));
+                }
+                if self.prefab_corners.iter().all(|c| c.is_some()) {
+                    self.currentbuttons.push(("Export Selection".to_string(), "exportselection".to_string()));
+                }
+                let names: Vec<String> = self
+                    .voxel_models
+                    .iter()
+                    .map(|m| m.name.clone())
+                    .chain(prefab_files(PREFABS_DIR).iter().map(|p| {
+                        p.file_stem().map_or(String::new(), |s| s.to_string_lossy().to_string())
+                    }))
+                    .collect();
+                for (index, name) in names.iter().enumerate() {
+                    self.currentbuttons.push((format!("Stamp {}", name), format!("stampprefab {}", index)));
+                }
+                self.vars.menu_open = true;
+            }
+            "markcorner A" | "markcorner B" => {
+                let cl = self.camera.lock().clone();
+                if let Some((_tip, hit, _normal)) =
+                    raycast_voxel_with_bob(cl.position, cl.direction, &self.chunksys, self.reach, self.vars.walkbobtimer)
+                {
+                    self.prefab_corners[if str.ends_with('A') { 0 } else { 1 }] = Some(hit);
+                }
+                self.button_command("prefabmenu".into());
+            }
+            "exportselection" => {
+                self.export_selection();
+                self.button_command("prefabmenu".into());
+            }
+            cmd if cmd.starts_with("stampprefab ") => {
+                if let Ok(index) = cmd["stampprefab ".len()..].parse::<usize>() {
+                    self.button_command("closemenu".into());
+                    self.stamp_prefab(index);
+                }
+            }
             _ => {
                 info!("Unknown button command given");
             }
         }
     }
+
+    //Stamps a built in model (or, past those, a file from PREFABS_DIR) onto the looked at block's face
+    pub fn stamp_prefab(&mut self, index: usize) {
+        if !self.game_mode.can_stamp_prefabs() {
+            return;
+        }
+        let blocks = if index < self.voxel_models.len() {
+            self.voxel_models[index].prefab_blocks(&VOX_COLOR_BLOCKS)
+        } else {
+            match prefab_files(PREFABS_DIR).get(index - self.voxel_models.len()).and_then(|p| JVoxModel::load(p)) {
+                Some(model) => model.prefab_blocks(&VOX_COLOR_BLOCKS),
+                None => return,
+            }
+        };
+        let cl = self.camera.lock().clone();
+        let base = match raycast_voxel_with_bob(cl.position, cl.direction, &self.chunksys, self.reach, self.vars.walkbobtimer) {
+            Some((_tip, hit, normal)) => hit + normal,
+            None => return,
+        };
+        info!("Stamping {} prefab blocks at {} {} {}", blocks.len(), base.x, base.y, base.z);
+
+        let csys = self.chunksys.read();
+        let mut implicated = HashSet::new();
+        for (offset, block) in blocks {
+            let spot = base + offset;
+            csys.set_block_no_sound(spot, block, true);
+            implicated.insert(csys.spot_to_chunk_pos(&spot));
+        }
+        for cpos in implicated {
+            if let Some(cf) = csys.takencare.get(&cpos) {
+                csys.user_rebuild_requests.push(cf.geo_index);
+            }
+        }
+    }
+
+    //Writes the marked region to PREFABS_DIR, where it shows up in the stamp list
+    pub fn export_selection(&mut self) {
+        let (a, b) = match self.prefab_corners {
+            [Some(a), Some(b)] => (a, b),
+            _ => return,
+        };
+        let model = self.chunksys.read().export_region(a, b);
+        let count = model.voxels.len();
+        let mut vox = vox_format::data::VoxModels::default();
+        vox.models.push(model);
+
+        let _ = fs::create_dir_all(PREFABS_DIR);
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let path = format!("{}/export{}.vox", PREFABS_DIR, stamp);
+        match vox_format::to_file(&path, &vox) {
+            Ok(()) => info!("Exported {} blocks to {}", count, path),
+            Err(e) => info!("Couldn't export to {}: {}", path, e),
+        }
+    }
+
+    //Sends queued block sets as predicted ones, as fast as the server will take them
+    pub fn send_queued_edits(&mut self) {
+        let now = Instant::now();
+        while !self.edit_queue.is_empty() && self.edit_limiter.allow(now) {
+            let (spot, block, drops, drop_at) = self.edit_queue.pop_front().unwrap();
+            self.predict_block_set(spot, block, 0, drops, drop_at);
+        }
+    }
+    #[cfg(feature = "audio")]
+    pub fn update_ambience(&mut self) {
+        static mut WANTED: &str = "";
+        let campos = self.camera.lock().position;
+        let (planet, biome, top) = {
+            let csys = self.chunksys.read();
+            let (x, z) = (campos.x.floor() as i32, campos.z.floor() as i32);
+            (csys.planet_type as u32, csys.biome_at(IVec2 { x, y: z }), csys.highest_solid_y(x, z))
+        };
+        let bed = ambient_bed(planet, biome, is_underground(campos.y, top));
+        unsafe {
+            if WANTED != bed {
+                WANTED = bed;
+                //Beds that aren't in the assets just leave it quiet
+                if std::path::Path::new(bed).exists() {
+                    self.ambience.set_target(Some(bed.to_string()));
+                } else {
+                    info!("No ambient sound at {}", bed);
+                    self.ambience.set_target(None);
+                }
+            }
+            for cmd in self.ambience.advance(self.delta_time) {
+                match cmd {
+                    AmbientCommand::Start(bed) => {
+                        AUDIOPLAYER.set_head_scale(&bed, 0.0);
+                        AUDIOPLAYER.play_looping_in_head(bed);
+                    }
+                    AmbientCommand::Stop(bed) => AUDIOPLAYER.stop_head_sound(bed),
+                }
+            }
+            for (bed, vol) in &self.ambience.beds {
+                AUDIOPLAYER.set_head_scale(bed, *vol);
+            }
+        }
+    }
+
     #[cfg(feature = "audio")]
     pub fn play_weather_sound(&mut self) {
         static mut TIMER: f32 = 0.0;
@@ -1682,15 +2353,10 @@ impl Game {
     pub fn initialize_being_in_world(&mut self) -> JoinHandle<()> {
         let mut ship_pos = vec::IVec3::new(20, 200, 0);
 
-        // Function to decrement y until a block is found
-        fn find_ground_y(position: &mut vec::IVec3, game: &Game) {
-            while game.chunksys.read().blockat(*position) == 0 {
-                position.y -= 1;
-            }
-        }
-
         // Find the ground positions
-        find_ground_y(&mut ship_pos, &self);
+        if let Some(y) = self.chunksys.read().highest_solid_y(ship_pos.x, ship_pos.z) {
+            ship_pos.y = y;
+        }
 
         // Determine the highest y position found
         let decided_pos_y = ship_pos.y;
@@ -1707,7 +2373,9 @@ impl Game {
             }
         }
 
-        self.vars.hostile_world = (self.chunksys.read().planet_type % 2) != 0;
+        self.vars.hostile_world = Planets::is_hostile(self.chunksys.read().planet_type as u32);
+        self.vars.gravity = self.chunksys.read().gravity;
+        self.update_far_plane();
 
         //self.audiop.play("assets/music/Farfromhome.mp3", &ship_float_pos, &Vec3::new(0.0,0.0,0.0));
         //self.audiop.play("assets/sfx/shipland28sec.mp3", &ship_float_pos, &Vec3::new(0.0,0.0,0.0));
@@ -1720,6 +2388,8 @@ impl Game {
             SPAWNPOINT = ship_float_pos + Vec3::new(5.0, 10.0, 0.0);
             self.camera.lock().position = SPAWNPOINT;
         }
+        //Ground on the last planet means nothing here
+        self.vars.last_ground = None;
 
         //self.static_model_entities.push(ModelEntity::new(5, Vec3::new(0.0, 25.0, 200.0), 140.0, Vec3::new(0.0, 0.0, 0.0), &self.chunksys, &self.camera));
         //self.update_model_collisions(0);
@@ -1734,6 +2404,9 @@ impl Game {
 
     #[cfg(feature = "glfw")]
     pub fn draw_clouds(&self) {
+        if self.headless {
+            return;
+        }
         static mut HASUPLOADED: bool = false;
         static mut VBO: GLuint = 0;
 
@@ -1935,13 +2608,26 @@ impl Game {
             );
 
             // Draw the clouds
+            //They can reach past the far plane at low render distances, clamp instead of clipping them
+            gl::Enable(gl::DEPTH_CLAMP);
+            gl::DepthFunc(gl::LEQUAL);
             gl::Disable(gl::CULL_FACE);
             gl::DrawArrays(gl::TRIANGLES, 0, 6);
             gl::Enable(gl::CULL_FACE);
+            gl::DepthFunc(gl::LESS);
+            gl::Disable(gl::DEPTH_CLAMP);
         }
     }
     #[cfg(feature = "glfw")]
     pub fn draw_stars(&self) {
+        if self.headless {
+            return;
+        }
+        let opacity = Self::star_opacity(self.ambient_bright_mult);
+        if opacity <= 0.0 {
+            return;
+        }
+
         static mut HASUPLOADED: bool = false;
         static mut VBO: GLuint = 0;
         static mut hasmulted: bool = false;
@@ -2043,7 +2729,7 @@ impl Game {
                     self.starshader.shader_id,
                     b"opacity\0".as_ptr() as *const i8,
                 ),
-                1.0,
+                opacity,
             );
 
             gl::Uniform1f(
@@ -2121,9 +2807,13 @@ impl Game {
             );
 
             // Draw the stars
+            gl::Enable(gl::DEPTH_CLAMP);
+            gl::DepthFunc(gl::LEQUAL);
             gl::Disable(gl::CULL_FACE);
             gl::DrawArrays(gl::TRIANGLES, 0, 24);
             gl::Enable(gl::CULL_FACE);
+            gl::DepthFunc(gl::LESS);
+            gl::Disable(gl::DEPTH_CLAMP);
         }
     }
     #[cfg(feature = "glfw")]
@@ -2341,6 +3031,17 @@ impl Game {
         return result;
     }
 
+    //Merges count into a stack of id, returning the new stack size and what didn't fit
+    pub fn merge_stacks(id: u32, stack: u32, count: u32) -> (u32, u32) {
+        let max = Blocks::get_max_stack(id);
+        let total = stack + count;
+        if total > max {
+            (max.max(stack), total - max.max(stack))
+        } else {
+            (total, 0)
+        }
+    }
+
     pub fn add_to_inventory(
         inv: &Arc<RwLock<Inventory>>,
         id: u32,
@@ -2350,18 +3051,19 @@ impl Game {
     ) -> Result<bool, bool> {
         let mut updaterecipes = false;
         let result;
+        let max = Blocks::get_max_stack(id);
 
         if in_m {
             let n = needtosend.clone();
 
             let inventory = inv.read();
 
-            // First, try to find an item with the given `id`
+            // First, try to find a stack of `id` that still has room
             if let Some((index, item)) = inventory
                 .inv
                 .iter()
                 .enumerate()
-                .find(|(_index, item)| item.0 == id)
+                .find(|(_index, item)| item.0 == id && item.1 < max)
             {
                 let mut msg = Message::new(
                     MessageType::ChestInvUpdate,
@@ -2406,17 +3108,23 @@ impl Game {
         } else {
             let mut inventory = inv.write();
 
-            // First, try to find an item with the given `id`
-            if let Some(item) = inventory.inv.iter_mut().find(|item| item.0 == id) {
-                item.1 += count;
-                inventory.dirty = true;
-                result = Ok(true);
-                updaterecipes = true;
-            } else
-            // If not found, try to find an empty slot to add the new item
-            if let Some(item) = inventory.inv.iter_mut().find(|item| item.0 == 0) {
-                item.0 = id;
-                item.1 = count;
+            // Only add anything if all of it fits, topping up stacks of `id` before using empty slots
+            if inventory.room_for(id) >= count {
+                let mut remaining = count;
+                for item in inventory.inv.iter_mut().filter(|item| item.0 == id) {
+                    let add = max.saturating_sub(item.1).min(remaining);
+                    item.1 += add;
+                    remaining -= add;
+                }
+                for item in inventory.inv.iter_mut().filter(|item| item.0 == 0) {
+                    if remaining == 0 {
+                        break;
+                    }
+                    let add = max.min(remaining);
+                    item.0 = id;
+                    item.1 = add;
+                    remaining -= add;
+                }
                 inventory.dirty = true;
                 result = Ok(true);
                 updaterecipes = true;
@@ -2433,234 +3141,31 @@ impl Game {
     }
 
     pub fn craft_recipe_index(&mut self, index: usize, all: bool) {
-        unsafe {
-            let recipe = {
-                let r = CURRENT_AVAIL_RECIPES.lock();
-                &r[index].clone().recipe
-            };
-
-            let mut hasreqs = true;
-            let invlock = self.inventory.write();
-
-            let originalinvinv = invlock.clone();
-            let originalinv = invlock.inv.clone();
-
-            drop(invlock);
+        let recipe = unsafe {
+            let r = CURRENT_AVAIL_RECIPES.lock();
+            r[index].clone().recipe
+        };
 
+        let originalinv = self.inventory.read().inv.clone();
+        let mut newinv = originalinv.clone();
 
+        if all {
+            while try_craft(&mut newinv, &recipe) {}
+        } else {
+            try_craft(&mut newinv, &recipe);
+        }
 
-            let mut morepossible = true;
-            fn craft_in_local_inv(recipe: &Recipe, inven: &mut Inventory, morepossible: &mut bool) {
-                let mut hasreqs = true;
-                for req in &recipe.0 {
-                    let mut amt = 0;
-    
-                    for i in 0..ROWLENGTH as usize {
-                        let typehere = inven.inv[i].0;
-                        if typehere == req.0 {
-                            amt += inven.inv[i].1;
-                        }
-                    }
-    
-                    if amt < req.1 {
-                        hasreqs = false;
-                        (*morepossible) = false;
-                    }
-                }
-    
-                if hasreqs {
-                    //Find an empty spot OR MATCHING RESULT ITEM SPOT in their imaginary inv that would exist if we were to subtract the necessary ingredients:
-                    //Make an imaginary clone of their inventory:
-                    let invclone = &mut inven.inv;
-    
-                    //Subtract the ingredients
-                    for req in &recipe.0 {
-                        let mut amt = 0;
-    
-                        for i in 0..ROWLENGTH as usize {
-                            let typehere = invclone[i].0;
-                            if typehere == req.0 {
-                                while invclone[i].1 > 0 && amt < req.1 {
-                                    amt += 1;
-                                    invclone[i].1 -= 1;
-    
-                                    if invclone[i].1 == 0 {
-                                        invclone[i].0 = 0;
-                                    }
-                                }
-                                if amt >= req.1 {
-                                    break;
-                                }
-                            }
-                        }
-                    }
-    
-                    //Find the predicted empty spot or matching item slot
-                    let resultslot = {
-                        let mut slot = None;
-    
-                        for i in 0..ROWLENGTH as usize {
-                            let typehere = invclone[i].0;
-                            if (typehere == 0 || typehere == recipe.1 .0)
-                                && (invclone[i].1 + recipe.1 .1) <= 999
-                            {
-                                slot = Some(i);
-                                break;
-                            }
-                        }
-    
-                        slot
-                    };
-
-                    match resultslot {
-                        Some(slot) => {
-                            invclone[slot].0 = recipe.1.0;
-                            invclone[slot].1 = invclone[slot].1 + recipe.1.1;
-                        },
-                        None => {
-                            (*morepossible) = false;
-                        },
-                    }
-                }
+        for i in 0..ROWLENGTH as usize {
+            if newinv[i] != originalinv[i] {
+                Game::set_in_inventory(
+                    &self.inventory.clone(),
+                    i,
+                    newinv[i].0,
+                    newinv[i].1,
+                    self.vars.in_multiplayer,
+                    &self.needtosend,
+                ).unwrap();
             }
-
-            
-
-            if all {
-
-                let mut newinv = originalinvinv.clone();
-
-
-                while morepossible {
-                    craft_in_local_inv(recipe, &mut newinv, &mut morepossible);
-                }
-
-
-                if newinv != originalinvinv {
-                    for i in 0..ROWLENGTH as usize {
-                        //Turning their inventory into the invclone
-
-                        if newinv.inv[i] != originalinv[i] {
-                            Game::set_in_inventory(
-                                &self.inventory.clone(),
-                                i,
-                                newinv.inv[i].0,
-                                newinv.inv[i].1,
-                                self.vars.in_multiplayer,
-                                &self.needtosend,
-                            );
-                        }
-                        
-                    }
-                }
-
-
-
-            } else {
-                for req in &recipe.0 {
-                    let mut amt = 0;
-    
-                    for i in 0..ROWLENGTH as usize {
-                        let typehere = originalinvinv.inv[i].0;
-                        if typehere == req.0 {
-                            amt += originalinvinv.inv[i].1;
-                        }
-                    }
-    
-                    if amt < req.1 {
-                        hasreqs = false;
-                    }
-                }
-    
-                if hasreqs {
-                    //Find an empty spot OR MATCHING RESULT ITEM SPOT in their imaginary inv that would exist if we were to subtract the necessary ingredients:
-                    //Make an imaginary clone of their inventory:
-                    let invlock = self.inventory.read();
-                    let mut invclone = invlock.inv.clone();
-                    drop(invlock);
-    
-                    //Subtract the ingredients
-                    for req in &recipe.0 {
-                        let mut amt = 0;
-    
-                        for i in 0..ROWLENGTH as usize {
-                            let typehere = invclone[i].0;
-                            if typehere == req.0 {
-                                while invclone[i].1 > 0 && amt < req.1 {
-                                    amt += 1;
-                                    invclone[i].1 -= 1;
-    
-                                    if invclone[i].1 == 0 {
-                                        invclone[i].0 = 0;
-                                    }
-                                }
-                                if amt >= req.1 {
-                                    break;
-                                }
-                            }
-                        }
-                    }
-    
-                    //Find the predicted empty spot or matching item slot
-                    let resultslot = {
-                        let mut slot = None;
-    
-                        for i in 0..ROWLENGTH as usize {
-                            let typehere = invclone[i].0;
-                            if (typehere == 0 || typehere == recipe.1 .0)
-                                && (invclone[i].1 + recipe.1 .1) <= 999
-                            {
-                                slot = Some(i);
-                                break;
-                            }
-                        }
-    
-                        slot
-                    };
-    
-                    //drop(invlock);
-    
-                    //Only execute the subtraction and addition of items if they will have that result slot available
-                    match resultslot {
-                        None => {}
-                        Some(slot) => {
-                            //Take the reqs away from their real inventory by...
-
-                            for i in 0..ROWLENGTH as usize {
-                                //Turning their inventory into the invclone
-
-                                if invclone[i] != originalinv[i] {
-                                    Game::set_in_inventory(
-                                        &self.inventory.clone(),
-                                        i,
-                                        invclone[i].0,
-                                        invclone[i].1,
-                                        self.vars.in_multiplayer,
-                                        &self.needtosend,
-                                    );
-                                }
-                                
-                            }
-
-    
-                            //Give them the resulting item
-                            Game::set_in_inventory(
-                                &self.inventory.clone(),
-                                slot,
-                                recipe.1 .0,
-                                invclone[slot].1 + recipe.1 .1,
-                                self.vars.in_multiplayer,
-                                &self.needtosend,
-                            );
-                        }
-                    }
-                }
-            }
-
-            
-
-
-            
         }
     }
     #[cfg(feature = "audio")]
@@ -2803,35 +3308,8 @@ impl Game {
         }
     }
 
-    #[cfg(feature = "audio")]
-    pub fn update_music_volume(&mut self) {
-        use crate::statics::MISCSETTINGS;
-
-        unsafe {
-            static mut PASTVOLUME: f32 = 1.0;
-            if MISCSETTINGS.music_vol != PASTVOLUME {
-
-
-                for songname in SONGS {
-                    match AUDIOPLAYER.headsinks.get(songname) {
-                        Some(s) => {
-                            s.set_volume(MISCSETTINGS.music_vol);
-                        },
-                        None => {
-
-                        },
-                    }
-                }
-
-                PASTVOLUME = MISCSETTINGS.music_vol;
-            }
-
-        }
-        
-    }
-
     pub fn takeoff_ship(&mut self) {
-        if !self.vars.ship_taken_off {
+        if self.vars.ship_state == ShipState::Grounded {
             #[cfg(feature = "audio")]
             unsafe {
                 AUDIOPLAYER.play(
@@ -2842,10 +3320,7 @@ impl Game {
                 );
             }
 
-            self.vars.ship_going_up = true;
-            self.vars.ship_going_down = false;
-            self.vars.ship_taken_off = true;
-            self.vars.on_new_world = false;
+            self.vars.ship_state = ShipState::Ascending;
         }
     }
 
@@ -2862,6 +3337,33 @@ impl Game {
         return b / peak_height;
     }
 
+    //Stars start showing once the ambient brightness drops under this
+    pub const STAR_FADE_START: f32 = 0.6;
+
+    //0 for the whole day, ramping to 1 at the darkest the night gets (ambient is clamped to 0.08)
+    pub fn star_opacity(ambient: f32) -> f32 {
+        ((Self::STAR_FADE_START - ambient) / (Self::STAR_FADE_START - 0.08)).clamp(0.0, 1.0)
+    }
+
+    //Where the sun is for a time of day. It rises at the sunrise peak, is overhead at the
+    //brightness peak (half the day) and sets at the sunset peak, then swings under for the night.
+    pub fn sun_direction(timeofday: f32, daylength: f32) -> Vec3 {
+        let t = (timeofday / daylength).rem_euclid(1.0);
+        let (rise, noon, set) = (1.0 / 6.0, 0.5, 0.75);
+
+        let angle = if t >= rise && t < noon {
+            (t - rise) / (noon - rise) * consts::FRAC_PI_2
+        } else if t >= noon && t < set {
+            consts::FRAC_PI_2 + (t - noon) / (set - noon) * consts::FRAC_PI_2
+        } else {
+            let night = if t >= set { t - set } else { t + 1.0 - set };
+            consts::PI + night / (1.0 - set + rise) * consts::PI
+        };
+
+        //Tilted a little toward +z so it doesn't pass straight through the zenith
+        Vec3::new(angle.cos(), angle.sin(), 0.25).normalize()
+    }
+
     pub fn load_my_inv_from_file(&self) {
         let table_name = "invs";
 
@@ -2953,28 +3455,235 @@ impl Game {
         }
     }
 
+    pub fn player_state_path(&self) -> String {
+        match CHOSEN_SAVE_SLOT.lock().as_ref() {
+            Some(slot) => slot.player_state_path(SAVES_DIR),
+            None => PLAYER_STATE_PATH.to_string()
+        }
+    }
+
+    //Points the chosen slot at the current planet and stamps it as played, returning its folder
+    fn touch_save_slot(&self) -> Option<String> {
+        let mut chosen = CHOSEN_SAVE_SLOT.lock();
+        let slot = chosen.as_mut()?;
+        //Flying to another planet changes both, so the slot follows wherever the player ended up
+        slot.seed = unsafe { CURRSEED.load(Ordering::Relaxed) };
+        slot.planet_type = self.chunksys.read().planet_type;
+        slot.touch(SAVES_DIR);
+        Some(slot.path(SAVES_DIR))
+    }
+
+    //Writes the world edits, chests, player and slot metadata back to the chosen save slot
+    pub fn save_singleplayer(&mut self) {
+        if self.vars.in_multiplayer {
+            return;
+        }
+        //Don't let a running autosave write the same region files at the same time
+        if let Some(handle) = self.autosave_thread.take() {
+            if handle.join().is_err() {
+                info!("The autosave thread panicked, saving anyway");
+            }
+        }
+        if let Some(path) = self.touch_save_slot() {
+            self.chunksys.read().save_current_world_to_file(path);
+            self.save_current_chests_to_file();
+        }
+        self.save_player_state(&self.player_state_path());
+        self.autosave_saved_edits = self.chunksys.read().user_edits.load(Ordering::Relaxed);
+    }
+
+    //Manual save from the key or the menu, the server owns the world in multiplayer
+    pub fn save_world(&mut self) {
+        if self.vars.in_multiplayer {
+            info!("Not saving, the server keeps the world in multiplayer");
+            return;
+        }
+        let start = Instant::now();
+        self.save_singleplayer();
+        info!("Saved world in {:?}", start.elapsed());
+        self.saved_indicator_timer = 2.0;
+    }
+
+    //Same as save_singleplayer but the world itself is written on another thread
+    pub fn autosave(&mut self) {
+        if self.is_autosaving() {
+            return;
+        }
+        self.autosave_timer = 0.0;
+        self.autosave_saved_edits = self.chunksys.read().user_edits.load(Ordering::Relaxed);
+
+        if let Some(path) = self.touch_save_slot() {
+            self.save_current_chests_to_file();
+            let csys = self.chunksys.clone();
+            self.autosave_thread = Some(thread::spawn(move || {
+                let start = Instant::now();
+                csys.read().save_current_world_to_file(path);
+                info!("Autosaved in {:?}", start.elapsed());
+            }));
+            self.saving_indicator_timer = 1.5;
+        }
+        self.save_player_state(&self.player_state_path());
+    }
+
+    pub fn is_autosaving(&self) -> bool {
+        self.autosave_thread.as_ref().map_or(false, |h| !h.is_finished())
+    }
+
+    pub fn show_saving_indicator(&self) -> bool {
+        self.saving_indicator_timer > 0.0 || self.is_autosaving()
+    }
+
+    //Share of the chunks around the player that are loaded, the loading screen stays up until this hits 1
+    pub fn loading_progress(&self) -> f32 {
+        let pos = self.camera.lock().position;
+        let csys = self.chunksys.read();
+        let cpos = csys.spot_to_chunk_pos(&IVec3::new(pos.x.floor() as i32, pos.y.floor() as i32, pos.z.floor() as i32));
+        csys.loaded_fraction(cpos)
+    }
+
+    pub fn loading_screen_lines(&self) -> Vec<String> {
+        let planet = self.chunksys.read().planet_type as u32;
+        vec![
+            format!("Loading world... {}%", (self.loading_progress() * 100.0) as i32),
+            format!("Seed {} - {}", unsafe { CURRSEED.load(Ordering::Relaxed) }, Planets::get_name(planet)),
+        ]
+    }
+
+    //Swaps whatever menu is up for the failure and a way out, the world isn't drawn again after this
+    pub fn show_gl_failure(&mut self, message: &str) {
+        if self.currentbuttons.first().map_or(false, |(_, command)| command == "glfailure") {
+            return;
+        }
+        self.currentbuttons = vec![
+            (message.to_string(), "glfailure".to_string()),
+            ("Quit Game".to_string(), "quittomainmenu".to_string()),
+        ];
+        self.vars.menu_open = true;
+        #[cfg(feature = "glfw")]
+        self.set_cursor_mode(glfw::CursorMode::Normal);
+        self.set_mouse_focused(false);
+    }
+
+    //The little box in the bottom right corner, empty when there's nothing to say
+    pub fn status_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.game_mode == GameMode::Creative {
+            lines.push(self.game_mode.name().to_string());
+        }
+        if self.show_saving_indicator() {
+            lines.push("Saving...".to_string());
+        } else if self.saved_indicator_timer > 0.0 {
+            lines.push("World Saved".to_string());
+        }
+        lines
+    }
+
+    //Creative turns flying on, going back to survival drops you out of the air
+    pub fn set_game_mode(&mut self, mode: GameMode) {
+        if mode == self.game_mode {
+            return;
+        }
+        if !mode.allowed(self.vars.in_multiplayer) {
+            info!("{} isn't allowed in multiplayer", mode.name());
+            return;
+        }
+        self.game_mode = mode;
+        self.reach = mode.reach();
+        info!("Game mode: {}", mode.name());
+        if self.vars.fly != (mode == GameMode::Creative) {
+            self.toggle_fly();
+        }
+    }
+
+    fn tick_autosave(&mut self) {
+        self.saving_indicator_timer = (self.saving_indicator_timer - self.delta_time).max(0.0);
+        self.saved_indicator_timer = (self.saved_indicator_timer - self.delta_time).max(0.0);
+        self.autosave_timer += self.delta_time;
+
+        let edits = self.chunksys.read().user_edits.load(Ordering::Relaxed);
+        if edits != self.autosave_seen_edits {
+            self.autosave_seen_edits = edits;
+            self.autosave_quiet_timer = 0.0;
+        } else {
+            self.autosave_quiet_timer += self.delta_time;
+        }
+
+        if edits != self.autosave_saved_edits
+            && (self.autosave_timer >= self.autosave_interval
+                || self.autosave_quiet_timer >= AUTOSAVE_DEBOUNCE)
+        {
+            self.autosave();
+        }
+    }
+
+    pub fn save_player_state(&self, path: &str) {
+        let camlock = self.camera.lock();
+        let state = PlayerState {
+            position: PlayerPosition {
+                pos: PlayerVec { x: camlock.position.x, y: camlock.position.y, z: camlock.position.z },
+                pitch: camlock.pitch,
+                yaw: camlock.yaw
+            },
+            inv: self.inventory.read().inv,
+            health: self.health.load(Ordering::Relaxed),
+            slot: 0
+        };
+        drop(camlock);
+        #[cfg(feature = "glfw")]
+        let state = PlayerState { slot: self.hud.bumped_slot, ..state };
+
+        match state.save(path) {
+            Ok(_) => info!("Saved player state to {}", path),
+            Err(e) => info!("Couldn't save player state to {}: {}", path, e)
+        }
+    }
+
+    pub fn load_player_state(&mut self, path: &str) -> bool {
+        match PlayerState::load(path) {
+            Some(state) => {
+                let mut camlock = self.camera.lock();
+                camlock.position = Vec3::new(state.position.pos.x, state.position.pos.y, state.position.pos.z);
+                camlock.pitch = state.position.pitch;
+                camlock.yaw = state.position.yaw;
+                drop(camlock);
+
+                let mut invlock = self.inventory.write();
+                invlock.inv = state.inv;
+                invlock.dirty = true;
+                drop(invlock);
+
+                //A save made while dead would just kill you again on load
+                self.health.store(state.health.clamp(1.0, 20.0), Ordering::Relaxed);
+                #[cfg(feature = "glfw")]
+                {
+                    self.hud.bumped_slot = state.slot;
+                    self.update_inventory();
+                }
+                info!("Loaded player state from {}", path);
+                true
+            }
+            None => {
+                info!("No usable player state at {}, starting fresh", path);
+                false
+            }
+        }
+    }
+
     pub fn update(&mut self) {
         
+        //glfw is never initialised headless, so its clock would stand still
         #[cfg(feature = "glfw")]
-        {
+        if !self.headless {
             let current_time = unsafe { glfwGetTime() as f32 };
             self.delta_time = (current_time - self.prev_time).min(0.05);
             self.prev_time = current_time;
+        } else {
+            self.delta_time = instant_delta_time();
         }
 
         #[cfg(not(feature = "glfw"))]
-        unsafe {
-            static mut PREVTIME: Lazy<Instant> = Lazy::new(|| Instant::now());
-            let delta_time = match Instant::now().checked_duration_since(*PREVTIME) {
-                Some(time) => {
-                    time
-                }
-                None => {
-                    Duration::from_secs_f32(0.0)
-                }
-            };
-            self.delta_time = delta_time.as_secs_f32().min(0.05);
-            (*PREVTIME) = Instant::now();
+        {
+            self.delta_time = instant_delta_time();
         }
 
 
@@ -2982,14 +3691,20 @@ impl Game {
         
         let stam = self.stamina.load(Ordering::Relaxed);
 
+        if !self.vars.in_multiplayer && !self.headless && self.loadedworld.load(Ordering::Relaxed) {
+            self.tick_autosave();
+        }
+
+        //After flying to another planet the loading screen goes away by itself once the chunks are in
+        if self.vars.menu_open && self.currentbuttons.first().map_or(false, |(_, command)| command == "loading") && self.loading_progress() >= 1.0 {
+            self.button_command("closemenu".into());
+        }
+
         if unsafe { MOVING } {
             self.vars.walkbobtimer = self.vars.walkbobtimer + self.delta_time * 10.0;
             self.vars.walkbobtimer %= 2.0 * consts::PI;
         }
 
-        #[cfg(feature = "audio")]
-        self.update_music_volume();
-
         unsafe {
             if TRAMPOLINE {
                 TRAMP_TIMER += self.delta_time;
@@ -3029,6 +3744,8 @@ impl Game {
         if !self.headless {
             #[cfg(feature = "audio")]
             self.play_weather_sound();
+            #[cfg(feature = "audio")]
+            self.update_ambience();
         }
 
         unsafe {
@@ -3048,7 +3765,7 @@ impl Game {
             if sprintchecktimer > 0.2 {
                 sprintchecktimer = 0.0;
 
-                if self.controls.shift && !self.vars.in_climbable {
+                if self.controls.sprint && self.controls.forward && !self.vars.in_climbable && !self.vars.fly {
                     if stam > 0 {
                         unsafe {
                             SPRINTING = true;
@@ -3084,22 +3801,8 @@ impl Game {
             STAMINA = self.stamina.load(Ordering::Relaxed);
         }
 
-        let mut rng = StdRng::from_entropy();
-        if !self.vars.in_multiplayer {
-            unsafe {
-                if SONGTIMER < SONGINTERVAL {
-                    SONGTIMER += self.delta_time;
-                } else {
-                    SONGTIMER = 0.0;
-
-                    if !self.headless {
-                        SONGINDEX = (SONGINDEX + rng.gen_range(1..SONGS.len())) % SONGS.len();
-
-                        #[cfg(feature = "audio")]
-                        AUDIOPLAYER.play_in_head(SONGS[SONGINDEX]);
-                    }
-                }
-            }
+        if !self.headless {
+            self.update_music();
         }
 
         unsafe {
@@ -3107,8 +3810,20 @@ impl Game {
         }
 
 
+        let paused = self.is_paused();
+
         let mut todlock = self.timeofday.lock();
-        *todlock = (*todlock + self.delta_time) % self.daylength;
+        if self.vars.in_multiplayer {
+            if let Some(target) = self.server_timeofday.as_mut() {
+                //The server's clock keeps running between its updates, so the target does too
+                if !self.day_frozen {
+                    *target = Self::advance_clock(*target, self.daylength, self.delta_time);
+                }
+                *todlock = Self::approach_time_of_day(*todlock, *target, self.daylength, self.delta_time);
+            }
+        } else if !paused && !self.day_frozen {
+            *todlock = Self::advance_clock(*todlock, self.daylength, self.delta_time);
+        }
 
         let gaussian_value =
             Self::gaussian(*todlock, self.daylength / 2.0, self.daylength / 2.0) * 1.3;
@@ -3132,7 +3847,7 @@ impl Game {
 
         #[cfg(feature = "glfw")]
         if !self.headless {
-            let (x, y) = self.window.read().get_cursor_pos();
+            let (x, y) = self.window.as_ref().map_or((0.0, 0.0), |window| window.read().get_cursor_pos());
 
             unsafe {
                 MOUSEX = x;
@@ -3210,67 +3925,73 @@ impl Game {
             // }
 
 
+            if self.vars.in_multiplayer {
+                self.expire_pending_edits();
+                self.send_queued_edits();
+            }
+
             let mut morestuff = true;
             for _ in 0..5 {
                 match self.hp_server_command_queue.pop() {
                     Some(comm) => {
                         match comm.message_type {
                             MessageType::BlockSet => {
-                                if comm.infof == 1.0 {
-                                    if comm.info == 0 {
-                                        self.chunksys.read().set_block_and_queue_rerender(
-                                            IVec3::new(comm.x as i32, comm.y as i32, comm.z as i32),
-                                            comm.info,
-                                            true,
-                                            true,
-                                            false
-                                        );
-                                    } else {
-                                        self.chunksys.read().set_block_and_queue_rerender(
-                                            IVec3::new(comm.x as i32, comm.y as i32, comm.z as i32),
-                                            comm.info,
-                                            false,
-                                            true,
-                                            false
-                                        );
-                                    }
-                                } else {
-                                    if comm.info == 0 {
-                                        self.chunksys
-                                            .read()
-                                           
-                                            .set_block_and_queue_rerender_no_sound(
-                                                IVec3::new(
-                                                    comm.x as i32,
-                                                    comm.y as i32,
-                                                    comm.z as i32,
-                                                ),
+                                //Our own predicted edits are already on screen
+                                if !self.reconcile_block_set(&comm) {
+                                    //Other players' edits are heard where they happen
+                                    let from_other = *self.my_uuid.read() != Some(Uuid::from_u64_pair(comm.goose.0, comm.goose.1));
+                                    if comm.infof == 1.0 || from_other {
+                                        if comm.info == 0 {
+                                            self.chunksys.read().set_block_and_queue_rerender(
+                                                IVec3::new(comm.x as i32, comm.y as i32, comm.z as i32),
                                                 comm.info,
                                                 true,
                                                 true,
                                                 false
                                             );
-                                    } else {
-                                        self.chunksys
-                                            .read()
-                                       
-                                            .set_block_and_queue_rerender_no_sound(
-                                                IVec3::new(
-                                                    comm.x as i32,
-                                                    comm.y as i32,
-                                                    comm.z as i32,
-                                                ),
+                                        } else {
+                                            self.chunksys.read().set_block_and_queue_rerender(
+                                                IVec3::new(comm.x as i32, comm.y as i32, comm.z as i32),
                                                 comm.info,
                                                 false,
                                                 true,
                                                 false
                                             );
+                                        }
+                                    } else {
+                                        if comm.info == 0 {
+                                            self.chunksys
+                                                .read()
+                                           
+                                                .set_block_and_queue_rerender_no_sound(
+                                                    IVec3::new(
+                                                        comm.x as i32,
+                                                        comm.y as i32,
+                                                        comm.z as i32,
+                                                    ),
+                                                    comm.info,
+                                                    true,
+                                                    true,
+                                                    false
+                                                );
+                                        } else {
+                                            self.chunksys
+                                                .read()
+                                       
+                                                .set_block_and_queue_rerender_no_sound(
+                                                    IVec3::new(
+                                                        comm.x as i32,
+                                                        comm.y as i32,
+                                                        comm.z as i32,
+                                                    ),
+                                                    comm.info,
+                                                    false,
+                                                    true,
+                                                    false
+                                                );
+                                        }
                                     }
                                 }
-
-                                unsafe {
-                                    UPDATE_THE_BLOCK_OVERLAY = true;
-                                }
                             }
                             MessageType::MultiBlockSet => {
                                 let cread = self.chunksys.read();
@@ -3287,11 +4008,7 @@ impl Game {
                                     true,
                                     true,
                                     false
-                                );
-                                unsafe {
-                                    UPDATE_THE_BLOCK_OVERLAY = true;
-                                }
-                            }
+                                );                            }
                             MessageType::ChestReg => {
                                 self.load_my_inv_from_file();
                                 self.load_my_pos_from_file();
@@ -3395,26 +4112,51 @@ impl Game {
                             MessageType::RequestTakeoff => {
                                 self.takeoff_ship();
                             }
+                            MessageType::SetDayLength => {
+                                self.set_day_length(comm.infof);
+                            }
+                            MessageType::PlayerDied | MessageType::PlayerRespawned => {
+                                //Their avatar falls over on death and stands back up on respawn
+                                let uuid = Uuid::from_u64_pair(comm.goose.0, comm.goose.1);
+                                let roll = if comm.message_type == MessageType::PlayerDied { consts::FRAC_PI_2 } else { 0.0 };
+                                if let Some(mut me) = self.player_model_entities.get_mut(&uuid) {
+                                    me.value_mut().set_roll(roll, unsafe { glfwGetTime() });
+                                }
+                            }
+                            MessageType::MobHit => {
+                                let pos = Vec3::new(comm.x, comm.y, comm.z);
+                                if comm.infof <= 0.0 {
+                                    self.non_static_model_entities.remove(&comm.info);
+                                    //Only whoever landed the kill gets the drop
+                                    if *self.my_uuid.read() == Some(Uuid::from_u64_pair(comm.goose.0, comm.goose.1)) {
+                                        self.drop_mob_loot(pos, comm.info2 as usize);
+                                    }
+                                }
+                                #[cfg(feature = "audio")]
+                                unsafe {
+                                    AUDIOPLAYER.play("assets/sfx/slam.mp3", &pos, &Vec3::ZERO, 0.5);
+                                }
+                            }
                             MessageType::TimeUpdate => {
                                 //println!("Songindex: {}", unsafe { SONGINDEX });
-                                let mut todlock = self.timeofday.lock();
-                                *todlock = comm.infof;
+                                //The server's day length wins so everyone's sky lines up, 0 means its clock is frozen
+                                if comm.x > 0.0 {
+                                    self.daylength = comm.x;
+                                    self.day_frozen = false;
+                                } else {
+                                    self.day_frozen = true;
+                                }
+                                self.server_timeofday = Some(comm.infof);
                                 unsafe {
                                     WEATHERTYPE = comm.rot;
                                 }
                                 unsafe {
                                     let newsongindex = comm.info;
 
-                                    if SONGINDEX as u32 != newsongindex {
+                                    if SONGINDEX as u32 != newsongindex && (newsongindex as usize) < SONGS.len() {
                                         SONGINDEX = newsongindex as usize;
-
-                                        #[cfg(feature = "audio")]
-                                        for (name, sink) in &AUDIOPLAYER.headsinks {
-                                            sink.stop();
-                                        }
-
-                                        #[cfg(feature = "audio")]
-                                        AUDIOPLAYER.play_in_head(SONGS[SONGINDEX]);
+                                        let cmds = self.playlist.cue(SONGS[SONGINDEX]);
+                                        Self::run_music_commands(cmds);
                                     }
                                 }
                             }
@@ -3457,16 +4199,12 @@ impl Game {
                                 match nsme.get_mut(&id) {
                                     Some(mut me) => {
                                         let modent = me.value_mut();
-                                        (*modent).lastpos = (*modent).position.clone();
-                                        (*modent).position = newpos;
+                                        unsafe {
+                                            (*modent).receive_network_update(newpos, Vec3::new(0.0, rot, 0.0), glfwGetTime());
+                                        }
                                         (*modent).scale = scale;
-                                        (*modent).lastrot = (*modent).rot.clone();
-                                        (*modent).rot = Vec3::new(0.0, rot, 0.0);
                                         (*modent).sounding = sounding;
                                         (*modent).hostile = hostile;
-                                        unsafe {
-                                            (*modent).time_stamp = glfwGetTime();
-                                        }
                                     }
                                     None => {
                                         //info!("Received an update for a mob {} that doesn't exist. Creating it...", id);
@@ -3486,7 +4224,7 @@ impl Game {
                                 let newpos = Vec3::new(comm.x, comm.y, comm.z);
                                 //let id = comm.info;
                                 let modind = 0;
-                                let rot = comm.rot;
+                                let rot = received_yaw(comm.rot);
                                 let scale = 0.3;
                                 //let sounding  = comm.bo;
 
@@ -3498,15 +4236,11 @@ impl Game {
                                 match pme.get_mut(&uuid) {
                                     Some(mut me) => {
                                         let modent = me.value_mut();
-                                        (*modent).lastpos = (*modent).position.clone();
-                                        (*modent).position = newpos;
-                                        (*modent).scale = scale;
-                                        (*modent).lastrot = (*modent).rot.clone();
-                                        (*modent).rot = Vec3::new(0.0, rot, 0.0);
-                                        //(*modent).sounding = sounding;
                                         unsafe {
-                                            (*modent).time_stamp = glfwGetTime();
+                                            (*modent).receive_network_update(newpos, Vec3::new(0.0, rot, 0.0), glfwGetTime());
                                         }
+                                        (*modent).scale = scale;
+                                        //(*modent).sounding = sounding;
                                     }
                                     None => {
                                         info!("Received an update for a player {} that doesn't exist. Creating it...", uuid);
@@ -3568,7 +4302,7 @@ impl Game {
             }
             self.draw();
 
-            if !self.vars.ship_taken_off {
+            if !self.vars.ship_state.taken_off() {
                 self.draw_select_cube();
             }
 
@@ -3578,6 +4312,12 @@ impl Game {
 
             self.drops.update_and_draw_drops(&self.delta_time, &mvp);
 
+            let (right, up) = {
+                let camlock = self.camera.lock();
+                (camlock.right, camlock.up)
+            };
+            self.particles.update_and_draw(self.delta_time, self.vars.gravity, &mvp, right, up);
+
             self.hud.update();
             self.hud.draw();
 
@@ -3620,16 +4360,7 @@ impl Game {
             // }
 
             unsafe {
-                PLAYERPOS.pos.0.store(pos.x, Ordering::Relaxed);
-                PLAYERPOS.pos.1.store(pos.y, Ordering::Relaxed);
-                PLAYERPOS.pos.2.store(pos.z, Ordering::Relaxed);
-
-                PLAYERPOS.dir.0.store(dir.x, Ordering::Relaxed);
-                PLAYERPOS.dir.1.store(dir.y, Ordering::Relaxed);
-                PLAYERPOS.dir.2.store(dir.z, Ordering::Relaxed);
-
-                PLAYERPOS.yaw.store(yaw, Ordering::Relaxed);
-                PLAYERPOS.pitch.store(pitch, Ordering::Relaxed);
+                PLAYERPOS.store(pos, dir, yaw, pitch);
             }
             
 
@@ -3644,25 +4375,15 @@ impl Game {
                 self.update_inventory();
             }
 
-            if self.vars.ship_taken_off {
-                if !self.vars.on_new_world {
-                    if self.planet_y_offset > REQUIRED_SHIP_FLYAWAY_HEIGHT {
-                    } else {
-                        self.new_world_func();
-
-                        //self.audiop.play("assets/sfx/shipland28sec.mp3", &self.ship_pos, &Vec3::ZERO);
+            let (ship_state, planet_y_offset, load_new_world) =
+                self.vars.ship_state.step(self.planet_y_offset, self.delta_time);
+            if load_new_world {
+                self.new_world_func();
 
-                        self.vars.on_new_world = true;
-                        self.vars.ship_going_down = true;
-                        self.vars.ship_going_up = false;
-                    }
-                } else {
-                    if self.planet_y_offset >= 0.0 {
-                        self.vars.ship_going_down = false;
-                        self.vars.ship_taken_off = false;
-                    }
-                }
+                //self.audiop.play("assets/sfx/shipland28sec.mp3", &self.ship_pos, &Vec3::ZERO);
             }
+            self.vars.ship_state = ship_state;
+            self.planet_y_offset = planet_y_offset;
 
             // let camlock = self.camera.lock();
             // let shipdist = camlock.position.distance(self.ship_pos);
@@ -3674,16 +4395,6 @@ impl Game {
             // }
             // drop(camlock);
 
-            let planet_speed = -self.planet_y_offset.clamp(-100.0, -0.5);
-
-            if self.vars.ship_going_down {
-                self.planet_y_offset =
-                    (self.planet_y_offset + self.delta_time * planet_speed).clamp(-1000.0, 0.0);
-            }
-            if self.vars.ship_going_up {
-                self.planet_y_offset =
-                    (self.planet_y_offset - self.delta_time * planet_speed).clamp(-1000.0, 0.0);
-            }
         }
 
 
@@ -3701,18 +4412,27 @@ impl Game {
             if self.headless {
                 //println!("Headless so updating nsmes");
                 self.update_non_static_model_entities();
+                self.chunksys.read().update_fluids();
             } else {
                 if !self.vars.in_multiplayer {
                     //println!("Singleplayer so updating nsmes");
-                    self.update_non_static_model_entities();
+                    if !paused {
+                        self.update_non_static_model_entities();
+                    }
                 } else {
                     //YOu are in multiplayer
                     //println!("MUltiplayer so aug updating nsmes");
                     self.update_server_received_modents();
                 }
-                if overlayfade <= 0.1 {
+                if overlayfade <= 0.1 && !paused {
 
                             self.update_movement_and_physics();
+                            self.update_head_bob();
+
+                            let y = self.camera.lock().position.y;
+                            if !self.vars.dead && !self.vars.noclip && Game::fell_into_void(y) {
+                                self.die();
+                            }
 
                     
                     
@@ -3725,6 +4445,43 @@ impl Game {
 
     
 
+    //Advances with ground actually covered rather than keys held, so walking into a wall doesn't bob
+    pub fn update_head_bob(&mut self) {
+        const STRIDE_LENGTH: f32 = 1.8;
+        const MIN_BOB_SPEED: f32 = 0.5;
+
+        let amplitude = unsafe { MISCSETTINGS.head_bob };
+        let mut camlock = self.camera.lock();
+
+        let moved = (camlock.position - self.vars.head_bob_last_pos) * Vec3::new(1.0, 0.0, 1.0);
+        self.vars.head_bob_last_pos = camlock.position;
+        //Teleports and respawns shouldn't spin the phase
+        let distance = moved.length().min(1.0);
+        let speed = if self.delta_time > 0.0 { distance / self.delta_time } else { 0.0 };
+
+        let bobbing = amplitude > 0.0
+            && self.grounded
+            && !self.inwater
+            && !self.vars.fly
+            && speed > MIN_BOB_SPEED;
+
+        if bobbing {
+            self.vars.head_bob_phase = (self.vars.head_bob_phase
+                + distance / STRIDE_LENGTH * 2.0 * consts::PI)
+                % (2.0 * consts::PI);
+            camlock.bob_offset = Camera::head_bob_offset(self.vars.head_bob_phase, amplitude, camlock.right);
+        } else {
+            //Settle back to level instead of snapping
+            let settle = (self.delta_time * 10.0).min(1.0);
+            camlock.bob_offset = camlock.bob_offset.lerp(Vec3::ZERO, settle);
+            if camlock.bob_offset.length() < 0.001 {
+                camlock.bob_offset = Vec3::ZERO;
+                self.vars.head_bob_phase = 0.0;
+            }
+        }
+        camlock.recalculate();
+    }
+
     pub fn update_movement_and_physics(&mut self) {
 
         static mut NUDM: Lazy<Arc<DashMap<IVec3, u32>>> = Lazy::new(|| Arc::new(DashMap::new()));
@@ -3732,6 +4489,10 @@ impl Game {
         static mut PERL: Lazy<Arc<RwLock<Perlin>>> = Lazy::new(|| Arc::new(RwLock::new(Perlin::new(0))));
         static mut hasbeenset: bool = false;
 
+        let (cavefreq, orespecs, octaves, planettype) = {
+            let cr = self.chunksys.read();
+            (cr.cave_frequency, cr.ore_specs.clone(), cr.terrain_octaves, cr.planet_type)
+        };
 
         unsafe {
             let cr = self.chunksys.read();
@@ -3816,20 +4577,23 @@ impl Game {
        
         
 
-        let blockfeetin = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), feetposi) & Blocks::block_id_bits()};
+        let blockfeetin = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), cavefreq, &orespecs, octaves, planettype, feetposi) & Blocks::block_id_bits()};
         let blockfeetinlower = unsafe {
-        ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), feetposi2) & Blocks::block_id_bits()};
-        let blockbitsunderfeet = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), underfeetposi) };
+        ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), cavefreq, &orespecs, octaves, planettype, feetposi2) & Blocks::block_id_bits()};
+        let blockbitsunderfeet = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), cavefreq, &orespecs, octaves, planettype, underfeetposi) };
         let blockunderfeet = blockbitsunderfeet & Blocks::block_id_bits();
        // println!("BUF: {}", blockunderfeet);
 
-        let blockheadin = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), headposi) & Blocks::block_id_bits() };
+        let blockheadin = unsafe { ChunkSystem::_blockat(&NUDM, &UDM, &PERL.read(), cavefreq, &orespecs, octaves, planettype, headposi) & Blocks::block_id_bits() };
 
-        if blockheadin == 2 {
-            self.headinwater = true;
-        } else {
-            self.headinwater = false;
+        let headinwater = blockheadin == 2;
+        if headinwater != self.headinwater {
+            #[cfg(feature = "audio")]
+            unsafe {
+                AUDIOPLAYER.set_muffled(headinwater);
+            }
         }
+        self.headinwater = headinwater;
 
         static mut wasconveyor: bool = false;
 
@@ -3944,15 +4708,37 @@ impl Game {
         } else {
         }
 
-        const GRAV: f32 = 9.8;
+        //Sprint cancels when you stop going forward or get in water
+        if self.controls.sprint && (!self.controls.forward || self.inwater) {
+            self.controls.sprint = false;
+            unsafe {
+                SPRINTING = false;
+            }
+        }
+
+        let grav = self.vars.gravity;
+        //Lower gravity lets the same jump carry higher, heavier keeps it short
+        let jump_height = self.allowable_jump_height * (HOME_GRAVITY / grav).sqrt();
+        const FLY_VERTICAL_SPEED: f32 = 10.0;
+        const SAFE_FALL_HEIGHT: f32 = 4.0;
 
-        if self.inwater || self.vars.in_climbable {
+        if self.vars.fly {
+            self.time_falling_scalar = 1.0;
+            self.vars.fall_peak_y = cam_clone.position.y;
+            self.jumping_up = false;
+
+            if self.controls.up {
+                cam_clone.velocity += Vec3::new(0.0, FLY_VERTICAL_SPEED * self.delta_time, 0.0);
+            }
+            if self.controls.shift {
+                cam_clone.velocity += Vec3::new(0.0, -FLY_VERTICAL_SPEED * self.delta_time, 0.0);
+            }
+        } else if self.inwater || self.vars.in_climbable {
 
             unsafe {
                 if WASFREEFALLING {
                     FREEFALLING = false;
                     WASFREEFALLING = false;
-                    self.vars.time_tfs_at_3 = 0.0;
                     self.time_falling_scalar = 1.0;
 
                     #[cfg(feature = "audio")]
@@ -3965,6 +4751,8 @@ impl Game {
                 
             }
             self.time_falling_scalar = 1.0;
+            //Water and ladders break the fall
+            self.vars.fall_peak_y = cam_clone.position.y;
             if !self.grounded {
                 cam_clone.velocity += Vec3::new(0.0, -2.0 * self.delta_time, 0.0);
                 if unsafe {CROUCHING} {
@@ -4004,12 +4792,6 @@ impl Game {
                             AUDIOPLAYER.play_in_head("assets/sfx/freefall.mp3");
                         }
                     }
-                    
-                    
-                    self.vars.time_tfs_at_3 += self.delta_time;
-                } else {
-                    self.vars.time_tfs_at_3 = 0.0;
-                    
                 }
                     //println!("Time falscal: {}", self.time_falling_scalar);
             } else {
@@ -4026,17 +4808,24 @@ impl Game {
                 }
             }
 
+            if self.grounded {
+                self.vars.fall_peak_y = cam_clone.position.y;
+                self.vars.last_ground = Some(cam_clone.position);
+            } else {
+                self.vars.fall_peak_y = self.vars.fall_peak_y.max(cam_clone.position.y);
+            }
+
             if !self.grounded && !self.jumping_up {
                 cam_clone.velocity +=
-                    Vec3::new(0.0, -GRAV * self.time_falling_scalar * self.delta_time, 0.0);
+                    Vec3::new(0.0, -grav * self.time_falling_scalar * self.delta_time, 0.0);
             }
 
             if self.jumping_up {
-                if cam_clone.position.y < self.current_jump_y + self.allowable_jump_height {
+                if cam_clone.position.y < self.current_jump_y + jump_height {
                     let curr_cam_y = cam_clone.position.y;
                     cam_clone.velocity += Vec3::new(
                         0.0,
-                        (((self.current_jump_y + self.allowable_jump_height + 0.3) - curr_cam_y)
+                        (((self.current_jump_y + jump_height + 0.3) - curr_cam_y)
                             * 15.0)
                             * self.delta_time,
                         0.0,
@@ -4061,8 +4850,12 @@ impl Game {
             (*camlock) = cam_clone;
 
             let proposed = unsafe {
-                if CROUCHING && self.grounded {
+                if self.vars.fly {
+                    camlock.respond_to_controls(&self.controls, &self.delta_time, 11.0)
+                } else if CROUCHING && self.grounded {
                     camlock.respond_to_controls(&self.controls, &self.delta_time, 1.5)
+                } else if SPRINTING {
+                    camlock.respond_to_controls(&self.controls, &self.delta_time, 5.5 * self.vars.sprint_multiplier)
                 } else {
                     camlock.respond_to_controls(&self.controls, &self.delta_time, 5.5)
                 }
@@ -4094,34 +4887,34 @@ impl Game {
             }
         }
 
-        self.user_bound_box
-            .set_center(proposed + Vec3::new(0.0, -0.5, 0.0), 0.2, 0.95);
-        self.coll_cage.update_colliding(&self.user_bound_box);
-
-        let mut corr_made: Vec<Vec3> = Vec::new();
-
         let mut stepsoundqueued = false;
         let mut activate_jump_queued = false;
         let mut falldamage = None;
 
-        if self.coll_cage.colliding.len() > 0 {
-            for side in &self.coll_cage.colliding {
-                if !corr_made.contains(&self.coll_cage.normals[*side as usize]) {
-                    proposed += self.coll_cage.normals[*side as usize]
-                        * self.coll_cage.penetrations[*side as usize];
-                    corr_made.push(self.coll_cage.normals[*side as usize]);
-                }
+        //Noclip only takes effect while flying
+        let noclipping = self.vars.fly && self.vars.noclip;
+
+        if !noclipping {
+            let (resolved, hit) = self.coll_cage.sweep(
+                &mut self.user_bound_box,
+                cam_clone.position,
+                proposed,
+                (0.2, 0.95),
+                Vec3::new(0.0, -0.5, 0.0),
+                Vec3::new(0.0, -1.0, 0.0),
+            );
+            proposed = resolved;
+
+            for side in &hit {
                 if *side == Side::FLOOR {
                     self.grounded = true;
                     unsafe {
                         if wasngrounded {
-                            if self.vars.time_tfs_at_3 > 0.0 {
-                                falldamage = Some(self.vars.time_tfs_at_3);
+                            let fallen = self.vars.fall_peak_y - proposed.y;
+                            if fallen > SAFE_FALL_HEIGHT && !self.inwater {
+                                falldamage = Some(fallen - SAFE_FALL_HEIGHT);
                             }
-                            
-                            
-                            
-                            self.vars.time_tfs_at_3 = 0.0;
+                            self.vars.fall_peak_y = proposed.y;
                             activate_jump_queued = true;
                             stepsoundqueued = true;
                             wasngrounded = false;
@@ -4133,6 +4926,8 @@ impl Game {
                     self.grounded = false;
                 }
             }
+
+            proposed = self.push_player_out_of_mobs(proposed);
         }
 
         cam_clone.position = Vec3::new(proposed.x, proposed.y, proposed.z);
@@ -4170,7 +4965,8 @@ impl Game {
                     #[cfg(feature = "audio")]
                     AUDIOPLAYER.play_in_head("assets/sfx/falldamage.mp3");
                 }
-                self.take_damage((fd*20.0) as u8);
+                //One point of damage per block fallen past the safe height
+                self.take_damage(fd);
             }
             None => {
 
@@ -4178,45 +4974,298 @@ impl Game {
         }
     }
 
-    pub fn take_damage(&mut self, amount: u8) {
+    //Text for the F3 overlay
+    pub fn debug_overlay_lines(&self) -> Vec<String> {
+        let cam = self.camera.lock().clone();
+        let csys = self.chunksys.read();
+        let cpos = csys.spot_to_chunk_pos(&IVec3::new(
+            cam.position.x.floor() as i32,
+            cam.position.y.floor() as i32,
+            cam.position.z.floor() as i32,
+        ));
+        drop(csys);
+
+        let looking_at = match raycast_voxel_with_bob(
+            cam.position,
+            cam.direction,
+            &self.chunksys,
+            self.reach,
+            self.vars.walkbobtimer,
+        ) {
+            Some((_head, hit, _normal)) => {
+                let id = self.chunksys.read().blockat(hit) & Blocks::block_id_bits();
+                format!("Looking at: {} ({}) at {} {} {}", Blocks::get_name(id), id, hit.x, hit.y, hit.z)
+            }
+            None => "Looking at: nothing".to_string(),
+        };
+
+        let (drawn, culled) = unsafe { (CHUNKS_DRAWN, CHUNKS_CULLED) };
+
+        vec![
+            format!("FPS: {:.0}", 1.0 / self.delta_time.max(0.0001)),
+            format!("Position: {:.2} {:.2} {:.2}", cam.position.x, cam.position.y, cam.position.z),
+            format!("Chunk: {} {}", cpos.x, cpos.y),
+            looking_at,
+            format!("Chunks drawn: {} culled: {}", drawn, culled),
+        ]
+    }
+
+    //Singleplayer stops the world under the menu, multiplayer keeps going with the server
+    pub fn is_paused(&self) -> bool {
+        self.vars.menu_open && !self.vars.in_multiplayer && !self.headless
+    }
+
+    //Resting FOV, the sprint widening stays 3 degrees above it
+    pub fn set_base_fov(&mut self, fov: f32) {
+        let fov = fov.clamp(MIN_FOV, MAX_FOV);
+        unsafe { MISCSETTINGS.fov = fov };
+        let mut faders = self.faders.write();
+        let fader = &mut faders[FaderNames::FovFader as usize];
+        fader.bottom = fov;
+        fader.top = fov + 3.0;
+    }
+
+    //Keeps the sun at the same point in the day when the length changes.
+    //A length of 0 freezes the clock and keeps the old length so the brightness math still works.
+    pub fn set_day_length(&mut self, length: f32) {
+        if length <= 0.0 {
+            self.day_frozen = true;
+            info!("Day/night cycle frozen");
+            return;
+        }
+        self.day_frozen = false;
+        let mut todlock = self.timeofday.lock();
+        *todlock = *todlock / self.daylength * length;
+        self.daylength = length;
+    }
+
+    //Fog distance for the shaders, 8.0 at the old fixed radius of 10
+    pub fn view_distance(&self) -> f32 {
+        self.chunksys.read().radius as f32 * 0.8
+    }
+
+    pub fn set_render_distance(&mut self, radius: u8) {
+        let cw = self.chunksys.read().chunk_width as f32;
+        let campos = self.camera.lock().position;
+        let center = IVec2 {
+            x: (campos.x / cw).floor() as i32,
+            y: (campos.z / cw).floor() as i32,
+        };
+        self.chunksys.write().set_radius(radius, center);
+        self.update_far_plane();
+    }
+
+    //Far plane just past the loaded chunks so fog, not clipping, is what hides the edge
+    pub fn update_far_plane(&self) {
+        let far = {
+            let cs = self.chunksys.read();
+            Camera::far_for_render_distance(cs.radius, cs.chunk_width, cs.chunk_height)
+        };
+        self.camera.lock().set_far(far);
+    }
+
+    //Fog color for the current planet, tinted blue while the camera is underwater
+    pub fn current_fog_col(&self) -> (f32, f32, f32, f32) {
+        let fc = Planets::get_fog_col(self.chunksys.read().planet_type as u32);
+        if self.headinwater {
+            let wc = Planets::get_underwater_fog_col();
+            let t = 0.8;
+            (
+                fc.0 + (wc.0 - fc.0) * t,
+                fc.1 + (wc.1 - fc.1) * t,
+                fc.2 + (wc.2 - fc.2) * t,
+                fc.3,
+            )
+        } else {
+            fc
+        }
+    }
+
+    pub fn toggle_fly(&mut self) {
+        self.vars.fly = !self.vars.fly;
+        info!("Fly mode: {}", self.vars.fly);
+
+        //Start falling again from rest instead of carrying the flight velocity
+        self.jumping_up = false;
+        self.time_falling_scalar = 1.0;
+        self.vars.fall_peak_y = self.camera.lock().position.y;
+        unsafe {
+            FREEFALLING = false;
+            if WASFREEFALLING {
+                WASFREEFALLING = false;
+                #[cfg(feature = "audio")]
+                AUDIOPLAYER.stop_head_sound("assets/sfx/freefall.mp3".to_string());
+            }
+        }
+        if !self.vars.fly {
+            self.camera.lock().velocity.y = 0.0;
+        }
+    }
+
+    pub fn take_damage(&mut self, amount: f32) {
+        if self.vars.dead {
+            return;
+        }
+        self.combat_timer = COMBAT_LINGER_SECONDS;
         let h = self.health.load(std::sync::atomic::Ordering::Relaxed);
-        let newamount = (h-amount as i8).max(0);
+        let newamount = (h - amount).max(0.0);
         self.health.store(newamount, std::sync::atomic::Ordering::Relaxed);
-        if newamount <= 0 { //DEAD
+        if newamount <= 0.0 { //DEAD
+            self.die();
+        }
+        
+    }
+
+    fn update_music(&mut self) {
+        self.combat_timer = (self.combat_timer - self.delta_time).max(0.0);
+        let planet = self.chunksys.read().planet_type;
+        let (night_volume, combat_volume) = Planets::get_music_mood(planet as u32);
+        let night = Self::star_opacity(self.ambient_bright_mult);
+        self.playlist.set_mood_target(mood_volume(night, self.combat_timer > 0.0, night_volume, combat_volume));
+
+        let cmds = self.playlist.set_planet(planet);
+        Self::run_music_commands(cmds);
+
+        #[cfg(feature = "audio")]
+        let finished = self.playlist.current_track().map_or(false, |t| unsafe { AUDIOPLAYER.head_sound_finished(t) });
+        #[cfg(not(feature = "audio"))]
+        let finished = false;
+        let cmds = self.playlist.advance(self.delta_time, finished);
+        Self::run_music_commands(cmds);
+
+        #[cfg(feature = "audio")]
+        for (track, scale) in self.playlist.volumes() {
+            unsafe {
+                AUDIOPLAYER.set_head_scale(&track, scale);
+            }
+        }
+    }
 
+    pub fn run_music_commands(cmds: Vec<MusicCommand>) {
+        #[cfg(feature = "audio")]
+        for cmd in cmds {
             unsafe {
-                #[cfg(feature = "audio")]
-                AUDIOPLAYER.play_in_head("assets/sfx/death.mp3");
+                match cmd {
+                    MusicCommand::Play(track) => {
+                        //Starts silent, the crossfade brings it up
+                        AUDIOPLAYER.set_head_scale(&track, 0.0);
+                        AUDIOPLAYER.play_music(track);
+                    }
+                    MusicCommand::Stop(track) => AUDIOPLAYER.stop_head_sound(track),
+                    MusicCommand::Pause(track) => AUDIOPLAYER.pause_head_sound(&track),
+                    MusicCommand::Resume(track) => AUDIOPLAYER.resume_head_sound(&track),
+                }
             }
+        }
+        #[cfg(not(feature = "audio"))]
+        let _ = cmds;
+    }
+
+    pub fn advance_clock(time: f32, daylength: f32, delta_time: f32) -> f32 {
+        (time + delta_time).rem_euclid(daylength)
+    }
+
+    //Eases toward the server's clock the short way around midnight
+    pub fn approach_time_of_day(current: f32, target: f32, daylength: f32, delta_time: f32) -> f32 {
+        let half = daylength / 2.0;
+        let diff = (target - current + half).rem_euclid(daylength) - half;
+        if diff.abs() > TIME_SNAP_SECONDS {
+            return target.rem_euclid(daylength);
+        }
+        (current + diff * (TIME_SYNC_RATE * delta_time).min(1.0)).rem_euclid(daylength)
+    }
+
+    pub fn fell_into_void(y: f32) -> bool {
+        y < VOID_DEATH_Y
+    }
+
+    //Dropped items land a little above where you died, unless that's the void, then back on the last ground or the spawn
+    pub fn death_drop_spot(died_at: Vec3, last_ground: Option<Vec3>, spawn: Vec3) -> Vec3 {
+        let base = if Self::fell_into_void(died_at.y) {
+            last_ground.unwrap_or(spawn)
+        } else {
+            died_at
+        };
+        base + Vec3::new(0.0, 2.0, 0.0)
+    }
+
+    //Drops the inventory where you fell and puts up the respawn menu
+    pub fn die(&mut self) {
+        if self.vars.dead {
+            return;
+        }
+        self.vars.dead = true;
+        self.health.store(0.0, std::sync::atomic::Ordering::Relaxed);
+
+        unsafe {
+            #[cfg(feature = "audio")]
+            AUDIOPLAYER.play_in_head("assets/sfx/death.mp3");
+        }
+        let campos = {
             let mut camlock = self.camera.lock();
-            let campos = camlock.position.clone();
+            camlock.velocity = Vec3::ZERO;
+            camlock.position
+        };
 
+        #[cfg(feature = "glfw")]
+        let dropspot = Self::death_drop_spot(campos, self.vars.last_ground, unsafe { SPAWNPOINT });
+        {
             let mut inv = self.inventory.write();
             for i in 0..ROWLENGTH {
                 let amt = inv.inv[i as usize].1;
                 #[cfg(feature = "glfw")]
-                self.drops.add_drop(campos + Vec3::new(0.0, 2.0, 0.0), inv.inv[i as usize].0, amt);
-                
-                
+                self.drops.add_drop(dropspot, inv.inv[i as usize].0, amt);
             }
             inv.inv = STARTINGITEMS;
+        }
+        #[cfg(feature = "glfw")]
+        self.update_inventory();
+
+        if self.vars.in_multiplayer {
+            self.netconn.send(&Message::new(MessageType::PlayerDied, campos, 0.0, 0));
+        }
 
+        self.controls.clear();
+        self.vars.mouse_clicked = false;
+        self.vars.right_mouse_clicked = false;
+        self.currentbuttons = vec![
+            ("Respawn".to_string(), "respawn".to_string()),
+            ("Quit Game".to_string(), "quittomainmenu".to_string()),
+        ];
+        self.vars.menu_open = true;
+        #[cfg(feature = "glfw")]
+        self.set_cursor_mode(glfw::CursorMode::Normal);
+        self.set_mouse_focused(false);
+    }
 
-            
+    //Back to the ship with full health
+    pub fn respawn(&mut self) {
+        if !self.vars.dead {
+            return;
+        }
+        {
+            let mut camlock = self.camera.lock();
             unsafe {
                 camlock.position = SPAWNPOINT;
                 camlock.velocity = Vec3::ZERO;
+                self.vars.fall_peak_y = SPAWNPOINT.y;
             }
-            
-            drop(camlock);
-            self.health.store(20, std::sync::atomic::Ordering::Relaxed);
         }
-        
+        self.health.store(20.0, std::sync::atomic::Ordering::Relaxed);
+        self.vars.dead = false;
+
+        if self.vars.in_multiplayer {
+            self.netconn.send(&Message::new(MessageType::PlayerRespawned, Vec3::ZERO, 0.0, 0));
+        }
+        self.button_command("closemenu".into());
     }
 
 
     #[cfg(feature = "glfw")]
     pub fn draw_sky(&self, top: Vec4, bot: Vec4, amb: f32, pitch: f32) {
+        if self.headless {
+            return;
+        }
         //Sky
         unsafe {
             gl::BindVertexArray(self.skyshader.vao);
@@ -4231,6 +5280,7 @@ impl Game {
         static mut S_R_LOC: i32 = 0;
         static mut C_D_LOC: i32 = 0;
         static mut P_Y_LOC: i32 = 0;
+        static mut S_D_LOC: i32 = 0;
 
         unsafe {
             if T_C_LOC == -1 {
@@ -4266,6 +5316,10 @@ impl Game {
                     self.skyshader.shader_id,
                     b"planety\0".as_ptr() as *const i8,
                 );
+                S_D_LOC = gl::GetUniformLocation(
+                    self.skyshader.shader_id,
+                    b"sunDir\0".as_ptr() as *const i8,
+                );
             }
 
             let camlock = self.camera.lock();
@@ -4290,6 +5344,10 @@ impl Game {
 
             gl::Uniform1f(P_Y_LOC, self.planet_y_offset);
 
+            //The moon is drawn opposite this in the shader
+            let sundir = Self::sun_direction(*self.timeofday.lock(), self.daylength);
+            gl::Uniform3f(S_D_LOC, sundir.x, sundir.y, sundir.z);
+
             gl::DrawArrays(gl::TRIANGLES, 0, 3);
             gl::BindVertexArray(0);
             gl::Enable(gl::DEPTH_TEST);
@@ -4297,25 +5355,12 @@ impl Game {
     }
     #[cfg(feature = "glfw")]
     pub fn draw_select_cube(&mut self) {
-        static mut LAST_CAM_POS: Vec3 = Vec3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        };
-        static mut LAST_CAM_DIR: Vec3 = Vec3 {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        };
-        static mut LAST_BLOCK_POS: IVec3 = IVec3 { x: 0, y: 0, z: 0 };
-
-        static mut HIT_RESULT: Option<(Vec3, IVec3)> = None;
-
-        static mut BLOCK_TYPE: u32 = 0;
-
-        static mut BLOCK_MATERIAL: Material = Material::Dirt;
+        if self.headless {
+            return;
+        }
+        static mut HIT_RESULT: Option<(Vec3, IVec3, IVec3)> = None;
 
-        static mut BREAK_TIME: f32 = 0.0;
+        static mut BREAKING: BreakProgress = BreakProgress::new();
 
         let cam_clone = {
             let c = self.camera.lock();
@@ -4323,58 +5368,41 @@ impl Game {
         };
 
         unsafe {
-            if cam_clone.position != LAST_CAM_POS
-                || cam_clone.direction != LAST_CAM_DIR
-                || UPDATE_THE_BLOCK_OVERLAY
-            {
-                UPDATE_THE_BLOCK_OVERLAY = false;
-
-                LAST_CAM_POS = cam_clone.position;
-                LAST_CAM_DIR = cam_clone.direction;
-
-                HIT_RESULT = raycast_voxel_with_bob(
-                    cam_clone.position,
-                    cam_clone.direction,
-                    &self.chunksys,
-                    10.0,
-                    self.vars.walkbobtimer,
-                );
+            //Redone every frame, the block in front of you can change (or be broken) without the camera moving
+            HIT_RESULT = raycast_voxel_with_bob(
+                cam_clone.position,
+                cam_clone.direction,
+                &self.chunksys,
+                self.reach,
+                self.vars.walkbobtimer,
+            );
 
-                BLOCK_TYPE = match HIT_RESULT {
-                    Some((_head, hit)) => {
-                        if LAST_BLOCK_POS != hit {
-                            BREAK_TIME = 0.0;
-                            LAST_BLOCK_POS = hit;
-                        }
-                        self.chunksys.read().blockat(hit) & Blocks::block_id_bits()
-                    }
-                    None => 0,
-                };
+            let target = HIT_RESULT.map(|(_head, hit, _normal)| hit);
+            let block_type = match target {
+                Some(hit) => self.chunksys.read().blockat(hit) & Blocks::block_id_bits(),
+                None => 0,
+            };
+            BREAKING.aim(target, block_type);
 
-                BLOCK_MATERIAL = get_block_material(BLOCK_TYPE);
+            //Letting go starts the block over
+            if !self.vars.mouse_clicked {
+                BREAKING.release();
             }
 
             match HIT_RESULT {
-                Some((_head, hit)) => {
+                Some((_head, hit, _normal)) => {
                     let hitvec3 = Vec3::new(hit.x as f32, hit.y as f32, hit.z as f32);
                     self.select_cube
                         .draw_at(hitvec3, &cam_clone.mvp, self.vars.walkbobtimer);
-                    let bprog = (BREAK_TIME / Blocks::get_break_time(BLOCK_TYPE)).clamp(0.0, 1.0);
-
                     let slot_selected = self.hud.bumped_slot;
                     let slot = {
                         let b = self.inventory.read().inv[slot_selected];
                         b.clone()
                     };
 
-                    let tooltype = get_tools_target_material(slot.0);
-
-                    let tool_is_for_this_material = tooltype == BLOCK_MATERIAL;
-
-                    let mut modifier = 1.0;
-                    if tool_is_for_this_material {
-                        modifier = 4.0;
-                    }
+                    let break_time = Blocks::get_break_time(block_type) / get_tool_speed(slot.0, block_type);
+                    let creative = self.game_mode == GameMode::Creative;
+                    let bprog = if creative { 0.0 } else { BREAKING.progress(break_time) };
 
                     if self.vars.mouse_clicked && !self.crafting_open && !self.vars.menu_open {
                         self.block_overlay.draw_at(
@@ -4383,14 +5411,15 @@ impl Game {
                             &cam_clone.mvp,
                             self.vars.walkbobtimer,
                         );
-                        BREAK_TIME = BREAK_TIME + self.delta_time * modifier;
-                        if bprog >= 1.0 {
-
-                            if !self.vars.ship_taken_off {
+                        let broke = if creative {
+                            BREAKING.hold_repeating(self.delta_time, CREATIVE_BREAK_REPEAT)
+                        } else {
+                            BREAKING.hold(self.delta_time, break_time)
+                        };
+                        if broke {
+                            if !self.vars.ship_state.taken_off() {
                                 self.cast_break_ray();
-                                //UPDATE_THE_OVERLAY = true;
                             }
-                            BREAK_TIME = 0.0;
                         }
                     }
                 }
@@ -4405,6 +5434,9 @@ impl Game {
 
     #[cfg(feature = "glfw")]
     pub fn draw(&self) {
+        if self.headless {
+            return;
+        }
         let campitch = self.camera.lock().pitch;
 
         //Sky
@@ -4434,6 +5466,11 @@ impl Game {
             gl::UseProgram(self.shader0.shader_id);
         }
 
+        let (cw, ch) = {
+            let cs = self.chunksys.read();
+            (cs.chunk_width, cs.chunk_height)
+        };
+
         if true { //unsafe { GLCHUNKS } {
 
         
@@ -4461,7 +5498,7 @@ impl Game {
                 cmemlock.memories[ready.geo_index].tlength = ready.newtlength;
                 cmemlock.memories[ready.geo_index].vlength = ready.newvlength;
                 cmemlock.memories[ready.geo_index].wvlength = ready.newwvlength;
-                cmemlock.memories[ready.geo_index].pos = ready.newpos;
+                cmemlock.memories[ready.geo_index].set_pos(ready.newpos, cw, ch);
                 cmemlock.memories[ready.geo_index].used = true;
 
                 //info!("Received update to {} {} {} {}", ready.newlength, ready.newtlength, ready.newpos.x, ready.newpos.y);
@@ -4540,7 +5577,7 @@ impl Game {
                 cmemlock.memories[ready.geo_index].tlength = ready.newtlength;
                 cmemlock.memories[ready.geo_index].vlength = ready.newvlength;
                 cmemlock.memories[ready.geo_index].wvlength = ready.newwvlength;
-                cmemlock.memories[ready.geo_index].pos = ready.newpos;
+                cmemlock.memories[ready.geo_index].set_pos(ready.newpos, cw, ch);
                 cmemlock.memories[ready.geo_index].used = true;
 
                 //info!("Received update to {} {} {} {}", ready.newlength, ready.newtlength, ready.newpos.x, ready.newpos.y);
@@ -4617,7 +5654,7 @@ impl Game {
                             cmemlock.memories[ready.geo_index].tlength = ready.newtlength;
                             cmemlock.memories[ready.geo_index].vlength = ready.newvlength;
                             cmemlock.memories[ready.geo_index].wvlength = ready.newwvlength;
-                            cmemlock.memories[ready.geo_index].pos = ready.newpos;
+                            cmemlock.memories[ready.geo_index].set_pos(ready.newpos, cw, ch);
                             cmemlock.memories[ready.geo_index].used = true;
 
                             //info!("Received update to {} {} {} {}", ready.newlength, ready.newtlength, ready.newpos.x, ready.newpos.y);
@@ -4768,7 +5805,7 @@ impl Game {
                 cam_clone.position.z,
             );
             gl::Uniform1f(AMBIENT_BRIGHT_MULT_LOC, self.ambient_bright_mult);
-            gl::Uniform1f(VIEW_DISTANCE_LOC, 8.0);
+            gl::Uniform1f(VIEW_DISTANCE_LOC, self.view_distance());
             gl::Uniform1f(UNDERWATER_LOC, if self.headinwater { 1.0 } else { 0.0 });
             gl::Uniform1f(WALKBOB_LOC, self.vars.walkbobtimer);
             gl::Uniform3f(
@@ -4787,16 +5824,41 @@ impl Game {
                 ),
                 0,
             );
-            let fc = Planets::get_fog_col(self.chunksys.read().planet_type as u32);
+            let fc = self.current_fog_col();
             gl::Uniform4f(FOGCOL_LOC, fc.0, fc.1, fc.2, fc.3);
 
 
         }
 
+        //Pad the boxes a little, the shader moves vertices around for walk bob and planet takeoff
+        let planes = cam_clone.frustum_planes();
+        let pad = Vec3::new(2.0, 2.0 + self.planet_y_offset.abs(), 2.0);
+        let visible: Vec<bool> = {
+            let cs = self.chunksys.read();
+            let cmem = cs.chunk_memories.lock();
+            cmem.memories
+                .iter()
+                .map(|cfl| {
+                    cfl.used
+                        && Camera::aabb_in_frustum(&planes, cfl.bounds_min - pad, cfl.bounds_max + pad)
+                })
+                .collect()
+        };
+        unsafe {
+            CHUNKS_DRAWN = visible.iter().filter(|v| **v).count();
+            CHUNKS_CULLED = visible.len() - CHUNKS_DRAWN;
+        }
+
+        if self.vars.wireframe {
+            unsafe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            }
+        }
+
         let cs = self.chunksys.read();
         let cmem = cs.chunk_memories.lock();
-        for (_index, cfl) in cmem.memories.iter().enumerate() {
-            if cfl.used {
+        for (index, cfl) in cmem.memories.iter().enumerate() {
+            if cfl.used && visible[index] {
                 let dd1: Mutex<Vec<u32>> = Mutex::new(Vec::new());
                 let dd2: Mutex<Vec<u8>> = Mutex::new(Vec::new());
                 let dd3: Mutex<Vec<u16>> = Mutex::new(Vec::new());
@@ -4813,16 +5875,10 @@ impl Game {
                 unsafe {
                     gl::Uniform2f(C_POS_LOC, cfl.pos.x as f32, cfl.pos.y as f32);
 
-                    let error = gl::GetError();
-                    if error != gl::NO_ERROR {
-                        info!("OpenGL Error after uniforming the chunk pos: {}", error);
-                    }
+                    gl_check("uniforming the chunk pos");
                     //info!("Rendering {} in chunk at {}, {}", banklock.data32.len(), banklock.pos.x, banklock.pos.y);
                     gl::DrawArrays(gl::TRIANGLES, 0, cfl.length as i32);
-                    let error = gl::GetError();
-                    if error != gl::NO_ERROR {
-                        info!("OpenGL Error after drawing arrays: {}", error);
-                    }
+                    gl_check("drawing arrays");
                     // info!("Chunk rending!");
                 }
             }
@@ -4830,10 +5886,41 @@ impl Game {
         unsafe {
             gl::Disable(gl::CULL_FACE);
         }
+
+        if self.vars.wireframe {
+            unsafe {
+                gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            }
+            #[cfg(feature = "glfw")]
+            for (index, cfl) in cmem.memories.iter().enumerate() {
+                if cfl.used && visible[index] {
+                    self.chunk_border.draw_at(
+                        Vec3::new((cfl.pos.x * cw) as f32, 0.0, (cfl.pos.y * cw) as f32),
+                        &cam_clone.mvp,
+                        self.vars.walkbobtimer,
+                    );
+                }
+            }
+        }
+
         self.draw_models();
 
-        for (_index, cfl) in cmem.memories.iter().enumerate() {
-            if cfl.used {
+        //Blend transparent geometry back to front so near water doesn't hide far water
+        let mut transparent_order: Vec<(usize, f32)> = cmem.memories
+            .iter()
+            .enumerate()
+            .filter(|(index, cfl)| cfl.used && visible[*index])
+            .map(|(index, cfl)| {
+                let center = (cfl.bounds_min + cfl.bounds_max) * 0.5;
+                let d = Vec3::new(center.x - cam_clone.position.x, 0.0, center.z - cam_clone.position.z);
+                (index, d.length_squared())
+            })
+            .collect();
+        transparent_order.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (index, _) in transparent_order {
+            let cfl = &cmem.memories[index];
+            {
                 let dd1: Mutex<Vec<u32>> = Mutex::new(Vec::new());
                 let dd2: Mutex<Vec<u8>> = Mutex::new(Vec::new());
                 let dd3: Mutex<Vec<u16>> = Mutex::new(Vec::new());
@@ -4855,16 +5942,14 @@ impl Game {
                 unsafe {
                     gl::Uniform2f(C_POS_LOC, cfl.pos.x as f32, cfl.pos.y as f32);
 
-                    let error = gl::GetError();
-                    if error != gl::NO_ERROR {
-                        info!("OpenGL Error after uniforming the chunk pos: {}", error);
-                    }
+                    gl_check("uniforming the chunk pos");
                     //info!("Rendering {} in chunk at {}, {}", banklock.data32.len(), banklock.pos.x, banklock.pos.y);
-                    gl::DrawArrays(gl::TRIANGLES, 0, cfl.tlength as i32);
-                    let error = gl::GetError();
-                    if error != gl::NO_ERROR {
-                        info!("OpenGL Error after drawing arrays: {}", error);
+                    if self.vars.wireframe {
+                        gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
                     }
+                    gl::DrawArrays(gl::TRIANGLES, 0, cfl.tlength as i32);
+                    gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+                    gl_check("drawing arrays");
                     // info!("Chunk rending!");
                 }
                 //We drew the transparents, then...
@@ -4945,7 +6030,7 @@ impl Game {
                         cam_clone.position.z,
                     );
                     gl::Uniform1f(AMBIENT_BRIGHT_MULT_LOC, self.ambient_bright_mult);
-                    gl::Uniform1f(VIEW_DISTANCE_LOC, 8.0);
+                    gl::Uniform1f(VIEW_DISTANCE_LOC, self.view_distance());
                     gl::Uniform1f(UNDERWATER_LOC, 0.0);
                     gl::Uniform3f(
                         CAM_DIR_LOC,
@@ -5012,10 +6097,7 @@ impl Game {
                 unsafe {
                     //gl::Disable(gl::CULL_FACE);
                     gl::DrawArrays(gl::TRIANGLES, 0, cfl.vlength as i32 / 5);
-                    let error = gl::GetError();
-                    if error != gl::NO_ERROR {
-                        info!("OpenGL Error after drawing arrays: {}", error);
-                    }
+                    gl_check("drawing arrays");
                     //gl::Enable(gl::CULL_FACE);
                     // info!("Chunk rending!");
                 }
@@ -5039,10 +6121,7 @@ impl Game {
                     unsafe {
                         //gl::Disable(gl::CULL_FACE);
                         gl::DrawArrays(gl::TRIANGLES, 0, cfl.wvlength as i32 / 5);
-                        let error = gl::GetError();
-                        if error != gl::NO_ERROR {
-                            info!("OpenGL Error after drawing arrays: {}", error);
-                        }
+                        gl_check("drawing arrays");
                         //gl::Enable(gl::CULL_FACE);
                         // info!("Chunk rending!");
                     }
@@ -5056,24 +6135,23 @@ impl Game {
     }
 
     pub fn start_world(&mut self) {
-        (*self.run_chunk_thread).store(true, Ordering::Relaxed);
+        if !self.vars.in_multiplayer && !self.vars.player_state_restored {
+            self.vars.player_state_restored = true;
+            let path = self.player_state_path();
+            self.load_player_state(&path);
+        }
         self.initial_timer = 0.0;
 
-        let rctarc = self.run_chunk_thread.clone();
         let carc = self.camera.clone();
         let csysarc = self.chunksys.clone();
 
         //Uncomment to do automata (just snow updating grass simulation for now)
         //csysarc.write().do_automata(&carc);
 
-        let handle = thread::spawn(move || {
-            Game::chunk_thread_function(&rctarc, carc, csysarc);
+        self.chunk_thread.start(move |run| {
+            Game::chunk_thread_function(&run, carc, csysarc);
         });
 
-
-
-        self.chunk_thread = Some(handle);
-
         //self.chunksys.voxel_models[0].stamp_here(&vec::IVec3::new(0, 40, 0), &self.chunksys, None);
     }
 
@@ -5081,15 +6159,19 @@ impl Game {
         self.update_model_collisions(self.static_model_entities.len() - 1);
     }
 
-    pub fn exit(&mut self) {
-        (*self.run_chunk_thread).store(false, Ordering::Relaxed);
-
-        if let Some(handle) = self.chunk_thread.take() {
-            handle.join().unwrap();
-            info!("Thread joined successfully!");
-        } else {
-            info!("No thread to join or already joined.");
+    //Everything else that reads the chunk system off the main thread, done before it gets reset or torn down
+    fn stop_chunk_threads(&mut self) {
+        self.chunk_thread.stop();
+        if let Some(handle) = self.autosave_thread.take() {
+            if handle.join().is_err() {
+                info!("The autosave thread had panicked before it was stopped");
+            }
         }
+        info!("Chunk thread stopped");
+    }
+
+    pub fn exit(&mut self) {
+        self.stop_chunk_threads();
         #[cfg(feature = "glfw")]
         self.drops.drops.clear();
 
@@ -5098,20 +6180,16 @@ impl Game {
     }
 
     pub fn start_chunks_with_radius(&mut self, newradius: u8, seed: u32, nt: usize) {
-        (*self.run_chunk_thread).store(false, Ordering::Relaxed);
-
-        if let Some(handle) = self.chunk_thread.take() {
-            handle.join().unwrap();
-            info!("Thread joined successfully!");
-        } else {
-            info!("No thread to join or already joined.");
-        }
+        self.stop_chunk_threads();
 
         #[cfg(feature = "glfw")]
         self.drops.drops.clear();
         self.non_static_model_entities.clear();
 
         self.chunksys.write().reset(newradius, seed, nt);
+        //Swapped along with the terrain, so it's already in effect when the ship lands
+        self.vars.gravity = self.chunksys.read().gravity;
+        self.update_far_plane();
 
         self.chunksys.write().voxel_models = Some(self.voxel_models.clone());
 
@@ -5120,7 +6198,7 @@ impl Game {
         let mut rng = StdRng::from_entropy();
 
         if !self.vars.in_multiplayer {
-            if nt == 1 {
+            if Planets::is_hostile(nt as u32) {
                 self.create_non_static_model_entity(
                     0,
                     Vec3::new(-100.0, 100.0, 350.0),
@@ -5294,6 +6372,8 @@ impl Game {
         //     }
         // }
 
+        csys_arc.read().update_fluids();
+
         let mut lightstuff = true;
         while lightstuff {
             let csys_arc = csys_arc.read();
@@ -5335,28 +6415,37 @@ impl Game {
         while genstuff {
             let csys_arc = csys_arc.read();
 
-            match csys_arc.gen_rebuild_requests.pop() {
-                Some(index) => {
-                    csys_arc.rebuild_index(index, true, false);
-                    // info!("Popping stuff GEN {}", rng.gen_range(0..255));
-                    match csys_arc.user_rebuild_requests.pop() {
-                        Some(index) => {
-                            // info!("Popping stuff USER {}", rng.gen_range(0..255));
-                            csys_arc.rebuild_index(index, true, false);
-                        }
-                        None => {}
+            let mut batch = Vec::new();
+            while batch.len() < ChunkSystem::mesh_workers() {
+                match csys_arc.gen_rebuild_requests.pop() {
+                    Some(index) => {
+                        batch.push((index, false));
                     }
-                    match csys_arc.light_rebuild_requests.pop() {
-                        Some(index) => {
-                            // info!("Popping stuff LIGHT {}", rng.gen_range(0..255));
-                            csys_arc.rebuild_index(index, true, true);
-                        }
-                        None => {}
+                    None => {
+                        genstuff = false;
+                        break;
                     }
                 }
-                None => {
-                    genstuff = false;
+            }
+            if batch.is_empty() {
+                break;
+            }
+            // info!("Popping stuff GEN {}", rng.gen_range(0..255));
+            csys_arc.rebuild_indices_parallel(batch, true);
+
+            match csys_arc.user_rebuild_requests.pop() {
+                Some(index) => {
+                    // info!("Popping stuff USER {}", rng.gen_range(0..255));
+                    csys_arc.rebuild_index(index, true, false);
                 }
+                None => {}
+            }
+            match csys_arc.light_rebuild_requests.pop() {
+                Some(index) => {
+                    // info!("Popping stuff LIGHT {}", rng.gen_range(0..255));
+                    csys_arc.rebuild_index(index, true, true);
+                }
+                None => {}
             }
         }
 
@@ -5374,7 +6463,7 @@ impl Game {
     
                                     println!("Settin");
                                     csys_arc.set_block(comm.spot, comm.changeto, false);
-                                    csys_arc.queue_rerender_with_key(ChunkSystem::spot_to_chunk_pos(&comm.spot), false, false);
+                                    csys_arc.queue_rerender_with_key(csys_arc.spot_to_chunk_pos(&comm.spot), false, false);
                                     //csys_arc.rebuild_index(comm.geo_index, false, false);
                                 } else {
                                     println!("Expected {} here but its {} for this change", comm.expectedhere, (csys_arc.blockat(comm.spot) & Blocks::block_id_bits()) );
@@ -5447,9 +6536,10 @@ impl Game {
 
             let delta_time = current_time - last_time;
 
-            static mut time_since_last_check: f32 = 1.0;
+            //Start due so the loading screen isn't waiting on the first check
+            static mut time_since_last_check: f32 = 5.0;
 
-            let user_c_pos = ChunkSystem::spot_to_chunk_pos(&IVec3::new(
+            let user_c_pos = csys_arc.read().spot_to_chunk_pos(&IVec3::new(
                 vec3.x.floor() as i32,
                 vec3.y.floor() as i32,
                 vec3.z.floor() as i32,
@@ -5464,13 +6554,17 @@ impl Game {
 
                 let mut neededspots: Vec<IVec2> = Vec::new();
 
+                let cw = csys_arc.read().chunk_width as f32;
                 let cam_lock = cam_arc.lock();
                 let user_cpos = IVec2 {
-                    x: (cam_lock.position.x / 15.0).floor() as i32,
-                    y: (cam_lock.position.z / 15.0).floor() as i32,
+                    x: (cam_lock.position.x / cw).floor() as i32,
+                    y: (cam_lock.position.z / cw).floor() as i32,
                 };
                 drop(cam_lock);
 
+                PLAYERCHUNKPOS.0.store(user_cpos.x, Ordering::Relaxed);
+                PLAYERCHUNKPOS.1.store(user_cpos.y, Ordering::Relaxed);
+
                 let radius = {
                     let x = csys_arc.read().radius;
                     x.clone()
@@ -5518,6 +6612,21 @@ impl Game {
                         }
                     });
 
+                //Far chunks give their memory back now rather than whenever their slot gets picked
+                {
+                    let csys_arc = csys_arc.read();
+                    let mut unloaded = 0;
+                    for chunk in &unused_or_distant {
+                        if chunk.used && csys_arc.is_distant(chunk.pos, user_cpos) {
+                            csys_arc.unload_index(chunk.geo_index);
+                            unloaded += 1;
+                        }
+                    }
+                    if unloaded > 0 {
+                        info!("Unloaded {} distant chunks, {} spots still loaded", unloaded, csys_arc.takencare.len());
+                    }
+                }
+
                 sorted_chunk_facades.extend(unused_or_distant);
                 sorted_chunk_facades.extend(used_and_close);
                 //info!("Neededspots size: {}", neededspots.len());
@@ -5528,10 +6637,26 @@ impl Game {
                     dist_a.cmp(&dist_b)
                 });
 
-                for (index, ns) in neededspots.iter().enumerate() {
+                //Generation stays serial since structures stamp into neighbours, the meshing of each batch is spread over threads
+                let meshstart = Instant::now();
+                let mut meshed = 0;
+                let workers = ChunkSystem::mesh_workers();
+                let mut spots = neededspots.iter().enumerate().peekable();
+                while spots.peek().is_some() {
                     let csys_arc = csys_arc.read();
-                    csys_arc.move_and_rebuild(sorted_chunk_facades[index].geo_index, *ns);
-                    
+
+                    let mut batch = Vec::new();
+                    while batch.len() < workers {
+                        match spots.next() {
+                            Some((index, ns)) => {
+                                batch.push(csys_arc.move_index(sorted_chunk_facades[index].geo_index, *ns));
+                            }
+                            None => break,
+                        }
+                    }
+                    meshed += batch.len();
+                    csys_arc.rebuild_indices_parallel(batch, false);
+
                     match csys_arc.user_rebuild_requests.pop() {
                         Some(index) => {
                             csys_arc.rebuild_index(index, true, false);
@@ -5553,6 +6678,27 @@ impl Game {
                         None => {}
                     }
                 }
+                if meshed > 0 {
+                    let csys_arc = csys_arc.read();
+                    let (hits, misses) = csys_arc.take_column_cache_stats();
+                    info!("Meshed {} chunks on {} threads in {:?}, column cache hit rate {:.1}% ({} columns computed)",
+                        meshed, workers, meshstart.elapsed(),
+                        100.0 * hits as f32 / (hits + misses).max(1) as f32, misses);
+                    csys_arc.trim_column_cache();
+                }
+
+                //Remesh loaded chunks the player has moved closer to or further from
+                let csys_arc = csys_arc.read();
+                for carc in &csys_arc.chunks {
+                    match carc.try_lock() {
+                        Some(cf) => {
+                            if cf.used && cf.lod != csys_arc.lod_for(cf.pos) {
+                                csys_arc.background_rebuild_requests.push(cf.geo_index);
+                            }
+                        }
+                        None => {}
+                    }
+                }
             } else {
                 time_since_last_check += delta_time;
             }
@@ -5600,11 +6746,20 @@ impl Game {
                     LASTY = ypos;
                 }
                 self.vars.first_mouse = false;
+                self.vars.smoothed_look = (0.0, 0.0);
             }
 
             unsafe {
-                let x_offset = (xpos - LASTX) * MISCSETTINGS.mouse_sense as f64;
-                let y_offset = (LASTY - ypos) * MISCSETTINGS.mouse_sense as f64;
+                let raw = (
+                    ((xpos - LASTX) * MISCSETTINGS.mouse_sense as f64) as f32,
+                    ((LASTY - ypos) * MISCSETTINGS.mouse_sense as f64) as f32,
+                );
+                let (x_offset, y_offset) = Game::look_delta(
+                    raw,
+                    &mut self.vars.smoothed_look,
+                    self.vars.mouse_smoothing,
+                    self.vars.invert_y,
+                );
 
                 LASTY = ypos;
                 LASTX = xpos;
@@ -5648,6 +6803,23 @@ impl Game {
             }
         }
     }
+    //Yaw/pitch change for one mouse move, averaged with the previous ones when smoothing is above 0
+    pub fn look_delta(raw: (f32, f32), smoothed: &mut (f32, f32), smoothing: f32, invert_y: bool) -> (f32, f32) {
+        let s = smoothing.clamp(0.0, 0.95);
+        smoothed.0 = smoothed.0 * s + raw.0 * (1.0 - s);
+        smoothed.1 = smoothed.1 * s + raw.1 * (1.0 - s);
+        if invert_y {
+            (smoothed.0, -smoothed.1)
+        } else {
+            *smoothed
+        }
+    }
+    #[cfg(feature = "glfw")]
+    pub fn set_cursor_mode(&self, mode: glfw::CursorMode) {
+        if let Some(window) = &self.window {
+            window.write().set_cursor_mode(mode);
+        }
+    }
     pub fn set_mouse_focused(&mut self, tf: bool) {
         if tf {
             self.vars.mouse_focused = true;
@@ -5657,34 +6829,165 @@ impl Game {
         }
         SAVE_MISC();
     }
-    pub fn delete_block_recursively(
+    //Breaks origin and every block of match_id connected to it, up to max blocks.
+    //Returns the chunk keys that need a rerender and how many blocks were broken
+    pub fn flood_break(
         chunksys: &Arc<RwLock<ChunkSystem>>,
-        id: u32,
-        at: IVec3,
-        set: &mut HashSet<IVec2>,
-    ) {
-        let mut stack = vec![at]; // Initialize stack with initial position
+        match_id: u32,
+        origin: IVec3,
+        max: usize,
+    ) -> (HashSet<IVec2>, usize) {
+        let mut set: HashSet<IVec2> = HashSet::new();
+        let chunksys = chunksys.read();
+        let spots = Game::flood_spots(&chunksys, match_id, origin, max);
+
+        for (i, spot) in spots.iter().enumerate() {
+            //Only the first one makes a sound, a whole tree at once is too loud
+            if i == 0 {
+                chunksys.set_block(*spot, 0, true);
+            } else {
+                chunksys.set_block_no_sound(*spot, 0, true);
+            }
+            set.insert(chunksys.spot_to_chunk_pos(spot));
+        }
+        (set, spots.len())
+    }
+
+    //The blocks a flood break would take, without touching them, so multiplayer can send each one to the server
+    pub fn flood_spots(chunksys: &ChunkSystem, match_id: u32, origin: IVec3, max: usize) -> Vec<IVec3> {
+        let mut spots = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![origin];
 
         while let Some(current) = stack.pop() {
-            // Check if the block at the current position is already deleted
-
-            let chunksys = chunksys.read();
-
-            if chunksys.blockat(current) != 0 {
-                // Set the block at the current position
-                chunksys.set_block(current, 0, true);
-                let key = ChunkSystem::spot_to_chunk_pos(&current);
-                set.insert(key);
-                // Add neighbors to the stack if they have the same id
-                for neighbor in Cube::get_neighbors() {
-                    let neighbor_pos = *neighbor + current;
-                    if chunksys.blockat(neighbor_pos) == id {
-                        stack.push(neighbor_pos);
-                    }
+            if spots.len() >= max {
+                break;
+            }
+            if !seen.insert(current) || chunksys.blockat(current) & Blocks::block_id_bits() != match_id {
+                continue;
+            }
+            spots.push(current);
+            for neighbor in Cube::get_neighbors() {
+                stack.push(*neighbor + current);
+            }
+        }
+        spots
+    }
+    //If a mob is in front of the first block along the crosshair, returns its id
+    pub fn cast_attack_ray(&self) -> Option<u32> {
+        let cl = {
+            let cl = self.camera.lock();
+            cl.clone()
+        };
+        let bob = Vec3::new(0.0, self.vars.walkbobtimer.sin() / 20.0, 0.0) + Vec3::new(0.0, 0.3, 0.0);
+
+        let entity_hit = raycast_entity(
+            cl.position + bob,
+            cl.direction,
+            &self.non_static_model_entities,
+            MOB_HIT_REACH,
+        );
+
+        match entity_hit {
+            Some((id, entity_dist)) => {
+                let block_dist = match raycast_voxel_with_bob(
+                    cl.position,
+                    cl.direction,
+                    &self.chunksys,
+                    MOB_HIT_REACH,
+                    self.vars.walkbobtimer,
+                ) {
+                    Some((tip, _block_hit, _normal)) => tip.distance(cl.position + bob),
+                    None => f32::INFINITY,
+                };
+                if entity_dist < block_dist {
+                    Some(id)
+                } else {
+                    None
                 }
             }
+            None => None,
+        }
+    }
+    //Whether the server should believe a hit from a player standing at attacker
+    pub fn mob_hit_in_reach(attacker: Vec3, mob: Vec3) -> bool {
+        attacker.distance(mob) <= MOB_HIT_REACH + MOB_HIT_LEEWAY
+    }
+    //The server decides what a hit does in multiplayer, otherwise it lands here and a kill drops loot
+    pub fn hit_mob(&mut self, id: u32) {
+        self.combat_timer = COMBAT_LINGER_SECONDS;
+        let dir = self.camera.lock().direction;
+        if self.vars.in_multiplayer {
+            let mut message = Message::new(MessageType::MobHit, dir, 0.0, id);
+            message.infof = PLAYER_HIT_DAMAGE as f32;
+            self.netconn.sendqueue.push(message);
+        } else if let Some((pos, model_index, health)) = self.damage_mob(id, PLAYER_HIT_DAMAGE, dir) {
+            if health <= 0 {
+                self.drop_mob_loot(pos, model_index);
+            }
+        }
+    }
+    //Multiplayer block sets go on screen right away and get squared up with the server's echo
+    pub fn predict_block_set(&mut self, spot: IVec3, block: u32, refund: u32, drops: Vec<(u32, u32)>, drop_at: Vec3) {
+        let previous = self.chunksys.read().blockat(spot);
+        self.chunksys
+            .read()
+            .set_block_and_queue_rerender(spot, block, block == 0, true, false);
+
+        let mut edit = PendingEdit::new(spot, previous, block, Instant::now());
+        edit.refund = refund;
+        edit.drops = drops;
+        edit.drop_at = drop_at;
+        let seq = self.pending_edits.push(edit);
+
+        let mut message = Message::new(
+            MessageType::BlockSet,
+            Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32),
+            0.0,
+            block,
+        );
+        message.info2 = seq;
+        self.netconn.send(&message);
+    }
+
+    //True if this echo was one of our predicted edits and has been dealt with
+    pub fn reconcile_block_set(&mut self, comm: &Message) -> bool {
+        if comm.info2 == 0 || *self.my_uuid.read() != Some(Uuid::from_u64_pair(comm.goose.0, comm.goose.1)) {
+            return false;
+        }
+        //Already undone by the timeout, so whatever the server says goes
+        let edit = match self.pending_edits.take(comm.info2) {
+            Some(edit) => edit,
+            None => return false,
+        };
+        if comm.bo {
+            info!("Server rejected block set at {} {} {}", edit.spot.x, edit.spot.y, edit.spot.z);
+            self.undo_predicted_edit(&edit, comm.info);
+        } else {
+            #[cfg(feature = "glfw")]
+            for (item, count) in edit.drops {
+                self.drops.add_drop(edit.drop_at, item, count);
+            }
+        }
+        true
+    }
+
+    pub fn expire_pending_edits(&mut self) {
+        for edit in self.pending_edits.take_expired(Instant::now(), PENDING_EDIT_TIMEOUT) {
+            info!("No echo for block set at {} {} {}, undoing it", edit.spot.x, edit.spot.y, edit.spot.z);
+            self.undo_predicted_edit(&edit, edit.previous);
+        }
+    }
+
+    fn undo_predicted_edit(&mut self, edit: &PendingEdit, actual: u32) {
+        self.chunksys
+            .read()
+            .set_block_and_queue_rerender_no_sound(edit.spot, actual, true, true, false);
+        if edit.refund != 0 {
+            let _ = Game::add_to_inventory(&self.inventory, edit.refund, 1, true, &self.needtosend);
         }
     }
+
     pub fn cast_break_ray(&mut self) {
         
         let cl = {
@@ -5695,15 +6998,32 @@ impl Game {
             cl.position,
             cl.direction,
             &self.chunksys,
-            10.0,
+            self.reach,
             self.vars.walkbobtimer,
         ) {
-            Some((tip, block_hit)) => {
+            Some((tip, block_hit, hit_normal)) => {
+                //Spawn drops just outside the face we hit so they don't start inside the neighbor
+                let tip = tip + Vec3::new(hit_normal.x as f32, hit_normal.y as f32, hit_normal.z as f32) * 0.1;
                 let blockbits = self.chunksys.read().blockat(block_hit);
                 let blockat = blockbits & Blocks::block_id_bits();
-                if blockat == 16 {
-                    let mut set: HashSet<IVec2> = HashSet::new();
-                    Game::delete_block_recursively(&self.chunksys, 16, block_hit, &mut set);
+                if Blocks::is_unbreakable(blockat) {
+                    return;
+                }
+                #[cfg(feature = "glfw")]
+                if blockat != 0 {
+                    let center = Vec3::new(block_hit.x as f32, block_hit.y as f32, block_hit.z as f32);
+                    self.particles.block_broken(center, blockat);
+                }
+                if flood_breaks(blockat) && self.vars.in_multiplayer {
+                    //Every block goes through the server like a normal break, drops come when it agrees
+                    let spots = Game::flood_spots(&self.chunksys.read(), blockat, block_hit, FLOOD_BREAK_MAX);
+                    for spot in spots {
+                        let drops = roll_drops(&DROP_TABLES, blockat, &mut rand::thread_rng());
+                        self.edit_queue.push_back((spot, 0, drops, tip));
+                    }
+                    self.send_queued_edits();
+                } else if flood_breaks(blockat) {
+                    let (set, broken) = Game::flood_break(&self.chunksys, blockat, block_hit, FLOOD_BREAK_MAX);
                     for key in set {
                         self.chunksys
                             .read()
@@ -5711,7 +7031,11 @@ impl Game {
                             .queue_rerender_with_key(key, true, false);
                     }
                     #[cfg(feature = "glfw")]
-                    self.drops.add_drop(tip, 17, 1);
+                    for _ in 0..broken {
+                        for (item, count) in roll_drops(&DROP_TABLES, blockat, &mut rand::thread_rng()) {
+                            self.drops.add_drop(tip, item, count);
+                        }
+                    }
                 } else if blockat == 19 {
                     //Door stuff
                     let top = DoorInfo::get_door_top_bit(blockbits);
@@ -5742,21 +7066,20 @@ impl Game {
                             .set_block_and_queue_rerender(other_half, 0, true, true, false);
                     }
                 } else {
-                    if blockat != 0 {
-                        #[cfg(feature = "glfw")]
-                        self.drops.add_drop(tip, blockat, 1);
-                    }
+                    let drops = if blockat != 0 {
+                        roll_drops(&DROP_TABLES, blockat, &mut rand::thread_rng())
+                    } else {
+                        Vec::new()
+                    };
 
-                    //TODO: PROBLEM HERE THAT WILL ALLOW USERS TO KEEP DUPING A BLOCK AS LONG AS THE SERVER DOESNT RESPOND
                     if self.vars.in_multiplayer {
-                        let message = Message::new(
-                            MessageType::BlockSet,
-                            Vec3::new(block_hit.x as f32, block_hit.y as f32, block_hit.z as f32),
-                            0.0,
-                            0,
-                        );
-                        self.netconn.send(&message);
+                        //Drops wait for the server so a rejected break can't dupe them
+                        self.predict_block_set(block_hit, 0, 0, drops, tip);
                     } else {
+                        #[cfg(feature = "glfw")]
+                        for (item, count) in drops {
+                            self.drops.add_drop(tip, item, count);
+                        }
                         self.chunksys
                             .read()
                         
@@ -5776,15 +7099,43 @@ impl Game {
         if y < 0.0 {
             invrowchange -= 1;
         }
-        let mut proposednewslot = self.hud.bumped_slot as i8 + invrowchange;
-        if proposednewslot < 0 {
-            proposednewslot = ROWLENGTH as i8-1;
-        }
-        self.hud.bumped_slot = proposednewslot as usize % ROWLENGTH as usize;
+        self.select_hotbar_slot(wrap_hotbar_slot(self.hud.bumped_slot, invrowchange));
+    }
+    #[cfg(feature = "glfw")]
+    pub fn select_hotbar_slot(&mut self, slot: usize) {
+        self.hud.bumped_slot = slot % ROWLENGTH as usize;
         self.hud.dirty = true;
         self.hud.update();
     }
     #[cfg(feature = "glfw")]
+    pub fn drop_held_item(&mut self) {
+        let slot_selected = self.hud.bumped_slot;
+        let slot = self.inventory.read().inv[slot_selected];
+
+        if slot.0 == 0 || slot.1 == 0 {
+            return;
+        }
+
+        let cl = {
+            let c = self.camera.lock();
+            c.clone()
+        };
+
+        let newcount = slot.1 - 1;
+        let newid = if newcount == 0 { 0 } else { slot.0 };
+
+        Game::set_in_inventory(
+            &self.inventory.clone(),
+            slot_selected,
+            newid,
+            newcount,
+            self.vars.in_multiplayer,
+            &self.needtosend,
+        ).unwrap();
+
+        self.drops.add_thrown_drop(cl.position + cl.direction, slot.0, 1, cl.direction * 3.0);
+    }
+    #[cfg(feature = "glfw")]
     pub fn cast_place_ray(&mut self) {
         let slot_selected = self.hud.bumped_slot;
         let slot = self.inventory.read().inv[slot_selected];
@@ -5802,10 +7153,10 @@ impl Game {
                 cl.position,
                 cl.direction,
                 &self.chunksys,
-                10.0,
+                self.reach,
                 self.vars.walkbobtimer,
             ) {
-                Some((tip, block_hit)) => {
+                Some((_tip, block_hit, hit_normal)) => {
                     let mut blockbitshere = self.chunksys.read().blockat(block_hit);
                     let blockidhere = blockbitshere & Blocks::block_id_bits();
 
@@ -5864,35 +7215,10 @@ impl Game {
                         }
                         self.crafting_open = true;
 
-                        self.window
-                            .write()
-                           
-                            .set_cursor_mode(glfw::CursorMode::Normal);
+                        self.set_cursor_mode(glfw::CursorMode::Normal);
                         openedcraft = true;
                     } else if slot.0 != 0 && slot.1 > 0 {
                         let id = slot.0;
-                        let diff = (tip + Vec3::new(-0.5, -0.5, -0.5))
-                            - (Vec3::new(
-                                block_hit.x as f32,
-                                block_hit.y as f32,
-                                block_hit.z as f32, //TODO ADD -0.5 to these?????
-                            ));
-
-                        let hit_normal;
-
-                        // Determine the primary axis of intersection
-                        if (diff.x).abs() > (diff.y).abs() && (diff.x).abs() > (diff.z).abs() {
-                            // The hit was primarily along the X-axis
-                            hit_normal = vec::IVec3::new(if diff.x > 0.0 { 1 } else { -1 }, 0, 0);
-                        } else if (diff.y).abs() > (diff.x).abs() && (diff.y).abs() > (diff.z).abs()
-                        {
-                            // The hit was primarily along the Y-axis
-                            hit_normal = vec::IVec3::new(0, if diff.y > 0.0 { 1 } else { -1 }, 0);
-                        } else {
-                            // The hit was primarily along the Z-axis
-                            hit_normal = vec::IVec3::new(0, 0, if diff.z > 0.0 { 1 } else { -1 });
-                        }
-
                         info!(
                             "Hit normal is {} {} {}",
                             hit_normal.x, hit_normal.y, hit_normal.z
@@ -5905,6 +7231,11 @@ impl Game {
                         );
 
 
+                        //Nothing goes into the bedrock or past the top of the world, it would never show
+                        if !self.chunksys.read().is_in_world(place_point) {
+                            return ();
+                        }
+
                         //Don't allow placing blocks where solid blocks or the player are
                         let blockbitsatplacepoint = self.chunksys.read().blockat(place_point);
                         let blockidatplacepoint = blockbitsatplacepoint & Blocks::block_id_bits();
@@ -5913,11 +7244,16 @@ impl Game {
                             return ();
                         }
 
-                        let camblockspot = IVec3::new(cl.position.x.floor() as i32, cl.position.y.round() as i32, cl.position.z.floor() as i32);
-
-                        if place_point ==  camblockspot || place_point == camblockspot - IVec3::new(0, 1, 0){
+                        //The box follows the player every physics step, except when noclipping where it doesn't matter
+                        let noclipping = self.vars.fly && self.vars.noclip;
+                        if !noclipping && self.user_bound_box.overlaps_block(place_point) {
                             return ();
                         }
+
+                        //Puff out of the face it was placed against
+                        let face = Vec3::new(place_point.x as f32, place_point.y as f32, place_point.z as f32)
+                            - Vec3::new(hit_normal.x as f32, hit_normal.y as f32, hit_normal.z as f32) * 0.5;
+                        self.particles.block_placed(face, id);
                         
 
                         if id == 19 {
@@ -5935,7 +7271,7 @@ impl Game {
 
                             let csysread = self.chunksys.read();
 
-                            let condition1 = csysread.blockat(place_above) == 0;
+                            let condition1 = csysread.blockat(place_above) == 0 && csysread.is_in_world(place_above);
                             let condition2 = csysread.blockat(place_below) != 0;
 
                             drop(csysread);
@@ -5994,7 +7330,7 @@ impl Game {
                                         DoorInfo::set_opposite_door_bits(&mut blockbitsright, 0);
                                         DoorInfo::set_opposite_door_bits(&mut neightopbits, 0);
 
-                                        let _chunktoreb = ChunkSystem::spot_to_chunk_pos(&right);
+                                        let _chunktoreb = csysread.spot_to_chunk_pos(&right);
 
                                         if self.vars.in_multiplayer {
                                             let mut message = Message::new(
@@ -6054,7 +7390,7 @@ impl Game {
                                         DoorInfo::set_opposite_door_bits(&mut blockbitsleft, 0);
                                         DoorInfo::set_opposite_door_bits(&mut neightopbits, 0);
 
-                                        let _chunktoreb = ChunkSystem::spot_to_chunk_pos(&left);
+                                        let _chunktoreb = csysread.spot_to_chunk_pos(&left);
 
                                         if self.vars.in_multiplayer {
                                             let mut message = Message::new(
@@ -6149,18 +7485,7 @@ impl Game {
                             Blocks::set_direction_bits(&mut conveyor_id, direction);
 
                             if self.vars.in_multiplayer {
-                                let message = Message::new(
-                                    MessageType::BlockSet,
-                                    Vec3::new(
-                                        place_point.x as f32,
-                                        place_point.y as f32,
-                                        place_point.z as f32,
-                                    ),
-                                    0.0,
-                                    conveyor_id,
-                                );
-
-                                self.netconn.send(&message);
+                                self.predict_block_set(place_point, conveyor_id, id, Vec::new(), Vec3::ZERO);
                             } else {
                                 self.chunksys.read().set_block_and_queue_rerender(
                                     place_point,
@@ -6189,18 +7514,7 @@ impl Game {
                             Blocks::set_direction_bits(&mut ladder_id, direction);
 
                             if self.vars.in_multiplayer {
-                                let message = Message::new(
-                                    MessageType::BlockSet,
-                                    Vec3::new(
-                                        place_point.x as f32,
-                                        place_point.y as f32,
-                                        place_point.z as f32,
-                                    ),
-                                    0.0,
-                                    ladder_id,
-                                );
-
-                                self.netconn.send(&message);
+                                self.predict_block_set(place_point, ladder_id, id, Vec::new(), Vec3::ZERO);
                             } else {
                                 self.chunksys.read().set_block_and_queue_rerender(
                                     place_point,
@@ -6229,18 +7543,7 @@ impl Game {
                             Blocks::set_direction_bits(&mut chest_id, direction);
 
                             if self.vars.in_multiplayer {
-                                let message = Message::new(
-                                    MessageType::BlockSet,
-                                    Vec3::new(
-                                        place_point.x as f32,
-                                        place_point.y as f32,
-                                        place_point.z as f32,
-                                    ),
-                                    0.0,
-                                    chest_id,
-                                );
-
-                                self.netconn.send(&message);
+                                self.predict_block_set(place_point, chest_id, id, Vec::new(), Vec3::ZERO);
                             } else {
                                 self.chunksys.read().set_block_and_queue_rerender(
                                     place_point,
@@ -6252,22 +7555,16 @@ impl Game {
                             }
                         } else {
                             if !Blocks::is_non_placeable(slot.0) {
+                                let mut placed_id = id;
+                                if Blocks::is_axis_oriented(id) {
+                                    Blocks::set_axis_bits(&mut placed_id, Blocks::axis_from_normal(hit_normal));
+                                }
                                 if self.vars.in_multiplayer {
-                                    let message = Message::new(
-                                        MessageType::BlockSet,
-                                        Vec3::new(
-                                            place_point.x as f32,
-                                            place_point.y as f32,
-                                            place_point.z as f32,
-                                        ),
-                                        0.0,
-                                        id,
-                                    );
-                                    self.netconn.send(&message);
+                                    self.predict_block_set(place_point, placed_id, id, Vec::new(), Vec3::ZERO);
                                 } else {
                                     self.chunksys.read().set_block_and_queue_rerender(
                                         place_point,
-                                        id,
+                                        placed_id,
                                         false,
                                         true,
                                         false
@@ -6275,7 +7572,7 @@ impl Game {
                                 }
                             }
                         }
-                        if !Blocks::is_non_placeable(slot.0) {
+                        if !Blocks::is_non_placeable(slot.0) && self.game_mode.consumes_items() {
                             
                             if self.vars.in_multiplayer {
                                 if slot.1 == 1 {
@@ -6339,7 +7636,7 @@ impl Game {
                 //let s = self.stamina.load(Ordering::Relaxed);
 
                 self.health
-                    .store((h + foodstats.0 as i8).min(20), Ordering::Relaxed);
+                    .store((h + foodstats.0 as f32).min(20.0), Ordering::Relaxed);
                 // self.stamina
                 //     .store((s + foodstats.1).min(100), Ordering::Relaxed);
 
@@ -6393,10 +7690,7 @@ impl Game {
             self.update_inventory();
             self.hud.chest_open = true;
 
-            self.window
-                .write()
-           
-                .set_cursor_mode(glfw::CursorMode::Normal);
+            self.set_cursor_mode(glfw::CursorMode::Normal);
             self.set_mouse_focused(false);
         }
 
@@ -6448,9 +7742,10 @@ impl Game {
                                                             msg.bo = false;
                                                         self.netconn.send(&msg);
                                                     } else {
-                                                        slot.1 = slot.1 + self.mouse_slot.1;
+                                                        let (merged, leftover) = Game::merge_stacks(slot.0, slot.1, self.mouse_slot.1);
+                                                        slot.1 = merged;
 
-                                                        self.mouse_slot = (0, 0);
+                                                        self.mouse_slot = if leftover > 0 { (slot.0, leftover) } else { (0, 0) };
                                                     }
                                                 } else
                                                 //SWAP YOUR mouse_slot AND slot
@@ -6520,9 +7815,10 @@ impl Game {
                                                 msg.bo = false;
                                                 self.netconn.send(&msg);
                                             } else {
-                                                slot.1 = slot.1 + self.mouse_slot.1;
+                                                let (merged, leftover) = Game::merge_stacks(slot.0, slot.1, self.mouse_slot.1);
+                                                slot.1 = merged;
 
-                                                self.mouse_slot = (0, 0);
+                                                self.mouse_slot = if leftover > 0 { (slot.0, leftover) } else { (0, 0) };
                                             }
                                         } else
                                         //SWAP YOUR mouse_slot AND slot
@@ -6585,23 +7881,75 @@ impl Game {
             match unsafe { MISCSETTINGS.mousebinds.get(&format!("{:?}", mb)).unwrap_or(&"_".to_string()).as_str() } {
                 "Break/Attack" => {
                     self.vars.mouse_clicked = a == Action::Press;
+                    if a == Action::Press {
+                        match self.cast_attack_ray() {
+                            Some(id) => {
+                                self.hit_mob(id);
+                                //Don't start breaking the block behind it
+                                self.vars.mouse_clicked = false;
+                            }
+                            None => {}
+                        }
+                    }
                     // if self.vars.mouse_clicked {
                     //     self.cast_break_ray();
                     // }
                 }
                 "Place/Use" => {
                     self.vars.right_mouse_clicked = a == Action::Press;
-                    if !self.vars.ship_taken_off {
+                    if !self.vars.ship_state.taken_off() {
                         if self.vars.right_mouse_clicked {
                             self.cast_place_ray();
                         }
                     }
                 }
+                "Pick Block" => {
+                    if a == Action::Press {
+                        self.pick_block();
+                    }
+                }
+                //Settings saved before pick block existed have nothing on the middle button
+                _ if mb == MouseButton::Button3 && a == Action::Press => unsafe {
+                    if !MISCSETTINGS.mousebinds.contains_key("Button3") {
+                        self.pick_block();
+                    }
+                },
                 _ => {}
             }
         }
     }
 
+    //Selects the hotbar slot holding whatever block is being looked at. In creative a block we don't carry
+    //gets put in the current slot, in survival that (or a miss) does nothing.
+    #[cfg(feature = "glfw")]
+    pub fn pick_block(&mut self) {
+        let cl = {
+            let c = self.camera.lock();
+            c.clone()
+        };
+        if let Some((_tip, block_hit, _normal)) =
+            raycast_voxel_with_bob(cl.position, cl.direction, &self.chunksys, self.reach, self.vars.walkbobtimer)
+        {
+            let id = self.chunksys.read().blockat(block_hit) & Blocks::block_id_bits();
+            let slot = self.inventory.read().slot_holding(id, self.hud.bumped_slot);
+            match slot {
+                Some(slot) => self.select_hotbar_slot(slot),
+                None if self.game_mode == GameMode::Creative && id != 0 && !Blocks::is_non_placeable(id) => {
+                    Game::set_in_inventory(
+                        &self.inventory.clone(),
+                        self.hud.bumped_slot,
+                        id,
+                        Blocks::get_max_stack(id),
+                        self.vars.in_multiplayer,
+                        &self.needtosend,
+                    ).unwrap();
+                    self.update_inventory();
+                }
+                None => {}
+            }
+        }
+    }
+
     pub fn new_world_func(&mut self) {
         if self.vars.in_multiplayer {
             //let msg = Message::new(MessageType::ShutUpMobMsgs, Vec3::ZERO, 0.0, 0);
@@ -6618,11 +7966,12 @@ impl Game {
 
 
             let currseed = unsafe {CURRSEED.load(std::sync::atomic::Ordering::Relaxed)};
-            let nt = 0;
+            //The server's world came with its planet type
+            let nt = self.chunksys.read().planet_type;
 
-            self.vars.hostile_world = (nt % 2) != 0;
+            self.vars.hostile_world = Planets::is_hostile(nt as u32);
 
-            self.start_chunks_with_radius(10, currseed, nt as usize);
+            self.start_chunks_with_radius(unsafe { MISCSETTINGS.render_distance }, currseed, nt as usize);
         } else {
             let mut rng = StdRng::from_entropy();
 
@@ -6633,10 +7982,10 @@ impl Game {
             self.camera.lock().position = Vec3::new(0.0, 100.0, 0.0);
 
             unsafe {
-                self.vars.hostile_world = (CURR_NT % 2) == 0;
-                CURR_NT = (CURR_NT + 1) % 2;
+                CURR_NT = (CURR_NT + 1) % PLANET_TYPES as usize;
+                self.vars.hostile_world = Planets::is_hostile(CURR_NT as u32);
                 unsafe {CURRSEED.store(seed, Ordering::Relaxed)};
-                self.start_chunks_with_radius(10, seed, CURR_NT);
+                self.start_chunks_with_radius(MISCSETTINGS.render_distance, seed, CURR_NT);
 
                 info!(
                     "Now noise type is {}",
@@ -6656,6 +8005,15 @@ impl Game {
     pub fn keyboard(&mut self, key: Key, action: Action) {
         use crate::keybinds::{ABOUTTOREBIND, LISTENINGFORREBIND};
 
+        //Number keys only pick slots when nothing else has been bound to them
+        if action == Action::Press && !self.vars.menu_open {
+            if let Some(slot) = hotbar_slot_for_key(key) {
+                if unsafe { !MISCSETTINGS.keybinds.contains_key(&key.get_scancode().unwrap_or(0)) } {
+                    self.select_hotbar_slot(slot);
+                }
+            }
+        }
+
         {
         match unsafe { MISCSETTINGS.keybinds.get(&key.get_scancode().unwrap_or(0)).unwrap_or(&"_".to_string()).as_str() } {
             "Exit/Menu" => {
@@ -6664,10 +8022,7 @@ impl Game {
                         self.button_command("escapemenu".to_string());
                     } else {
                         self.vars.menu_open = false;
-                        self.window
-                            .write()
-                         
-                            .set_cursor_mode(glfw::CursorMode::Disabled);
+                        self.set_cursor_mode(glfw::CursorMode::Disabled);
                         self.set_mouse_focused(true);
                         unsafe {
                             uncapkb.store(true, Ordering::Relaxed);
@@ -6676,10 +8031,7 @@ impl Game {
 
                     if self.crafting_open {
                         self.crafting_open = false;
-                        self.window
-                            .write()
-                         
-                            .set_cursor_mode(glfw::CursorMode::Disabled);
+                        self.set_cursor_mode(glfw::CursorMode::Disabled);
                         self.set_mouse_focused(true);
                         unsafe {
                             uncapkb.store(true, Ordering::Relaxed);
@@ -6688,10 +8040,7 @@ impl Game {
 
                     if self.hud.chest_open {
                         self.hud.chest_open = false;
-                        self.window
-                            .write()
-                         
-                            .set_cursor_mode(glfw::CursorMode::Disabled);
+                        self.set_cursor_mode(glfw::CursorMode::Disabled);
                         self.set_mouse_focused(true);
                         unsafe {
                             uncapkb.store(true, Ordering::Relaxed);
@@ -6702,6 +8051,9 @@ impl Game {
             "Forward" => {
                 if action == Action::Press || action == Action::Repeat {
                     self.controls.forward = true;
+                    if action == Action::Press && self.controls.shift {
+                        self.controls.sprint = true;
+                    }
                 } else {
                     self.controls.forward = false;
                 }
@@ -6721,10 +8073,7 @@ impl Game {
                     Game::update_avail_recipes(&self.inventory);
                     self.crafting_open = true;
 
-                    self.window
-                        .write()
-                       
-                        .set_cursor_mode(glfw::CursorMode::Normal);
+                    self.set_cursor_mode(glfw::CursorMode::Normal);
                     self.set_mouse_focused(false);
                 } else {
                 }
@@ -6744,6 +8093,19 @@ impl Game {
                 }
             }
             "Jump/Swim/Climb Up" => {
+                if action == Action::Press {
+                    //Double tap toggles fly mode
+                    static mut LAST_JUMP_PRESS: f32 = -1.0;
+                    let now = unsafe { glfwGetTime() as f32 };
+                    unsafe {
+                        if LAST_JUMP_PRESS >= 0.0 && now - LAST_JUMP_PRESS < 0.3 {
+                            LAST_JUMP_PRESS = -1.0;
+                            self.toggle_fly();
+                        } else {
+                            LAST_JUMP_PRESS = now;
+                        }
+                    }
+                }
                 if action == Action::Press || action == Action::Repeat {
                     self.controls.up = true;
                 } else {
@@ -6753,8 +8115,34 @@ impl Game {
             "Sprint" => {
                 if action == Action::Press || action == Action::Repeat {
                     self.controls.shift = true;
+                    if action == Action::Press {
+                        self.controls.sprint = true;
+                    }
                 } else {
                     self.controls.shift = false;
+                    self.controls.sprint = false;
+                }
+            }
+            "Drop Item" => {
+                if action == Action::Press {
+                    self.drop_held_item();
+                }
+            }
+            "Noclip" => {
+                if action == Action::Press || action == Action::Repeat {
+                    self.vars.noclip = true;
+                } else {
+                    self.vars.noclip = false;
+                }
+            }
+            "Toggle Game Mode" => {
+                if action == Action::Press {
+                    self.set_game_mode(self.game_mode.toggled());
+                }
+            }
+            "Save World" => {
+                if action == Action::Press {
+                    self.save_world();
                 }
             }
             "Crouch" => unsafe {
@@ -6789,11 +8177,6 @@ impl Game {
             //             self.takeoff_ship();
             //         }
 
-            //     }
-            // }
-            // Key::L => {
-            //     if action == Action::Press {
-            //         self.chunksys.read().save_current_world_to_file(String::from("saves/world1"));
             //     }
             // }
             // Key::Num8 => {
@@ -6817,13 +8200,11 @@ impl Game {
             // }
             "Fov Up" => {
                 self.faders.write()[FaderNames::FovFader as usize].up();
-                self.faders.write()[FaderNames::FovFader as usize].top += 1.0;
-                self.faders.write()[FaderNames::FovFader as usize].bottom += 1.0;
+                unsafe { self.set_base_fov(MISCSETTINGS.fov + 1.0) };
             }
             "Fov Down" => {
                 self.faders.write()[FaderNames::FovFader as usize].down();
-                self.faders.write()[FaderNames::FovFader as usize].top -= 1.0;
-                self.faders.write()[FaderNames::FovFader as usize].bottom -= 1.0;
+                unsafe { self.set_base_fov(MISCSETTINGS.fov - 1.0) };
             }
 
             // Key::P => { //VISION
@@ -0,0 +1,77 @@
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tracing::info;
+
+//Not in every set of bindings, so spelled out
+pub const GL_CONTEXT_LOST: gl::types::GLenum = 0x0507;
+
+//The first thing that went badly wrong with GL, the window shows it instead of drawing on a broken context
+static GL_FAILURE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn gl_error_name(code: gl::types::GLenum) -> &'static str {
+    match code {
+        gl::NO_ERROR => "no error",
+        gl::INVALID_ENUM => "invalid enum",
+        gl::INVALID_VALUE => "invalid value",
+        gl::INVALID_OPERATION => "invalid operation",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "invalid framebuffer operation",
+        gl::OUT_OF_MEMORY => "out of memory",
+        gl::STACK_UNDERFLOW => "stack underflow",
+        gl::STACK_OVERFLOW => "stack overflow",
+        GL_CONTEXT_LOST => "context lost",
+        _ => "unknown error",
+    }
+}
+
+//Anything else is a bug in one call, these mean nothing drawn from here on can be trusted
+pub fn is_fatal_gl_error(code: gl::types::GLenum) -> bool {
+    code == gl::OUT_OF_MEMORY || code == GL_CONTEXT_LOST
+}
+
+//Only the first failure is kept, whatever follows it is usually fallout
+pub fn report_gl_failure(message: String) {
+    info!("GL failure: {}", message);
+    let mut failure = GL_FAILURE.lock();
+    if failure.is_none() {
+        *failure = Some(message);
+    }
+}
+
+pub fn gl_failure() -> Option<String> {
+    GL_FAILURE.lock().clone()
+}
+
+//Everything that didn't load at startup in one message, with a hint when it looks like the game was started from the wrong folder
+pub fn startup_failure_message(errors: &[String]) -> Option<String> {
+    if errors.is_empty() {
+        return None;
+    }
+    let mut message = format!("The game couldn't start:\n{}", errors.join("\n"));
+    if errors.iter().any(|e| e.starts_with("Couldn't find")) {
+        message.push_str("\nIt has to be run from the folder that has the assets folder in it.");
+    }
+    Some(message)
+}
+
+//Logs every error GL has queued up since the last check, true when there were none
+pub fn gl_check(context: &str) -> bool {
+    let mut clean = true;
+    //A lost context can keep handing out errors, don't spin on it
+    for _ in 0..16 {
+        let error = unsafe { gl::GetError() };
+        if error == gl::NO_ERROR {
+            break;
+        }
+        clean = false;
+        info!("OpenGL Error after {}: {} ({})", context, error, gl_error_name(error));
+        if is_fatal_gl_error(error) {
+            report_gl_failure(format!(
+                "The graphics driver reported {} after {}. Updating your graphics driver may help.",
+                gl_error_name(error),
+                context
+            ));
+            break;
+        }
+    }
+    clean
+}
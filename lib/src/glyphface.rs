@@ -1,3 +1,5 @@
+use crate::textureface::{atlas, AtlasLayout, GLYPHS_PER_ROW, GLYPH_PX};
+
 pub struct GlyphFace {
     pub tlx: f32,
     pub tly: f32, 
@@ -25,21 +27,26 @@ impl GlyphFace {
         return gf;
     }
     pub fn set_char(&mut self, code: u8) {
-        static FATLX: f32 = 288.0/544.0;
-        static FATLY: f32 = 0.0;
-        static GLYPH_WIDTH: f32 = 16.0/544.0;
+        self.set_char_in(&atlas(), code);
+    }
+    pub fn set_char_in(&mut self, atlas: &AtlasLayout, code: u8) {
+        let (originx, originy) = atlas.glyph_origin_px();
+        let fatlx: f32 = originx as f32 / atlas.width as f32;
+        let fatly: f32 = originy as f32 / atlas.height as f32;
+        let glyph_width: f32 = GLYPH_PX as f32 / atlas.width as f32;
+        let glyph_height: f32 = GLYPH_PX as f32 / atlas.height as f32;
     
         let offset: i32 = code as i32 - 32;
-        let x_offset: f32 = (offset % 16) as f32 * GLYPH_WIDTH;
-        let y_offset = ((offset/16) as u8) as f32 * GLYPH_WIDTH;
+        let x_offset: f32 = (offset % GLYPHS_PER_ROW as i32) as f32 * glyph_width;
+        let y_offset = ((offset/GLYPHS_PER_ROW as i32) as u8) as f32 * glyph_height;
         
-        self.tlx = FATLX + x_offset;
-        self.tly = FATLY + y_offset;
-        self.trx = self.tlx + GLYPH_WIDTH;
+        self.tlx = fatlx + x_offset;
+        self.tly = fatly + y_offset;
+        self.trx = self.tlx + glyph_width;
         self.tr_y = self.tly;
         self.brx = self.trx;
-        self.bry = self.tr_y + GLYPH_WIDTH;
+        self.bry = self.tr_y + glyph_height;
         self.blx = self.tlx;
-        self.bly = self.tly + GLYPH_WIDTH;
+        self.bly = self.tly + glyph_height;
     }
 }
\ No newline at end of file
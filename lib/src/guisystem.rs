@@ -16,27 +16,29 @@ use crate::{shader::Shader, text::Text, texture::Texture};
 
 pub struct GuiSystem {
     pub texts: Vec<Text>,
-    pub window: Arc<RwLock<PWindow>>,
+    pub window: Option<Arc<RwLock<PWindow>>>,
     pub menu_shader: Shader
 }
 
 
 
 impl GuiSystem {
-    pub fn new(window: &Arc<RwLock<PWindow>>, texture: &Texture) -> GuiSystem {
+    //Headless there's no window to lay text out on, so there are no texts
+    pub fn new(window: Option<&Arc<RwLock<PWindow>>>, texture: &Texture) -> GuiSystem {
 
 
-        let menu_shader = Shader::new("assets/menuvert.glsl", "assets/menufrag.glsl");
+        let menu_shader = Shader::new("assets/menuvert.glsl", "assets/menufrag.glsl").unwrap_or_else(Shader::failed);
         let mut texts = Vec::new();
 
+        if let Some(window) = window {
+            texts.push(Text::new("", &window.read(), menu_shader.shader_id, Vec2::new(100.0,100.0), texture.id));
 
-        texts.push(Text::new("", &window.read(), menu_shader.shader_id, Vec2::new(100.0,100.0), texture.id));
-
-        texts.push(Text::new("Press B to board the ship.", &window.read(), menu_shader.shader_id, Vec2::new(800.0,100.0), texture.id));
+            texts.push(Text::new("Press B to board the ship.", &window.read(), menu_shader.shader_id, Vec2::new(800.0,100.0), texture.id));
+        }
 
         GuiSystem {
             texts,
-            window: window.clone(),
+            window: window.cloned(),
             menu_shader
         }
     }
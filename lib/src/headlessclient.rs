@@ -0,0 +1,123 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use glam::Vec3;
+use lockfree::queue::Queue;
+use parking_lot::{Mutex, RwLock};
+use tracing::info;
+
+use crate::camera::Camera;
+use crate::chunk::ChunkSystem;
+use crate::game::{HEADLESS, PLAYERPOS, SHOULDRUN};
+use crate::modelentity::PLAYER_UPDATE_INTERVAL;
+use crate::network::NetworkConnector;
+use crate::server_types::{Message, MessageType};
+use crate::vec::IVec3;
+
+//A bot walks a loop this wide around where it spawns
+pub const BOT_PATH_RADIUS: f32 = 24.0;
+//Blocks per second, about a walking player
+pub const BOT_WALK_SPEED: f32 = 4.0;
+//How often a bot places a block, and on the next go takes it away again
+pub const BOT_EDIT_INTERVAL: Duration = Duration::from_secs(5);
+const BOT_EDIT_BLOCK: u32 = 5;
+const BOT_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+//Where a bot is after walking its loop for this long, and which way it's heading
+pub fn scripted_path(center: Vec3, seconds: f32) -> (Vec3, Vec3) {
+    let angle = seconds * BOT_WALK_SPEED / BOT_PATH_RADIUS;
+    let pos = center + Vec3::new(angle.cos(), 0.0, angle.sin()) * BOT_PATH_RADIUS;
+    let dir = Vec3::new(-angle.sin(), 0.0, angle.cos());
+    (pos, dir)
+}
+
+//A block set for the bot's scratch block, placing it or clearing it depending on the turn
+pub fn bot_edit(spot: IVec3, placing: bool) -> Message {
+    Message::new(
+        MessageType::BlockSet,
+        Vec3::new(spot.x as f32, spot.y as f32, spot.z as f32),
+        0.0,
+        if placing { BOT_EDIT_BLOCK } else { 0 },
+    )
+}
+
+//One simulated player, no window or GL. Run one process per player to load a server.
+pub fn run_headless_client(address: String) {
+    unsafe {
+        HEADLESS = true;
+        SHOULDRUN = true;
+    }
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(10, 0, 0, true)));
+    let commqueue = Arc::new(Queue::new());
+    let hpcommqueue = Arc::new(Queue::new());
+    let knowncams = Arc::new(DashMap::new());
+    let my_uuid = Arc::new(RwLock::new(None));
+    let nsme = Arc::new(DashMap::new());
+    let cam = Arc::new(Mutex::new(Camera::new()));
+    let pme = Arc::new(DashMap::new());
+    let chest_registry = Arc::new(DashMap::new());
+    let sendqueue = Arc::new(Queue::new());
+
+    let mut netconn = NetworkConnector::new(
+        &csys, &commqueue, &hpcommqueue, &knowncams, &my_uuid, &nsme, &cam, &pme, &chest_registry, &sendqueue,
+    );
+    info!("Headless client connecting to {}", address);
+    netconn.connect(address.clone());
+
+    while !netconn.received_world.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    let spawn_y = csys.read().highest_solid_y(0, 0).map_or(100.0, |y| y as f32 + 2.0);
+    let center = Vec3::new(0.0, spawn_y, 0.0);
+    info!("Headless client in the world, walking around {}", center);
+
+    let start = Instant::now();
+    let mut last_edit = Instant::now();
+    let mut last_stats = Instant::now();
+    let mut edit_spot: Option<IVec3> = None;
+    let mut received = 0;
+
+    while unsafe { SHOULDRUN } {
+        let (mut pos, dir) = scripted_path(center, start.elapsed().as_secs_f32());
+        if let Some(y) = csys.read().highest_solid_y(pos.x.floor() as i32, pos.z.floor() as i32) {
+            pos.y = y as f32 + 2.0;
+        }
+        let yaw = dir.z.atan2(dir.x).to_degrees();
+        unsafe {
+            PLAYERPOS.store(pos, dir, yaw, 0.0);
+        }
+
+        if last_edit.elapsed() >= BOT_EDIT_INTERVAL {
+            last_edit = Instant::now();
+            //Put one up over the bot's head (only into air), then take that same one down, so the world ends up as it was
+            match edit_spot.take() {
+                Some(spot) => netconn.send(&bot_edit(spot, false)),
+                None => {
+                    let spot = IVec3::new(pos.x.floor() as i32, pos.y as i32 + 3, pos.z.floor() as i32);
+                    if csys.read().blockat(spot) == 0 {
+                        edit_spot = Some(spot);
+                        netconn.send(&bot_edit(spot, true));
+                    }
+                }
+            }
+        }
+
+        //Nobody's drawing any of this, just keep the queues from growing
+        while commqueue.pop().is_some() {
+            received += 1;
+        }
+        while hpcommqueue.pop().is_some() {
+            received += 1;
+        }
+        if last_stats.elapsed() >= BOT_STATS_INTERVAL {
+            last_stats = Instant::now();
+            info!("Headless client at {}, {} messages received, {} other players", pos, received, knowncams.len());
+        }
+
+        thread::sleep(Duration::from_secs_f64(PLAYER_UPDATE_INTERVAL));
+    }
+}
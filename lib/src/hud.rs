@@ -1,18 +1,22 @@
 
 use std::sync::*;
 use parking_lot::{Mutex, RwLock};
-use atomic::{AtomicI32, AtomicI8};
+use atomic::AtomicI32;
+use atomic_float::AtomicF32;
 use gl::types::{GLuint, GLvoid};
 use glam::Vec2;
 use glfw::PWindow;
 
 use crate::game::ROWLENGTH;
 use crate::shader::Shader;
-use crate::textureface::{TextureFace, ONE_OVER_16};
+use crate::textureface::{atlas, TextureFace};
 use crate::vec::{self, IVec3};
 use crate::windowandkey::{WINDOWHEIGHT, WINDOWWIDTH};
 use crate::{game, windowandkey};
 
+//The HUD offsets and sizes were laid out on a window this size, other shapes get corrected from it
+pub const HUD_REFERENCE_SIZE: (f32, f32) = (1280.0, 720.0);
+
 #[derive(Clone)]
 pub enum SlotIndexType {
     ChestSlot(i32),
@@ -40,16 +44,28 @@ impl HudElement {
         }
     }
 
+    //Where the element really lands in NDC on this window. Sizes follow the window height and
+    //x is squeezed by the aspect, so squares stay square and the hotbar keeps its proportions.
+    pub fn placed(&self, winsize: (i32, i32)) -> (Vec2, Vec2) {
+        let (width, height) = (winsize.0.max(1) as f32, winsize.1.max(1) as f32);
+        let (refw, refh) = HUD_REFERENCE_SIZE;
+        let squash = (refw / refh) / (width / height);
+        let pos = Vec2::new(self.normalized_pos.x * squash, self.normalized_pos.y);
+        let size = Vec2::new(self.size.x * 800.0 / refw * squash, self.size.y * 800.0 / refh);
+        (pos, size)
+    }
+
     pub fn overlaps(&self, x: f64, y: f64) -> bool {
         unsafe {
+            let (pos, size) = self.placed((windowandkey::WINDOWWIDTH, windowandkey::WINDOWHEIGHT));
             let xnorm = x / windowandkey::WINDOWWIDTH as f64;
             let ynorm = y / windowandkey::WINDOWHEIGHT as f64;
 
             let ndcx = 2.0 * xnorm - 1.0;
             let ndcy = 1.0 - 2.0 * ynorm;
 
-            if ndcx >= self.normalized_pos.x as f64 - (self.size.x as f64 / 2.0) && ndcx <= self.normalized_pos.x as f64 + (self.size.x as f64 / 2.0)  {
-                if ndcy <= self.normalized_pos.y as f64 + (self.size.y as f64 / 2.0) && ndcy >= self.normalized_pos.y as f64 - (self.size.y as f64 / 2.0) {
+            if ndcx >= pos.x as f64 - (size.x as f64 / 2.0) && ndcx <= pos.x as f64 + (size.x as f64 / 2.0)  {
+                if ndcy <= pos.y as f64 + (size.y as f64 / 2.0) && ndcy >= pos.y as f64 - (size.y as f64 / 2.0) {
                     return true;
                 }
             }
@@ -107,7 +123,8 @@ pub struct Hud {
 
     pub healthvbo: GLuint,
     pub shader: Shader,
-    pub window: Arc<RwLock<PWindow>>,
+    //None on a headless game, sizes come from the window statics then
+    pub window: Option<Arc<RwLock<PWindow>>>,
     pub dirty: bool,
     pub elements: Vec<HudElement>,
     pub chestelements: Vec<HudElement>,
@@ -123,34 +140,38 @@ pub struct Hud {
     pub chestdirty: bool,
     pub highlightedslot: SlotIndexType,
     pub mousetrans: Vec2,
-    pub health: Arc<AtomicI8>,
-    pub stamina: Arc<AtomicI32>
+    pub health: Arc<AtomicF32>,
+    pub stamina: Arc<AtomicI32>,
+    //Size the geometry was last built for, a resize rebuilds it
+    pub winsize: (i32, i32)
 }
 
 impl Hud {
-    pub fn new(window: &Arc<RwLock<PWindow>>, texture: GLuint, health: Arc<AtomicI8>, stamina: Arc<AtomicI32>) -> Hud {
+    pub fn new(window: Option<&Arc<RwLock<PWindow>>>, texture: GLuint, health: Arc<AtomicF32>, stamina: Arc<AtomicI32>) -> Hud {
         let mut vbo: GLuint = 0;
         let mut chestvbo: GLuint = 0;
         let mut healthvbo: GLuint = 0;
-        let shader = Shader::new("assets/menuvert.glsl", "assets/menufrag.glsl");
+        let shader = Shader::new("assets/menuvert.glsl", "assets/menufrag.glsl").unwrap_or_else(Shader::failed);
         let mut chestvao: GLuint = 0;
         let mut healthvao: GLuint = 0;
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::BindVertexArray(shader.vao);
-            gl::CreateVertexArrays(1, &mut chestvao);
-            gl::CreateVertexArrays(1, &mut healthvao);
-            gl::CreateBuffers(1, &mut vbo);
-            gl::CreateBuffers(1, &mut chestvbo);
-            gl::CreateBuffers(1, &mut healthvbo);
-            gl::BindTextureUnit(0, texture);
+        if game::gl_allowed() {
+            unsafe {
+                gl::BindVertexArray(shader.vao);
+                gl::CreateVertexArrays(1, &mut chestvao);
+                gl::CreateVertexArrays(1, &mut healthvao);
+                gl::CreateBuffers(1, &mut vbo);
+                gl::CreateBuffers(1, &mut chestvbo);
+                gl::CreateBuffers(1, &mut healthvbo);
+                gl::BindTextureUnit(0, texture);
+            }
         }
         Hud {
             vbo,
             chestvbo,
             healthvbo,
             shader,
-            window: window.clone(),
+            window: window.cloned(),
             dirty: true,
             elements: Vec::new(),
             chestelements: Vec::new(),
@@ -165,27 +186,32 @@ impl Hud {
             highlightedslot: SlotIndexType::None,
             mousetrans: Vec2::ZERO,
             health: health.clone(),
-            stamina
+            stamina,
+            winsize: (0, 0)
         }
     }
     pub fn update(&mut self) {
+        let winsize = match &self.window {
+            Some(window) => window.read().get_size(),
+            None => unsafe { (WINDOWWIDTH, WINDOWHEIGHT) },
+        };
+        if winsize != self.winsize {
+            self.winsize = winsize;
+            self.dirty = true;
+        }
         if self.dirty {
 
             fn bindthisgeo(vbo: GLuint, elements: &Vec<HudElement>, vao: GLuint, bumped_slot: i32, winsize: (i32, i32)) -> i32 {
                 let mut allgeo = Vec::new();
                 for (index, element) in elements.iter().enumerate() {
 
-                    let (width,height) = winsize;
-
-                    let realsize = (element.size*800.0) / Vec2::new(width as f32, height as f32);
-
-                    let mut realpos = element.normalized_pos;
+                    let (mut realpos, realsize) = element.placed(winsize);
                     let mut xoff = 0.0;
                     if bumped_slot != -1 
                     {
                         if bumped_slot as usize == index || bumped_slot as usize + ROWLENGTH as usize == index {
                             if bumped_slot as usize == index {
-                                xoff = ONE_OVER_16;
+                                xoff = atlas().cell_u();
                             }
                                 realpos += Vec2::new(0.0, 0.03);
                             
@@ -244,7 +270,6 @@ impl Hud {
 
             let elements2 = self.chestelements.clone();
 
-            let winsize = self.window.read().get_size();
             self.count = bindthisgeo( vbo, &elements1, vao1, self.bumped_slot as i32, winsize);
             self.chestcount = bindthisgeo( chestvbo, &elements2, vao2, -1, winsize);
             self.dirty = false;
@@ -257,7 +282,7 @@ impl Hud {
 
     pub fn draw_health(&self) {
 
-        static mut LASTHEALTH: i8 = -99;
+        static mut LASTHEALTH: f32 = -99.0;
         static mut LASTSTAMINA: i32 = -999;
 
         let redface   = TextureFace::new(0, 5);
@@ -291,7 +316,7 @@ impl Hud {
 
             let h = self.health.load(atomic::Ordering::Relaxed);
 
-            let redwidth = (h as f32 * wwf) / WINDOWWIDTH as f32;
+            let redwidth = (h * wwf) / WINDOWWIDTH as f32;
 
             let stam = self.stamina.load(atomic::Ordering::Relaxed);
 
@@ -383,6 +408,7 @@ impl Hud {
     }
     #[cfg(feature = "glfw")]
     pub fn draw(&self) {
+        game::debug_assert_gl_allowed("Hud::draw");
         unsafe {
 
             gl::Disable(gl::CULL_FACE);
@@ -1,3 +1,4 @@
+use crate::blockinfo::Blocks;
 use crate::game::ROWLENGTH;
 
 #[derive(Clone, PartialEq)]
@@ -6,6 +7,27 @@ pub struct Inventory {
     pub inv: [(u32, u32); ROWLENGTH as usize]
 }
 
+impl Inventory {
+    //How many more of id fit, counting partial stacks of it and empty slots
+    pub fn room_for(&self, id: u32) -> u32 {
+        let max = Blocks::get_max_stack(id);
+        self.inv.iter()
+            .map(|item| if item.0 == id { max.saturating_sub(item.1) } else if item.0 == 0 { max } else { 0 })
+            .sum()
+    }
+
+    //The slot pick block jumps to, the one already selected wins if it holds id too
+    pub fn slot_holding(&self, id: u32, selected: usize) -> Option<usize> {
+        if id == 0 {
+            return None;
+        }
+        if self.inv.get(selected).map_or(false, |item| item.0 == id) {
+            return Some(selected);
+        }
+        self.inv.iter().position(|item| item.0 == id)
+    }
+}
+
 #[derive(Clone)]
 pub struct ChestInventory {
     pub dirty: bool,
@@ -1,8 +1,5 @@
 use std::collections::HashMap;
 
-
-use once_cell::sync::Lazy;
-
 pub enum Rebindable {
     Key(i32),
     MouseButton(glfw::MouseButton)
@@ -19,3 +16,54 @@ pub static mut LISTENINGFORREBIND: bool = false;
 pub static mut ABOUTTOREBIND: Option<AboutToRebind> = None;
 
 
+
+//Keys that glfw doesn't give a printable name for
+static NAMED_KEYS: [(glfw::Key, &str); 22] = [
+    (glfw::Key::Space, "Space"),
+    (glfw::Key::Escape, "Escape"),
+    (glfw::Key::Enter, "Enter"),
+    (glfw::Key::Tab, "Tab"),
+    (glfw::Key::Backspace, "Backspace"),
+    (glfw::Key::Up, "Up"),
+    (glfw::Key::Down, "Down"),
+    (glfw::Key::Left, "Left"),
+    (glfw::Key::Right, "Right"),
+    (glfw::Key::LeftShift, "Left Shift"),
+    (glfw::Key::RightShift, "Right Shift"),
+    (glfw::Key::LeftControl, "Left Ctrl"),
+    (glfw::Key::RightControl, "Right Ctrl"),
+    (glfw::Key::LeftAlt, "Left Alt"),
+    (glfw::Key::RightAlt, "Right Alt"),
+    (glfw::Key::CapsLock, "Caps Lock"),
+    (glfw::Key::Insert, "Insert"),
+    (glfw::Key::Delete, "Delete"),
+    (glfw::Key::Home, "Home"),
+    (glfw::Key::End, "End"),
+    (glfw::Key::PageUp, "Page Up"),
+    (glfw::Key::PageDown, "Page Down"),
+];
+
+pub fn key_name(scancode: i32) -> String {
+    if let Some(name) = glfw::get_key_name(None, Some(scancode)) {
+        return name.to_uppercase();
+    }
+    for (key, name) in NAMED_KEYS.iter() {
+        if key.get_scancode() == Some(scancode) {
+            return name.to_string();
+        }
+    }
+    format!("Key {}", scancode)
+}
+
+//Moves the action bound to old onto new. If new already had an action it goes to old,
+//so picking a taken key swaps the two instead of leaving one unbound.
+pub fn rebind<K: std::hash::Hash + Eq>(binds: &mut HashMap<K, String>, old: K, new: K) {
+    if old == new {
+        return;
+    }
+    if let Some(action) = binds.remove(&old) {
+        if let Some(displaced) = binds.insert(new, action) {
+            binds.insert(old, displaced);
+        }
+    }
+}
@@ -28,16 +28,25 @@ pub mod guisystem;
 pub mod hud;
 pub mod textureface;
 pub mod drops;
+pub mod particles;
 pub mod audio;
 pub mod monsters;
 pub mod serializemap;
 pub mod server_types;
 pub mod network;
+pub mod headlessclient;
 pub mod inventory;
 pub mod visions;
 pub mod specialblocks;
 pub mod recipes;
+pub mod droptables;
+pub mod animatedtiles;
+pub mod music;
+pub mod ambience;
+pub mod blockedits;
 pub mod statics;
 pub mod playerposition;
+pub mod saveslots;
 pub mod tools;
 pub mod keybinds;
+pub mod glcheck;
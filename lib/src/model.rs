@@ -1,17 +1,17 @@
 
 
-use std::{fs, path::Path, sync::Arc};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
 use tracing::info;
 use dashmap::DashMap;
 use gl::types::{GLsizeiptr, GLuint, GLvoid};
-use glam::{Mat4, Vec3, Vec4};
+use glam::{Mat4, Quat, Vec3, Vec4};
 use glfw::ffi::glfwGetTime;
 use gltf::{accessor::{Dimensions}, image::Source, mesh::util::ReadIndices};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use uuid::Uuid;
 use crate::{camera::Camera, planetinfo::Planets};
 use gltf::{animation::util::ReadOutputs};
-use crate::{collisioncage::{CollCage, Side}, game::*, modelentity::{AggroTarget, ModelEntity}, vec};
+use crate::{collisioncage::{BoundBox, CollCage, Side}, game::*, modelentity::{AggroTarget, ModelEntity, PLAYER_UPDATE_INTERVAL}, vec};
 use percent_encoding::percent_decode_str;
 
 
@@ -146,6 +146,121 @@ fn rasterize_triangle(triangle: [Vec3; 3], collision_map: &DashMap<vec::IVec3, u
         }
     }
 }
+//Finds the keyframes around time and how far between them it is
+fn keyframe_span(keyframes: &[(f32, Vec<f32>)], time: f32) -> Option<(&[f32], &[f32], f32)> {
+    let first = keyframes.first()?;
+    if keyframes.len() == 1 || time <= first.0 {
+        return Some((&first.1, &first.1, 0.0));
+    }
+    for pair in keyframes.windows(2) {
+        let (t0, ref a) = pair[0];
+        let (t1, ref b) = pair[1];
+        if time < t1 {
+            let f = if t1 > t0 { (time - t0) / (t1 - t0) } else { 0.0 };
+            return Some((a, b, f.clamp(0.0, 1.0)));
+        }
+    }
+    let last = keyframes.last()?;
+    Some((&last.1, &last.1, 0.0))
+}
+
+pub fn animation_duration(animation: &Animation) -> f32 {
+    animation.channels.iter()
+        .filter_map(|c| c.keyframes.last().map(|k| k.0))
+        .fold(0.0, f32::max)
+}
+
+//Global transform of every node with the animation applied at time (looped)
+pub fn sample_animation(nodes: &[Node], animation: &Animation, time: f32) -> Vec<Mat4> {
+    let duration = animation_duration(animation);
+    let t = if duration > 0.0 { time % duration } else { 0.0 };
+
+    let mut trs: Vec<(Vec3, Quat, Vec3)> = nodes.iter().map(|n| {
+        let (s, r, tr) = n.transform.to_scale_rotation_translation();
+        (tr, r, s)
+    }).collect();
+
+    for channel in &animation.channels {
+        if channel.node_index >= trs.len() {
+            continue;
+        }
+        if let Some((a, b, f)) = keyframe_span(&channel.keyframes, t) {
+            let node = &mut trs[channel.node_index];
+            match channel.property {
+                gltf::animation::Property::Translation if a.len() >= 3 && b.len() >= 3 => {
+                    node.0 = Vec3::new(a[0], a[1], a[2]).lerp(Vec3::new(b[0], b[1], b[2]), f);
+                }
+                gltf::animation::Property::Rotation if a.len() >= 4 && b.len() >= 4 => {
+                    let qa = Quat::from_xyzw(a[0], a[1], a[2], a[3]);
+                    let qb = Quat::from_xyzw(b[0], b[1], b[2], b[3]);
+                    node.1 = qa.slerp(qb, f).normalize();
+                }
+                gltf::animation::Property::Scale if a.len() >= 3 && b.len() >= 3 => {
+                    node.2 = Vec3::new(a[0], a[1], a[2]).lerp(Vec3::new(b[0], b[1], b[2]), f);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let locals: Vec<Mat4> = trs.iter()
+        .map(|(tr, r, s)| Mat4::from_scale_rotation_translation(*s, *r, *tr))
+        .collect();
+    global_transforms(nodes, &locals)
+}
+
+//Walks the node hierarchy from its roots, multiplying down parent transforms
+pub fn global_transforms(nodes: &[Node], locals: &[Mat4]) -> Vec<Mat4> {
+    let mut is_child = vec![false; nodes.len()];
+    for node in nodes {
+        for c in &node.children {
+            if *c < is_child.len() {
+                is_child[*c] = true;
+            }
+        }
+    }
+
+    let mut globals = vec![Mat4::IDENTITY; nodes.len()];
+    let mut stack: Vec<(usize, Mat4)> = (0..nodes.len())
+        .filter(|i| !is_child[*i])
+        .map(|i| (i, Mat4::IDENTITY))
+        .collect();
+
+    while let Some((i, parent)) = stack.pop() {
+        let g = parent * locals[i];
+        globals[i] = g;
+        for c in &nodes[i].children {
+            if *c < nodes.len() {
+                stack.push((*c, g));
+            }
+        }
+    }
+    globals
+}
+
+//Rest and current global node transforms for an entity (posed is rest when nothing is playing)
+fn node_poses(modelent: &ModelEntity, nodes: &[Node]) -> (Vec<Mat4>, Vec<Mat4>) {
+    let locals: Vec<Mat4> = nodes.iter().map(|n| n.transform).collect();
+    let rest = global_transforms(nodes, &locals);
+    let posed = match modelent.current_animation.and_then(|a| modelent.animations.get(a)) {
+        Some(animation) => sample_animation(nodes, animation, modelent.animation_time),
+        None => rest.clone(),
+    };
+    (rest, posed)
+}
+
+//Joint matrices for a skin, relative to the node the skinned mesh hangs off
+pub fn joint_matrices(skin: &Skin, posed: &[Mat4], mesh_node: usize) -> Vec<Mat4> {
+    let mesh_inv = posed.get(mesh_node).map(|m| m.inverse()).unwrap_or(Mat4::IDENTITY);
+    skin.joints.iter()
+        .take(MAX_JOINTS)
+        .map(|j| mesh_inv * posed.get(j.node_index).copied().unwrap_or(Mat4::IDENTITY) * j.inverse_bind_matrix)
+        .collect()
+}
+
+//Has to match the jointMatrices array size in mvert.glsl
+pub const MAX_JOINTS: usize = 64;
+
 enum ModelEntityType<'a> {
     Static(&'a ModelEntity),
     NonStatic(&'a ModelEntity),
@@ -194,17 +309,17 @@ impl Game {
         let mut modent = ModelEntity::new_with_jump_height(model_index, pos, scale, rot, &self.chunksys, &self.camera, jump_height, hostile);
         
 
-        //let animations = self.animations[model_index].clone();
-        //let nodes = self.nodes[model_index].clone();
-
-        modent.animations = Vec::new();
-        modent.nodes = Vec::new();
+        modent.animations = self.animations.get(model_index).cloned().unwrap_or_default();
+        modent.nodes = self.nodes.get(model_index).cloned().unwrap_or_default();
+        if !modent.animations.is_empty() {
+            modent.current_animation = Some(0);
+        }
 
         let solid_pred: Box<dyn Fn(vec::IVec3) -> bool  + Send + Sync> = {
             let csys_arc = Arc::clone(&self.chunksys);
             //println!("This thing thinks the seed is {}", csys_arc.read().currentseed.read());
             Box::new(move |v: vec::IVec3| {
-                return csys_arc.read().collision_predicate(v);
+                return csys_arc.read().is_solid(v);
             })
         };
 
@@ -222,11 +337,14 @@ impl Game {
 
         modent.animations = animations;
         modent.nodes = nodes;
+        if !modent.animations.is_empty() {
+            modent.current_animation = Some(0);
+        }
 
         let solid_pred: Box<dyn Fn(vec::IVec3) -> bool  + Send + Sync> = {
             let csys_arc = Arc::clone(&self.chunksys);
             Box::new(move |v: vec::IVec3| {
-                return csys_arc.read().collision_predicate(v);
+                return csys_arc.read().is_solid(v);
             })
         };
 
@@ -239,6 +357,7 @@ impl Game {
     pub fn insert_player_model_entity(&mut self, id: Uuid, model_index: usize, pos: Vec3, scale: f32, rot: Vec3, jump_height: f32) {
         let mut modent = ModelEntity::new_with_id(0/*Does not use model entities id system, uses players id system */, model_index, pos, scale, rot, &self.chunksys, &self.camera, false);
         modent.allowable_jump_height = jump_height;
+        modent.update_interval = PLAYER_UPDATE_INTERVAL;
 
        // let animations = self.animations[model_index].clone();
         //let nodes = self.nodes[model_index].clone();
@@ -249,7 +368,7 @@ impl Game {
         let solid_pred: Box<dyn Fn(vec::IVec3) -> bool  + Send + Sync> = {
             let csys_arc = Arc::clone(&self.chunksys);
             Box::new(move |v: vec::IVec3| {
-                return csys_arc.read().collision_predicate(v);
+                return csys_arc.read().is_solid(v);
             })
         };
 
@@ -266,6 +385,10 @@ impl Game {
         for mut model in self.non_static_model_entities.iter_mut() {
             let model: &mut ModelEntity = model.value_mut();
 
+            if model.current_animation.is_some() {
+                model.animation_time += self.delta_time;
+            }
+
             if model.sounding {
                 if model.soundtimer < model.soundinterval {
                     model.soundtimer += self.delta_time + rng.gen_range(0.0..0.001);
@@ -310,7 +433,7 @@ impl Game {
             
         }
         if tookdamage {
-            self.take_damage(4);
+            self.take_damage(4.0);
         }
     }
 
@@ -318,6 +441,8 @@ impl Game {
 
     pub fn update_non_static_model_entities(&mut self) {
 
+        let grav = self.vars.gravity;
+
         //println!("UYdpawdaw");
         //info!("Updating NSMEs, delta time: {}", self.delta_time);
         for mut model in self.non_static_model_entities.iter_mut() {
@@ -331,8 +456,6 @@ impl Game {
                 model.generate_chunk_on_server_if_not_generated();
             }
 
-            const GRAV: f32 = 9.8;
-
             if !model.grounded && !model.jumping_up {
                 model.time_falling_scalar = (model.time_falling_scalar + self.delta_time * 5.0).min(3.0);
             } else {
@@ -343,7 +466,7 @@ impl Game {
     
             if !model.grounded && !model.jumping_up {
                 model.velocity +=
-                    Vec3::new(0.0, -GRAV * model.time_falling_scalar * self.delta_time, 0.0);
+                    Vec3::new(0.0, -grav * model.time_falling_scalar * self.delta_time, 0.0);
             }
     
             if model.jumping_up {
@@ -369,15 +492,15 @@ impl Game {
                 model.controls.up = false;
             }
 
-            if let Some(_current_animation) = model.current_animation {
+            if model.current_animation.is_some() {
                 model.animation_time += self.delta_time;
-                //apply_animation(&mut model.nodes, &model.animations[current_animation], model.animation_time);
             }
 
             let cc_center = model.position + Vec3::new(0.0, -1.0, 0.0);
             model.coll_cage.update_readings(cc_center);
             model.respond_to_own_controls(&self.delta_time, 5.0);
             model.behavior_loop(&self.delta_time, &self.known_cameras);
+            model.avoid_walls();
 
             let makebelievepos = model.position + Vec3::new(0.0, self.planet_y_offset, 0.0);
 
@@ -475,9 +598,149 @@ impl Game {
             model.recalculate();
             //camlock.recalculate();
         }
+
+        self.separate_model_entities();
+    }
+
+    pub fn drop_mob_loot(&mut self, pos: Vec3, model_index: usize) {
+        #[cfg(feature = "glfw")]
+        if let Some((item, amt)) = Planets::get_mob_drop(model_index) {
+            self.drops.add_drop(pos, item, amt);
+        }
+    }
+
+    fn player_box(pos: Vec3) -> BoundBox {
+        let mut bb = BoundBox::new(Vec3::ZERO);
+        bb.set_center(pos + Vec3::new(0.0, -0.5, 0.0), 0.2, 0.95);
+        bb
+    }
+
+    //Whether a body standing at pos would be inside terrain
+    fn body_in_terrain(&self, pos: Vec3) -> bool {
+        let csys = self.chunksys.read();
+        let spot = vec::IVec3::new(pos.x.floor() as i32, pos.y.floor() as i32, pos.z.floor() as i32);
+        csys.is_solid(spot) || csys.is_solid(spot + vec::IVec3::new(0, -1, 0))
+    }
+
+    //Pushes mobs out of each other and out of players. Mobs are bucketed by chunk so each only checks its neighbours
+    pub fn separate_model_entities(&mut self) {
+        let mut positions: HashMap<u32, Vec3> = HashMap::new();
+        let mut buckets: HashMap<vec::IVec2, Vec<u32>> = HashMap::new();
+        {
+            let csys = self.chunksys.read();
+            for model in self.non_static_model_entities.iter() {
+                let p = model.position;
+                let cpos = csys.spot_to_chunk_pos(&vec::IVec3::new(p.x.floor() as i32, p.y.floor() as i32, p.z.floor() as i32));
+                buckets.entry(cpos).or_default().push(model.id);
+                positions.insert(model.id, p);
+            }
+        }
+
+        let mut pushes: HashMap<u32, Vec3> = HashMap::new();
+
+        for (cpos, ids) in buckets.iter() {
+            for id in ids {
+                let mybox = ModelEntity::body_box_at(positions[id]);
+                for x in -1..=1 {
+                    for z in -1..=1 {
+                        let Some(others) = buckets.get(&(*cpos + vec::IVec2::new(x, z))) else {
+                            continue;
+                        };
+                        for other in others {
+                            if other <= id {
+                                continue;
+                            }
+                            if let Some(push) = mybox.push_out_xz(&ModelEntity::body_box_at(positions[other])) {
+                                *pushes.entry(*id).or_insert(Vec3::ZERO) += push * 0.5;
+                                *pushes.entry(*other).or_insert(Vec3::ZERO) -= push * 0.5;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        //Players shove mobs aside; on the server every connected player does
+        let offset = Vec3::new(0.0, self.planet_y_offset, 0.0);
+        let players: Vec<Vec3> = if self.headless {
+            self.known_cameras.iter().map(|c| *c.value() - offset).collect()
+        } else {
+            vec![self.camera.lock().position - offset]
+        };
+        for player in players {
+            let pbox = Self::player_box(player);
+            for (id, pos) in positions.iter() {
+                if pos.distance(player) > 3.0 {
+                    continue;
+                }
+                if let Some(push) = ModelEntity::body_box_at(*pos).push_out_xz(&pbox) {
+                    *pushes.entry(*id).or_insert(Vec3::ZERO) += push;
+                }
+            }
+        }
+
+        for (id, push) in pushes {
+            let newpos = positions[&id] + push;
+            if self.body_in_terrain(newpos) {
+                continue;
+            }
+            if let Some(mut model) = self.non_static_model_entities.get_mut(&id) {
+                model.position = newpos;
+            }
+        }
+    }
+
+    //Hits a mob and knocks it back. Returns its position, model index and health left, removing it if that killed it
+    pub fn damage_mob(&mut self, id: u32, damage: i32, dir: Vec3) -> Option<(Vec3, usize, i32)> {
+        let hit = match self.non_static_model_entities.get_mut(&id) {
+            Some(mut model) => {
+                model.health -= damage;
+                let flat = dir * Vec3::new(1.0, 0.0, 1.0);
+                if flat.length() > 0.0 {
+                    model.velocity += flat.normalize() * MOB_KNOCKBACK + Vec3::new(0.0, 2.0, 0.0);
+                }
+                Some((model.position, model.model_index, model.health))
+            }
+            None => None,
+        };
+
+        if let Some((pos, _, health)) = hit {
+            #[cfg(feature = "audio")]
+            if !self.headless {
+                unsafe {
+                    AUDIOPLAYER.play("assets/sfx/slam.mp3", &(pos + Vec3::new(0.0, self.planet_y_offset, 0.0)), &Vec3::ZERO, 0.5);
+                }
+            }
+            if health <= 0 {
+                self.non_static_model_entities.remove(&id);
+            }
+        }
+        hit
+    }
+
+    //Where the player ends up after being pushed out of any mob they walked into
+    pub fn push_player_out_of_mobs(&self, pos: Vec3) -> Vec3 {
+        let offset = Vec3::new(0.0, self.planet_y_offset, 0.0);
+        let pbox = Self::player_box(pos);
+        let mut push = Vec3::ZERO;
+        for model in self.non_static_model_entities.iter() {
+            if (model.position + offset).distance(pos) > 3.0 {
+                continue;
+            }
+            if let Some(p) = pbox.push_out_xz(&ModelEntity::body_box_at(model.position + offset)) {
+                push += p;
+            }
+        }
+        if push == Vec3::ZERO || self.body_in_terrain(pos + push) {
+            return pos;
+        }
+        pos + push
     }
 
     pub fn draw_models(&self) {
+        if self.headless {
+            return;
+        }
 
 
         #[cfg(feature = "glfw")]
@@ -544,6 +807,16 @@ impl Game {
                     
                 let index = modelent.model_index;
                 if index < self.gltf_vaos.len() && index < self.gltf_textures.len() {
+
+                let nodes: &[Node] = if modelent.nodes.is_empty() {
+                    self.nodes.get(index).map(|n| n.as_slice()).unwrap_or(&[])
+                } else {
+                    &modelent.nodes
+                };
+                let (rest, posed) = node_poses(modelent, nodes);
+
+                //Networked mobs glide from their previous update toward the latest one
+                let (drawpos, drawrot) = modelent.interpolated_pos_rot(glfwGetTime());
                        
                 let vaosetset = &self.gltf_vaos[index];
 
@@ -555,6 +828,19 @@ impl Game {
 
                     let texset = &texsetset[ind];
 
+                    let mesh_node = nodes.iter().position(|n| n.mesh == Some(ind));
+                    let skin = mesh_node
+                        .and_then(|ni| nodes[ni].skin)
+                        .and_then(|si| self.skins.get(index).and_then(|skins| skins.get(si)));
+
+                    //Skinned meshes are moved by their joints, rigid ones by their node
+                    let (anim_transform, joints) = match (mesh_node, skin) {
+                        (Some(ni), Some(skin)) => (Mat4::IDENTITY, joint_matrices(skin, &posed, ni)),
+                        (Some(ni), None) => (posed[ni] * rest[ni].inverse(), Vec::new()),
+                        _ => (Mat4::IDENTITY, Vec::new()),
+                    };
+                    let joint_floats: Vec<f32> = joints.iter().flat_map(|m| m.to_cols_array()).collect();
+
                     for(ii, vao) in vaoset.iter().enumerate() {
                         //info!("Doing Vao {ii} of Vaoset {ind} of Vaosetset {index}");
                         gl::BindVertexArray(*vao);
@@ -581,27 +867,57 @@ impl Game {
                                 modelent.scale,
                             );
 
+                            gl::UniformMatrix4fv(
+                                gl::GetUniformLocation(
+                                    self.modelshader.shader_id,
+                                    b"animTransform\0".as_ptr() as *const i8,
+                                ),
+                                1,
+                                gl::FALSE,
+                                anim_transform.to_cols_array().as_ptr(),
+                            );
+
+                            gl::Uniform1i(
+                                gl::GetUniformLocation(
+                                    self.modelshader.shader_id,
+                                    b"hasSkin\0".as_ptr() as *const i8,
+                                ),
+                                if joints.is_empty() { 0 } else { 1 },
+                            );
+
+                            if !joints.is_empty() {
+                                gl::UniformMatrix4fv(
+                                    gl::GetUniformLocation(
+                                        self.modelshader.shader_id,
+                                        b"jointMatrices\0".as_ptr() as *const i8,
+                                    ),
+                                    joints.len() as i32,
+                                    gl::FALSE,
+                                    joint_floats.as_ptr(),
+                                );
+                            }
+
                             match modelt {
-                                ModelEntityType::Static(entity) => {
+                                ModelEntityType::Static(_) => {
                                     gl::Uniform3f(
                                         gl::GetUniformLocation(
                                             self.modelshader.shader_id,
                                             b"pos\0".as_ptr() as *const i8,
                                         ),
-                                        entity.position.x,
-                                        entity.position.y  - 1.0,
-                                        entity.position.z
+                                        drawpos.x,
+                                        drawpos.y - 1.0,
+                                        drawpos.z
                                     );
                                 },
-                                ModelEntityType::NonStatic(entity) => {
+                                ModelEntityType::NonStatic(_) => {
                                     gl::Uniform3f(
                                         gl::GetUniformLocation(
                                             self.modelshader.shader_id,
                                             b"pos\0".as_ptr() as *const i8,
                                         ),
-                                        entity.position.x,
-                                        entity.position.y + self.planet_y_offset - 1.0,
-                                        entity.position.z
+                                        drawpos.x,
+                                        drawpos.y + self.planet_y_offset - 1.0,
+                                        drawpos.z
                                     );
                                 },
                             }
@@ -619,9 +935,9 @@ impl Game {
                                     self.modelshader.shader_id,
                                     b"lastpos\0".as_ptr() as *const i8,
                                 ),
-                                modelent.lastpos.x,
-                                modelent.lastpos.y  - 1.0,
-                                modelent.lastpos.z
+                                drawpos.x,
+                                drawpos.y - 1.0,
+                                drawpos.z
                             );
                             
 
@@ -630,14 +946,14 @@ impl Game {
                                     self.modelshader.shader_id,
                                     b"xrot\0".as_ptr() as *const i8,
                                 ),
-                                modelent.rot.x,
+                                drawrot.x,
                             );
                             gl::Uniform1f(
                                 gl::GetUniformLocation(
                                     self.modelshader.shader_id,
                                     b"yrot\0".as_ptr() as *const i8,
                                 ),
-                                modelent.rot.y,
+                                drawrot.y,
                             );
 
                             gl::Uniform1f(
@@ -645,7 +961,7 @@ impl Game {
                                     self.modelshader.shader_id,
                                     b"zrot\0".as_ptr() as *const i8,
                                 ),
-                                modelent.rot.z,
+                                drawrot.z,
                             );
 
                             match modelt {
@@ -709,9 +1025,9 @@ impl Game {
                                     self.modelshader.shader_id,
                                     b"lastrot\0".as_ptr() as *const i8,
                                 ),
-                                modelent.lastrot.x,
-                                modelent.lastrot.y,
-                                modelent.lastrot.z
+                                drawrot.x,
+                                drawrot.y,
+                                drawrot.z
                             );
 
 
@@ -730,10 +1046,10 @@ impl Game {
                                     self.modelshader.shader_id,
                                     b"viewDistance\0".as_ptr() as *const i8,
                                 ),
-                                8.0
+                                self.view_distance()
                             );
 
-                            let fogcol = Planets::get_fog_col(self.chunksys.read().planet_type as u32);
+                            let fogcol = self.current_fog_col();
 
                             gl::Uniform4f(
                                 gl::GetUniformLocation(
@@ -792,9 +1108,11 @@ impl Game {
 
         let animindex = self.animations.len();
         let nodeindex = self.nodes.len();
+        let skinindex = self.skins.len();
 
         self.animations.push(Vec::new());
         self.nodes.push(Vec::new());
+        self.skins.push(Vec::new());
 
         for animation in document.animations() {
             let mut channels = Vec::new();
@@ -841,13 +1159,15 @@ impl Game {
                 })
                 .collect();
 
-            self.skins.push(Skin { joints });
+            self.skins[skinindex].push(Skin { joints });
         }
 
         for node in document.nodes() {
             self.nodes[nodeindex].push(Node {
                 transform: Mat4::from_cols_array_2d(&node.transform().matrix()),
                 children: node.children().map(|child| child.index()).collect(),
+                mesh: node.mesh().map(|m| m.index()),
+                skin: node.skin().map(|s| s.index()),
             });
         }
     }
@@ -867,6 +1187,7 @@ impl Game {
     }
     #[cfg(feature = "glfw")]
     pub fn create_model_vbos(&mut self) {
+        debug_assert_gl_allowed("create_model_vbos");
         for (index, (document, buffers, _images)) in self.gltf_models.iter().enumerate() {
             self.gltf_counts.push(Vec::new());
             self.gltf_drawmodes.push(Vec::new());
@@ -904,6 +1225,12 @@ impl Game {
                         let indices = Game::collect_indices(reader.read_indices().unwrap()); 
                         let uvs = reader.read_tex_coords(0).unwrap().into_f32().collect::<Vec<_>>();
 
+                        //Rigid primitives leave these out and the shader sees hasSkin == 0
+                        let joints = reader.read_joints(0)
+                            .map(|j| j.into_u16().map(|j| j.map(|i| i as f32)).collect::<Vec<[f32; 4]>>());
+                        let weights = reader.read_weights(0)
+                            .map(|w| w.into_f32().collect::<Vec<[f32; 4]>>());
+
                         let mut ebo: GLuint = 0;
                         unsafe {
                             gl::CreateBuffers(1, &mut ebo);
@@ -963,6 +1290,34 @@ impl Game {
                             gl::VertexArrayAttribFormat(vao, 1, 2, gl::FLOAT, gl::FALSE, 0);
                             gl::VertexArrayAttribBinding(vao, 1, 1);
 
+                            if let (Some(joints), Some(weights)) = (&joints, &weights) {
+                                let mut joint_vbo: GLuint = 0;
+                                gl::CreateBuffers(1, &mut joint_vbo);
+                                gl::NamedBufferData(
+                                    joint_vbo,
+                                    (joints.len() * std::mem::size_of::<[f32; 4]>()) as GLsizeiptr,
+                                    joints.as_ptr() as *const GLvoid,
+                                    gl::STATIC_DRAW,
+                                );
+                                gl::VertexArrayVertexBuffer(vao, 2, joint_vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
+                                gl::EnableVertexArrayAttrib(vao, 2);
+                                gl::VertexArrayAttribFormat(vao, 2, 4, gl::FLOAT, gl::FALSE, 0);
+                                gl::VertexArrayAttribBinding(vao, 2, 2);
+
+                                let mut weight_vbo: GLuint = 0;
+                                gl::CreateBuffers(1, &mut weight_vbo);
+                                gl::NamedBufferData(
+                                    weight_vbo,
+                                    (weights.len() * std::mem::size_of::<[f32; 4]>()) as GLsizeiptr,
+                                    weights.as_ptr() as *const GLvoid,
+                                    gl::STATIC_DRAW,
+                                );
+                                gl::VertexArrayVertexBuffer(vao, 3, weight_vbo, 0, (4 * std::mem::size_of::<f32>()) as i32);
+                                gl::EnableVertexArrayAttrib(vao, 3);
+                                gl::VertexArrayAttribFormat(vao, 3, 4, gl::FLOAT, gl::FALSE, 0);
+                                gl::VertexArrayAttribBinding(vao, 3, 3);
+                            }
+
 
                             gl::VertexArrayElementBuffer(vao, ebo);
 
@@ -11,6 +11,21 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use uuid::Uuid;
 
 
+//Seconds between mob updates from the server (20 Hz)
+pub static MOB_UPDATE_INTERVAL: f64 = 0.05;
+//Seconds between each client's own position updates (4 Hz)
+pub static PLAYER_UPDATE_INTERVAL: f64 = 0.25;
+
+//Blends between two angles the short way around, landing exactly on b once t reaches 1
+pub fn lerp_angle(a: f32, b: f32, t: f32) -> f32 {
+    if t >= 1.0 {
+        return b;
+    }
+    let tau = std::f32::consts::TAU;
+    let diff = ((b - a) % tau + tau * 1.5) % tau - tau * 0.5;
+    a + diff * t
+}
+
 pub enum AggroTarget {
     ThisCamera,
     ModelEntityID(u32),
@@ -35,6 +50,17 @@ pub fn direction_to_euler(direction: Vec3) -> Vec3 {
     Vec3::new(pitch, yaw, roll)
 }
 
+//Yaw a player's avatar should face, 0 when there's no usable direction yet
+pub fn facing_yaw(direction: Vec3) -> f32 {
+    let yaw = direction_to_euler(direction).y;
+    if yaw.is_finite() { yaw } else { 0.0 }
+}
+
+//Position-only updates leave rot unset or garbage, so those face the default way
+pub fn received_yaw(rot: f32) -> f32 {
+    if rot.is_finite() { rot } else { 0.0 }
+}
+
 /// Converts a Vec3 containing Euler angles (in radians) to a normalized direction vector.
 pub fn euler_to_direction(euler_angles: Vec3) -> Vec3 {
     // Create a quaternion from the Euler angles
@@ -92,6 +118,8 @@ pub struct ModelEntity {
     pub animations: Vec<Animation>,
     pub nodes: Vec<Node>,
     pub time_stamp: f64,
+    //How long a blend from one network update to the next takes
+    pub update_interval: f64,
     pub hostile: bool,
     pub lastrot: Vec3,
     pub sounding: bool,
@@ -100,7 +128,9 @@ pub struct ModelEntity {
     pub soundvolume: f32,
     pub attackinterval: f32,
     pub soundinterval: f32,
-    pub lastchunkpos: vec::IVec2
+    pub lastchunkpos: vec::IVec2,
+    pub turning_from_wall: bool,
+    pub health: i32
 }
 
 pub static SERVER_GENERATED_CHUNKS: Lazy<DashMap<vec::IVec2, bool>> = Lazy::new(|| DashMap::new());
@@ -134,7 +164,7 @@ impl ModelEntity {
             }
         ];
 
-        let chunkpos = ChunkSystem::spot_to_chunk_pos(&IVec3::new(self.position.x as i32, self.position.y as i32, self.position.z as i32));
+        let chunkpos = self.csys.read().spot_to_chunk_pos(&IVec3::new(self.position.x as i32, self.position.y as i32, self.position.z as i32));
         if self.lastchunkpos != chunkpos {
 
             let csys = self.csys.write();
@@ -197,6 +227,7 @@ impl ModelEntity {
                 animations: Vec::new(),
                 nodes: Vec::new(),
                 time_stamp: 0.0,
+                update_interval: MOB_UPDATE_INTERVAL,
                 hostile,
                 lastrot: Vec3::ZERO,
                 sounding: false,
@@ -205,7 +236,9 @@ impl ModelEntity {
                 soundvolume: 0.0,
                 attackinterval: Planets::get_mob_attack_interval(model_index),
                 soundinterval: Planets::get_mob_sound_interval(model_index),
-                lastchunkpos: vec::IVec2::new(-99,99)
+                lastchunkpos: vec::IVec2::new(-99,99),
+                turning_from_wall: false,
+                health: Planets::get_mob_max_health(model_index)
             }
         }
         
@@ -256,6 +289,7 @@ impl ModelEntity {
                 animations: Vec::new(),
                 nodes: Vec::new(),
                 time_stamp: 0.0,
+                update_interval: MOB_UPDATE_INTERVAL,
                 hostile,
                 lastrot: Vec3::ZERO,
                 sounding: false,
@@ -264,7 +298,9 @@ impl ModelEntity {
                 soundvolume: 0.0,
                 attackinterval: Planets::get_mob_attack_interval(model_index),
                 soundinterval: Planets::get_mob_sound_interval(model_index),
-                lastchunkpos: vec::IVec2::new(-99,99)
+                lastchunkpos: vec::IVec2::new(-99,99),
+                turning_from_wall: false,
+                health: Planets::get_mob_max_health(model_index)
             }
      
         
@@ -316,6 +352,40 @@ impl ModelEntity {
         }
     }
 
+    //A wall two blocks tall one step along our heading, too tall to hop
+    pub fn blocked_ahead(&self) -> bool {
+        let flat = self.direction * Vec3::new(1.0, 0.0, 1.0);
+        if flat.length() == 0.0 {
+            return false;
+        }
+        let ahead = self.position + flat.normalize() * 0.8;
+        let spot = IVec3::new(ahead.x.floor() as i32, ahead.y.floor() as i32, ahead.z.floor() as i32);
+        let csys = self.csys.read();
+        csys.is_solid(spot) && csys.is_solid(spot + IVec3::new(0, 1, 0))
+    }
+
+    //Wanderers stop and turn when they walk up to a wall, then carry on once it's clear
+    pub fn avoid_walls(&mut self) {
+        if !matches!(self.target, AggroTarget::NoAggro) {
+            self.turning_from_wall = false;
+            return;
+        }
+        if self.controls.forward && self.blocked_ahead() {
+            self.controls.forward = false;
+            self.controls.up = false;
+            if !self.controls.lookingleft && !self.controls.lookingright {
+                self.controls.lookingleft = self.rng.gen_bool(0.5);
+                self.controls.lookingright = !self.controls.lookingleft;
+            }
+            self.turning_from_wall = true;
+        } else if self.turning_from_wall && !self.blocked_ahead() {
+            self.controls.lookingleft = false;
+            self.controls.lookingright = false;
+            self.controls.forward = true;
+            self.turning_from_wall = false;
+        }
+    }
+
     pub fn cricket_behavior(&mut self, _delta: &f32) {
 
 
@@ -513,6 +583,50 @@ impl ModelEntity {
 
 
 
+    //Rough body volume for picking, centered a bit above the feet
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        (self.position + Vec3::new(0.0, 0.75, 0.0), 1.0)
+    }
+
+    //Where to draw this entity at time now, blending lastpos/lastrot toward position/rot over one update interval
+    pub fn interpolated_pos_rot(&self, now: f64) -> (Vec3, Vec3) {
+        let t = ((now - self.time_stamp) / self.update_interval).clamp(0.0, 1.0) as f32;
+        let pos = self.lastpos.lerp(self.position, t);
+        let rot = Vec3::new(
+            lerp_angle(self.lastrot.x, self.rot.x, t),
+            lerp_angle(self.lastrot.y, self.rot.y, t),
+            lerp_angle(self.lastrot.z, self.rot.z, t),
+        );
+        (pos, rot)
+    }
+
+    //Starts a new blend from wherever the entity is currently drawn, so late updates don't snap
+    pub fn receive_network_update(&mut self, newpos: Vec3, newrot: Vec3, now: f64) {
+        let (drawpos, drawrot) = self.interpolated_pos_rot(now);
+        self.lastpos = drawpos;
+        self.lastrot = drawrot;
+        self.position = newpos;
+        //Roll never comes over the network, it's only set locally (see set_roll)
+        self.rot = Vec3::new(newrot.x, newrot.y, self.rot.z);
+        self.time_stamp = now;
+    }
+
+    //Tips the entity over (or back up) smoothly, used for downed players
+    pub fn set_roll(&mut self, roll: f32, now: f64) {
+        let (drawpos, drawrot) = self.interpolated_pos_rot(now);
+        self.lastpos = drawpos;
+        self.lastrot = drawrot;
+        self.rot.z = roll;
+        self.time_stamp = now;
+    }
+
+    //Body volume other mobs and players get pushed out of
+    pub fn body_box_at(pos: Vec3) -> BoundBox {
+        let mut bb = BoundBox::new(Vec3::ZERO);
+        bb.set_center(pos + Vec3::new(0.0, -0.5, 0.0), 0.2, 0.85);
+        bb
+    }
+
     pub fn set_pos(&mut self, newpos: Vec3) {
         self.lastpos = self.position;
         self.position = newpos;
@@ -0,0 +1,212 @@
+use std::{fs, path::Path};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+//How long two tracks overlap when one hands over to the next
+pub const CROSSFADE_SECONDS: f32 = 4.0;
+//Music stays lowered this long after the last hit given or taken
+pub const COMBAT_LINGER_SECONDS: f32 = 8.0;
+//A track's sink reads empty for a moment while the audio thread is still loading it
+pub const TRACK_START_GRACE: f32 = 2.0;
+//How fast the mood volume follows night falling or a fight starting, per second
+const MOOD_RATE: f32 = 0.5;
+
+//What the playlist wants done to the audio player, in order
+#[derive(Clone, Debug, PartialEq)]
+pub enum MusicCommand {
+    Play(String),
+    Stop(String),
+    Pause(String),
+    Resume(String),
+}
+
+//Every track in the folder, sorted so the same folder always gives the same list
+pub fn scan_music_dir(dir: &str) -> Vec<String> {
+    let mut tracks = Vec::new();
+    if let Ok(entries) = fs::read_dir(Path::new(dir)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            if path.is_file() && ["mp3", "ogg", "wav", "flac"].contains(&ext.as_str()) {
+                tracks.push(format!("{}/{}", dir, entry.file_name().to_string_lossy()));
+            }
+        }
+    }
+    tracks.sort();
+    tracks
+}
+
+//Volume the music should sit at, night is 0 (day) to 1 (darkest), the mood pair comes from the planet
+pub fn mood_volume(night: f32, in_combat: bool, night_volume: f32, combat_volume: f32) -> f32 {
+    let night = night.clamp(0.0, 1.0);
+    let vol = 1.0 + (night_volume - 1.0) * night;
+    if in_combat {
+        vol * combat_volume
+    } else {
+        vol
+    }
+}
+
+pub struct Playlist {
+    pub tracks: Vec<String>,
+    //Shuffled bag, every track plays once before any repeats
+    order: Vec<usize>,
+    next_in_order: usize,
+    pub current: Option<usize>,
+    //The track fading out under the current one
+    pub outgoing: Option<usize>,
+    //0 when a track just started, 1 once its crossfade is done
+    pub fade: f32,
+    pub playing_for: f32,
+    //A track hands over to the next after this long even if it hasn't ended
+    pub max_track_seconds: f32,
+    pub paused: bool,
+    pub mood: f32,
+    mood_target: f32,
+    planet: Option<u8>,
+    rng: StdRng,
+}
+
+impl Playlist {
+    pub fn new(tracks: Vec<String>, seed: u64) -> Playlist {
+        Playlist {
+            tracks,
+            order: Vec::new(),
+            next_in_order: 0,
+            current: None,
+            outgoing: None,
+            fade: 1.0,
+            playing_for: 0.0,
+            max_track_seconds: 300.0,
+            paused: false,
+            mood: 1.0,
+            mood_target: 1.0,
+            planet: None,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn current_track(&self) -> Option<&String> {
+        self.current.map(|i| &self.tracks[i])
+    }
+
+    fn next_index(&mut self) -> usize {
+        if self.next_in_order >= self.order.len() {
+            self.order = (0..self.tracks.len()).collect();
+            self.order.shuffle(&mut self.rng);
+            //Don't play the same track twice across a reshuffle
+            if self.order.len() > 1 && Some(self.order[0]) == self.current {
+                let last = self.order.len() - 1;
+                self.order.swap(0, last);
+            }
+            self.next_in_order = 0;
+        }
+        self.next_in_order += 1;
+        self.order[self.next_in_order - 1]
+    }
+
+    fn start(&mut self, index: usize) -> Vec<MusicCommand> {
+        let mut cmds = Vec::new();
+        //A third track cuts off whatever was still fading out
+        if let Some(old) = self.outgoing.take() {
+            cmds.push(MusicCommand::Stop(self.tracks[old].clone()));
+        }
+        self.outgoing = self.current.filter(|c| *c != index);
+        self.current = Some(index);
+        self.fade = 0.0;
+        self.playing_for = 0.0;
+        cmds.push(MusicCommand::Play(self.tracks[index].clone()));
+        cmds
+    }
+
+    //Skipping while paused starts the next track playing
+    pub fn skip(&mut self) -> Vec<MusicCommand> {
+        if self.tracks.is_empty() {
+            return Vec::new();
+        }
+        let mut cmds = Vec::new();
+        if self.paused {
+            self.paused = false;
+            cmds.extend(self.outgoing.take().map(|i| MusicCommand::Stop(self.tracks[i].clone())));
+        }
+        let index = self.next_index();
+        cmds.extend(self.start(index));
+        cmds
+    }
+
+    //Crossfades to a particular track (the server picking one), nothing happens if it's already on or paused
+    pub fn cue(&mut self, track: &str) -> Vec<MusicCommand> {
+        match self.tracks.iter().position(|t| t == track) {
+            Some(index) if self.current != Some(index) && !self.paused => self.start(index),
+            _ => Vec::new(),
+        }
+    }
+
+    //Only a different planet changes the track, reloading the same one keeps it going
+    pub fn set_planet(&mut self, planet: u8) -> Vec<MusicCommand> {
+        let previous = self.planet.replace(planet);
+        if previous.is_some() && previous != Some(planet) && !self.paused && self.current.is_some() {
+            return self.skip();
+        }
+        Vec::new()
+    }
+
+    pub fn toggle_pause(&mut self) -> Vec<MusicCommand> {
+        self.paused = !self.paused;
+        [self.current, self.outgoing]
+            .into_iter()
+            .flatten()
+            .map(|i| {
+                let track = self.tracks[i].clone();
+                if self.paused {
+                    MusicCommand::Pause(track)
+                } else {
+                    MusicCommand::Resume(track)
+                }
+            })
+            .collect()
+    }
+
+    pub fn set_mood_target(&mut self, target: f32) {
+        self.mood_target = target.clamp(0.0, 1.0);
+    }
+
+    //current_finished is whether the current track's sink has run dry
+    pub fn advance(&mut self, delta_time: f32, current_finished: bool) -> Vec<MusicCommand> {
+        if self.paused || self.tracks.is_empty() {
+            return Vec::new();
+        }
+        let step = MOOD_RATE * delta_time;
+        self.mood += (self.mood_target - self.mood).clamp(-step, step);
+
+        if self.current.is_none() {
+            return self.skip();
+        }
+
+        let mut cmds = Vec::new();
+        self.playing_for += delta_time;
+        self.fade = (self.fade + delta_time / CROSSFADE_SECONDS).min(1.0);
+        if self.fade >= 1.0 {
+            if let Some(old) = self.outgoing.take() {
+                cmds.push(MusicCommand::Stop(self.tracks[old].clone()));
+            }
+        }
+        if (current_finished && self.playing_for > TRACK_START_GRACE) || self.playing_for >= self.max_track_seconds {
+            cmds.extend(self.skip());
+        }
+        cmds
+    }
+
+    //Volume scale for each playing track, the music slider is applied on top of this by the audio player
+    pub fn volumes(&self) -> Vec<(String, f32)> {
+        let angle = self.fade * std::f32::consts::FRAC_PI_2;
+        let mut vols = Vec::new();
+        if let Some(i) = self.current {
+            vols.push((self.tracks[i].clone(), angle.sin() * self.mood));
+        }
+        if let Some(i) = self.outgoing {
+            vols.push((self.tracks[i].clone(), angle.cos() * self.mood));
+        }
+        vols
+    }
+}
@@ -17,9 +17,9 @@ use uuid::Uuid;
 
 use crate::camera::Camera;
 use crate::chunk::ChunkSystem;
-use crate::game::{Game, CURRSEED, PLAYERPOS};
+use crate::game::{Game, CURRSEED, HEADLESS, PLAYERPOS};
 use crate::inventory::ChestInventory;
-use crate::modelentity::{direction_to_euler, ModelEntity};
+use crate::modelentity::{facing_yaw, received_yaw, ModelEntity, PLAYER_UPDATE_INTERVAL};
 use crate::server_types::{self, Message, MessageType, MOB_BATCH_SIZE};
 use crate::statics::MY_MULTIPLAYER_UUID;
 use crate::vec;
@@ -140,7 +140,7 @@ impl NetworkConnector {
                     let recv_world_bool = self.received_world.clone();
                     let commqueue = self.commqueue.clone();
                     let gknowncams = self.gknowncams.clone();
-                    let _my_uuid = self.my_uuid.clone();
+                    let my_uuid = self.my_uuid.clone();
                     let _nsmes = self.nsme.clone();
                     let pme = self.pme.clone();
 
@@ -178,8 +178,8 @@ impl NetworkConnector {
                                 };
 
                            
-                                let dir = direction_to_euler(c.dir.into());
-                                let mut message = Message::new(MessageType::PlayerUpdate, c.pos.into(), dir.y, 0);
+                                //ROT carries the yaw the avatar faces
+                                let mut message = Message::new(MessageType::PlayerUpdate, c.pos.into(), facing_yaw(c.dir.into()), 0);
                                 message.infof = c.pitch;
                                 message.info2 = c.yaw as u32;
 
@@ -188,7 +188,7 @@ impl NetworkConnector {
                                 
                                 
                             }
-                            thread::sleep(Duration::from_millis(250));
+                            thread::sleep(Duration::from_secs_f64(PLAYER_UPDATE_INTERVAL));
                         }
                     }));
 
@@ -208,6 +208,9 @@ impl NetworkConnector {
                         let reqseed = Message::new(MessageType::RequestSeed, Vec3::ZERO, 0.0, 0);
                         let reqpt = Message::new(MessageType::RequestPt, Vec3::ZERO, 0.0, 0);
                         let reqchest = Message::new(MessageType::ReqChestReg, Vec3::ZERO, 0.0, 0);
+
+                        //Held until the planet type arrives, then both go into the chunk system together
+                        let mut server_seed: Option<u32> = None;
                         
                         NetworkConnector::sendto(&requdm, &stream);
 
@@ -253,8 +256,11 @@ impl NetworkConnector {
                                         };
 
                                         match comm.message_type {
-                                            MessageType::Disconnect => {
-                                                pme.remove(&Uuid::from_u64_pair(comm.goose.0, comm.goose.1));
+                                            MessageType::Disconnect | MessageType::PlayerDisconnect => {
+                                                let uuid = Uuid::from_u64_pair(comm.goose.0, comm.goose.1);
+                                                info!("Player {uuid} left");
+                                                pme.remove(&uuid);
+                                                gknowncams.remove(&uuid);
                                             }
                                             MessageType::ChestReg => {
                                                 
@@ -350,6 +356,8 @@ impl NetworkConnector {
                                                 
                                             },
                                             
+                                            //Never draw ourselves
+                                            MessageType::PlayerUpdate if *my_uuid.read() == Some(Uuid::from_u64_pair(comm.goose.0, comm.goose.1)) => {}
                                             MessageType::PlayerUpdate => {
 
                                                 
@@ -357,27 +365,24 @@ impl NetworkConnector {
                                                 let newpos = Vec3::new(comm.x, comm.y, comm.z);
                                                 //let id = comm.info;
                                                 let _modind = comm.info2;
-                                                let rot = comm.rot;
+                                                let rot = received_yaw(comm.rot);
                                                 let scale = 0.3;
 
                                                 let pme: Arc<DashMap<Uuid, ModelEntity>> = pme.clone();
 
 
                                                 let uuid = Uuid::from_u64_pair(comm.goose.0, comm.goose.1);
+                                                gknowncams.insert(uuid, newpos);
 
                                                 //info!("Player update: {uuid}");
                                                 //info!("NSME Length: {}", nsme.len());
                                                 match pme.get_mut(&uuid) {
                                                     Some(mut me) => {
                                                         let modent = me.value_mut();
-                                                        (*modent).lastpos = (*modent).position.clone();
-                                                        (*modent).position = newpos;
+                                                        //No glfw clock without a window, a headless client never draws them anyway
+                                                        let now = unsafe { if HEADLESS { 0.0 } else { glfwGetTime() } };
+                                                        (*modent).receive_network_update(newpos, Vec3::new(0.0, rot, 0.0), now);
                                                         (*modent).scale = scale;
-                                                        (*modent).lastrot = (*modent).rot.clone();
-                                                        (*modent).rot = Vec3::new(0.0, rot, 0.0);
-                                                        unsafe {
-                                                            (*modent).time_stamp = glfwGetTime();
-                                                        }
                                                         
                                                         
                                                     }
@@ -454,6 +459,7 @@ impl NetworkConnector {
 
 
                                                 let recv_s = format!("{}", comm.info);
+                                                server_seed = Some(comm.info);
 
                                                 info!("Received seed: {}", recv_s);
 
@@ -510,10 +516,18 @@ impl NetworkConnector {
 
 
 
-                                                csys.write().load_world_from_file(String::from("mp"));
+                                                match server_seed {
+                                                    Some(seed) => {
+                                                        csys.write().load_server_world(seed, pt as u8);
 
-                                                thread::sleep(Duration::from_millis(200));
-                                                NetworkConnector::sendtolocked(&reqchest, &mut stream_lock);
+                                                        thread::sleep(Duration::from_millis(200));
+                                                        NetworkConnector::sendtolocked(&reqchest, &mut stream_lock);
+                                                    }
+                                                    None => {
+                                                        info!("Got the planet type before the seed, asking for the seed again");
+                                                        NetworkConnector::sendtolocked(&reqseed, &mut stream_lock);
+                                                    }
+                                                }
                                                 
                             
                                                 //info!("{}", recv_s);
@@ -575,6 +589,15 @@ impl NetworkConnector {
                                             MessageType::TimeUpdate => {
                                                 commqueue.push(comm.clone());
                                             }
+                                            MessageType::SetDayLength => {
+                                                commqueue.push(comm.clone());
+                                            }
+                                            MessageType::MobHit => {
+                                                commqueue.push(comm.clone());
+                                            }
+                                            MessageType::PlayerDied | MessageType::PlayerRespawned => {
+                                                commqueue.push(comm.clone());
+                                            }
                                             MessageType::ChestInvUpdate => {
                                                 //info!("Receiving CIU from goose {}", Uuid::from_u64_pair(comm.goose.0, comm.goose.1));
                                                 hpcommqueue.push(comm.clone());
@@ -1,7 +1,19 @@
 pub struct PackedVertex {}
 
+//Tile coords get 4 bits each in the u8, u high and v low, so the chunk mesh addresses the first 16x16 tiles of the sheet
+pub const TEX_COORD_BITS: u32 = 4;
+pub const MAX_TEX_COORD: u8 = (1 << TEX_COORD_BITS) - 1;
+
 impl PackedVertex {
+    //Everything but y gets 4 bits, anything wider would bleed into the next field, so catch it here instead of on screen
     pub fn pack(x: u8, y: u8, z: u8, corner: u8, al: u8, bl: u8, u: u8, v: u8) -> (u32, u8) {
+        debug_assert!(x <= 0b1111, "x {} doesn't fit 4 bits", x);
+        debug_assert!(z <= 0b1111, "z {} doesn't fit 4 bits", z);
+        debug_assert!(corner <= 0b1111, "corner {} doesn't fit 4 bits", corner);
+        debug_assert!(al <= 0b1111, "light {} doesn't fit 4 bits", al);
+        debug_assert!(bl <= 0b1111, "block bits {} don't fit 4 bits", bl);
+        debug_assert!(u <= MAX_TEX_COORD && v <= MAX_TEX_COORD, "tex coord {},{} doesn't fit {} bits", u, v, TEX_COORD_BITS);
+
         let shifted_x = (x as u32) << 28;
         let shifted_y = ((y as u32) & 0b0000_0000_0000_0000_0000_0000_1111_1111) << 20;
         let shifted_cropped_z = ((z as u32) & 0b0000_0000_0000_0000_0000_0000_0000_1111) << 16;
@@ -27,6 +39,20 @@ impl PackedVertex {
         (sum32_bits, sum8_bits)
     }
 
+    //What the chunk vertex shader reads back out of the u8
+    pub fn unpack_tex_coords(packed8: u8) -> (u8, u8) {
+        (packed8 >> 4, packed8 & MAX_TEX_COORD)
+    }
+
+    //Sky light goes in the low 4 bits, which pack leaves empty
+    pub fn with_sky_light(packed32: u32, sky: u8) -> u32 {
+        (packed32 & !0b1111) | (sky as u32 & 0b1111)
+    }
+
+    pub fn unpack_sky_light(packed32: u32) -> u8 {
+        (packed32 & 0b1111) as u8
+    }
+
     pub fn pack_rgb(r: u16, g: u16, b: u16) -> u16 {
         // Ensure the values are within the 4-bit range
         let r = r & 0b0000_1111;
@@ -0,0 +1,221 @@
+use gl::types::{GLsizeiptr, GLuint, GLvoid};
+use glam::{Mat4, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{blockinfo::Blocks, cube::CubeSide, shader::Shader, textureface::{atlas, TextureFace}};
+
+//Past this many the oldest particles get written over
+pub const MAX_PARTICLES: usize = 1024;
+pub const BREAK_PARTICLES: usize = 24;
+pub const PLACE_PARTICLES: usize = 8;
+
+//Each particle shows this fraction of its block's tile
+const CHIP_FRACTION: f32 = 0.25;
+//x, y, z, u, v, alpha per vertex, six vertices per particle
+const FLOATS_PER_VERTEX: usize = 6;
+
+#[derive(Clone, Copy, Default)]
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    //Top left corner of the bit of atlas it shows
+    pub uv: (f32, f32),
+    pub size: f32,
+    pub age: f32,
+    pub life: f32,
+}
+
+impl Particle {
+    pub fn alive(&self) -> bool {
+        self.age < self.life
+    }
+    //Solid for the first half of its life, then fades out
+    pub fn alpha(&self) -> f32 {
+        if !self.alive() {
+            return 0.0;
+        }
+        let t = self.age / self.life;
+        if t < 0.5 { 1.0 } else { 1.0 - (t - 0.5) * 2.0 }
+    }
+}
+
+//Fixed size ring of particles, new bursts overwrite the oldest slots
+pub struct ParticlePool {
+    pub particles: Vec<Particle>,
+    next: usize,
+    rng: StdRng,
+}
+
+impl ParticlePool {
+    pub fn new(capacity: usize) -> ParticlePool {
+        ParticlePool {
+            particles: vec![Particle::default(); capacity],
+            next: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    //Top face tile, so grass sprays green and logs show their rings
+    pub fn atlas_tile(block_id: u32) -> (f32, f32, f32) {
+        let tex = Blocks::get_tex_coords(block_id & Blocks::block_id_bits(), CubeSide::TOP);
+        let face = TextureFace::new(tex.0 as i8, tex.1 as i8);
+        (face.tlx, face.tly, face.trx - face.tlx)
+    }
+
+    pub fn burst(&mut self, center: Vec3, block_id: u32, count: usize, speed: f32) {
+        if self.particles.is_empty() {
+            return;
+        }
+        let (u, v, tile) = Self::atlas_tile(block_id);
+        let chip = tile * CHIP_FRACTION;
+        for _ in 0..count {
+            let offset = Vec3::new(
+                self.rng.gen_range(-0.4..0.4),
+                self.rng.gen_range(-0.4..0.4),
+                self.rng.gen_range(-0.4..0.4),
+            );
+            let particle = Particle {
+                position: center + offset,
+                velocity: offset.normalize_or_zero() * speed * self.rng.gen_range(0.5..1.0)
+                    + Vec3::new(0.0, speed * 0.6, 0.0),
+                uv: (
+                    u + self.rng.gen_range(0.0..(tile - chip)),
+                    v + self.rng.gen_range(0.0..(tile - chip)),
+                ),
+                size: self.rng.gen_range(0.06..0.12),
+                age: 0.0,
+                life: self.rng.gen_range(0.5..1.0),
+            };
+            self.particles[self.next] = particle;
+            self.next = (self.next + 1) % self.particles.len();
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32, gravity: f32) {
+        for p in self.particles.iter_mut().filter(|p| p.alive()) {
+            p.age += delta_time;
+            p.velocity.y -= gravity * delta_time;
+            p.position += p.velocity * delta_time;
+        }
+    }
+
+    pub fn live_count(&self) -> usize {
+        self.particles.iter().filter(|p| p.alive()).count()
+    }
+
+    //Camera facing quads for every live particle
+    pub fn build_vertices(&self, right: Vec3, up: Vec3, out: &mut Vec<f32>) {
+        out.clear();
+        for p in self.particles.iter().filter(|p| p.alive()) {
+            let r = right * p.size;
+            let u = up * p.size;
+            let chip = atlas().tile_u() * CHIP_FRACTION;
+            let a = p.alpha();
+            let corners = [
+                (p.position - r - u, p.uv.0, p.uv.1 + chip),
+                (p.position + r - u, p.uv.0 + chip, p.uv.1 + chip),
+                (p.position + r + u, p.uv.0 + chip, p.uv.1),
+                (p.position - r + u, p.uv.0, p.uv.1),
+            ];
+            for i in [0, 1, 2, 2, 3, 0] {
+                let (pos, tu, tv) = corners[i];
+                out.extend_from_slice(&[pos.x, pos.y, pos.z, tu, tv, a]);
+            }
+        }
+    }
+}
+
+pub struct Particles {
+    pub shader: Shader,
+    pub vbo: GLuint,
+    pub texture: GLuint,
+    pub pool: ParticlePool,
+    vertices: Vec<f32>,
+}
+
+impl Particles {
+    pub fn new(texture: GLuint) -> Particles {
+        let shader = Shader::new("assets/particlevert.glsl", "assets/particlefrag.glsl").unwrap_or_else(Shader::failed);
+        let mut vbo: GLuint = 0;
+        #[cfg(feature = "glfw")]
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::CreateBuffers(1, &mut vbo);
+                gl::NamedBufferData(
+                    vbo,
+                    (MAX_PARTICLES * 6 * FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as GLsizeiptr,
+                    std::ptr::null() as *const GLvoid,
+                    gl::DYNAMIC_DRAW,
+                );
+                let stride = (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as i32;
+                gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, stride);
+
+                gl::EnableVertexArrayAttrib(shader.vao, 0);
+                gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
+                gl::VertexArrayAttribBinding(shader.vao, 0, 0);
+
+                gl::EnableVertexArrayAttrib(shader.vao, 1);
+                gl::VertexArrayAttribFormat(shader.vao, 1, 2, gl::FLOAT, gl::FALSE, 3 * std::mem::size_of::<f32>() as u32);
+                gl::VertexArrayAttribBinding(shader.vao, 1, 0);
+
+                gl::EnableVertexArrayAttrib(shader.vao, 2);
+                gl::VertexArrayAttribFormat(shader.vao, 2, 1, gl::FLOAT, gl::FALSE, 5 * std::mem::size_of::<f32>() as u32);
+                gl::VertexArrayAttribBinding(shader.vao, 2, 0);
+            }
+        }
+        Particles {
+            shader,
+            vbo,
+            texture,
+            pool: ParticlePool::new(MAX_PARTICLES),
+            vertices: Vec::with_capacity(MAX_PARTICLES * 6 * FLOATS_PER_VERTEX),
+        }
+    }
+
+    pub fn block_broken(&mut self, spot: Vec3, block_id: u32) {
+        self.pool.burst(spot, block_id, BREAK_PARTICLES, 3.0);
+    }
+
+    pub fn block_placed(&mut self, spot: Vec3, block_id: u32) {
+        self.pool.burst(spot, block_id, PLACE_PARTICLES, 1.5);
+    }
+
+    pub fn update_and_draw(&mut self, delta_time: f32, gravity: f32, mvp: &Mat4, right: Vec3, up: Vec3) {
+        self.pool.update(delta_time, gravity);
+        self.pool.build_vertices(right, up, &mut self.vertices);
+        #[cfg(feature = "glfw")]
+        self.draw(mvp);
+    }
+
+    #[cfg(feature = "glfw")]
+    fn draw(&self, mvp: &Mat4) {
+        crate::game::debug_assert_gl_allowed("Particles::draw");
+        if self.vertices.is_empty() {
+            return;
+        }
+        unsafe {
+            gl::NamedBufferSubData(
+                self.vbo,
+                0,
+                (self.vertices.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                self.vertices.as_ptr() as *const GLvoid,
+            );
+
+            gl::Disable(gl::CULL_FACE);
+            gl::DepthMask(gl::FALSE);
+            gl::BindVertexArray(self.shader.vao);
+            gl::UseProgram(self.shader.shader_id);
+            gl::BindTextureUnit(0, self.texture);
+
+            let mvp_loc = gl::GetUniformLocation(self.shader.shader_id, b"mvp\0".as_ptr() as *const i8);
+            let tex_loc = gl::GetUniformLocation(self.shader.shader_id, b"ourTexture\0".as_ptr() as *const i8);
+            gl::UniformMatrix4fv(mvp_loc, 1, gl::FALSE, mvp.to_cols_array().as_ptr());
+            gl::Uniform1i(tex_loc, 0);
+
+            gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / FLOATS_PER_VERTEX) as i32);
+
+            gl::DepthMask(gl::TRUE);
+            gl::Enable(gl::CULL_FACE);
+        }
+    }
+}
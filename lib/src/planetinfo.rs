@@ -4,15 +4,32 @@
 
 pub struct Planets {}
 
+//How many planet types the ship cycles through
+pub const PLANET_TYPES: u8 = 4;
+
+//The home planet's pull, everything else is tuned relative to it
+pub const HOME_GRAVITY: f32 = 9.8;
 
 impl Planets {
+    pub fn is_hostile(dim_id: u32) -> bool {
+        dim_id % 2 != 0
+    }
+    pub fn get_name(dim_id: u32) -> &'static str {
+        match dim_id {
+            0 => "Home",
+            1 => "Red Rock",
+            2 => "Frozen",
+            3 => "Salt Flats",
+            _ => "Unknown"
+        }
+    }
     pub fn get_voxel_model_index_range(dim_id: u32) -> (usize, usize) {
         //inclusive range of what voxel model indexes go in each dimension
         return match dim_id {
-            0 => {
+            0 | 2 => {
                 (0, 13)
             }
-            1 => {
+            1 | 3 => {
                 (14, 16)
             }
             _ => {
@@ -23,10 +40,58 @@ impl Planets {
     pub fn get_floor_blocks(dim_id: u32) -> Vec<u32> {
         return match dim_id {
             0 => vec![3, 34],
-            1 => vec![1],
+            1 => vec![1, 13],
+            2 => vec![48],
+            3 => vec![14],
             _ => vec![3, 34]
         }
     }
+    //(surface, undersurface, beach) replacing the biome's blocks, None keeps the biome's
+    pub fn get_terrain_palette(dim_id: u32) -> Option<(u32, u32, u32)> {
+        return match dim_id {
+            //Red rock, the alien models (redrock, crystals) are stamped on it
+            1 => Some((13, 13, 1)),
+            //Frozen
+            2 => Some((48, 4, 48)),
+            //Salt flats over red stone
+            3 => Some((14, 13, 14)),
+            _ => None
+        }
+    }
+    pub fn get_terrain_octaves(dim_id: u32) -> u32 {
+        return match dim_id {
+            2 => 2,
+            3 => 5,
+            _ => 3
+        }
+    }
+    //(night, combat) music volume, how far the music drops after dark and in a fight
+    pub fn get_music_mood(dim_id: u32) -> (f32, f32) {
+        return match dim_id {
+            //Hostile worlds stay tense at night, fights still cut through
+            1 | 3 => (0.8, 0.35),
+            2 => (0.5, 0.5),
+            _ => (0.6, 0.5)
+        }
+    }
+    pub fn get_gravity(dim_id: u32) -> f32 {
+        return match dim_id {
+            1 => 13.0,
+            2 => 6.0,
+            3 => 7.5,
+            _ => HOME_GRAVITY
+        }
+    }
+    pub fn get_cave_frequency(dim_id: u32) -> f64 {
+        return match dim_id {
+            2 => 0.6,
+            3 => 1.6,
+            _ => 1.0
+        }
+    }
+    pub fn get_underwater_fog_col() -> (f32, f32, f32, f32) {
+        (0.05, 0.2, 0.45, 1.0)
+    }
     pub fn get_fog_col(dim_id: u32) -> (f32, f32, f32, f32) {
         return match dim_id {
             0 => {
@@ -35,6 +100,12 @@ impl Planets {
             1 => {
                 (0.5, 0.0, 0.0, 1.0)
             }
+            2 => {
+                (0.85, 0.92, 0.95, 1.0)
+            }
+            3 => {
+                (0.55, 0.35, 0.6, 1.0)
+            }
             _ => {
                 (0.7, 0.8, 1.0, 1.0)
             }
@@ -92,4 +163,31 @@ impl Planets {
             }
         }
     }
+
+    pub fn get_mob_max_health(modelindex: usize) -> i32 {
+        match modelindex {
+            6 => {
+                2
+            }
+            2 | 3 => {
+                20
+            }
+            _ => {
+                10
+            }
+        }
+    }
+
+    //Item and amount a mob leaves behind when killed
+    pub fn get_mob_drop(modelindex: usize) -> Option<(u32, u32)> {
+        match modelindex {
+            4 => {
+                //Cows, until there's a meat item
+                Some((32, 2))
+            }
+            _ => {
+                None
+            }
+        }
+    }
 }
\ No newline at end of file
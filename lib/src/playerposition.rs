@@ -1,5 +1,10 @@
+use std::fs;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 
+use crate::game::ROWLENGTH;
+
 #[derive(Serialize, Deserialize)]
 pub struct PlayerVec {
     pub x: f32,
@@ -12,4 +17,35 @@ pub struct PlayerPosition {
     pub pos: PlayerVec,
     pub pitch: f32,
     pub yaw: f32
-}
\ No newline at end of file
+}
+
+//Everything about the player that should survive quitting a singleplayer world
+#[derive(Serialize, Deserialize)]
+pub struct PlayerState {
+    pub position: PlayerPosition,
+    pub inv: [(u32, u32); ROWLENGTH as usize],
+    pub health: f32,
+    pub slot: usize
+}
+
+impl PlayerState {
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        //Write beside and rename so a crash mid-write can't leave a half file behind
+        let temp = format!("{}.tmp", path);
+        fs::write(&temp, bincode::serialize(self).unwrap())?;
+        fs::rename(&temp, path)
+    }
+
+    //None if there is no save yet or it can't be read back
+    pub fn load(path: &str) -> Option<PlayerState> {
+        let bytes = fs::read(path).ok()?;
+        let state: PlayerState = bincode::deserialize(&bytes).ok()?;
+        if state.slot >= ROWLENGTH as usize {
+            return None;
+        }
+        Some(state)
+    }
+}
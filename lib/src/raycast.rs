@@ -5,38 +5,119 @@
 use parking_lot::{Mutex, RwLock};
 use glam::{Vec3};
 
+use dashmap::DashMap;
+
 use crate::chunk::ChunkSystem;
+use crate::modelentity::ModelEntity;
 use crate::vec::IVec3;
 
 
-pub fn raycast_voxel(origin: Vec3, direction: Vec3, csys: &RwLock<ChunkSystem>, max_distance: f32) -> Option<(Vec3, IVec3)> {
-    let step_size = 0.1; // Smaller step sizes increase accuracy but decrease performance
+//Walks the grid one voxel at a time (Amanatides & Woo), so we always know which face we came through.
+//Returns the point on that face, the block hit, and the face normal (zero if we started inside a block)
+pub fn raycast_voxel(origin: Vec3, direction: Vec3, csys: &RwLock<ChunkSystem>, max_distance: f32) -> Option<(Vec3, IVec3, IVec3)> {
     let direction = direction.normalize(); // Ensure the direction vector is normalized
-    let mut current_pos = origin;
 
-    for _ in 0..(max_distance / step_size) as i32 {
-        let grid_pos = IVec3 {
-            x: current_pos.x.floor() as i32,
-            y: current_pos.y.floor() as i32,
-            z: current_pos.z.floor() as i32,
-        };
+    let mut grid_pos = IVec3 {
+        x: origin.x.floor() as i32,
+        y: origin.y.floor() as i32,
+        z: origin.z.floor() as i32,
+    };
+
+    if csys.read().is_solid(grid_pos) {
+        return Some((origin, grid_pos, IVec3::new(0, 0, 0)));
+    }
+
+    let step = IVec3::new(
+        direction.x.signum() as i32,
+        direction.y.signum() as i32,
+        direction.z.signum() as i32,
+    );
 
-        if csys.read().collision_predicate(grid_pos) {
-            // Hit a block, return the current position and the grid position
-            return Some((current_pos, grid_pos));
+    let axis_setup = |o: f32, d: f32, g: i32| -> (f32, f32) {
+        if d == 0.0 {
+            return (f32::INFINITY, f32::INFINITY);
         }
+        let next_boundary = if d > 0.0 { g as f32 + 1.0 } else { g as f32 };
+        ((next_boundary - o) / d, (1.0 / d).abs())
+    };
 
-        // Move the ray forward by step_size
-        current_pos += direction * step_size;
-    }
+    let (mut t_max_x, t_delta_x) = axis_setup(origin.x, direction.x, grid_pos.x);
+    let (mut t_max_y, t_delta_y) = axis_setup(origin.y, direction.y, grid_pos.y);
+    let (mut t_max_z, t_delta_z) = axis_setup(origin.z, direction.z, grid_pos.z);
+
+    loop {
+        let t;
+        let normal;
+        if t_max_x < t_max_y && t_max_x < t_max_z {
+            grid_pos.x += step.x;
+            t = t_max_x;
+            t_max_x += t_delta_x;
+            normal = IVec3::new(-step.x, 0, 0);
+        } else if t_max_y < t_max_z {
+            grid_pos.y += step.y;
+            t = t_max_y;
+            t_max_y += t_delta_y;
+            normal = IVec3::new(0, -step.y, 0);
+        } else {
+            grid_pos.z += step.z;
+            t = t_max_z;
+            t_max_z += t_delta_z;
+            normal = IVec3::new(0, 0, -step.z);
+        }
+
+        if t > max_distance {
+            return None;
+        }
 
-    None
+        if csys.read().is_solid(grid_pos) {
+            // Hit a block, return where we entered it, the grid position and the face we crossed
+            return Some((origin + direction * t, grid_pos, normal));
+        }
+    }
 }
 
-pub fn raycast_voxel_with_bob(origin: Vec3, direction: Vec3, csys: &RwLock<ChunkSystem>, max_distance: f32, walkbob: f32) -> Option<(Vec3, IVec3)> {
+pub fn raycast_voxel_with_bob(origin: Vec3, direction: Vec3, csys: &RwLock<ChunkSystem>, max_distance: f32, walkbob: f32) -> Option<(Vec3, IVec3, IVec3)> {
     
     let bob = Vec3::new(0.0, walkbob.sin() /20.0, 0.0) + Vec3::new(0.0, 0.3, 0.0);
 
     //info!("Raycasting with a {}, {}, {} origin shift for bob", bob.x, bob.y, bob.z);
     raycast_voxel(origin + bob, direction, csys, max_distance)
 }
+
+//Nearest mob whose bounding sphere the ray passes through, with the distance along the ray
+pub fn raycast_entity(origin: Vec3, direction: Vec3, entities: &DashMap<u32, ModelEntity>, max_distance: f32) -> Option<(u32, f32)> {
+    let direction = direction.normalize();
+    let mut nearest: Option<(u32, f32)> = None;
+
+    for entry in entities.iter() {
+        let (center, radius) = entry.value().bounding_sphere();
+        let to_center = center - origin;
+        let along = to_center.dot(direction);
+        let dist_sq = to_center.length_squared() - along * along;
+        let radius_sq = radius * radius;
+
+        if dist_sq > radius_sq {
+            continue;
+        }
+
+        let half_chord = (radius_sq - dist_sq).sqrt();
+        let mut t = along - half_chord;
+        if t < 0.0 {
+            //We're inside the sphere
+            t = along + half_chord;
+        }
+
+        if t < 0.0 || t > max_distance {
+            continue;
+        }
+
+        match nearest {
+            Some((_, best)) if best <= t => {}
+            _ => {
+                nearest = Some((*entry.key(), t));
+            }
+        }
+    }
+
+    nearest
+}
@@ -1,7 +1,78 @@
+use std::{fs, path::Path};
+
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 use tracing::info;
+
+use crate::blockinfo::Blocks;
+
+//(inputs, output, craftable without a crafting table)
 pub type Recipe = (Vec<(u32, u32)>, (u32, u32), bool);
 
+//How a recipe is written in recipes.json
+#[derive(Deserialize)]
+pub struct RecipeDef {
+    pub inputs: Vec<(u32, u32)>,
+    pub output: (u32, u32),
+    #[serde(default = "default_by_hand")]
+    pub by_hand: bool,
+}
+
+fn default_by_hand() -> bool {
+    true
+}
+
+impl RecipeDef {
+    pub fn to_recipe(self) -> Recipe {
+        (self.inputs, self.output, self.by_hand)
+    }
+}
+
+pub fn parse_recipes(json: &str) -> Result<Vec<Recipe>, serde_json::Error> {
+    let defs: Vec<RecipeDef> = serde_json::from_str(json)?;
+    Ok(defs.into_iter().map(RecipeDef::to_recipe).collect())
+}
+
+//Consumes the inputs and adds the output, or leaves inv untouched if either can't be done
+pub fn try_craft(inv: &mut [(u32, u32)], recipe: &Recipe) -> bool {
+    let mut scratch = inv.to_vec();
+
+    for req in &recipe.0 {
+        let have: u32 = scratch.iter().filter(|slot| slot.0 == req.0).map(|slot| slot.1).sum();
+        if have < req.1 {
+            return false;
+        }
+
+        let mut needed = req.1;
+        for slot in scratch.iter_mut().filter(|slot| slot.0 == req.0) {
+            let take = slot.1.min(needed);
+            slot.1 -= take;
+            needed -= take;
+            if slot.1 == 0 {
+                slot.0 = 0;
+            }
+            if needed == 0 {
+                break;
+            }
+        }
+    }
+
+    let (outid, outcount) = recipe.1;
+    let max = Blocks::get_max_stack(outid);
+    match scratch.iter_mut().find(|slot| (slot.0 == 0 || slot.0 == outid) && slot.1 + outcount <= max) {
+        Some(slot) => {
+            slot.0 = outid;
+            slot.1 += outcount;
+        }
+        None => {
+            return false;
+        }
+    }
+
+    inv.copy_from_slice(&scratch);
+    true
+}
+
 pub static mut RECIPES_DISABLED: bool = false;
 pub static mut RECIPE_COOLDOWN_TIMER: f32 = 0.0;
 
@@ -39,32 +110,51 @@ impl RecipeEntry {
 
 
 
-pub static RECIPES: Lazy<[Recipe; 25]> = Lazy::new(|| [
-    (vec![(6, 1)], (10, 4), true),
-    (vec![(10, 4)], (19, 2), false),
-    (vec![(10, 2)], (20, 10), false),
-    (vec![(10, 8)], (21, 1), false),
-    (vec![(11, 1)], (18, 1), true),
-    (vec![(22, 1)], (30, 1), true),
-    (vec![(10, 10)], (31, 1), true),
-    (vec![(1, 1)], (8, 1), false),
-    (vec![(18, 1), (34, 1)], (24, 1), true),
-    (vec![(1, 1)], (14, 8), true),
-    (vec![(5, 1)], (9, 1), false),
-    (vec![(22, 1)], (33, 4), true),
-    (vec![(32, 1), (18, 1)], (27, 1), true),
-    (vec![(24, 1), (27, 1)], (25, 1), true),
-    (vec![(33, 1), (18, 1)], (26, 1), true),
-    (vec![(10, 1), (35, 3)], (36, 1), true),
-
-    (vec![(10, 1), (35, 17)], (37, 1), true),
-    (vec![(10, 1), (35, 8)], (38, 1), true),
-    (vec![(10, 1), (35, 14)], (39, 1), true),
-
-    (vec![(24, 1), (26, 1)], (40, 1), true),
-    (vec![(10, 1), (22, 1)], (42, 1), true),
-    (vec![(10, 1), (22, 1), (35, 1)], (45, 10), true),
-    (vec![(42, 1), (22, 1)], (46, 1), true),
-    (vec![(35, 1)], (47, 10), true),
-    (vec![(10, 1), (5, 1)], (49, 4), true),
-]);
\ No newline at end of file
+//recipes.json next to the executable replaces the built in list
+pub static RECIPES: Lazy<Vec<Recipe>> = Lazy::new(|| {
+    let path = Path::new("recipes.json");
+    if path.exists() {
+        match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|json| parse_recipes(&json).map_err(|e| e.to_string())) {
+            Ok(recipes) => {
+                info!("Loaded {} recipes from recipes.json", recipes.len());
+                return recipes;
+            }
+            Err(e) => {
+                info!("Couldn't load recipes.json, using built in recipes: {}", e);
+            }
+        }
+    }
+    default_recipes()
+});
+
+pub fn default_recipes() -> Vec<Recipe> {
+    vec![
+        (vec![(6, 1)], (10, 4), true),
+        (vec![(10, 4)], (19, 2), false),
+        (vec![(10, 2)], (20, 10), false),
+        (vec![(10, 8)], (21, 1), false),
+        (vec![(11, 1)], (18, 1), true),
+        (vec![(22, 1)], (30, 1), true),
+        (vec![(10, 10)], (31, 1), true),
+        (vec![(1, 1)], (8, 1), false),
+        (vec![(18, 1), (34, 1)], (24, 1), true),
+        (vec![(1, 1)], (14, 8), true),
+        (vec![(5, 1)], (9, 1), false),
+        (vec![(22, 1)], (33, 4), true),
+        (vec![(32, 1), (18, 1)], (27, 1), true),
+        (vec![(24, 1), (27, 1)], (25, 1), true),
+        (vec![(33, 1), (18, 1)], (26, 1), true),
+        (vec![(10, 1), (35, 3)], (36, 1), true),
+
+        (vec![(10, 1), (35, 17)], (37, 1), true),
+        (vec![(10, 1), (35, 8)], (38, 1), true),
+        (vec![(10, 1), (35, 14)], (39, 1), true),
+
+        (vec![(24, 1), (26, 1)], (40, 1), true),
+        (vec![(10, 1), (22, 1)], (42, 1), true),
+        (vec![(10, 1), (22, 1), (35, 1)], (45, 10), true),
+        (vec![(42, 1), (22, 1)], (46, 1), true),
+        (vec![(35, 1)], (47, 10), true),
+        (vec![(10, 1), (5, 1)], (49, 4), true),
+    ]
+}
\ No newline at end of file
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tracing::info;
+
+pub const SAVES_DIR: &str = "saves";
+
+//The world picked on the title screen, read when the singleplayer game gets built
+pub static CHOSEN_SAVE_SLOT: Lazy<Mutex<Option<SaveSlot>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveSlot {
+    pub folder: String,
+    pub name: String,
+    pub seed: u32,
+    pub planet_type: u8,
+    pub last_played: u64,
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+//Numbers are used as-is, anything else is hashed so typed words still give a repeatable world
+pub fn seed_from_text(text: &str) -> Option<u32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(seed) = text.parse::<u32>() {
+        return Some(seed);
+    }
+    let mut hash: u32 = 2166136261;
+    for b in text.bytes() {
+        hash ^= b as u32;
+        hash = hash.wrapping_mul(16777619);
+    }
+    Some(hash)
+}
+
+impl SaveSlot {
+    pub fn path(&self, dir: &str) -> String {
+        format!("{}/{}", dir, self.folder)
+    }
+
+    pub fn player_state_path(&self, dir: &str) -> String {
+        format!("{}/playerstate", self.path(dir))
+    }
+
+    //Block edits for every planet this world has been to, deleted along with the slot
+    pub fn db_path(&self, dir: &str) -> String {
+        format!("{}/db", self.path(dir))
+    }
+
+    pub fn write_meta(&self, dir: &str) -> std::io::Result<()> {
+        fs::create_dir_all(self.path(dir))?;
+        fs::write(
+            format!("{}/meta", self.path(dir)),
+            format!("{}\n{}\n{}\n{}\n", self.name, self.seed, self.last_played, self.planet_type),
+        )
+    }
+
+    pub fn touch(&mut self, dir: &str) {
+        self.last_played = now_secs();
+        if let Err(e) = self.write_meta(dir) {
+            info!("Couldn't update save slot {}: {}", self.folder, e);
+        }
+    }
+
+    pub fn read_meta(dir: &str, folder: &str) -> Option<SaveSlot> {
+        let contents = fs::read_to_string(format!("{}/{}/meta", dir, folder)).ok()?;
+        let mut lines = contents.lines();
+        let name = lines.next()?.to_string();
+        let seed = lines.next()?.trim().parse::<u32>().ok()?;
+        let last_played = lines.next().and_then(|l| l.trim().parse::<u64>().ok()).unwrap_or(0);
+        let planet_type = lines.next().and_then(|l| l.trim().parse::<u8>().ok()).unwrap_or(0);
+        Some(SaveSlot { folder: folder.to_string(), name, seed, planet_type, last_played })
+    }
+}
+
+//Most recently played first; folders without a readable meta file are left out
+pub fn list_save_slots(dir: &str) -> Vec<SaveSlot> {
+    let mut slots = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let folder = entry.file_name().to_string_lossy().to_string();
+            if let Some(slot) = SaveSlot::read_meta(dir, &folder) {
+                slots.push(slot);
+            }
+        }
+    }
+    slots.sort_by(|a, b| b.last_played.cmp(&a.last_played).then(a.name.cmp(&b.name)));
+    slots
+}
+
+pub fn create_save_slot(dir: &str, name: &str, seed: u32) -> std::io::Result<SaveSlot> {
+    let name = if name.trim().is_empty() { "New World" } else { name.trim() };
+
+    let base: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    let mut folder = base.clone();
+    let mut n = 2;
+    while Path::new(&format!("{}/{}", dir, folder)).exists() {
+        folder = format!("{}_{}", base, n);
+        n += 1;
+    }
+
+    let slot = SaveSlot { folder, name: name.to_string(), seed, planet_type: 0, last_played: now_secs() };
+    slot.write_meta(dir)?;
+    Ok(slot)
+}
+
+pub fn delete_save_slot(dir: &str, slot: &SaveSlot) -> std::io::Result<()> {
+    //Only ever remove a direct child of the saves folder
+    if slot.folder.is_empty() || slot.folder.contains('/') || slot.folder.contains("..") {
+        return Ok(());
+    }
+    fs::remove_dir_all(slot.path(dir))
+}
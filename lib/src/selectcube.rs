@@ -16,43 +16,52 @@ pub struct SelectCube {
 
 impl SelectCube {
     pub fn new() -> SelectCube {
-        let shader = Shader::new("assets/wfvert.glsl", "assets/wffrag.glsl");
+        Self::new_box(Vec3::splat(-0.501), Vec3::splat(0.501))
+    }
+
+    //Edges of an arbitrary box, draw_at translates it the same way as the block cube
+    pub fn new_box(a: Vec3, b: Vec3) -> SelectCube {
+        let shader = Shader::new("assets/wfvert.glsl", "assets/wffrag.glsl").unwrap_or_else(Shader::failed);
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::BindVertexArray(shader.vao);
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::BindVertexArray(shader.vao);
+            }
         }
         let mut vbo: GLuint = 0;
 
         let faces: [f32; 72] = [
 
-            -0.501, -0.501, -0.501,  0.501, -0.501, -0.501, // Bottom ace
-            0.501, -0.501, -0.501,   0.501, -0.501,  0.501,
-            0.501, -0.501,  0.501,  -0.501, -0.501,  0.501,
-            -0.501, -0.501,  0.501, -0.501, -0.501, -0.501,
+            a.x, a.y, a.z,  b.x, a.y, a.z, // Bottom ace
+            b.x, a.y, a.z,  b.x, a.y, b.z,
+            b.x, a.y, b.z,  a.x, a.y, b.z,
+            a.x, a.y, b.z,  a.x, a.y, a.z,
     
-            -0.501,  0.501, -0.501,  0.501,  0.501, -0.501, // Top ace
-            0.501,  0.501, -0.501,   0.501,  0.501,  0.501,
-            0.501,  0.501,  0.501,  -0.501,  0.501,  0.501,
-            -0.501,  0.501,  0.501, -0.501,  0.501, -0.501,
+            a.x, b.y, a.z,  b.x, b.y, a.z, // Top ace
+            b.x, b.y, a.z,  b.x, b.y, b.z,
+            b.x, b.y, b.z,  a.x, b.y, b.z,
+            a.x, b.y, b.z,  a.x, b.y, a.z,
     
-            -0.501, -0.501, -0.501, -0.501,  0.501, -0.501, // Side Edges
-            0.501, -0.501, -0.501,   0.501,  0.501, -0.501,
-            0.501, -0.501,  0.501,   0.501,  0.501,  0.501,
-            -0.501, -0.501,  0.501, -0.501,  0.501,  0.501
+            a.x, a.y, a.z,  a.x, b.y, a.z, // Side Edges
+            b.x, a.y, a.z,  b.x, b.y, a.z,
+            b.x, a.y, b.z,  b.x, b.y, b.z,
+            a.x, a.y, b.z,  a.x, b.y, b.z
     
         ];
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::CreateBuffers(1, &mut vbo);
-            gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
-            faces.as_ptr() as *const GLvoid,
-            gl::STATIC_DRAW);
-
-            gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (3 * std::mem::size_of::<f32>()) as i32);
-            gl::EnableVertexArrayAttrib(shader.vao, 0);
-            gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
-            gl::VertexArrayAttribBinding(shader.vao, 0, 0);
+        if crate::game::gl_allowed() {
+            unsafe {
+                gl::CreateBuffers(1, &mut vbo);
+                gl::NamedBufferData(vbo, (faces.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                faces.as_ptr() as *const GLvoid,
+                gl::STATIC_DRAW);
+
+                gl::VertexArrayVertexBuffer(shader.vao, 0, vbo, 0, (3 * std::mem::size_of::<f32>()) as i32);
+                gl::EnableVertexArrayAttrib(shader.vao, 0);
+                gl::VertexArrayAttribFormat(shader.vao, 0, 3, gl::FLOAT, gl::FALSE, 0);
+                gl::VertexArrayAttribBinding(shader.vao, 0, 0);
             
+            }
         }
         SelectCube {
             shader,
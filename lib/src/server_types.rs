@@ -48,6 +48,7 @@ pub enum MessageType {
     Pt,
     Udm,
     Seed,
+    //XYZ POSITION, ROT YAW THE AVATAR FACES (0 IF NOT SENT), INFOF PITCH, INFO2 CAMERA YAW FOR SAVING
     PlayerUpdate,
     BlockSet,
     RequestTakeoff,
@@ -57,13 +58,25 @@ pub enum MessageType {
     WhatsThatMob,
     ShutUpMobMsgs,
     MobUpdateBatch,
+    //INFOF TIME OF DAY, INFO2 DAY LENGTH IN SECONDS, ROT WEATHER, INFO SONG INDEX
     TimeUpdate,
     TellYouMyID,
     MultiBlockSet,
     ChestReg,
     ReqChestReg,
     ChestInvUpdate,
-    Disconnect
+    Disconnect,
+    //INFOF: NEW DAY LENGTH IN SECONDS, 0 FREEZES THE CLOCK
+    SetDayLength,
+    //TO SERVER: INFO MOB ID, INFOF DAMAGE, XYZ HIT DIRECTION
+    //FROM SERVER: INFO MOB ID, INFO2 MODEL INDEX, INFOF HEALTH LEFT, XYZ MOB POSITION, GOOSE WHO HIT IT
+    MobHit,
+    //XYZ WHERE THEY DIED, GOOSE WHO DIED
+    PlayerDied,
+    //GOOSE WHO RESPAWNED
+    PlayerRespawned,
+    //FROM SERVER: GOOSE WHO LEFT
+    PlayerDisconnect
 }
 
 impl Display for MessageType {
@@ -150,6 +163,21 @@ impl Display for MessageType {
             MessageType::Disconnect => {
                 write!(f, "Disconnect")
             }
+            MessageType::SetDayLength => {
+                write!(f, "SetDayLength")
+            }
+            MessageType::MobHit => {
+                write!(f, "MobHit")
+            }
+            MessageType::PlayerDied => {
+                write!(f, "PlayerDied")
+            }
+            MessageType::PlayerRespawned => {
+                write!(f, "PlayerRespawned")
+            }
+            MessageType::PlayerDisconnect => {
+                write!(f, "PlayerDisconnect")
+            }
         }
     } 
 }
@@ -2,8 +2,11 @@ use gl;
 use std::fs::File;
 use std::io::Read;
 use std::ptr;
-use std::str;
-use tracing::info;
+
+#[cfg(feature = "glfw")]
+use crate::glcheck::gl_check;
+use crate::glcheck::report_gl_failure;
+
 #[derive(Clone)]
 pub struct Shader {
     pub shader_id: gl::types::GLuint,
@@ -11,48 +14,72 @@ pub struct Shader {
 }
 
 impl Shader {
-    pub fn new(vertpath: &str, fragpath: &str) -> Shader {
-        #[cfg(feature = "glfw")]
-        let vertex_shader = Self::compile_shader(vertpath, gl::VERTEX_SHADER);
-        #[cfg(feature = "glfw")]
-        let fragment_shader = Self::compile_shader(fragpath, gl::FRAGMENT_SHADER);
-        #[cfg(feature = "glfw")]
-        let shader_prog = Self::link_shader_program(vertex_shader, fragment_shader);
+    //Stands in for a real program on a headless game, no GL involved
+    pub fn empty() -> Shader {
+        Shader { shader_id: 0, vao: 0 }
+    }
+
+    //Missing files and compile or link errors come back as the message to show, instead of a panic or a black screen
+    pub fn new(vertpath: &str, fragpath: &str) -> Result<Shader, String> {
         #[cfg(feature = "glfw")]
-        unsafe {
-            gl::DeleteShader(vertex_shader);
-            gl::DeleteShader(fragment_shader);
+        if !crate::game::gl_allowed() {
+            return Ok(Shader::empty());
         }
+        #[cfg(feature = "glfw")]
+        let shader_prog = {
+            let vertex_shader = Self::compile_shader(vertpath, gl::VERTEX_SHADER)?;
+            let fragment_shader = match Self::compile_shader(fragpath, gl::FRAGMENT_SHADER) {
+                Ok(shader) => shader,
+                Err(e) => {
+                    unsafe { gl::DeleteShader(vertex_shader) };
+                    return Err(e);
+                }
+            };
+            let program = Self::link_shader_program(vertex_shader, fragment_shader);
+            unsafe {
+                gl::DeleteShader(vertex_shader);
+                gl::DeleteShader(fragment_shader);
+            }
+            program.map_err(|e| format!("{} + {}: {}", vertpath, fragpath, e))?
+        };
 
         let mut vao: u32 = 0;
         #[cfg(feature = "glfw")]
         unsafe {
             gl::CreateVertexArrays(1, &mut vao);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after creating vertex array: {}", error);
-            }
+            gl_check("creating vertex array");
         }
         {
             #[cfg(feature = "glfw")]
-            Shader {
+            Ok(Shader {
                 shader_id: shader_prog,
                 vao,
-            }
+            })
         }
         #[cfg(not(feature = "glfw"))]
-        Shader {
+        Ok(Shader {
             shader_id: 0,
             vao,
-        }
+        })
+    }
 
+    //Where a shader failed to build: the failure gets shown, and drawing with program 0 puts nothing on screen
+    pub fn failed(message: String) -> Shader {
+        report_gl_failure(format!("A shader failed to build. {}", message));
+        Shader::empty()
     }
+
     #[cfg(feature = "glfw")]
-    fn compile_shader(path: &str, shader_type: gl::types::GLenum) -> gl::types::GLuint {
-        let mut file = File::open(path).unwrap();
+    fn compile_shader(path: &str, shader_type: gl::types::GLenum) -> Result<gl::types::GLuint, String> {
         let mut shader_source = String::new();
-        file.read_to_string(&mut shader_source).unwrap();
-        let shader_source_c_str = std::ffi::CString::new(shader_source.as_bytes()).unwrap();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut shader_source))
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => format!("Couldn't find {}", path),
+                _ => format!("Couldn't read {}: {}", path, e),
+            })?;
+        let shader_source_c_str = std::ffi::CString::new(shader_source.as_bytes())
+            .map_err(|_| format!("{} has a nul byte in it", path))?;
 
         let shader = unsafe { gl::CreateShader(shader_type) };
 
@@ -70,20 +97,18 @@ impl Shader {
                     ptr::null_mut(),
                     log.as_mut_ptr() as *mut gl::types::GLchar,
                 );
-                info!(
-                    "ERROR::SHADER::COMPILATION_FAILED\n{}",
-                    str::from_utf8(&log).unwrap()
-                );
+                gl::DeleteShader(shader);
+                return Err(format!("{} didn't compile: {}", path, Self::info_log(&log)));
             }
         }
 
-        shader
+        Ok(shader)
     }
     #[cfg(feature = "glfw")]
     fn link_shader_program(
         vertex_shader: gl::types::GLuint,
         fragment_shader: gl::types::GLuint,
-    ) -> gl::types::GLuint {
+    ) -> Result<gl::types::GLuint, String> {
         let program = unsafe { gl::CreateProgram() };
         unsafe {
             gl::AttachShader(program, vertex_shader);
@@ -100,13 +125,17 @@ impl Shader {
                     ptr::null_mut(),
                     log.as_mut_ptr() as *mut gl::types::GLchar,
                 );
-                info!(
-                    "ERROR::PROGRAM::LINKING_FAILED\n{}",
-                    str::from_utf8(&log).unwrap()
-                );
+                gl::DeleteProgram(program);
+                return Err(format!("didn't link: {}", Self::info_log(&log)));
             }
         }
 
-        program
+        Ok(program)
+    }
+
+    //Driver logs are nul terminated and not always valid utf8
+    pub fn info_log(log: &[u8]) -> String {
+        let end = log.iter().position(|b| *b == 0).unwrap_or(log.len());
+        String::from_utf8_lossy(&log[..end]).trim().to_string()
     }
 }
@@ -2,7 +2,7 @@ use once_cell::sync::Lazy;
 
 use crate::specialblocks::vertexutils::rotate_coordinates_around_y_negative_90;
 use crate::textureface::TextureFace;
-use crate::textureface::TEXTURE_WIDTH;
+use crate::textureface::atlas;
 
 
 
@@ -69,6 +69,7 @@ impl DoorInfo {
 
     pub fn get_door_uvs(face: TextureFace) -> Vec<f32> {
         let side = TextureFace::new(12, 0);
+        let sliver = atlas().tile_u() / 4.0;
 
         let uvs = vec![
             face.blx, face.bly,face.brx, face.bry,
@@ -82,10 +83,10 @@ impl DoorInfo {
             
             side.brx, side.bry, side.trx, side.tr_y,
             side.trx, side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
             
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.bry, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.bry, side.trx, side.tr_y,
             side.brx, side.bry, side.trx, side.tr_y,//left  
             
             
@@ -93,20 +94,20 @@ impl DoorInfo {
             
             side.brx, side.bry, side.trx, side.tr_y,
             side.trx, side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
 
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.bry, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.bry, side.trx, side.tr_y,
             side.brx, side.bry, side.trx, side.tr_y,//top
             
             
 
             side.brx, side.bry, side.trx, side.tr_y,
             side.trx, side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
 
-            side.brx-(TEXTURE_WIDTH/4.0), side.tr_y, side.trx, side.tr_y,
-            side.brx-(TEXTURE_WIDTH/4.0), side.bry, side.trx, side.tr_y,
+            side.brx-sliver, side.tr_y, side.trx, side.tr_y,
+            side.brx-sliver, side.bry, side.trx, side.tr_y,
             side.brx, side.bry, side.trx, side.tr_y,//right
             
             
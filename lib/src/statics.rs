@@ -20,6 +20,24 @@ pub struct MiscellaneousSettingsData {
     pub mouse_sense: f32,
     pub music_vol: f32,
     pub sound_vol: f32,
+    #[serde(default = "default_render_distance")]
+    pub render_distance: u8,
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+    #[serde(default = "default_day_length")]
+    pub day_length: f32,
+    #[serde(default = "default_master_vol")]
+    pub master_vol: f32,
+    //Head bob strength while walking, 0 turns it off
+    #[serde(default = "default_head_bob")]
+    pub head_bob: f32,
+    #[serde(default)]
+    pub invert_y: bool,
+    //0 is raw mouse input, closer to 1 is smoother but laggier
+    #[serde(default)]
+    pub mouse_smoothing: f32,
+    #[serde(default)]
+    pub music_paused: bool,
     #[serde(with = "vectorize")]
     pub keybinds: HashMap<i32, String>,
     #[serde(with = "vectorize")]
@@ -27,29 +45,65 @@ pub struct MiscellaneousSettingsData {
 
 }
 
+fn default_render_distance() -> u8 {
+    10
+}
+
+fn default_fov() -> f32 {
+    80.0
+}
+
+fn default_day_length() -> f32 {
+    900.0
+}
+
+fn default_master_vol() -> f32 {
+    1.0
+}
+
+fn default_head_bob() -> f32 {
+    1.0
+}
+
+//Number keys up to the hotbar length are left free for picking slots
+pub static DEFAULT_KEYBINDS: [(glfw::Key, &str); 15] = [
+    (glfw::Key::Escape, "Exit/Menu"),
+    (glfw::Key::W, "Forward"),
+    (glfw::Key::A, "Left"),
+    (glfw::Key::S, "Backward"),
+    (glfw::Key::D, "Right"),
+
+    (glfw::Key::C, "Craft"),
+    (glfw::Key::Q, "Drop Item"),
+
+    (glfw::Key::Space, "Jump/Swim/Climb Up"),
+    (glfw::Key::LeftShift, "Sprint"),
+    (glfw::Key::LeftControl, "Crouch"),
+    (glfw::Key::N, "Noclip"),
+    (glfw::Key::G, "Toggle Game Mode"),
+    (glfw::Key::F5, "Save World"),
+
+    (glfw::Key::Num0, "Fov Up"),
+    (glfw::Key::Num9, "Fov Down"),
+];
+
 pub static mut MISCSETTINGS: Lazy<MiscellaneousSettingsData> = Lazy::new(|| MiscellaneousSettingsData {
     mouse_sense: 0.25,
     music_vol: 1.0,
     sound_vol: 1.0,
-    keybinds: HashMap::from([
-        (glfw::Key::Escape.get_scancode().unwrap(), "Exit/Menu".into()),
-        (glfw::Key::W.get_scancode().unwrap(), "Forward".into()),
-        (glfw::Key::A.get_scancode().unwrap(), "Left".into()),
-        (glfw::Key::S.get_scancode().unwrap(), "Backward".into()),
-        (glfw::Key::D.get_scancode().unwrap(), "Right".into()),
-
-        (glfw::Key::C.get_scancode().unwrap(), "Craft".into()),
-        
-        (glfw::Key::Space.get_scancode().unwrap(), "Jump/Swim/Climb Up".into()),
-        (glfw::Key::LeftShift.get_scancode().unwrap(), "Sprint".into()),
-        (glfw::Key::LeftControl.get_scancode().unwrap(), "Crouch".into()),
-
-        (glfw::Key::Num0.get_scancode().unwrap(), "Fov Up".into()),
-        (glfw::Key::Num9.get_scancode().unwrap(), "Fov Down".into()),
-    ]),
+    render_distance: default_render_distance(),
+    fov: default_fov(),
+    day_length: default_day_length(),
+    master_vol: default_master_vol(),
+    head_bob: default_head_bob(),
+    invert_y: false,
+    mouse_smoothing: 0.0,
+    music_paused: false,
+    keybinds: DEFAULT_KEYBINDS.iter().map(|(key, action)| (key.get_scancode().unwrap(), action.to_string())).collect(),
     mousebinds: HashMap::from([
         ("Button2".into(), "Place/Use".into()),
         ("Button1".into(), "Break/Attack".into()),
+        ("Button3".into(), "Pick Block".into()),
 
     ])
 } );
@@ -63,13 +117,25 @@ pub fn SAVE_MISC() {
     }
 }
 
+//Settings saved before a bind existed get it, unless they've used its key for something else or already bound the action
+pub fn merge_default_keybinds(keybinds: &mut HashMap<i32, String>, defaults: &[(i32, &str)]) {
+    for (code, action) in defaults {
+        if keybinds.contains_key(code) || keybinds.values().any(|bound| bound == action) {
+            continue;
+        }
+        keybinds.insert(*code, action.to_string());
+    }
+}
+
 pub fn LOAD_MISC() {
     if Path::new("misc").exists() {
         let mut file = File::open("misc").expect("Failed to open file");
         let mut json = String::new();
         file.read_to_string(&mut json).expect("Failed to read file");
-        let loaded_settings: MiscellaneousSettingsData = from_str(&json).expect("Failed to deserialize JSON");
-        
+        let mut loaded_settings: MiscellaneousSettingsData = from_str(&json).expect("Failed to deserialize JSON");
+        let defaults: Vec<(i32, &str)> = DEFAULT_KEYBINDS.iter().filter_map(|(key, action)| key.get_scancode().map(|code| (code, *action))).collect();
+        merge_default_keybinds(&mut loaded_settings.keybinds, &defaults);
+
         unsafe {
             *MISCSETTINGS = loaded_settings;
         }
@@ -1,10 +1,85 @@
-pub const ONE_PIXEL: f32 = 0.00183823529411764705882352941176;
-pub const TEXTURE_WIDTH: f32 = 0.02941176470588235294117647058824;
-pub const ONE_OVER_16: f32 = 0.03308823529411764705882352941176; //Not actually 1 over 16, but the size of one entire tile on the spritesheet including the padding
+//Every tile on the spritesheet is 16px with a 1px border around it, so tiles sit on an 18px grid
+pub const TILE_PX: u32 = 16;
+pub const TILE_PADDING_PX: u32 = 1;
+pub const CELL_PX: u32 = TILE_PX + TILE_PADDING_PX * 2;
+//Size of the stock assets/world.png, used until the real one is loaded (and on the headless server)
+pub const DEFAULT_ATLAS_PX: u32 = 544;
+//The font is 16 glyphs across in the top right corner of the sheet
+pub const GLYPH_PX: u32 = 16;
+pub const GLYPHS_PER_ROW: u32 = 16;
+
+//Where things are on the spritesheet, worked out from its pixel size
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl AtlasLayout {
+    pub const fn new(width: u32, height: u32) -> AtlasLayout {
+        AtlasLayout { width, height }
+    }
+
+    pub fn tiles_per_row(&self) -> u32 {
+        self.width / CELL_PX
+    }
+
+    pub fn tiles_per_column(&self) -> u32 {
+        self.height / CELL_PX
+    }
+
+    pub fn one_pixel_u(&self) -> f32 {
+        1.0 / self.width as f32
+    }
+
+    pub fn one_pixel_v(&self) -> f32 {
+        1.0 / self.height as f32
+    }
+
+    //Just the tile, without its border
+    pub fn tile_u(&self) -> f32 {
+        TILE_PX as f32 / self.width as f32
+    }
+
+    pub fn tile_v(&self) -> f32 {
+        TILE_PX as f32 / self.height as f32
+    }
+
+    //One whole grid step, border included
+    pub fn cell_u(&self) -> f32 {
+        CELL_PX as f32 / self.width as f32
+    }
+
+    pub fn cell_v(&self) -> f32 {
+        CELL_PX as f32 / self.height as f32
+    }
+
+    //Top left pixel of a tile's cell in the image, rows count up from the bottom of the sheet
+    pub fn cell_origin_px(&self, x: u32, y: u32) -> (usize, usize) {
+        ((x * CELL_PX) as usize, (self.height - (y + 1) * CELL_PX) as usize)
+    }
+
+    pub fn glyph_origin_px(&self) -> (u32, u32) {
+        (self.width - GLYPH_PX * GLYPHS_PER_ROW, 0)
+    }
+}
+
+pub static mut ATLAS: AtlasLayout = AtlasLayout::new(DEFAULT_ATLAS_PX, DEFAULT_ATLAS_PX);
+
+pub fn atlas() -> AtlasLayout {
+    unsafe { ATLAS }
+}
+
+//Call once the spritesheet is loaded, before anything builds UVs from it
+pub fn set_atlas_size(width: u32, height: u32) {
+    unsafe {
+        ATLAS = AtlasLayout::new(width, height);
+    }
+}
 
 pub struct TextureFace {
     pub tlx: f32,
-    pub tly: f32, 
+    pub tly: f32,
     pub blx: f32,
     pub bly: f32,
     pub brx: f32,
@@ -15,15 +90,23 @@ pub struct TextureFace {
 
 impl TextureFace {
     pub fn new(x: i8, y: i8) -> TextureFace {
+        TextureFace::new_in(&atlas(), x, y)
+    }
+
+    pub fn new_in(atlas: &AtlasLayout, x: i8, y: i8) -> TextureFace {
+        let left = atlas.one_pixel_u() + (atlas.cell_u() * x as f32);
+        let right = left + atlas.tile_u();
+        let bottom = 1.0 - (y as f32 * atlas.cell_v()) - atlas.one_pixel_v();
+        let top = bottom - atlas.tile_v();
         TextureFace {
-            tlx: 0.0 + ONE_PIXEL + (ONE_OVER_16 * x as f32),
-            tly: 1.0 - (y as f32 * ONE_OVER_16) - TEXTURE_WIDTH - ONE_PIXEL,
-            blx: 0.0 + ONE_PIXEL + (ONE_OVER_16 * x as f32),
-            bly: 1.0 - (y as f32 * ONE_OVER_16) - ONE_PIXEL,
-            brx: 0.0 + ONE_PIXEL + (ONE_OVER_16 * x as f32) + TEXTURE_WIDTH,
-            bry: 1.0 - (y as f32 * ONE_OVER_16) - ONE_PIXEL,
-            trx: 0.0 + ONE_PIXEL + (ONE_OVER_16 * x as f32) + TEXTURE_WIDTH,
-            tr_y: 1.0 - (y as f32 * ONE_OVER_16) - TEXTURE_WIDTH - ONE_PIXEL
+            tlx: left,
+            tly: top,
+            blx: left,
+            bly: bottom,
+            brx: right,
+            bry: bottom,
+            trx: right,
+            tr_y: top
         }
     }
-}
\ No newline at end of file
+}
@@ -40,4 +40,27 @@ pub fn get_tools_target_material(tool: u32) -> Material {
             Material::NoneOrNonTool
         }
     }
-}
\ No newline at end of file
+}
+
+//How many times faster the held item breaks this block than a bare hand
+pub fn get_tool_speed(tool: u32, block: u32) -> f32 {
+    //The blade has no material of its own, it just cuts through plants
+    if tool == 36 {
+        return match block {
+            7 | 11 | 22 | 23 | 34 | 44 => 3.0,
+            _ => 1.0
+        };
+    }
+
+    let target = get_tools_target_material(tool);
+    if target == Material::NoneOrNonTool || target != get_block_material(block) {
+        return 1.0;
+    }
+
+    match (tool, block) {
+        //The crude pick bites less into the harder rocks
+        (37, 9 | 13) => 3.0,
+        (37, 35) => 2.5,
+        _ => 4.0
+    }
+}
@@ -1,21 +1,100 @@
+use std::{collections::HashMap, fs, path::Path};
 
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tracing::info;
+use vox_format::data::*;
+use vox_format::types::*;
 
+use crate::blockinfo::Blocks;
+use crate::vec::IVec3;
 
+//One palette index to block id, as written in voxcolors.json
+#[derive(Deserialize, Clone, Debug)]
+pub struct VoxColorBlock {
+    pub color: u8,
+    pub block: u32,
+}
 
+pub fn parse_vox_colors(json: &str) -> Result<HashMap<u8, u32>, serde_json::Error> {
+    let list: Vec<VoxColorBlock> = serde_json::from_str(json)?;
+    Ok(list.into_iter().map(|c| (c.color, c.block)).collect())
+}
 
+//Our models are painted with palette index = block id, voxcolors.json next to the executable remaps indexes for prefabs painted with another palette
+pub static VOX_COLOR_BLOCKS: Lazy<HashMap<u8, u32>> = Lazy::new(|| {
+    let path = Path::new("voxcolors.json");
+    if path.exists() {
+        match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|json| parse_vox_colors(&json).map_err(|e| e.to_string())) {
+            Ok(colors) => {
+                info!("Loaded {} vox colors from voxcolors.json", colors.len());
+                return colors;
+            }
+            Err(e) => {
+                info!("Couldn't load voxcolors.json, using palette indexes as block ids: {}", e);
+            }
+        }
+    }
+    HashMap::new()
+});
 
-use vox_format::data::*;
-use vox_format::types::*;
+//Palette index to block, unmapped indexes past the last block become air
+pub fn vox_block(color: u8, mapping: &HashMap<u8, u32>) -> u32 {
+    match mapping.get(&color) {
+        Some(block) => *block,
+        None if (color as usize) < Blocks::get_texs_length() => color as u32,
+        None => 0,
+    }
+}
 
 pub struct JVoxModel {
+    pub name: String,
     pub model: VoxModels<Model>,
 }
 
 impl JVoxModel {
     pub fn new(path: &'static str) -> JVoxModel {
         JVoxModel {
+            name: JVoxModel::name_from_path(Path::new(path)),
             model: vox_format::from_file(path).unwrap(),
         }
     }
 
+    //For files picked up at runtime, a broken one is skipped instead of taking the game down
+    pub fn load(path: &Path) -> Option<JVoxModel> {
+        match vox_format::from_file(path) {
+            Ok(model) => Some(JVoxModel { name: JVoxModel::name_from_path(path), model }),
+            Err(e) => {
+                info!("Couldn't load {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn name_from_path(path: &Path) -> String {
+        path.file_stem().map_or(String::new(), |s| s.to_string_lossy().to_string())
+    }
+
+    //Blocks relative to where the prefab is stamped, centred on x/z and standing on the spot.
+    //.vox is z up and the world is y up, empty voxels aren't stored so air never overwrites anything.
+    pub fn prefab_blocks(&self, mapping: &HashMap<u8, u32>) -> Vec<(IVec3, u32)> {
+        let mut blocks = Vec::new();
+        for m in &self.model.models {
+            let size = m.size;
+            for v in &m.voxels {
+                let block = vox_block(v.color_index.0, mapping);
+                if block == 0 {
+                    continue;
+                }
+                //Points are stored as i8 but run 0-255
+                let offset = IVec3::new(
+                    v.point.x as u8 as i32 - (size.x / 2) as i32,
+                    v.point.z as u8 as i32,
+                    v.point.y as u8 as i32 - (size.y / 2) as i32,
+                );
+                blocks.push((offset, block));
+            }
+        }
+        blocks
+    }
 }
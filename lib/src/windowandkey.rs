@@ -1,5 +1,7 @@
-use crate::{blockinfo::Blocks, game::{Game, CROUCHING, CURRENT_AVAIL_RECIPES, DECIDEDSPORMP, MOUSEX, MOUSEY, SHOWTOOLTIP, SINGLEPLAYER, TOOLTIPNAME}, keybinds::{AboutToRebind, ABOUTTOREBIND, LISTENINGFORREBIND}, recipes::{RECIPES_DISABLED, RECIPE_COOLDOWN_TIMER}, statics::{LAST_ENTERED_SERVERADDRESS, LOAD_MISC, LOAD_OR_INITIALIZE_STATICS, MISCSETTINGS, SAVE_LESA}, texture::Texture};
+use crate::{blockinfo::Blocks, chunk::{MAX_RADIUS, MIN_RADIUS}, game::{Game, CROUCHING, MAX_FOV, MIN_FOV, CURRENT_AVAIL_RECIPES, DECIDEDSPORMP, MOUSEX, MOUSEY, SHOWTOOLTIP, SINGLEPLAYER, TOOLTIPNAME}, keybinds::{key_name, rebind, AboutToRebind, ABOUTTOREBIND, LISTENINGFORREBIND}, recipes::{RECIPES_DISABLED, RECIPE_COOLDOWN_TIMER}, statics::{LAST_ENTERED_SERVERADDRESS, LOAD_MISC, LOAD_OR_INITIALIZE_STATICS, MISCSETTINGS, SAVE_LESA, SAVE_MISC}, texture::Texture};
 
+#[cfg(feature = "audio")]
+use crate::game::AUDIOPLAYER;
 use glfw::{ffi::glfwGetKeyName, get_key_name, Action, Context, Glfw, GlfwReceiver, Key, Modifiers, PWindow, WindowEvent};
 
 use once_cell::sync::Lazy;
@@ -10,6 +12,10 @@ use parking_lot::{Mutex, RwLock};
 use imgui::*;
 use imgui::{Key as ImGuiKey};
 use imgui_opengl_renderer::Renderer;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tracing::info;
+use crate::saveslots::{create_save_slot, delete_save_slot, list_save_slots, now_secs, seed_from_text, SaveSlot, CHOSEN_SAVE_SLOT, SAVES_DIR};
+use crate::glcheck::{gl_failure, report_gl_failure, startup_failure_message};
 
 pub static mut WINDOWWIDTH: i32 = 0;
 pub static mut WINDOWHEIGHT: i32 = 0;
@@ -37,6 +43,12 @@ pub struct WindowAndKeyContext {
 
     pub serveraddrbuffer: String,
 
+    //Title screen world list, shown after picking Singleplayer
+    pub choosing_world: bool,
+    pub saveslots: Vec<SaveSlot>,
+    pub newworldname: String,
+    pub newworldseed: String,
+
     pub logo: Texture,
 
     #[cfg(feature = "glfw")]
@@ -45,6 +57,21 @@ pub struct WindowAndKeyContext {
     pub single: SingleClient
 }
 
+fn played_ago(last_played: u64) -> String {
+    let secs = now_secs().saturating_sub(last_played);
+    match secs {
+        0..=59 => "played just now".to_string(),
+        60..=3599 => format!("played {} min ago", secs / 60),
+        3600..=86399 => format!("played {} h ago", secs / 3600),
+        _ => format!("played {} days ago", secs / 86400),
+    }
+}
+
+//Where the window was before going fullscreen, so toggling back puts it where it was
+static mut WINDOWED_RECT: (i32, i32, i32, i32) = (100, 100, 1280, 720);
+
+//Uses the monitor's current video mode so it's borderless rather than a mode switch,
+//the GL context stays the same either way and the FramebufferSize event fixes up the viewport
 fn toggle_fullscreen(window_ptr: *mut glfw::ffi::GLFWwindow) {
     unsafe {
         let monitor = glfw::ffi::glfwGetWindowMonitor(window_ptr);
@@ -53,6 +80,12 @@ fn toggle_fullscreen(window_ptr: *mut glfw::ffi::GLFWwindow) {
             if !primary_monitor.is_null() {
                 let mode = glfw::ffi::glfwGetVideoMode(primary_monitor);
                 if !mode.is_null() {
+                    let (mut x, mut y, mut w, mut h) = (0, 0, 0, 0);
+                    glfw::ffi::glfwGetWindowPos(window_ptr, &mut x, &mut y);
+                    glfw::ffi::glfwGetWindowSize(window_ptr, &mut w, &mut h);
+                    if w > 0 && h > 0 {
+                        WINDOWED_RECT = (x, y, w, h);
+                    }
                     glfw::ffi::glfwSetWindowMonitor(
                         window_ptr,
                         primary_monitor,
@@ -60,18 +93,19 @@ fn toggle_fullscreen(window_ptr: *mut glfw::ffi::GLFWwindow) {
                         0,
                         (*mode).width as i32,
                         (*mode).height as i32,
-                        glfw::ffi::DONT_CARE,
+                        (*mode).refreshRate as i32,
                     );
                 }
             }
         } else {
+            let (x, y, w, h) = WINDOWED_RECT;
             glfw::ffi::glfwSetWindowMonitor(
                 window_ptr,
                 std::ptr::null_mut(),
-                100,
-                100,
-                1280,
-                720,
+                x,
+                y,
+                w,
+                h,
                 glfw::ffi::DONT_CARE,
             );
         }
@@ -190,7 +224,14 @@ impl WindowAndKeyContext {
             addressentered: Arc::new(AtomicBool::new(false)),
             serveraddress: Arc::new(Mutex::new(None)),
             serveraddrbuffer: String::with_capacity(128),
-            logo: Texture::new("assets/Untitled3.png").unwrap(),
+            choosing_world: false,
+            saveslots: Vec::new(),
+            newworldname: String::with_capacity(64),
+            newworldseed: String::with_capacity(32),
+            logo: Texture::new("assets/Untitled3.png").unwrap_or_else(|e| {
+                report_gl_failure(startup_failure_message(&[e]).unwrap());
+                Texture::empty()
+            }),
 
             #[cfg(feature = "glfw")]
             client: Arc::new(client),
@@ -290,16 +331,101 @@ impl WindowAndKeyContext {
                             let texture_id = imgui::TextureId::from(self.logo.id as usize);
                             imgui::Image::new(texture_id, scaled_size).build(&ui);
 
+                            //Nothing on the menu can work without the assets, so just say what's missing
+                            if let Some(failure) = gl_failure() {
+                                ui.set_cursor_pos([pos_x, pos_y - 50.0]);
+                                let wrap = ui.push_text_wrap_pos_with_pos(pos_x + button_width);
+                                ui.text_colored([1.0, 0.5, 0.5, 1.0], failure);
+                                wrap.end();
+                                ui.set_cursor_pos([pos_x, pos_y + 75.0]);
+                                if ui.button_with_size("Quit", [button_width, button_height]) {
+                                    self.window.write().set_should_close(true);
+                                }
+                                return;
+                            }
+
+                            if self.choosing_world {
+                                let mut y = pos_y - 50.0;
+                                ui.set_cursor_pos([pos_x, y]);
+                                ui.text_colored([1.0, 0.0, 0.0, 1.0], "Choose a world.");
+                                y += 25.0;
+
+                                let mut chosen: Option<SaveSlot> = None;
+                                let mut deleted: Option<SaveSlot> = None;
+
+                                for slot in &self.saveslots {
+                                    ui.set_cursor_pos([pos_x, y]);
+                                    let label = format!(
+                                        "{} (seed {}, {})##{}",
+                                        slot.name,
+                                        slot.seed,
+                                        played_ago(slot.last_played),
+                                        slot.folder
+                                    );
+                                    if ui.button_with_size(label, [button_width - 80.0, button_height]) {
+                                        chosen = Some(slot.clone());
+                                    }
+                                    ui.set_cursor_pos([pos_x + button_width - 75.0, y]);
+                                    if ui.button_with_size(format!("Delete##{}", slot.folder), [75.0, button_height]) {
+                                        deleted = Some(slot.clone());
+                                    }
+                                    y += button_height + 5.0;
+                                }
+
+                                y += 15.0;
+                                ui.set_cursor_pos([pos_x, y]);
+                                ui.set_next_item_width(button_width);
+                                ui.input_text("##newworldname", &mut self.newworldname)
+                                    .hint("World name")
+                                    .build();
+                                y += 25.0;
+                                ui.set_cursor_pos([pos_x, y]);
+                                ui.set_next_item_width(button_width);
+                                ui.input_text("##newworldseed", &mut self.newworldseed)
+                                    .hint("Seed (leave empty for random)")
+                                    .build();
+                                y += 25.0;
+                                ui.set_cursor_pos([pos_x, y]);
+                                if ui.button_with_size("New World", [button_width, button_height]) {
+                                    let seed = seed_from_text(&self.newworldseed)
+                                        .unwrap_or_else(|| StdRng::from_entropy().gen_range(0..72731273));
+                                    match create_save_slot(SAVES_DIR, &self.newworldname, seed) {
+                                        Ok(slot) => chosen = Some(slot),
+                                        Err(e) => info!("Couldn't create world: {}", e),
+                                    }
+                                }
+                                y += 25.0;
+                                ui.set_cursor_pos([pos_x, y]);
+                                if ui.button_with_size("Back", [button_width, button_height]) {
+                                    self.choosing_world = false;
+                                }
+
+                                if let Some(slot) = deleted {
+                                    if let Err(e) = delete_save_slot(SAVES_DIR, &slot) {
+                                        info!("Couldn't delete world {}: {}", slot.name, e);
+                                    }
+                                    self.saveslots = list_save_slots(SAVES_DIR);
+                                }
+
+                                if let Some(mut slot) = chosen {
+                                    slot.touch(SAVES_DIR);
+                                    *CHOSEN_SAVE_SLOT.lock() = Some(slot);
+                                    unsafe {
+                                        SINGLEPLAYER = true;
+                                        DECIDEDSPORMP = true;
+                                    }
+                                }
+                                return;
+                            }
+
                             ui.set_cursor_pos([pos_x, pos_y - 50.0]);
                             ui.text_colored([1.0, 0.0, 0.0, 1.0], "Welcome! Please choose an option.");
 
                             ui.set_cursor_pos([pos_x, pos_y - 25.0]);
 
                             if ui.button_with_size("Singleplayer", [button_width, button_height]) {
-                                unsafe {
-                                    SINGLEPLAYER = true;
-                                    DECIDEDSPORMP = true;
-                                }
+                                self.saveslots = list_save_slots(SAVES_DIR);
+                                self.choosing_world = true;
                             }
 
                             ui.set_cursor_pos([pos_x, pos_y]);
@@ -339,8 +465,11 @@ impl WindowAndKeyContext {
                             glfw::WindowEvent::FramebufferSize(wid, hei) => {
                                 self.width = wid as u32;
                                 self.height = hei as u32;
+                                io.display_size = [wid as f32, hei as f32];
                                 unsafe {
                                     gl::Viewport(0, 0, wid, hei);
+                                    WINDOWHEIGHT = hei;
+                                    WINDOWWIDTH = wid;
                                 }
                             }
                             glfw::WindowEvent::CursorPos(xpos, ypos) => {
@@ -364,6 +493,9 @@ impl WindowAndKeyContext {
                                         io.keys_down[glfw::Key::Backspace as usize] = true;
                                         io.add_input_character('\u{8}');
                                     }
+                                    glfw::Key::F11 => {
+                                        toggle_fullscreen(self.window.write().window_ptr());
+                                    }
                                     _ => {}
                                 }
                             } else if action == glfw::Action::Release {
@@ -395,6 +527,12 @@ impl WindowAndKeyContext {
                 true => {
                     match self.game.as_mut() {
                         Some(g) => {
+
+                            //Nothing drawn past a lost context or a broken shader can be trusted, the menu says what happened instead
+                            let glfailure = gl_failure();
+                            if let Some(message) = &glfailure {
+                                g.show_gl_failure(message);
+                            }
             
                             let gmenuopen = g.vars.menu_open;
             
@@ -411,7 +549,7 @@ impl WindowAndKeyContext {
                                 main_menu = true;
                             } else {
             
-                                if g.loadedworld.load(std::sync::atomic::Ordering::Relaxed) {
+                                if g.loadedworld.load(std::sync::atomic::Ordering::Relaxed) && glfailure.is_none() {
             
                                     
                                     g.update();
@@ -483,10 +621,32 @@ impl WindowAndKeyContext {
 
                                     self.guirenderer.render(&mut self.imgui);
                                 }
+
+                                //Only one imgui frame per loop, so the overlay sits out while another window is up
+                                let status = g.status_lines();
+                                if g.vars.show_debug && !gchestopen && !gmenuopen && !gcraftopen {
+                                    let mut lines = g.debug_overlay_lines();
+                                    lines.extend(status);
+
+                                    Self::draw_text_overlay(&mut self.imgui, &self.guirenderer, &self.window, "Debug Overlay", [420.0, 120.0], |_| [10.0, 10.0], &lines);
+                                } else if !status.is_empty() && !gchestopen && !gmenuopen && !gcraftopen {
+                                    let boxheight = 10.0 + 20.0 * status.len() as f32;
+                                    Self::draw_text_overlay(&mut self.imgui, &self.guirenderer, &self.window, "Status Indicator", [100.0, boxheight], |display| {
+                                        [display[0] - 110.0, display[1] - 10.0 - boxheight]
+                                    }, &status);
+                                }
             
                                 if gmenuopen {
             
                                     let gamecurrentbuttons = g.currentbuttons.clone();
+
+                                    let loading = gamecurrentbuttons.first().map_or(false, |(_, command)| command == "loading");
+                                    let failed = gamecurrentbuttons.first().map_or(false, |(_, command)| command == "glfailure");
+                                    let (loading_progress, loading_lines) = if loading {
+                                        (g.loading_progress(), g.loading_screen_lines())
+                                    } else {
+                                        (0.0, Vec::new())
+                                    };
                                     
                                     
                                     let (width, height) = self.window.read().get_framebuffer_size();
@@ -494,6 +654,15 @@ impl WindowAndKeyContext {
                                     
                                     // Start the ImGui frame
                                     let ui = self.imgui.frame();
+
+                                    if g.vars.dead {
+                                        let draw_list = ui.get_background_draw_list();
+                                        draw_list
+                                            .add_rect([0.0, 0.0], [width as f32, height as f32], [0.3, 0.0, 0.0, 0.6])
+                                            .filled(true)
+                                            .build();
+                                        draw_list.add_text([width as f32 / 2.0 - 30.0, height as f32 / 2.0 - 120.0], [1.0, 1.0, 1.0, 1.0], "You died!");
+                                    }
             
                                     let window_flags = WindowFlags::NO_DECORATION
                                         | WindowFlags::NO_MOVE
@@ -528,7 +697,30 @@ impl WindowAndKeyContext {
                                             let mut pos_y = (available_height - (len as f32 * button_height) - 10.0 * (len as f32 - 1.0)) / 2.0;
             
 
-                                            if gamecurrentbuttons.len() > 0 {
+                                            if loading {
+                                                let bar_width = 400.0;
+                                                ui.set_cursor_pos([(available_width - bar_width) / 2.0, pos_y]);
+                                                ProgressBar::new(loading_progress)
+                                                    .size([bar_width, button_height])
+                                                    .overlay_text(&loading_lines[0])
+                                                    .build(ui);
+                                                ui.set_cursor_pos([(available_width - bar_width) / 2.0, pos_y + button_height + 10.0]);
+                                                ui.text(&loading_lines[1]);
+                                            } else if failed {
+                                                let text_width = 600.0;
+                                                let text_x = (available_width - text_width) / 2.0;
+                                                ui.set_cursor_pos([text_x, pos_y - 60.0]);
+                                                let wrap = ui.push_text_wrap_pos_with_pos(text_x + text_width);
+                                                ui.text_colored([1.0, 0.5, 0.5, 1.0], &gamecurrentbuttons[0].0);
+                                                wrap.end();
+
+                                                let (quit, command) = &gamecurrentbuttons[1];
+                                                let button_width = quit.len() as f32 * 20.0;
+                                                ui.set_cursor_pos([(available_width - button_width) / 2.0, pos_y + 40.0]);
+                                                if ui.button_with_size(quit, [button_width, button_height]) {
+                                                    g.button_command(command.clone());
+                                                }
+                                            } else if gamecurrentbuttons.len() > 0 {
 
                                                 if gamecurrentbuttons[0].0 == "bindings" {
 
@@ -565,12 +757,11 @@ impl WindowAndKeyContext {
                                                             ui.set_cursor_pos([pos_x + button_width, pos_y]);
                                                             
 
-                                                            // let name = if glfwkey.starts_with("Button") { glfwkey } else { 
-                                                            //     &glfw::get_key_name(None, Some(glfwkey.parse::<i32>().unwrap_or(0))).unwrap_or("Unknown key".into())
-                                                            // };
-                                                            let name = glfwkey;
-                                                            if !name.is_empty() {
-                                                                if ui.button_with_size(name, [button_width, button_height]) {
+                                                            let name = if glfwkey.starts_with("Button") { glfwkey.clone() } else { 
+                                                                key_name(glfwkey.parse::<i32>().unwrap_or(0))
+                                                            };
+                                                            if !glfwkey.is_empty() {
+                                                                if ui.button_with_size(format!("{}##{}", name, glfwkey), [button_width, button_height]) {
                                                                 
                                                                 unsafe {
                                                                     LISTENINGFORREBIND = true;
@@ -645,14 +836,50 @@ impl WindowAndKeyContext {
                                                             }
                                                             if buttonname == "SliderMusic Volume" {
                                                                 if ui.slider(truncated_name, 0.0, 1.0, &mut MISCSETTINGS.music_vol) {
-                                                                    //g.button_command(command);
+                                                                    #[cfg(feature = "audio")]
+                                                                    AUDIOPLAYER.set_music_volume(MISCSETTINGS.music_vol);
                                                                 }
                                                             }
                                                             if buttonname == "SliderSounds Volume" {
                                                                 if ui.slider(truncated_name, 0.0, 1.0, &mut MISCSETTINGS.sound_vol) {
-                                                                    //g.button_command(command);
+                                                                    #[cfg(feature = "audio")]
+                                                                    AUDIOPLAYER.set_sfx_volume(MISCSETTINGS.sound_vol);
                                                                 }
                                                             }
+                                                            if buttonname == "SliderRender Distance" {
+                                                                if ui.slider(truncated_name, MIN_RADIUS, MAX_RADIUS, &mut MISCSETTINGS.render_distance) {
+                                                                    g.set_render_distance(MISCSETTINGS.render_distance);
+                                                                }
+                                                            }
+                                                            if buttonname == "SliderField of View" {
+                                                                let mut fov = MISCSETTINGS.fov;
+                                                                if ui.slider(truncated_name, MIN_FOV, MAX_FOV, &mut fov) {
+                                                                    g.set_base_fov(fov);
+                                                                }
+                                                            }
+                                                            if buttonname == "SliderDay Length" {
+                                                                if ui.slider(truncated_name, 120.0, 3600.0, &mut MISCSETTINGS.day_length) {
+                                                                    g.set_day_length(MISCSETTINGS.day_length);
+                                                                }
+                                                            }
+                                                            if buttonname == "SliderMaster Volume" {
+                                                                if ui.slider(truncated_name, 0.0, 1.0, &mut MISCSETTINGS.master_vol) {
+                                                                    #[cfg(feature = "audio")]
+                                                                    AUDIOPLAYER.set_master_volume(MISCSETTINGS.master_vol);
+                                                                }
+                                                            }
+                                                            if buttonname == "SliderHead Bob" {
+                                                                ui.slider(truncated_name, 0.0, 2.0, &mut MISCSETTINGS.head_bob);
+                                                            }
+                                                            if buttonname == "SliderMouse Smoothing" {
+                                                                if ui.slider(truncated_name, 0.0, 0.9, &mut MISCSETTINGS.mouse_smoothing) {
+                                                                    g.vars.mouse_smoothing = MISCSETTINGS.mouse_smoothing;
+                                                                }
+                                                            }
+                                                            //Write the settings out once a slider is let go
+                                                            if ui.is_item_deactivated_after_edit() {
+                                                                SAVE_MISC();
+                                                            }
                                                         } else {
                                                             if ui.button_with_size(buttonname, [button_width, button_height]) {
                                                                 g.button_command(command);
@@ -834,11 +1061,9 @@ impl WindowAndKeyContext {
                                                                                 
                                                                             },
                                                                             crate::keybinds::Rebindable::MouseButton(mb) => {
-                                                                                if !MISCSETTINGS.mousebinds.contains_key(&format!("{:?}", mousebutton)) {
-                                                                                    MISCSETTINGS.mousebinds.remove(&format!("{:?}", mb));
-                                                                                    MISCSETTINGS.mousebinds.insert(format!("{:?}", mousebutton), atr.action.clone());
-                                                                                    g.button_command("bindingsmenu".into());
-                                                                                }
+                                                                                rebind(&mut MISCSETTINGS.mousebinds, format!("{:?}", mb), format!("{:?}", mousebutton));
+                                                                                SAVE_MISC();
+                                                                                g.button_command("bindingsmenu".into());
                                                                                 
                                                                                 LISTENINGFORREBIND = false;
                                                                             },
@@ -900,11 +1125,19 @@ impl WindowAndKeyContext {
                                         glfw::WindowEvent::FramebufferSize(wid, hei) => {
                                             self.width = wid as u32;
                                             self.height = hei as u32;
+                                            io.display_size = [wid as f32, hei as f32];
                                             unsafe {
                                                 gl::Viewport(0, 0, wid, hei);
                                                 WINDOWHEIGHT = hei;
                                                 WINDOWWIDTH = wid
                                             }
+                                            //Otherwise the world stretches to the new shape
+                                            g.camera.lock().set_aspect(wid, hei);
+                                            //Slot quads are sized in pixels, so they need rebuilding for the new size
+                                            #[cfg(feature = "glfw")]
+                                            {
+                                                g.hud.dirty = true;
+                                            }
                                         }
                                         glfw::WindowEvent::CursorPos(xpos, ypos) => {
                                            
@@ -924,11 +1157,9 @@ impl WindowAndKeyContext {
                                                 
                                                                     match atr.key {
                                                                         crate::keybinds::Rebindable::Key(oldscan) => {
-                                                                            if !MISCSETTINGS.keybinds.contains_key(&keyscan) {
-                                                                                MISCSETTINGS.keybinds.remove(&oldscan);
-                                                                                MISCSETTINGS.keybinds.insert(keyscan, atr.action.clone());
-                                                                                g.button_command("bindingsmenu".into());
-                                                                            }
+                                                                            rebind(&mut MISCSETTINGS.keybinds, oldscan, keyscan);
+                                                                            SAVE_MISC();
+                                                                            g.button_command("bindingsmenu".into());
                                                                             
                                                                             LISTENINGFORREBIND = false;
                                                                         },
@@ -963,19 +1194,15 @@ impl WindowAndKeyContext {
                                                                 #[cfg(feature = "glfw")]
                                                                 g.keyboard(key, action);
                             
-                                                                if key == Key::Escape {
-                                                                    if g.vars.menu_open {
-                                                                    
-                                                                        self.window.write().set_cursor_mode(glfw::CursorMode::Normal);
-                                                                        g.set_mouse_focused(false);
-                                                                    } else {
-                                                                        g.vars.menu_open = false;
-                                                                        self.window.write().set_cursor_mode(glfw::CursorMode::Disabled);
-                                                                        g.set_mouse_focused(true);
-                                                                    }
-                                                                    
+                                                                //Whatever key is bound to the menu, free the mouse once it opens
+                                                                if g.vars.menu_open {
+                                                                    self.window.write().set_cursor_mode(glfw::CursorMode::Normal);
+                                                                    g.set_mouse_focused(false);
                                                                 }
                             
+                                                            } else if gmenuopen && action == Action::Press
+                                                                && MISCSETTINGS.keybinds.get(&scancode).map(|a| a.as_str()) == Some("Exit/Menu") {
+                                                                g.button_command("closemenu".into());
                                                             } else {
                                                                 //println!()
                                                             }
@@ -988,6 +1215,16 @@ impl WindowAndKeyContext {
                                                                         
                                                                     }
                                                                 }
+                                                                Key::F3 => {
+                                                                    if action == Action::Press {
+                                                                        g.vars.show_debug = !g.vars.show_debug;
+                                                                    }
+                                                                }
+                                                                Key::F4 => {
+                                                                    if action == Action::Press {
+                                                                        g.vars.wireframe = !g.vars.wireframe;
+                                                                    }
+                                                                }
                                                                 _ => {
                             
                                                                 }
@@ -1136,8 +1373,11 @@ impl WindowAndKeyContext {
                                 glfw::WindowEvent::FramebufferSize(wid, hei) => {
                                     self.width = wid as u32;
                                     self.height = hei as u32;
+                                    io.display_size = [wid as f32, hei as f32];
                                     unsafe {
                                         gl::Viewport(0, 0, wid, hei);
+                                        WINDOWHEIGHT = hei;
+                                        WINDOWWIDTH = wid;
                                     }
                                 }
                                 glfw::WindowEvent::CursorPos(xpos, ypos) => {
@@ -1169,6 +1409,9 @@ impl WindowAndKeyContext {
                                             io.keys_down[glfw::Key::Backspace as usize] = true;
                                             io.add_input_character('\x08');
                                         }
+                                        glfw::Key::F11 => {
+                                            toggle_fullscreen(self.window.write().window_ptr());
+                                        }
                                         _ => {}
                                     }
                                 } else if action == glfw::Action::Release {
@@ -1210,6 +1453,43 @@ impl WindowAndKeyContext {
         self.window.write().swap_buffers();
     }
 
+    //A see-through box of text lines in its own imgui frame, position gets the display size
+    fn draw_text_overlay(
+        imgui: &mut imgui::Context,
+        guirenderer: &imgui_opengl_renderer::Renderer,
+        window: &Arc<RwLock<PWindow>>,
+        name: &str,
+        size: [f32; 2],
+        position: impl FnOnce([f32; 2]) -> [f32; 2],
+        lines: &[String],
+    ) {
+        let (width, height) = window.read().get_framebuffer_size();
+        let display = [width as f32, height as f32];
+        imgui.io_mut().display_size = display;
+
+        let ui = imgui.frame();
+
+        let window_flags = WindowFlags::NO_DECORATION
+            | WindowFlags::NO_MOVE
+            | WindowFlags::NO_RESIZE
+            | WindowFlags::NO_SCROLLBAR
+            | WindowFlags::NO_TITLE_BAR
+            | WindowFlags::NO_INPUTS;
+
+        ui.window(name)
+            .size(size, Condition::Always)
+            .position(position(display), Condition::Always)
+            .bg_alpha(0.4)
+            .flags(window_flags)
+            .build(|| {
+                for line in lines {
+                    ui.text(line);
+                }
+            });
+
+        guirenderer.render(imgui);
+    }
+
     fn set_mod(io: &mut imgui::Io, modifier: Modifiers) {
         io.key_ctrl = modifier.intersects(Modifiers::Control);
         io.key_alt = modifier.intersects(Modifiers::Alt);
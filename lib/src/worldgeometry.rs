@@ -1,5 +1,7 @@
 use parking_lot::{Mutex, RwLock};
 use tracing::info;
+use crate::game::debug_assert_gl_allowed;
+use crate::glcheck::gl_check;
 use crate::shader::Shader;
 use gl;
 use gl::types::{GLsizei, GLsizeiptr, GLuint, GLvoid};
@@ -13,15 +15,13 @@ impl WorldGeometry {
         vbouv: GLuint,
         shader: &Shader,
     ) {
+        debug_assert_gl_allowed("bind_old_geometry_no_upload");
         unsafe {
 
 
             // Bind vertex buffer to the vertex array object
             gl::VertexArrayVertexBuffer(shader.vao, 0, vbov, 0, (5 * std::mem::size_of::<f32>()) as GLsizei);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbov with vao: {}", error);
-            }
+            gl_check("associating vbov with vao");
 
             // Position attribute
             let pos_attrib = gl::GetAttribLocation(shader.shader_id, b"position\0".as_ptr() as *const i8);
@@ -64,10 +64,7 @@ impl WorldGeometry {
 
             // Bind UV buffer to the vertex array object
             gl::VertexArrayVertexBuffer(shader.vao, 1, vbouv, 0, (4 * std::mem::size_of::<f32>()) as GLsizei);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbouv with vao: {}", error);
-            }
+            gl_check("associating vbouv with vao");
 
             // UV attribute
             let uv_attrib = gl::GetAttribLocation(shader.shader_id, b"uv\0".as_ptr() as *const i8);
@@ -104,6 +101,7 @@ impl WorldGeometry {
         uvdata: &[f32],
         shader: &Shader,
     ) {
+        debug_assert_gl_allowed("bind_old_geometry");
         unsafe {
             // Upload vertex data to named buffer
             gl::NamedBufferData(
@@ -112,17 +110,11 @@ impl WorldGeometry {
                 vdata.as_ptr() as *const GLvoid,
                 gl::STATIC_DRAW,
             );
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("Bind world geom err (vbov): {}", error);
-            }
+            gl_check("uploading vbov");
     
             // Bind vertex buffer to the vertex array object
             gl::VertexArrayVertexBuffer(shader.vao, 0, vbov, 0, (5 * std::mem::size_of::<f32>()) as GLsizei);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbov with vao: {}", error);
-            }
+            gl_check("associating vbov with vao");
     
             // Position attribute
             let pos_attrib = gl::GetAttribLocation(shader.shader_id, b"position\0".as_ptr() as *const i8);
@@ -181,17 +173,11 @@ impl WorldGeometry {
                 uvdata.as_ptr() as *const GLvoid,
                 gl::STATIC_DRAW,
             );
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("Bind world geom err (vbouv): {}", error);
-            }
+            gl_check("uploading vbouv");
     
             // Bind UV buffer to the vertex array object
             gl::VertexArrayVertexBuffer(shader.vao, 1, vbouv, 0, (4 * std::mem::size_of::<f32>()) as GLsizei);
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbouv with vao: {}", error);
-            }
+            gl_check("associating vbouv with vao");
     
             // UV attribute
             let uv_attrib = gl::GetAttribLocation(shader.shader_id, b"uv\0".as_ptr() as *const i8);
@@ -220,6 +206,7 @@ impl WorldGeometry {
         shader: &Shader,
         data: (&Mutex<Vec<u32>>, &Mutex<Vec<u8>>, &Mutex<Vec<u16>>),
     ) {
+        debug_assert_gl_allowed("bind_geometry");
         //info!("BInding geomery"); //Ah yes praise the lord when this is commented out it means nothing is wrong 
         unsafe {
             if upload {
@@ -231,13 +218,7 @@ impl WorldGeometry {
                     gl::STATIC_DRAW,
                 );
 
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!(
-                        "OpenGL Error after named buffering of vbo32 with upload true: {}",
-                        error
-                    );
-                }
+                gl_check("named buffering of vbo32 with upload true");
             }
 
             gl::VertexArrayVertexBuffer(
@@ -247,31 +228,19 @@ impl WorldGeometry {
                 0,
                 std::mem::size_of::<u32>() as i32,
             );
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbo32 with vao: {}", error);
-            }
+            gl_check("associating vbo32 with vao");
             if upload {
                 let u32one_attrib =
                     gl::GetAttribLocation(shader.shader_id, b"u32\0".as_ptr() as *const i8)
                         as gl::types::GLuint;
 
                 gl::EnableVertexArrayAttrib(shader.vao, u32one_attrib);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u32 array attrib: {}", error);
-                }
+                gl_check("u32 array attrib");
 
                 gl::VertexArrayAttribIFormat(shader.vao, u32one_attrib, 1, gl::UNSIGNED_INT, 0);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u32 attrib format: {}", error);
-                }
+                gl_check("u32 attrib format");
                 gl::VertexArrayAttribBinding(shader.vao, u32one_attrib, 0);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u32 attrib binding: {}", error);
-                }
+                gl_check("u32 attrib binding");
                 let data1lock = data.1.lock();
                 gl::NamedBufferData(
                     vbo8,
@@ -280,13 +249,7 @@ impl WorldGeometry {
                     gl::STATIC_DRAW,
                 );
 
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!(
-                        "OpenGL Error after named buffering of vbo8 with upload true: {}",
-                        error
-                    );
-                }
+                gl_check("named buffering of vbo8 with upload true");
 
                 drop(data1lock);
 
@@ -299,10 +262,7 @@ impl WorldGeometry {
                 0,
                 std::mem::size_of::<u8>() as i32,
             );
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbo8 with vao: {}", error);
-            }
+            gl_check("associating vbo8 with vao");
             if upload {
                 let u8_attrib =
                     gl::GetAttribLocation(shader.shader_id, b"eightbit\0".as_ptr() as *const i8)
@@ -310,22 +270,13 @@ impl WorldGeometry {
                 //info!("U8 attrib location: {}", u8_attrib);
                 gl::EnableVertexArrayAttrib(shader.vao, u8_attrib);
 
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib: {}", error);
-                }
+                gl_check("u8 array attrib");
 
                 gl::VertexArrayAttribIFormat(shader.vao, u8_attrib, 1, gl::UNSIGNED_BYTE, 0);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib format: {}", error);
-                }
+                gl_check("u8 array attrib format");
 
                 gl::VertexArrayAttribBinding(shader.vao, u8_attrib, 1);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib binding: {}", error);
-                }
+                gl_check("u8 array attrib binding");
             }
 
             gl::VertexArrayVertexBuffer(
@@ -335,10 +286,7 @@ impl WorldGeometry {
                 0,
                 std::mem::size_of::<u16>() as i32,
             );
-            let error = gl::GetError();
-            if error != gl::NO_ERROR {
-                info!("OpenGL Error after associating vbo8 with vao: {}", error);
-            }
+            gl_check("associating vbo8 with vao");
             if upload {
 
                 let data2lock = data.2.lock();
@@ -349,13 +297,7 @@ impl WorldGeometry {
                     gl::STATIC_DRAW,
                 );
 
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!(
-                        "OpenGL Error after named buffering of vbo8rgb with upload true: {}",
-                        error
-                    );
-                }
+                gl_check("named buffering of vbo8rgb with upload true");
 
 
                 let u8rgb_attrib =
@@ -364,22 +306,13 @@ impl WorldGeometry {
                 //info!("U8 attrib location: {}", u8_attrib);
                 gl::EnableVertexArrayAttrib(shader.vao, u8rgb_attrib);
 
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib: {}", error);
-                }
+                gl_check("u8 array attrib");
 
                 gl::VertexArrayAttribIFormat(shader.vao, u8rgb_attrib, 1, gl::UNSIGNED_SHORT, 0);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib format: {}", error);
-                }
+                gl_check("u8 array attrib format");
 
                 gl::VertexArrayAttribBinding(shader.vao, u8rgb_attrib, 2);
-                let error = gl::GetError();
-                if error != gl::NO_ERROR {
-                    info!("OpenGL Error after u8 array attrib binding: {}", error);
-                }
+                gl_check("u8 array attrib binding");
             }
         }
     }
@@ -1,4 +1,46 @@
-use voxelland::packedvertex::PackedVertex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use glam::{Vec2, Vec3};
+use lockfree::queue::Queue;
+use parking_lot::{Mutex, RwLock};
+use voxelland::ambience::{ambient_bed, is_underground, AmbientCommand, AmbientMixer, AMBIENT_FADE_SECONDS};
+use voxelland::animatedtiles::{parse_animated_tiles, AnimatedTile, TileAnimator, TileEffect, ANIMATED_TILES};
+use voxelland::blockedits::{EditBatch, EditRateLimiter, PendingEdit, PendingEdits, EDIT_WINDOW, MAX_EDITS_PER_WINDOW, PENDING_EDIT_TIMEOUT, SAVE_BATCH_INTERVAL};
+use voxelland::blockinfo::Blocks;
+use voxelland::blockoverlay::BreakProgress;
+use voxelland::camera::Camera;
+use voxelland::chunk::{Biome, ChunkFacade, ChunkGeo, ChunkSystem, LightColor, MAX_BLOCK_Y, MAX_CHUNK_HEIGHT, OCCLUSION_SECTION, REGION_CACHE_MAX, REGION_SIZE};
+use voxelland::collisioncage::{BoundBox, CollCage, Side};
+use voxelland::cube::{Cube, CubeSide};
+use voxelland::droptables::{break_series, flood_breaks, parse_block_defs, parse_drop_tables, place_series, roll_drops, sound_series_in, BLOCK_DEFS, DROP_TABLES};
+use voxelland::game::{hotbar_slot_for_key, wrap_hotbar_slot, ChunkThread, Game, HeadlessGuard, GameMode, ShipState, CREATIVE_BREAK_REPEAT, CREATIVE_REACH, FLOOD_BREAK_MAX, MOB_HIT_REACH, ROWLENGTH, SURVIVAL_REACH, TIME_SNAP_SECONDS, TIME_SYNC_INTERVAL, VOID_DEATH_Y};
+use voxelland::glcheck::{gl_error_name, gl_failure, is_fatal_gl_error, report_gl_failure, startup_failure_message, GL_CONTEXT_LOST};
+use voxelland::headlessclient::{bot_edit, scripted_path, BOT_PATH_RADIUS, BOT_WALK_SPEED};
+use voxelland::hud::{HudElement, SlotIndexType};
+use voxelland::inventory::Inventory;
+use voxelland::keybinds::rebind;
+use voxelland::music::{mood_volume, MusicCommand, Playlist, CROSSFADE_SECONDS};
+use voxelland::modelentity::{facing_yaw, lerp_angle, received_yaw, ModelEntity, PLAYER_UPDATE_INTERVAL};
+use voxelland::packedvertex::{PackedVertex, MAX_TEX_COORD};
+use voxelland::particles::ParticlePool;
+use voxelland::playerposition::{PlayerPosition, PlayerState, PlayerVec};
+use voxelland::planetinfo::{Planets, HOME_GRAVITY, PLANET_TYPES};
+use voxelland::server_types::{Message, MessageType};
+use voxelland::shader::Shader;
+use voxelland::raycast::raycast_voxel_with_bob;
+use voxelland::recipes::{parse_recipes, try_craft, Recipe};
+use voxelland::texture::Texture;
+use voxelland::textureface::{AtlasLayout, TextureFace, CELL_PX, DEFAULT_ATLAS_PX};
+use voxelland::glyphface::GlyphFace;
+use voxelland::tools::get_tool_speed;
+use voxelland::saveslots::{create_save_slot, delete_save_slot, list_save_slots, seed_from_text, SaveSlot};
+use voxelland::vec::{IVec2, IVec3};
+use voxelland::voxmodel::{parse_vox_colors, vox_block, JVoxModel};
+use uuid::Uuid;
 
 #[test]
 fn test_coord_packing() {
@@ -79,3 +121,2041 @@ fn test_coord_unpacking() {
     assert_eq!(amb, unpk_amb as u8);
     assert_eq!(unpk_bl as u8, bl);
 }
+
+fn sample_spots() -> Vec<IVec3> {
+    let mut spots = Vec::new();
+    for x in (-64..64).step_by(9) {
+        for z in (-64..64).step_by(11) {
+            for y in (0..120).step_by(7) {
+                spots.push(IVec3::new(x, y, z));
+            }
+        }
+    }
+    spots
+}
+
+#[test]
+fn test_same_seed_same_world() {
+    let a = ChunkSystem::new(1, 1234, 0, true);
+    let b = ChunkSystem::new(1, 1234, 0, true);
+
+    for spot in sample_spots() {
+        assert_eq!(a.blockat(spot), b.blockat(spot));
+    }
+}
+
+#[test]
+fn test_different_seed_different_world() {
+    let a = ChunkSystem::new(1, 1234, 0, true);
+    let b = ChunkSystem::new(1, 4321, 0, true);
+
+    let differing = sample_spots()
+        .into_iter()
+        .filter(|spot| a.blockat(*spot) != b.blockat(*spot))
+        .count();
+
+    assert!(differing > 0);
+}
+
+#[test]
+fn test_fbm_bounded_and_continuous() {
+    let csys = ChunkSystem::new(1, 99, 0, true);
+
+    for octaves in 1..6 {
+        let mut last = csys.fbm(0.0, 0.0, octaves, 2.0, 0.5);
+        for i in 1..2000 {
+            let x = i as f64 * 0.01;
+            let z = i as f64 * 0.007;
+            let v = csys.fbm(x, z, octaves, 2.0, 0.5);
+
+            assert!(!v.is_nan());
+            assert!(v >= -1.0 && v <= 1.0);
+            assert!((v - last).abs() < 0.5);
+            last = v;
+        }
+    }
+}
+
+fn empty_inventory() -> Arc<RwLock<Inventory>> {
+    Arc::new(RwLock::new(Inventory {
+        dirty: false,
+        inv: [(0, 0); ROWLENGTH as usize],
+    }))
+}
+
+#[test]
+fn test_add_to_inventory_fills_one_stack() {
+    let inv = empty_inventory();
+    let queue = Arc::new(Queue::new());
+
+    assert!(Game::add_to_inventory(&inv, 5, 40, false, &queue).is_ok());
+    assert!(Game::add_to_inventory(&inv, 5, 59, false, &queue).is_ok());
+
+    let inv = inv.read();
+    assert_eq!(inv.inv[0], (5, 99));
+    assert_eq!(inv.inv[1], (0, 0));
+}
+
+#[test]
+fn test_add_to_inventory_overflows_into_new_slot() {
+    let inv = empty_inventory();
+    let queue = Arc::new(Queue::new());
+
+    assert!(Game::add_to_inventory(&inv, 5, 90, false, &queue).is_ok());
+    assert!(Game::add_to_inventory(&inv, 5, 20, false, &queue).is_ok());
+
+    let inv = inv.read();
+    assert_eq!(inv.inv[0], (5, 99));
+    assert_eq!(inv.inv[1], (5, 11));
+    assert!(inv.inv.iter().all(|slot| slot.1 <= Blocks::get_max_stack(slot.0)));
+}
+
+#[test]
+fn test_add_to_inventory_splits_large_count() {
+    let inv = empty_inventory();
+    let queue = Arc::new(Queue::new());
+
+    assert!(Game::add_to_inventory(&inv, 3, 250, false, &queue).is_ok());
+
+    let inv = inv.read();
+    assert_eq!(inv.inv[0], (3, 99));
+    assert_eq!(inv.inv[1], (3, 99));
+    assert_eq!(inv.inv[2], (3, 52));
+}
+
+#[test]
+fn test_add_to_inventory_full_leaves_inventory_untouched() {
+    let inv = empty_inventory();
+    let queue = Arc::new(Queue::new());
+
+    for i in 0..ROWLENGTH as usize {
+        inv.write().inv[i] = (10 + i as u32, 99);
+    }
+    inv.write().inv[0] = (5, 95);
+    let before = inv.read().inv;
+
+    assert!(Game::add_to_inventory(&inv, 5, 10, false, &queue).is_err());
+    assert_eq!(inv.read().inv, before);
+}
+
+#[test]
+fn test_merge_stacks_caps_at_max() {
+    assert_eq!(Game::merge_stacks(5, 60, 30), (90, 0));
+    assert_eq!(Game::merge_stacks(5, 60, 50), (99, 11));
+    assert_eq!(Game::merge_stacks(37, 1, 1), (1, 1));
+}
+
+#[test]
+fn test_try_craft_consumes_inputs_and_adds_output() {
+    let recipe: Recipe = (vec![(10, 1), (35, 3)], (36, 1), true);
+    let mut inv = [(10, 2), (35, 2), (35, 5), (0, 0)];
+
+    assert!(try_craft(&mut inv, &recipe));
+    assert_eq!(inv, [(10, 1), (36, 1), (35, 4), (0, 0)]);
+}
+
+#[test]
+fn test_try_craft_missing_inputs_consumes_nothing() {
+    let recipe: Recipe = (vec![(10, 1), (35, 3)], (36, 1), true);
+    let mut inv = [(10, 2), (35, 2), (0, 0), (0, 0)];
+    let before = inv;
+
+    assert!(!try_craft(&mut inv, &recipe));
+    assert_eq!(inv, before);
+}
+
+#[test]
+fn test_try_craft_no_room_for_output_consumes_nothing() {
+    let recipe: Recipe = (vec![(6, 1)], (10, 4), true);
+    let mut inv = [(6, 5), (10, 99), (3, 99)];
+    let before = inv;
+
+    assert!(!try_craft(&mut inv, &recipe));
+    assert_eq!(inv, before);
+}
+
+#[test]
+fn test_parse_recipes() {
+    let json = r#"[
+        { "inputs": [[6, 1]], "output": [10, 4] },
+        { "inputs": [[10, 8]], "output": [21, 1], "by_hand": false }
+    ]"#;
+    let recipes = parse_recipes(json).unwrap();
+
+    assert_eq!(recipes.len(), 2);
+    assert_eq!(recipes[0], (vec![(6, 1)], (10, 4), true));
+    assert_eq!(recipes[1], (vec![(10, 8)], (21, 1), false));
+    assert!(parse_recipes("{ not json").is_err());
+}
+
+#[test]
+fn test_block_light_falls_off_with_distance() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let origin = IVec3::new(0, 230, 0);
+    let mut imp = HashSet::new();
+
+    csys.propagate_light_origin(origin, origin, LightColor::new(15, 15, 15), &mut imp);
+
+    let lm = csys.lightmap.lock();
+    let at = |v: IVec3| lm.get(&v).map(|seg| seg.sum().x).unwrap_or(0);
+
+    assert_eq!(at(origin), 15);
+    assert_eq!(at(origin + IVec3::new(1, 0, 0)), 13);
+    assert_eq!(at(origin + IVec3::new(2, 0, 1)), 9);
+    assert_eq!(at(origin + IVec3::new(0, -7, 0)), 1);
+    assert_eq!(at(origin + IVec3::new(0, 8, 0)), 0);
+}
+
+#[test]
+fn test_log_axis_bits_and_faces() {
+    let mut log = 6;
+    Blocks::set_axis_bits(&mut log, Blocks::axis_from_normal(IVec3::new(-1, 0, 0)));
+
+    assert_eq!(log & Blocks::block_id_bits(), 6);
+    assert_eq!(Blocks::get_axis_bits(log), 1);
+    assert_eq!(Blocks::axis_side(CubeSide::RIGHT, 1), CubeSide::TOP);
+    assert_eq!(Blocks::axis_side(CubeSide::TOP, 1), CubeSide::LEFT);
+    assert_eq!(Blocks::axis_side(CubeSide::FRONT, 2), CubeSide::TOP);
+    assert_eq!(Blocks::axis_side(CubeSide::TOP, 0), CubeSide::TOP);
+    assert_eq!(Blocks::get_axis_bits(6), 0);
+}
+
+#[test]
+fn test_walk_series_by_surface() {
+    assert_eq!(Blocks::get_walk_series(3), "grassstepseries");
+    assert_eq!(Blocks::get_walk_series(1), "sandstepseries");
+    assert_eq!(Blocks::get_walk_series(48), "snowstepseries");
+    assert_eq!(Blocks::get_walk_series(21), "woodstepseries");
+    assert_eq!(Blocks::get_walk_series(44), "leavesstepseries");
+    assert_eq!(Blocks::get_walk_series(47), "stonestepseries");
+}
+
+#[test]
+fn test_wandering_mob_turns_at_wall() {
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(1, 1234, 0, true)));
+    let cam = Arc::new(Mutex::new(Camera::new()));
+    csys.read().set_block(IVec3::new(0, 230, 1), 9, true);
+    csys.read().set_block(IVec3::new(0, 231, 1), 9, true);
+
+    let mut mob = ModelEntity::new(0, Vec3::new(0.5, 230.2, 0.5), 1.0, Vec3::ZERO, &csys, &cam, false);
+    mob.recalculate();
+    mob.controls.forward = true;
+
+    mob.avoid_walls();
+    assert!(!mob.controls.forward);
+    assert!(mob.controls.lookingleft != mob.controls.lookingright);
+
+    mob.rot.y = std::f32::consts::PI;
+    mob.recalculate();
+    mob.avoid_walls();
+    assert!(mob.controls.forward);
+    assert!(!mob.turning_from_wall);
+}
+
+#[test]
+fn test_push_out_xz_separates_overlapping_boxes() {
+    let mut a = BoundBox::new(Vec3::ZERO);
+    let mut b = BoundBox::new(Vec3::ZERO);
+    a.set_center(Vec3::new(0.0, 0.0, 0.0), 0.2, 0.85);
+    b.set_center(Vec3::new(0.3, 0.0, 0.1), 0.2, 0.85);
+
+    let push = a.push_out_xz(&b).unwrap();
+    assert!((push - Vec3::new(-0.1, 0.0, 0.0)).length() < 1e-5);
+
+    a.set_center(Vec3::new(0.0, 0.0, 0.0) + push, 0.2, 0.85);
+    assert!(a.push_out_xz(&b).map_or(true, |p| p.length() < 1e-5));
+
+    b.set_center(Vec3::new(2.0, 0.0, 0.0), 0.2, 0.85);
+    assert!(a.push_out_xz(&b).is_none());
+}
+
+#[test]
+fn test_water_spreads_and_drains() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    for x in -9..=9 {
+        for z in -9..=9 {
+            csys.set_block(IVec3::new(x, 229, z), 9, true);
+        }
+    }
+    let level = |spot: IVec3| {
+        let bits = csys.blockat(spot);
+        (bits & Blocks::block_id_bits() == 2).then(|| Blocks::get_water_level(bits))
+    };
+
+    csys.set_block(IVec3::new(0, 230, 0), 2, true);
+    csys.step_fluids();
+    assert_eq!(level(IVec3::new(1, 230, 0)), Some(1));
+    assert_eq!(level(IVec3::new(2, 230, 0)), None);
+
+    for _ in 0..10 {
+        csys.step_fluids();
+    }
+    assert_eq!(level(IVec3::new(7, 230, 0)), Some(7));
+    assert_eq!(level(IVec3::new(8, 230, 0)), None);
+
+    csys.set_block(IVec3::new(0, 230, 0), 0, true);
+    for _ in 0..40 {
+        csys.step_fluids();
+    }
+    assert_eq!(level(IVec3::new(1, 230, 0)), None);
+    assert_eq!(level(IVec3::new(5, 230, 3)), None);
+}
+
+#[test]
+fn test_bedrock_floor_cant_be_removed() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    csys.set_block(IVec3::new(3, 0, 3), 0, true);
+
+    assert_eq!(csys.blockat(IVec3::new(3, 0, 3)), 15);
+    assert_eq!(csys.blockat(IVec3::new(3, -40, 3)), 15);
+    assert!(csys.is_solid(IVec3::new(3, -1, 3)));
+    assert!(Blocks::is_unbreakable(15));
+}
+
+#[test]
+fn test_highest_solid_y_is_solid_ground() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+
+    let y = csys.highest_solid_y(0, 0).unwrap();
+
+    assert!(csys.is_solid(IVec3::new(0, y, 0)));
+    assert!(!csys.is_solid(IVec3::new(0, y + 1, 0)));
+}
+
+#[test]
+fn test_natural_column_matches_noise_and_is_reused() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+
+    let column = csys.natural_column(7, -3);
+    for y in 0..csys.chunk_height {
+        assert_eq!(column.block_at(y), csys.natural_blockat(IVec3::new(7, y, -3)));
+    }
+    assert_eq!(csys.take_column_cache_stats(), (0, 1));
+
+    csys.natural_column(7, -3);
+    assert_eq!(csys.take_column_cache_stats(), (1, 0));
+}
+
+#[test]
+fn test_far_lod_mesh_uses_fewer_vertices() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let cpos = IVec2::new(3, -2);
+
+    let full = csys.lod_mesh(cpos, 1);
+    let coarse = csys.lod_mesh(cpos, 4);
+
+    assert!(!coarse.data32.is_empty());
+    assert_eq!(coarse.data32.len() % 6, 0);
+    assert!(coarse.data32.len() * 2 < full.data32.len());
+}
+
+#[test]
+fn test_smooth_corner_light_occlusion() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let spot = IVec3::new(0, 230, 0);
+    let up = IVec3::new(0, 1, 0);
+    let amb = Cube::get_amb_occul_spots(CubeSide::TOP, 0);
+    csys.set_block(spot, 1, true);
+
+    let corner = |csys: &ChunkSystem| {
+        let (ao, sky, _) = csys.smooth_corner_light(spot, up, amb, &mut HashMap::new(), &mut HashMap::new());
+        (ao, sky)
+    };
+
+    assert_eq!(corner(&csys), (0, 15));
+
+    csys.set_block(spot + IVec3::new(-1, 1, -1), 1, true);
+    assert_eq!(corner(&csys), (1, 15));
+
+    csys.set_block(spot + IVec3::new(-1, 1, 0), 1, true);
+    csys.set_block(spot + IVec3::new(0, 1, -1), 1, true);
+    assert_eq!(corner(&csys).0, 3);
+}
+
+#[test]
+fn test_block_light_depropagates() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let origin = IVec3::new(0, 230, 0);
+    let mut imp = HashSet::new();
+
+    csys.propagate_light_origin(origin, origin, LightColor::new(15, 15, 15), &mut imp);
+    csys.depropagate_light_origin(origin, &mut imp);
+
+    let lm = csys.lightmap.lock();
+    assert!(lm.values().all(|seg| seg.rays.iter().all(|ray| ray.origin != origin)));
+}
+
+#[test]
+fn test_sky_light_bits_leave_other_fields_alone() {
+    let (packed32, _packed8) = PackedVertex::pack(7, 200, 9, 3, 12, 5, 0, 0);
+    let lit = PackedVertex::with_sky_light(packed32, 11);
+
+    assert_eq!(PackedVertex::unpack_sky_light(lit), 11);
+    assert_eq!(lit & !0b1111, packed32);
+    assert_eq!(PackedVertex::unpack_sky_light(PackedVertex::with_sky_light(lit, 2)), 2);
+}
+
+#[test]
+fn test_sky_light_open_sky_vs_underground() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let mut skytops = std::collections::HashMap::new();
+    let mut memo = std::collections::HashMap::new();
+
+    assert_eq!(csys.sky_light_at(IVec3::new(0, 250, 0), &mut skytops, &mut memo), 15);
+    assert!(csys.sky_light_at(IVec3::new(0, 2, 0), &mut skytops, &mut memo) < 15);
+}
+
+#[test]
+fn test_rebind_moves_action_to_free_key() {
+    let mut binds: HashMap<i32, String> = HashMap::from([(17, "Forward".to_string()), (30, "Left".to_string())]);
+    rebind(&mut binds, 17, 103);
+
+    assert_eq!(binds.get(&103).map(|s| s.as_str()), Some("Forward"));
+    assert!(!binds.contains_key(&17));
+    assert_eq!(binds.len(), 2);
+}
+
+#[test]
+fn test_rebind_to_taken_key_swaps() {
+    let mut binds: HashMap<i32, String> = HashMap::from([(17, "Forward".to_string()), (30, "Left".to_string())]);
+    rebind(&mut binds, 17, 30);
+
+    assert_eq!(binds.get(&30).map(|s| s.as_str()), Some("Forward"));
+    assert_eq!(binds.get(&17).map(|s| s.as_str()), Some("Left"));
+}
+
+#[test]
+fn test_sun_direction_follows_the_day() {
+    let day = 900.0;
+
+    let noon = Game::sun_direction(day / 2.0, day);
+    assert!(noon.y > 0.9);
+
+    let sunrise = Game::sun_direction(day / 6.0, day);
+    assert!(sunrise.y.abs() < 0.01);
+    assert!(sunrise.x > 0.9);
+
+    let sunset = Game::sun_direction(day * 0.75, day);
+    assert!(sunset.y.abs() < 0.01);
+    assert!(sunset.x < -0.9);
+
+    //Middle of the night, the moon (opposite the sun) is up
+    let midnight = Game::sun_direction(day * 0.75 + (day * 5.0 / 12.0) / 2.0, day);
+    assert!(midnight.y < -0.9);
+}
+
+#[test]
+fn test_stars_only_at_night() {
+    assert_eq!(Game::star_opacity(1.0), 0.0);
+    assert_eq!(Game::star_opacity(Game::STAR_FADE_START), 0.0);
+    assert_eq!(Game::star_opacity(0.08), 1.0);
+
+    let dusk = Game::star_opacity(0.4);
+    assert!(dusk > 0.0 && dusk < 1.0);
+}
+
+#[test]
+fn test_inventory_room_for() {
+    let inv = empty_inventory();
+    assert_eq!(inv.read().room_for(1), 99 * ROWLENGTH as u32);
+
+    for slot in inv.write().inv.iter_mut() {
+        *slot = (5, 99);
+    }
+    assert_eq!(inv.read().room_for(1), 0);
+
+    inv.write().inv[0] = (1, 90);
+    assert_eq!(inv.read().room_for(1), 9);
+}
+
+#[test]
+fn test_sweep_stops_large_move_at_wall() {
+    //Everything from x = 5 on is solid
+    let mut cage = CollCage::new(Box::new(|v: voxelland::vec::IVec3| v.x >= 5));
+    let start = glam::Vec3::new(2.5, 10.5, 0.5);
+    let end = glam::Vec3::new(9.5, 10.5, 0.5);
+    let box_offset = glam::Vec3::new(0.0, -0.5, 0.0);
+    let cage_offset = glam::Vec3::new(0.0, -1.0, 0.0);
+
+    let mut bound_box = BoundBox::new(start);
+    cage.update_readings(start + cage_offset);
+
+    let (resolved, hit) = cage.sweep(&mut bound_box, start, end, (0.2, 0.95), box_offset, cage_offset);
+
+    assert!(resolved.x < 5.0, "tunneled to x = {}", resolved.x);
+    assert!(hit.contains(&Side::RIGHTBOTTOM) || hit.contains(&Side::RIGHTTOP));
+    assert_eq!(resolved.y, start.y);
+}
+
+#[test]
+fn test_ship_flight_reaches_new_world_and_lands() {
+    let mut state = ShipState::Ascending;
+    let mut offset = 0.0;
+    let mut seen = vec![state];
+    let mut new_worlds = 0;
+
+    for _ in 0..10000 {
+        let (next, next_offset, load) = state.step(offset, 1.0 / 60.0);
+        if load {
+            new_worlds += 1;
+        }
+        if next != state {
+            seen.push(next);
+        }
+        state = next;
+        offset = next_offset;
+        if state == ShipState::Grounded {
+            break;
+        }
+    }
+
+    assert_eq!(
+        seen,
+        vec![ShipState::Ascending, ShipState::Transitioning, ShipState::Descending, ShipState::Grounded]
+    );
+    assert_eq!(new_worlds, 1);
+    assert_eq!(offset, 0.0);
+}
+
+#[test]
+fn test_planet_types_look_different() {
+    let types: Vec<u32> = (0..PLANET_TYPES as u32).collect();
+    for a in &types {
+        for b in &types {
+            if a != b {
+                assert_ne!(Planets::get_fog_col(*a), Planets::get_fog_col(*b));
+            }
+        }
+    }
+    assert!(types.iter().any(|t| Planets::is_hostile(*t)));
+    assert!(types.iter().any(|t| !Planets::is_hostile(*t)));
+    assert_eq!(Planets::get_name(2), "Frozen");
+
+    let home = ChunkSystem::new(1, 1234, 0, true);
+    for planet in [2, 3] {
+        let other = ChunkSystem::new(1, 1234, planet, true);
+        let differing = sample_spots()
+            .into_iter()
+            .filter(|spot| home.blockat(*spot) != other.blockat(*spot))
+            .count();
+        assert!(differing > 0);
+    }
+}
+
+#[test]
+fn test_hostile_planet_has_red_rock_ground() {
+    let a = ChunkSystem::new(1, 1234, 1, true);
+    let b = ChunkSystem::new(1, 1234, 1, true);
+    for spot in sample_spots() {
+        assert_eq!(a.blockat(spot), b.blockat(spot));
+    }
+
+    let mut tops = Vec::new();
+    for x in (-60..60).step_by(6) {
+        for z in (-60..60).step_by(6) {
+            if let Some(y) = a.highest_solid_y(x, z) {
+                tops.push(a.blockat(IVec3::new(x, y, z)) & Blocks::block_id_bits());
+            }
+        }
+    }
+    assert!(tops.contains(&13));
+    assert!(!tops.contains(&3));
+}
+
+#[test]
+fn test_player_state_round_trips_and_rejects_bad_saves() {
+    let dir = std::env::temp_dir().join(format!("voxelland-playerstate-{}", std::process::id()));
+    let path = dir.join("saves").join("playerstate");
+    let path = path.to_str().unwrap();
+
+    assert!(PlayerState::load(path).is_none());
+
+    let mut inv = [(0, 0); ROWLENGTH as usize];
+    inv[2] = (10, 64);
+    let state = PlayerState {
+        position: PlayerPosition { pos: PlayerVec { x: 1.5, y: 80.0, z: -3.25 }, pitch: -10.0, yaw: 90.0 },
+        inv,
+        health: 13.5,
+        slot: 2
+    };
+    state.save(path).unwrap();
+
+    let loaded = PlayerState::load(path).unwrap();
+    assert_eq!(loaded.position.pos.z, -3.25);
+    assert_eq!(loaded.position.yaw, 90.0);
+    assert_eq!(loaded.inv, inv);
+    assert_eq!(loaded.health, 13.5);
+    assert_eq!(loaded.slot, 2);
+
+    std::fs::write(path, [1, 2, 3]).unwrap();
+    assert!(PlayerState::load(path).is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_slots_create_list_and_delete() {
+    let dir = std::env::temp_dir().join(format!("voxelland-saveslots-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+
+    assert!(list_save_slots(dir).is_empty());
+
+    let mut first = create_save_slot(dir, "My World", 42).unwrap();
+    let second = create_save_slot(dir, "My World", seed_from_text("hello").unwrap()).unwrap();
+    assert_ne!(first.folder, second.folder);
+    assert_eq!(seed_from_text("hello"), seed_from_text(" hello "));
+    assert_eq!(seed_from_text("123"), Some(123));
+    assert_eq!(seed_from_text("  "), None);
+
+    first.last_played = second.last_played + 10;
+    first.planet_type = 2;
+    first.write_meta(dir).unwrap();
+    std::fs::create_dir_all(format!("{}/not_a_world", dir)).unwrap();
+
+    let slots = list_save_slots(dir);
+    assert_eq!(slots.len(), 2);
+    assert_eq!(slots[0], first);
+    assert_eq!(SaveSlot::read_meta(dir, &second.folder).unwrap().seed, second.seed);
+
+    delete_save_slot(dir, &first).unwrap();
+    assert_eq!(list_save_slots(dir), vec![second]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_save_slots_keep_their_own_block_edits() {
+    let dir = std::env::temp_dir().join(format!("voxelland-slotedits-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir = dir.to_str().unwrap();
+
+    //Same seed, still two separate worlds
+    let a = create_save_slot(dir, "Same Seed", 77).unwrap();
+    let b = create_save_slot(dir, "Same Seed", 77).unwrap();
+    assert_ne!(a.db_path(dir), b.db_path(dir));
+    assert!(a.db_path(dir).starts_with(&a.path(dir)));
+
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    *csys.db_path.write() = a.db_path(dir);
+    csys.set_block_no_queue(IVec3::new(1, 100, 1), 5, true);
+    csys.save_current_world_to_file(a.path(dir));
+    assert!(std::path::Path::new(&a.db_path(dir)).exists());
+    assert!(!std::path::Path::new(&b.db_path(dir)).exists());
+    assert!(std::path::Path::new(&format!("{}/regions", a.path(dir))).exists());
+
+    //Deleting the slot takes its edits and regions with it
+    delete_save_slot(dir, &a).unwrap();
+    assert!(!std::path::Path::new(&a.path(dir)).exists());
+
+    //Edits an old slot left in the shared db only come over for the seed asked for
+    let shared = format!("{}/shared_db", dir);
+    {
+        let conn = rusqlite::Connection::open(&shared).unwrap();
+        conn.execute("CREATE TABLE userdatamap_77 (x INTEGER, y INTEGER, z INTEGER, value INTEGER, PRIMARY KEY (x, y, z))", ()).unwrap();
+        conn.execute("INSERT INTO userdatamap_77 VALUES (1, 2, 3, 5)", ()).unwrap();
+    }
+    *csys.db_path.write() = b.db_path(dir);
+    assert_eq!(csys.adopt_shared_edits(&shared, 77).unwrap(), 1);
+    assert_eq!(csys.adopt_shared_edits(&shared, 78).unwrap(), 0);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_only_player_edits_count_toward_autosave() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let spot = IVec3::new(3, 90, 3);
+    assert_eq!(csys.user_edits.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+    csys.set_block_no_sound(spot, 1, true);
+    csys.set_block_no_sound(spot, 0, true);
+    csys.set_block_no_sound(IVec3::new(4, 90, 3), 1, false);
+
+    assert_eq!(csys.user_edits.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+    //Water the player pours counts once, where it spreads to is saved but isn't an edit
+    let source = IVec3::new(3, csys.chunk_height - 20, 3);
+    csys.set_block_no_sound(source, 2, true);
+    let stored = csys.userdatamap.len();
+    for _ in 0..5 {
+        csys.step_fluids();
+    }
+    assert!(csys.userdatamap.len() > stored);
+    assert_eq!(csys.user_edits.load(std::sync::atomic::Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_planet_gravity_follows_planet_type() {
+    let mut csys = ChunkSystem::new(1, 1234, 0, true);
+    assert_eq!(csys.gravity, HOME_GRAVITY);
+
+    csys.set_planet_type(1);
+    assert!(csys.gravity > HOME_GRAVITY);
+    csys.set_planet_type(2);
+    assert!(csys.gravity < HOME_GRAVITY);
+
+    let direct = ChunkSystem::new(1, 1234, 2, true);
+    assert_eq!(direct.gravity, csys.gravity);
+}
+
+#[test]
+fn test_head_bob_moves_view_not_position() {
+    let right = Vec3::X;
+    assert_eq!(Camera::head_bob_offset(0.0, 1.0, right), Vec3::ZERO);
+    assert_eq!(Camera::head_bob_offset(1.3, 0.0, right), Vec3::ZERO);
+
+    let mid = Camera::head_bob_offset(std::f32::consts::FRAC_PI_2, 1.0, right);
+    assert!(mid.x > 0.0);
+    assert!(mid.y < 0.0);
+    assert!(Camera::head_bob_offset(std::f32::consts::FRAC_PI_2, 2.0, right).y < mid.y);
+
+    let mut cam = Camera::new();
+    let still = cam.view;
+    cam.bob_offset = mid;
+    cam.recalculate();
+    assert_eq!(cam.position, Vec3::new(0.0, 100.0, 0.0));
+    assert_ne!(cam.view, still);
+}
+
+#[test]
+fn test_particle_pool_reuses_slots_falls_and_fades() {
+    let mut pool = ParticlePool::new(32);
+    pool.burst(Vec3::new(0.0, 50.0, 0.0), 3, 20, 3.0);
+    pool.burst(Vec3::new(0.0, 50.0, 0.0), 3, 20, 3.0);
+    assert_eq!(pool.particles.len(), 32);
+    assert_eq!(pool.live_count(), 32);
+
+    //Grass chips come from the grass tile
+    let (u, v, tile) = ParticlePool::atlas_tile(3);
+    for p in &pool.particles {
+        assert!(p.uv.0 >= u && p.uv.0 < u + tile);
+        assert!(p.uv.1 >= v && p.uv.1 < v + tile);
+        assert_eq!(p.alpha(), 1.0);
+    }
+
+    let starting_y: f32 = pool.particles.iter().map(|p| p.position.y).sum();
+    for _ in 0..30 {
+        pool.update(0.02, 9.8);
+    }
+    let later_y: f32 = pool.particles.iter().map(|p| p.position.y).sum();
+    assert!(later_y < starting_y);
+    assert!(pool.particles.iter().any(|p| p.alive() && p.alpha() < 1.0));
+
+    for _ in 0..100 {
+        pool.update(0.02, 9.8);
+    }
+    assert_eq!(pool.live_count(), 0);
+
+    let mut verts = Vec::new();
+    pool.build_vertices(Vec3::X, Vec3::Y, &mut verts);
+    assert!(verts.is_empty());
+}
+
+#[test]
+fn test_break_progress_finishes_without_moving() {
+    let spot = IVec3::new(1, 60, 1);
+    let mut breaking = BreakProgress::new();
+
+    //Aiming at the same block every frame with the mouse held breaks it eventually
+    let mut frames = 0;
+    loop {
+        breaking.aim(Some(spot), 3);
+        frames += 1;
+        if breaking.hold(0.1, 1.0) {
+            break;
+        }
+        assert!(frames < 20);
+    }
+    assert_eq!(breaking.time, 0.0);
+
+    //Letting go, looking elsewhere or the block changing all start it over
+    breaking.hold(0.1, 1.0);
+    breaking.release();
+    assert_eq!(breaking.progress(1.0), 0.0);
+
+    breaking.hold(0.5, 1.0);
+    breaking.aim(Some(spot), 3);
+    assert_eq!(breaking.progress(1.0), 0.5);
+    breaking.aim(Some(spot), 0);
+    assert_eq!(breaking.progress(1.0), 0.0);
+
+    breaking.hold(0.5, 1.0);
+    breaking.aim(Some(spot + IVec3::new(0, 1, 0)), 0);
+    assert_eq!(breaking.progress(1.0), 0.0);
+}
+
+#[test]
+fn test_tools_break_their_blocks_faster() {
+    let stone = 9;
+    let hand = 0;
+    let pick = 37;
+    let axe = 39;
+    assert_eq!(get_tool_speed(hand, stone), 1.0);
+    assert!(get_tool_speed(pick, stone) > 1.0);
+    assert_eq!(get_tool_speed(axe, stone), 1.0);
+    assert!(get_tool_speed(axe, 6) > 1.0);
+    assert!(get_tool_speed(36, 7) > 1.0);
+
+    //Same block, same frames: the pick gets further along than a bare hand
+    let break_time = Blocks::get_break_time(stone);
+    let mut by_hand = BreakProgress::new();
+    let mut with_pick = BreakProgress::new();
+    by_hand.hold(0.2, break_time / get_tool_speed(hand, stone));
+    with_pick.hold(0.2, break_time / get_tool_speed(pick, stone));
+    assert!(with_pick.progress(break_time / get_tool_speed(pick, stone)) > by_hand.progress(break_time));
+}
+
+#[test]
+fn test_drop_tables_come_from_block_data() {
+    let mut rng = rand::thread_rng();
+
+    //Built in data: grass gives dirt, the unattainable crystal gives the real one, unlisted blocks drop themselves
+    assert_eq!(roll_drops(&DROP_TABLES, 3, &mut rng), vec![(4, 1)]);
+    assert_eq!(roll_drops(&DROP_TABLES, 16, &mut rng), vec![(17, 1)]);
+    assert_eq!(roll_drops(&DROP_TABLES, 5, &mut rng), vec![(5, 1)]);
+
+    //Leaves usually give nothing and never give leaves
+    let leaf_drops: Vec<(u32, u32)> = (0..200).flat_map(|_| roll_drops(&DROP_TABLES, 7, &mut rng)).collect();
+    assert!(leaf_drops.len() < 200);
+    assert!(leaf_drops.iter().all(|&(item, _)| item == 32));
+
+    let tables = parse_drop_tables(r#"[
+        { "id": 6, "drops": [{ "item": 10, "count": 4 }, { "item": 32, "chance": 0.0 }] },
+        { "id": 1, "name": "sand", "drops": [] }
+    ]"#).unwrap();
+    assert_eq!(roll_drops(&tables, 6, &mut rng), vec![(10, 4)]);
+    assert!(roll_drops(&tables, 1, &mut rng).is_empty());
+    assert!(parse_drop_tables("[{ \"drops\": [] }]").is_err());
+}
+
+#[test]
+fn test_flood_break_fells_connected_blocks_up_to_cap() {
+    assert!(flood_breaks(6));
+    assert!(flood_breaks(16));
+    assert!(!flood_breaks(5));
+
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(1, 1234, 0, true)));
+    let base = IVec3::new(3, 200, 3);
+    {
+        let c = csys.read();
+        //A trunk with a branch, plus a log that only touches it diagonally
+        for y in 0..6 {
+            c.set_block_no_sound(base + IVec3::new(0, y, 0), 6, true);
+        }
+        c.set_block_no_sound(base + IVec3::new(1, 4, 0), 6, true);
+        c.set_block_no_sound(base + IVec3::new(1, 6, 1), 6, true);
+        c.set_block_no_sound(base + IVec3::new(0, 6, 0), 7, true);
+    }
+
+    //Multiplayer works out the spots first and leaves the breaking to the server
+    let spots = Game::flood_spots(&csys.read(), 6, base, FLOOD_BREAK_MAX);
+    assert_eq!(spots.len(), 7);
+    assert_eq!(spots.iter().collect::<HashSet<_>>().len(), 7);
+    assert_eq!(csys.read().blockat(base + IVec3::new(0, 5, 0)), 6);
+
+    let (chunks, broken) = Game::flood_break(&csys, 6, base, FLOOD_BREAK_MAX);
+    assert_eq!(broken, 7);
+    assert!(!chunks.is_empty());
+    let c = csys.read();
+    assert_eq!(c.blockat(base + IVec3::new(0, 5, 0)), 0);
+    assert_eq!(c.blockat(base + IVec3::new(1, 4, 0)), 0);
+    assert_eq!(c.blockat(base + IVec3::new(1, 6, 1)), 6);
+    assert_eq!(c.blockat(base + IVec3::new(0, 6, 0)), 7);
+    drop(c);
+
+    let column = IVec3::new(40, 150, 40);
+    for y in 0..20 {
+        csys.read().set_block_no_sound(column + IVec3::new(0, y, 0), 6, true);
+    }
+    let (_, broken) = Game::flood_break(&csys, 6, column, 5);
+    assert_eq!(broken, 5);
+}
+
+#[test]
+fn test_void_death_and_downed_player_roll() {
+    //Bedrock is solid ground, only well under it counts as the void
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    assert_eq!(csys.blockat(IVec3::new(0, 0, 0)), 15);
+    assert!(!Game::fell_into_void(0.5));
+    assert!(!Game::fell_into_void(VOID_DEATH_Y + 1.0));
+    assert!(Game::fell_into_void(VOID_DEATH_Y - 1.0));
+
+    //Dying in the void doesn't drop the inventory down there with you
+    let spawn = Vec3::new(25.0, 90.0, 0.0);
+    let ground = Vec3::new(3.0, 70.0, -4.0);
+    let above = Vec3::new(0.0, 2.0, 0.0);
+    assert_eq!(Game::death_drop_spot(Vec3::new(1.0, 40.0, 1.0), Some(ground), spawn), Vec3::new(1.0, 42.0, 1.0));
+    assert_eq!(Game::death_drop_spot(Vec3::new(1.0, VOID_DEATH_Y - 5.0, 1.0), Some(ground), spawn), ground + above);
+    assert_eq!(Game::death_drop_spot(Vec3::new(1.0, VOID_DEATH_Y - 5.0, 1.0), None, spawn), spawn + above);
+
+    //A downed player stays on their side through position updates until they get back up
+    let csys = Arc::new(RwLock::new(csys));
+    let cam = Arc::new(Mutex::new(Camera::new()));
+    let mut player = ModelEntity::new(0, Vec3::new(0.5, 100.0, 0.5), 0.3, Vec3::ZERO, &csys, &cam, false);
+    player.set_roll(std::f32::consts::FRAC_PI_2, 0.0);
+    player.receive_network_update(Vec3::new(0.5, 100.0, 1.5), Vec3::new(0.0, 1.0, 0.0), 1.0);
+    assert_eq!(player.rot.z, std::f32::consts::FRAC_PI_2);
+    assert_eq!(player.rot.y, 1.0);
+    player.set_roll(0.0, 2.0);
+    let (_, drawn) = player.interpolated_pos_rot(100.0);
+    assert_eq!(drawn.z, 0.0);
+    //Wrapping the difference in floats used to stop a hair short of the target
+    assert_eq!(lerp_angle(std::f32::consts::FRAC_PI_2, 0.0, 1.0), 0.0);
+    assert_eq!(lerp_angle(3.0, -3.0, 1.0), -3.0);
+}
+
+#[test]
+fn test_mouse_look_invert_and_smoothing() {
+    let mut smoothed = (0.0, 0.0);
+    assert_eq!(Game::look_delta((2.0, 3.0), &mut smoothed, 0.0, false), (2.0, 3.0));
+    assert_eq!(Game::look_delta((2.0, 3.0), &mut smoothed, 0.0, true), (2.0, -3.0));
+
+    //A single jolt gets spread over the following moves instead of landing all at once
+    let mut smoothed = (0.0, 0.0);
+    let first = Game::look_delta((10.0, 0.0), &mut smoothed, 0.5, false);
+    let second = Game::look_delta((0.0, 0.0), &mut smoothed, 0.5, false);
+    assert_eq!(first.0, 5.0);
+    assert_eq!(second.0, 2.5);
+
+    //Steady movement settles on the raw speed
+    let mut smoothed = (0.0, 0.0);
+    let mut last = (0.0, 0.0);
+    for _ in 0..100 {
+        last = Game::look_delta((1.0, -1.0), &mut smoothed, 0.8, true);
+    }
+    assert!((last.0 - 1.0).abs() < 1e-3 && (last.1 - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_camera_projection_follows_window_aspect() {
+    let mut cam = Camera::new();
+    let windowed = cam.projection;
+
+    cam.set_aspect(2560, 1080);
+    assert!((cam.aspect - 2560.0 / 1080.0).abs() < 1e-5);
+    assert_ne!(cam.projection, windowed);
+    //Wider screen, same vertical fov: x gets squeezed more
+    assert!(cam.projection.x_axis.x < windowed.x_axis.x);
+    assert_eq!(cam.projection.y_axis.y, windowed.y_axis.y);
+
+    //A minimized window reports 0 by 0, that mustn't wreck the projection
+    let before = cam.projection;
+    cam.set_aspect(0, 0);
+    assert_eq!(cam.projection, before);
+    assert!(cam.projection.is_finite());
+
+    //Changing fov keeps the aspect
+    cam.update_fov(90.0);
+    assert!((cam.projection.y_axis.y / cam.projection.x_axis.x - 2560.0 / 1080.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_cubes_stay_cubic_on_wide_windows() {
+    for (w, h) in [(1280, 720), (2560, 1080), (800, 800)] {
+        let mut cam = Camera::new();
+        cam.position = Vec3::ZERO;
+        cam.direction = Vec3::new(0.0, 0.0, -1.0);
+        cam.set_aspect(w, h);
+
+        //Project a unit face 5 blocks ahead and measure it in pixels
+        let to_pixels = |p: Vec3| {
+            let clip = cam.mvp * p.extend(1.0);
+            let ndc = clip.truncate() / clip.w;
+            (ndc.x * w as f32 / 2.0, ndc.y * h as f32 / 2.0)
+        };
+        let (left, _) = to_pixels(Vec3::new(-0.5, 0.0, -5.0));
+        let (right, _) = to_pixels(Vec3::new(0.5, 0.0, -5.0));
+        let (_, bottom) = to_pixels(Vec3::new(0.0, -0.5, -5.0));
+        let (_, top) = to_pixels(Vec3::new(0.0, 0.5, -5.0));
+
+        assert!(((right - left) - (top - bottom)).abs() < 0.01, "{}x{}", w, h);
+    }
+}
+
+#[test]
+fn test_far_plane_follows_render_distance() {
+    let near = Camera::far_for_render_distance(4, 15, 256);
+    let far = Camera::far_for_render_distance(20, 15, 256);
+    assert!(far > near);
+
+    //Covers the furthest corner of the loaded square seen from the top of the world
+    let corner = Vec3::new(21.0 * 15.0, 256.0, 21.0 * 15.0);
+    assert!(far >= corner.length() - 0.01);
+
+    let mut cam = Camera::new();
+    cam.set_far(far);
+    assert_eq!(cam.far, far);
+    cam.set_far(1.0);
+    assert!(cam.far > cam.near * 1000.0);
+    //Blocks one apart out near the far plane still land on different steps of a 24 bit depth buffer
+    cam.set_far(far);
+    let (za, zb) = (cam.projection.z_axis.z as f64, cam.projection.w_axis.z as f64);
+    let depth = |d: f64| ((za * -d + zb) / d) * 0.5 + 0.5;
+    let d = far as f64 * 0.95;
+    assert!((depth(d) - depth(d - 1.0)) * 16777216.0 >= 1.0);
+}
+
+#[test]
+fn test_client_matches_server_terrain_after_taking_its_world_params() {
+    let seed = 424242;
+    for planet_type in 0..PLANET_TYPES {
+        let server = ChunkSystem::new(1, seed, planet_type as usize, true);
+
+        //The client starts on its own random world and home planet, then gets the server's
+        let mut client = ChunkSystem::new(1, 7, 0, true);
+        client.apply_world_params(seed, planet_type);
+
+        assert_eq!(client.planet_type, server.planet_type);
+        assert_eq!(client.terrain_octaves, server.terrain_octaves);
+        assert_eq!(client.cave_frequency, server.cave_frequency);
+
+        for x in (-64..64).step_by(7) {
+            for z in (-64..64).step_by(7) {
+                for y in (0..140).step_by(3) {
+                    let spot = IVec3::new(x, y, z);
+                    assert_eq!(client.blockat(spot), server.blockat(spot), "planet {} at {:?}", planet_type, spot);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pending_block_edits_confirm_reject_and_time_out() {
+    let start = Instant::now();
+    let mut pending = PendingEdits::new();
+
+    let placed = pending.push(PendingEdit::new(IVec3::new(1, 60, 1), 0, 5, start));
+    let broken = pending.push(PendingEdit::new(IVec3::new(2, 60, 1), 3, 0, start));
+    let late = pending.push(PendingEdit::new(IVec3::new(3, 60, 1), 0, 9, start + Duration::from_secs(2)));
+    assert!(placed != 0 && placed != broken && broken != late);
+
+    //An echo settles its own edit once, a second echo with the same seq finds nothing
+    let confirmed = pending.take(placed).unwrap();
+    assert_eq!((confirmed.spot, confirmed.predicted), (IVec3::new(1, 60, 1), 5));
+    assert!(pending.take(placed).is_none());
+
+    //Only edits older than the timeout come back for undoing, with what was there before
+    let expired = pending.take_expired(start + PENDING_EDIT_TIMEOUT, PENDING_EDIT_TIMEOUT);
+    assert_eq!(expired.len(), 1);
+    assert_eq!((expired[0].seq, expired[0].previous), (broken, 3));
+    assert_eq!(pending.edits.len(), 1);
+    assert_eq!(pending.edits[0].seq, late);
+
+    let mut limiter = EditRateLimiter::new(start);
+    for _ in 0..MAX_EDITS_PER_WINDOW {
+        assert!(limiter.allow(start));
+    }
+    assert!(!limiter.allow(start + EDIT_WINDOW / 2));
+    assert!(limiter.allow(start + EDIT_WINDOW));
+}
+
+#[test]
+fn test_other_player_avatars_glide_and_disconnects_name_the_player() {
+    //Avatars blend across the whole gap between two position updates instead of snapping
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(1, 1234, 0, true)));
+    let cam = Arc::new(Mutex::new(Camera::new()));
+    let mut avatar = ModelEntity::new(0, Vec3::new(0.0, 100.0, 0.0), 0.3, Vec3::ZERO, &csys, &cam, false);
+    avatar.update_interval = PLAYER_UPDATE_INTERVAL;
+    avatar.receive_network_update(Vec3::new(0.0, 100.0, 0.0), Vec3::ZERO, 0.0);
+    avatar.receive_network_update(Vec3::new(2.0, 100.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 10.0);
+    let (halfway, rot) = avatar.interpolated_pos_rot(10.0 + PLAYER_UPDATE_INTERVAL / 2.0);
+    assert!((halfway.x - 1.0).abs() < 0.001);
+    assert!((rot.y - 0.5).abs() < 0.001);
+    let (arrived, _) = avatar.interpolated_pos_rot(10.0 + PLAYER_UPDATE_INTERVAL);
+    assert_eq!(arrived, Vec3::new(2.0, 100.0, 0.0));
+
+    //The leave notice fits the fixed packet size and says who left
+    let who = Uuid::new_v4();
+    let mut bye = Message::new(MessageType::PlayerDisconnect, Vec3::ZERO, 0.0, 0);
+    bye.goose = who.as_u64_pair();
+    let bytes = bincode::serialize(&bye).unwrap();
+    assert_eq!(bytes.len() as u64, bincode::serialized_size(&Message::new(MessageType::RequestSeed, Vec3::ZERO, 0.0, 0)).unwrap());
+    let back: Message = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(back.message_type, MessageType::PlayerDisconnect);
+    assert_eq!(Uuid::from_u64_pair(back.goose.0, back.goose.1), who);
+}
+
+#[test]
+fn test_player_updates_carry_a_usable_yaw() {
+    use std::f32::consts::FRAC_PI_2;
+    assert_eq!(facing_yaw(Vec3::new(0.0, 0.0, 1.0)), 0.0);
+    assert!((facing_yaw(Vec3::new(1.0, 0.0, 0.0)) - FRAC_PI_2).abs() < 0.0001);
+    //Looking straight down still keeps the heading of the horizontal part
+    assert!((facing_yaw(Vec3::new(-0.1, -1.0, 0.0)) + FRAC_PI_2).abs() < 0.0001);
+
+    //Before the camera has a direction, and for position-only senders, the avatar faces the default way
+    assert_eq!(facing_yaw(Vec3::ZERO), 0.0);
+    assert_eq!(received_yaw(f32::NAN), 0.0);
+    assert_eq!(received_yaw(1.25), 1.25);
+
+    //Same convention mobs use when they turn to face a direction
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(1, 1234, 0, true)));
+    let cam = Arc::new(Mutex::new(Camera::new()));
+    let mut mob = ModelEntity::new(0, Vec3::new(0.0, 100.0, 0.0), 0.3, Vec3::ZERO, &csys, &cam, false);
+    let dir = Vec3::new(0.3, 0.2, -0.9);
+    mob.set_direction(dir);
+    assert_eq!(mob.rot.y, facing_yaw(dir));
+}
+
+#[test]
+fn test_client_clock_eases_toward_server_time() {
+    let day = 900.0;
+    //Small gaps close a little each frame instead of snapping
+    let t = Game::approach_time_of_day(100.0, 104.0, day, 0.1);
+    assert!(t > 100.0 && t < 104.0);
+    let mut t = 100.0;
+    for _ in 0..300 {
+        t = Game::approach_time_of_day(t, 104.0, day, 1.0 / 60.0);
+    }
+    assert!((t - 104.0).abs() < 0.01);
+
+    //Across midnight it goes the short way instead of running back through the whole day
+    let t = Game::approach_time_of_day(899.0, 1.0, day, 0.1);
+    assert!(t > 899.0 || t < 1.0);
+
+    //A client standing still on a frozen server stays put
+    assert_eq!(Game::approach_time_of_day(450.0, 450.0, day, 0.5), 450.0);
+
+    //Far off (just joined) it jumps straight there
+    assert_eq!(Game::approach_time_of_day(0.0, TIME_SNAP_SECONDS * 3.0, day, 0.016), TIME_SNAP_SECONDS * 3.0);
+
+    //Between updates the target keeps running with the server's clock, so the client doesn't lag a sync behind
+    let (mut t, mut target) = (100.0, 100.0);
+    for _ in 0..(TIME_SYNC_INTERVAL * 60.0) as i32 {
+        target = Game::advance_clock(target, day, 1.0 / 60.0);
+        t = Game::approach_time_of_day(t, target, day, 1.0 / 60.0);
+    }
+    assert!((target - (100.0 + TIME_SYNC_INTERVAL)).abs() < 0.01);
+    assert!(t > 100.0 + TIME_SYNC_INTERVAL / 4.0);
+    assert!((Game::advance_clock(899.5, day, 1.0) - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn test_atlas_layout_comes_from_the_sheet_size() {
+    //The stock 544px sheet lands every tile and glyph exactly where the old fixed numbers put them
+    let stock = AtlasLayout::new(DEFAULT_ATLAS_PX, DEFAULT_ATLAS_PX);
+    let (one_pixel, tile, cell) = (1.0 / 544.0, 16.0 / 544.0, 18.0 / 544.0);
+    for (x, y) in [(0, 0), (2, 0), (11, 1), (15, 0), (0, 14), (29, 29)] {
+        let face = TextureFace::new_in(&stock, x, y);
+        let left = one_pixel + cell * x as f32;
+        let top = 1.0 - y as f32 * cell - tile - one_pixel;
+        assert!((face.tlx - left).abs() < 1e-6 && (face.tly - top).abs() < 1e-6);
+        assert!((face.brx - (left + tile)).abs() < 1e-6 && (face.bry - (top + tile)).abs() < 1e-6);
+    }
+    let mut glyph = GlyphFace::new(b'A');
+    glyph.set_char_in(&stock, b'A');
+    let offset = (b'A' - 32) as f32;
+    assert!((glyph.tlx - (288.0 / 544.0 + (offset % 16.0) * 16.0 / 544.0)).abs() < 1e-6);
+    assert!((glyph.tly - (offset / 16.0).floor() * 16.0 / 544.0).abs() < 1e-6);
+    //Animated water and conveyor tiles are still found at their old pixels
+    assert_eq!(stock.cell_origin_px(2, 0), (36, 526));
+    assert_eq!(stock.cell_origin_px(10, 6), (180, 418));
+
+    //A sheet with 32 tiles each way works as is, every tile stays inside it
+    let big = AtlasLayout::new(CELL_PX * 32, CELL_PX * 32);
+    assert_eq!((big.tiles_per_row(), big.tiles_per_column()), (32, 32));
+    let corner = TextureFace::new_in(&big, 31, 31);
+    assert!(corner.tlx > 0.0 && corner.trx < 1.0 && corner.tly > 0.0 && corner.bly < 1.0);
+    assert!((corner.trx - corner.tlx - 16.0 / big.width as f32).abs() < 1e-6);
+    assert_eq!(big.cell_origin_px(31, 31), (31 * 18, 0));
+    glyph.set_char_in(&big, b'~');
+    assert!(glyph.trx <= 1.0 + 1e-6);
+    assert_eq!(big.glyph_origin_px(), (big.width - 256, 0));
+}
+
+#[test]
+fn test_animated_tiles_step_only_when_due_and_leave_the_rest_alone() {
+    assert!(ANIMATED_TILES.iter().any(|t| t.tile == (2, 0) && t.effect == TileEffect::Ripple));
+    assert!(ANIMATED_TILES.iter().any(|t| t.tile == (10, 6) && t.effect == TileEffect::Scroll));
+    let parsed = parse_animated_tiles(r#"[{ "tile": [0, 0], "frames": [[0, 0], [1, 0]], "fps": 4 }]"#).unwrap();
+    assert_eq!(parsed[0].effect, TileEffect::Frames);
+
+    //Every cell of a small sheet gets its own colour so moved pixels can be traced
+    let layout = AtlasLayout::new(CELL_PX * 4, CELL_PX * 4);
+    let mut pix = vec![0u8; (layout.width * layout.height * 4) as usize];
+    let cell_of = |i: usize| {
+        let px = i / 4;
+        let (x, y) = ((px % layout.width as usize) / CELL_PX as usize, (px / layout.width as usize) / CELL_PX as usize);
+        (x + y * 4) as u8
+    };
+    for (i, p) in pix.iter_mut().enumerate() {
+        *p = cell_of(i);
+    }
+    //A row counter inside the scrolling cell
+    let (sx, sy) = layout.cell_origin_px(3, 3);
+    for y in 0..CELL_PX as usize {
+        for x in 0..CELL_PX as usize {
+            pix[((sy + y) * layout.width as usize + sx + x) * 4] = 100 + y as u8;
+        }
+    }
+    let before = pix.clone();
+
+    let mut animator = TileAnimator::new(vec![
+        AnimatedTile { name: "lava".into(), tile: (0, 0), effect: TileEffect::Frames, frames: vec![(0, 0), (1, 0), (2, 0)], fps: 4.0 },
+        AnimatedTile { name: "belt".into(), tile: (3, 3), effect: TileEffect::Scroll, frames: vec![], fps: 4.0 },
+        AnimatedTile { name: "too far".into(), tile: (9, 9), effect: TileEffect::Frames, frames: vec![(0, 0)], fps: 4.0 },
+    ]);
+
+    //Not due yet, nothing changes so there's nothing to upload
+    assert!(!animator.advance(0.1, &mut pix, &layout));
+    assert_eq!(pix, before);
+
+    assert!(animator.advance(0.2, &mut pix, &layout));
+    let (lx, ly) = layout.cell_origin_px(0, 0);
+    let (fx, fy) = layout.cell_origin_px(1, 0);
+    assert_eq!(pix[(ly * layout.width as usize + lx) * 4], before[(fy * layout.width as usize + fx) * 4]);
+    assert_eq!(pix[(sy * layout.width as usize + sx) * 4], 101);
+    assert_eq!(pix[((sy + CELL_PX as usize - 1) * layout.width as usize + sx) * 4], 100);
+
+    //Only the two animated cells differ from the untouched sheet
+    for (i, (now, was)) in pix.iter().zip(before.iter()).enumerate() {
+        let cell = cell_of(i);
+        if cell != cell_of((ly * layout.width as usize + lx) * 4) && cell != cell_of((sy * layout.width as usize + sx) * 4) {
+            assert_eq!(now, was);
+        }
+    }
+
+    //Two more steps wrap back round to the tile's own first frame
+    animator.advance(0.25, &mut pix, &layout);
+    animator.advance(0.25, &mut pix, &layout);
+    assert_eq!(animator.frames[0], 3);
+    assert_eq!(pix[(ly * layout.width as usize + lx) * 4], before[(ly * layout.width as usize + lx) * 4]);
+}
+
+#[test]
+fn test_block_sounds_come_from_block_data() {
+    //Stone and grass sound different, wood sounds like wood whether placed or broken
+    assert_ne!(break_series(9), break_series(3));
+    assert_eq!(break_series(9), "stoneplaceseries");
+    assert_eq!(break_series(3), "grassstepseries");
+    assert_eq!(break_series(6), "woodstepseries");
+    assert_eq!(place_series(6), "woodstepseries");
+
+    //Breaking goes by what was there before the edit, not the air left behind
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let (stone, grass) = (IVec3::new(2, 200, 2), IVec3::new(3, 200, 2));
+    csys.set_block_no_sound(stone, 9, true);
+    csys.set_block_no_sound(grass, 3, true);
+    assert_eq!(csys.edit_sound_series(stone, 0), break_series(9));
+    assert_eq!(csys.edit_sound_series(grass, 0), break_series(3));
+    assert_ne!(csys.edit_sound_series(stone, 0), csys.edit_sound_series(grass, 0));
+    assert_eq!(csys.edit_sound_series(stone, 6), place_series(6));
+
+    //Blocks without their own sound keep the old material lookup
+    assert_eq!(break_series(8), Blocks::get_place_series(8));
+    assert_eq!(place_series(35), Blocks::get_place_series(35));
+
+    //Every series named in the data is one the game loads
+    let known: HashSet<&str> = (0..64).flat_map(|id| [Blocks::get_place_series(id), Blocks::get_walk_series(id)]).collect();
+    for def in BLOCK_DEFS.iter() {
+        for series in [&def.break_sound, &def.place_sound].into_iter().flatten() {
+            assert!(known.contains(series.as_str()), "{} uses unknown sound series {}", def.id, series);
+        }
+    }
+
+    let defs = parse_block_defs(r#"[{ "id": 4, "break_sound": "dirtstepseries" }]"#).unwrap();
+    assert_eq!(sound_series_in(&defs, 4, true), Some("dirtstepseries"));
+    assert_eq!(sound_series_in(&defs, 4, false), None);
+    assert_eq!(sound_series_in(&defs, 5, true), None);
+}
+
+#[test]
+fn test_playlist_shuffles_crossfades_and_only_moves_on_for_a_new_planet() {
+    let tracks: Vec<String> = ["a.mp3", "b.mp3", "c.mp3"].iter().map(|t| t.to_string()).collect();
+    let mut playlist = Playlist::new(tracks.clone(), 7);
+
+    //First frame starts something, every track plays once before any repeats
+    let first = playlist.advance(0.016, false);
+    assert!(matches!(first.as_slice(), [MusicCommand::Play(_)]));
+    let mut heard = vec![playlist.current_track().unwrap().clone()];
+    for _ in 0..2 {
+        playlist.skip();
+        heard.push(playlist.current_track().unwrap().clone());
+    }
+    heard.sort();
+    assert_eq!(heard, tracks);
+    let before = playlist.current_track().unwrap().clone();
+    playlist.skip();
+    assert_ne!(playlist.current_track().unwrap(), &before);
+
+    //The old track fades out under the new one, then gets stopped
+    let outgoing = playlist.tracks[playlist.outgoing.unwrap()].clone();
+    let vols = playlist.volumes();
+    assert_eq!(vols[0].1, 0.0);
+    assert!((vols[1].1 - 1.0).abs() < 1e-5);
+    playlist.advance(CROSSFADE_SECONDS / 2.0, false);
+    let vols = playlist.volumes();
+    assert!(vols[0].1 > 0.5 && vols[1].1 > 0.5);
+    let cmds = playlist.advance(CROSSFADE_SECONDS, false);
+    assert_eq!(cmds, vec![MusicCommand::Stop(outgoing)]);
+    assert_eq!(playlist.volumes().len(), 1);
+
+    //Reloading the same planet keeps the track, a different one moves on
+    let playing = playlist.current_track().unwrap().clone();
+    assert!(playlist.set_planet(0).is_empty());
+    assert!(playlist.set_planet(0).is_empty());
+    assert_eq!(playlist.current_track().unwrap(), &playing);
+    let cmds = playlist.set_planet(1);
+    assert!(cmds.iter().any(|c| matches!(c, MusicCommand::Play(t) if *t != playing)));
+
+    //Paused, nothing moves on by itself
+    let cmds = playlist.toggle_pause();
+    assert!(cmds.iter().all(|c| matches!(c, MusicCommand::Pause(_))));
+    assert!(playlist.advance(1000.0, true).is_empty());
+    assert!(playlist.cue("a.mp3").is_empty() || playlist.current_track().unwrap() == "a.mp3");
+
+    //A track that plays out hands over once it's had time to start
+    playlist.toggle_pause();
+    playlist.skip();
+    assert!(playlist.advance(0.5, true).iter().all(|c| !matches!(c, MusicCommand::Play(_))));
+    assert!(playlist.advance(2.0, true).iter().any(|c| matches!(c, MusicCommand::Play(_))));
+
+    //Night and fights lower the music by the planet's mood, never raise it
+    assert_eq!(mood_volume(0.0, false, 0.6, 0.5), 1.0);
+    assert!((mood_volume(1.0, false, 0.6, 0.5) - 0.6).abs() < 1e-5);
+    assert!((mood_volume(1.0, true, 0.6, 0.5) - 0.3).abs() < 1e-5);
+    for dim in 0..PLANET_TYPES as u32 {
+        let (night, combat) = Planets::get_music_mood(dim);
+        assert!(night > 0.0 && night <= 1.0 && combat > 0.0 && combat <= 1.0);
+    }
+}
+
+#[test]
+fn test_ambient_bed_follows_cover_and_crossfades() {
+    //Under a roof isn't a cave, a few blocks of rock overhead is
+    assert!(!is_underground(70.0, None));
+    assert!(!is_underground(70.0, Some(72)));
+    assert!(is_underground(40.0, Some(72)));
+
+    let surface = ambient_bed(0, Biome::Plains, false);
+    let cave = ambient_bed(0, Biome::Plains, true);
+    assert_ne!(surface, cave);
+    assert_eq!(ambient_bed(1, Biome::Plains, true), cave);
+    assert_ne!(ambient_bed(1, Biome::Plains, false), surface);
+    assert_ne!(ambient_bed(0, Biome::Desert, false), ambient_bed(0, Biome::Snow, false));
+
+    let mut mixer = AmbientMixer::new();
+    mixer.set_target(Some(surface.to_string()));
+    assert_eq!(mixer.advance(0.0), vec![AmbientCommand::Start(surface.to_string())]);
+    assert!(mixer.advance(AMBIENT_FADE_SECONDS).is_empty());
+    assert_eq!(mixer.volume(surface), 1.0);
+
+    //Going underground fades the wind out under the cave, then stops it
+    mixer.set_target(Some(cave.to_string()));
+    assert_eq!(mixer.advance(AMBIENT_FADE_SECONDS / 2.0), vec![AmbientCommand::Start(cave.to_string())]);
+    assert!(mixer.volume(surface) > 0.0 && mixer.volume(cave) > 0.0);
+    assert_eq!(mixer.advance(AMBIENT_FADE_SECONDS), vec![AmbientCommand::Stop(surface.to_string())]);
+    assert_eq!(mixer.volume(cave), 1.0);
+
+    mixer.set_target(None);
+    assert_eq!(mixer.advance(AMBIENT_FADE_SECONDS), vec![AmbientCommand::Stop(cave.to_string())]);
+    assert!(mixer.beds.is_empty());
+}
+
+#[test]
+fn test_headless_bot_walks_a_loop_and_undoes_its_edits() {
+    let center = Vec3::new(10.0, 70.0, -5.0);
+    let (start, dir) = scripted_path(center, 0.0);
+    assert!(((start - center).length() - BOT_PATH_RADIUS).abs() < 1e-3);
+
+    //Moves at walking pace, heading along the loop
+    let (later, _) = scripted_path(center, 0.25);
+    let step = later - start;
+    assert!((step.length() - BOT_WALK_SPEED * 0.25).abs() < 0.05);
+    assert!(step.normalize().dot(dir) > 0.99);
+    assert!((dir.length() - 1.0).abs() < 1e-5);
+
+    //Comes back round to where it started
+    let lap = std::f32::consts::TAU * BOT_PATH_RADIUS / BOT_WALK_SPEED;
+    assert!((scripted_path(center, lap).0 - start).length() < 1e-2);
+
+    let spot = IVec3::new(3, 80, 4);
+    let place = bot_edit(spot, true);
+    let clear = bot_edit(spot, false);
+    assert_eq!(place.message_type, MessageType::BlockSet);
+    assert_ne!(place.info, 0);
+    assert_eq!(clear.info, 0);
+    assert_eq!((clear.x, clear.y, clear.z), (3.0, 80.0, 4.0));
+    //Untagged, the bot doesn't predict its edits
+    assert_eq!(place.info2, 0);
+}
+
+#[test]
+fn test_gl_entry_points_refuse_to_run_headless() {
+    voxelland::game::debug_assert_gl_allowed("windowed");
+    {
+        //Only this test's thread goes headless, the global HEADLESS other tests see is left alone
+        let _headless = HeadlessGuard::enter();
+        assert!(!voxelland::game::gl_allowed());
+        assert!(unsafe { !voxelland::game::HEADLESS });
+        let reached = std::panic::catch_unwind(|| voxelland::game::debug_assert_gl_allowed("headless"));
+        assert_eq!(reached.is_err(), cfg!(debug_assertions));
+
+        //The shared parts of a headless Game build without GL even with glfw compiled in
+        let shader = voxelland::shader::Shader::new("assets/vert.glsl", "assets/frag.glsl").unwrap();
+        let _cube = voxelland::selectcube::SelectCube::new();
+        assert_eq!((shader.shader_id, shader.vao), (0, 0));
+
+        //Another thread isn't headless just because this one is
+        assert!(std::thread::spawn(voxelland::game::gl_allowed).join().unwrap());
+    }
+    assert!(voxelland::game::gl_allowed());
+}
+
+#[test]
+fn test_headless_shaders_never_touch_gl() {
+    let empty = voxelland::shader::Shader::empty();
+    assert_eq!((empty.shader_id, empty.vao), (0, 0));
+    //Without glfw compiled in there's nothing to compile or link, a real path still comes back empty
+    if !cfg!(feature = "glfw") {
+        let built = voxelland::shader::Shader::new("assets/vert.glsl", "assets/frag.glsl").unwrap();
+        assert_eq!((built.shader_id, built.vao), (0, 0));
+    }
+}
+
+#[test]
+fn test_far_chunks_unload_and_free_their_mesh() {
+    let mut csys = ChunkSystem::new(4, 1234, 0, true);
+    let center = voxelland::vec::IVec2 { x: 10, y: -3 };
+    //Every spot of the loaded square stays, only past twice the radius goes
+    for i in -4..4 {
+        for k in -4..4 {
+            assert!(!csys.is_distant(voxelland::vec::IVec2 { x: center.x + i, y: center.y + k }, center));
+        }
+    }
+    assert!(csys.is_distant(voxelland::vec::IVec2 { x: center.x + 9, y: center.y }, center));
+
+    //Making geometry buffers needs a GL context when glfw is compiled in
+    if !cfg!(feature = "glfw") {
+        let far = voxelland::vec::IVec2 { x: center.x + 9, y: center.y };
+        let facade = ChunkFacade { geo_index: 0, used: true, pos: far, lod: 1 };
+        csys.chunks.push(Arc::new(Mutex::new(facade)));
+        csys.geobank.push(Arc::new(ChunkGeo::new()));
+        csys.takencare.insert(far, facade);
+        csys.geobank[0].data32.lock().extend(vec![7u32; 4096]);
+
+        csys.unload_index(0);
+        assert!(!csys.takencare.contains_key(&far));
+        assert!(!csys.chunks[0].lock().used);
+        assert_eq!(csys.geobank[0].data32.lock().capacity(), 0);
+    }
+}
+
+#[test]
+fn test_sealed_sections_hide_their_insides_until_opened() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let cpos = IVec2 { x: 0, y: 0 };
+    let cw = csys.chunk_width;
+    let section = csys.chunk_height / OCCLUSION_SECTION - 2;
+    let bottom = section * OCCLUSION_SECTION;
+    let top = bottom + OCCLUSION_SECTION - 1;
+    //A stone box the size of the section with a cave inside
+    for j in bottom..=top {
+        for i in 0..cw {
+            for k in 0..cw {
+                let shell = j == bottom || j == top || i == 0 || i == cw - 1 || k == 0 || k == cw - 1;
+                csys.set_block_no_sound(IVec3::new(i, j, k), if shell { 5 } else { 0 }, true);
+            }
+        }
+    }
+    let mut memo = HashMap::new();
+    let sealed = csys.sealed_sections(cpos, &mut memo);
+    assert!(sealed[section as usize]);
+    let cave = IVec3::new(cw / 2, bottom + 5, cw / 2);
+    assert!(csys.is_buried(cpos, cave, &sealed));
+    //The shell itself still shows its outside
+    assert!(!csys.is_buried(cpos, IVec3::new(0, bottom + 5, cw / 2), &sealed));
+
+    //Glass lets you see in, and so does a hole
+    csys.set_block_no_sound(IVec3::new(0, bottom + 5, cw / 2), 8, true);
+    assert!(!csys.sealed_sections(cpos, &mut HashMap::new())[section as usize]);
+    csys.set_block_no_sound(IVec3::new(0, bottom + 5, cw / 2), 0, true);
+    let sealed = csys.sealed_sections(cpos, &mut HashMap::new());
+    assert!(!csys.is_buried(cpos, cave, &sealed));
+}
+
+#[test]
+fn test_hud_stays_square_and_in_proportion_at_any_aspect() {
+    let crosshair = HudElement::new(Vec2::new(0.0, 0.0), Vec2::new(0.08, 0.08), [0.0; 12], SlotIndexType::None);
+    for (width, height) in [(1280, 720), (1920, 1080), (2560, 1080), (3440, 1440), (1024, 768)] {
+        let (_, size) = crosshair.placed((width, height));
+        let pixels = (size.x * width as f32 / 2.0, size.y * height as f32 / 2.0);
+        assert!((pixels.0 - pixels.1).abs() < 0.01, "{}x{} crosshair is {:?}", width, height, pixels);
+    }
+
+    //A hotbar slot keeps the same place relative to its own width, so the row doesn't spread on a wide screen
+    let slot = HudElement::new(Vec2::new(-0.35, -0.9), Vec2::new(0.15, 0.15), [0.0; 12], SlotIndexType::InvSlot(0));
+    let (pos169, size169) = slot.placed((1920, 1080));
+    let (pos219, size219) = slot.placed((2520, 1080));
+    assert!((pos169.x / size169.x - pos219.x / size219.x).abs() < 0.001);
+    assert_eq!(pos169.y, pos219.y);
+    assert_eq!(size169.y, size219.y);
+}
+
+#[test]
+fn test_hotbar_scroll_wraps_and_number_keys_pick_slots() {
+    let last = ROWLENGTH as usize - 1;
+    assert_eq!(wrap_hotbar_slot(last, 1), 0);
+    assert_eq!(wrap_hotbar_slot(0, -1), last);
+    assert_eq!(wrap_hotbar_slot(3, 1), 4);
+    assert_eq!(wrap_hotbar_slot(3, 0), 3);
+
+    assert_eq!(hotbar_slot_for_key(glfw::Key::Num1), Some(0));
+    assert_eq!(hotbar_slot_for_key(glfw::Key::Num8), Some(7.min(last)));
+    //Past the end of the hotbar the key is left for whatever it's bound to
+    assert_eq!(hotbar_slot_for_key(glfw::Key::Num9), if ROWLENGTH >= 9 { Some(8) } else { None });
+    assert_eq!(hotbar_slot_for_key(glfw::Key::Q), None);
+}
+
+#[test]
+fn test_default_binds_leave_the_hotbar_number_keys_free() {
+    for (key, action) in voxelland::statics::DEFAULT_KEYBINDS.iter() {
+        assert_eq!(hotbar_slot_for_key(*key), None, "{} is bound over a hotbar key", action);
+    }
+    assert_eq!(hotbar_slot_for_key(glfw::Key::Num3), Some(2));
+}
+
+#[test]
+fn test_pick_block_finds_the_slot_holding_the_looked_at_block() {
+    let mut inv = Inventory { dirty: false, inv: [(0, 0); ROWLENGTH as usize] };
+    inv.inv[2] = (5, 10);
+    inv.inv[6] = (5, 3);
+    inv.inv[4] = (10, 1);
+    assert_eq!(inv.slot_holding(5, 0), Some(2));
+    //Staying put beats jumping to another stack of the same thing
+    assert_eq!(inv.slot_holding(5, 6), Some(6));
+    assert_eq!(inv.slot_holding(10, 0), Some(4));
+    //Nothing to pick when we don't carry it, or when the ray hit air
+    assert_eq!(inv.slot_holding(9, 0), None);
+    assert_eq!(inv.slot_holding(0, 1), None);
+}
+
+#[test]
+fn test_creative_keeps_items_and_breaks_on_a_repeat() {
+    assert!(GameMode::Survival.consumes_items());
+    assert!(!GameMode::Creative.consumes_items());
+    assert_eq!(GameMode::Survival.toggled(), GameMode::Creative);
+    assert_eq!(GameMode::Creative.toggled().toggled(), GameMode::Creative);
+    //Creative is singleplayer only
+    assert!(GameMode::Creative.allowed(false));
+    assert!(!GameMode::Creative.allowed(true));
+    assert!(GameMode::Survival.allowed(true));
+    //Prefab stamping comes with creative, so never in multiplayer either
+    assert!(GameMode::Creative.can_stamp_prefabs());
+    assert!(!GameMode::Survival.can_stamp_prefabs());
+
+    //The first frame held breaks, then one block per interval however the target changes
+    let mut breaking = BreakProgress::new();
+    breaking.aim(Some(IVec3::new(0, 10, 0)), 5);
+    assert!(breaking.hold_repeating(0.016, CREATIVE_BREAK_REPEAT));
+    breaking.aim(Some(IVec3::new(0, 9, 0)), 5);
+    let mut frames = 0;
+    while !breaking.hold_repeating(0.016, CREATIVE_BREAK_REPEAT) {
+        frames += 1;
+    }
+    let waited = (frames + 1) as f32 * 0.016;
+    assert!(waited >= CREATIVE_BREAK_REPEAT - 0.001 && waited < CREATIVE_BREAK_REPEAT + 0.016);
+    //Letting go means the next click is instant again
+    breaking.hold_repeating(0.016, CREATIVE_BREAK_REPEAT);
+    breaking.release();
+    assert!(breaking.hold_repeating(0.016, CREATIVE_BREAK_REPEAT));
+}
+
+#[test]
+fn test_vox_prefabs_stand_upright_and_skip_air() {
+    use vox_format::data::VoxModels;
+    use vox_format::types::{Model, Vector, Voxel};
+
+    //A two tall trunk with a leaf on top, in .vox's z up axes, plus a palette index nothing maps to
+    let mut models = VoxModels::default();
+    models.models.push(Model {
+        size: Vector::new(3, 3, 3),
+        voxels: vec![
+            Voxel::new([1, 1, 0], 6u8),
+            Voxel::new([1, 1, 1], 6u8),
+            Voxel::new([1, 2, 2], 7u8),
+            Voxel::new([0, 0, 0], 255u8),
+        ],
+    });
+    let tree = JVoxModel { name: "tree".to_string(), model: models };
+
+    let blocks = tree.prefab_blocks(&HashMap::new());
+    assert_eq!(blocks.len(), 3);
+    assert!(blocks.contains(&(IVec3::new(0, 0, 0), 6)));
+    assert!(blocks.contains(&(IVec3::new(0, 1, 0), 6)));
+    //.vox y is the world's z
+    assert!(blocks.contains(&(IVec3::new(0, 2, 1), 7)));
+
+    //voxcolors.json can point a palette index at another block, or at air to leave it out
+    let mapping = parse_vox_colors(r#"[{ "color": 6, "block": 43 }, { "color": 7, "block": 0 }]"#).unwrap();
+    let remapped = tree.prefab_blocks(&mapping);
+    assert_eq!(remapped.len(), 2);
+    assert!(remapped.iter().all(|(_, block)| *block == 43));
+    assert_eq!(vox_block(255, &HashMap::new()), 0);
+}
+
+#[test]
+fn test_exported_region_stamps_back_the_same_build() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let y = csys.chunk_height - 20;
+    //A little L of logs with a plank on top, straddling a chunk edge
+    let edge = csys.chunk_width;
+    let build = [
+        (IVec3::new(edge - 1, y, 3), 6),
+        (IVec3::new(edge, y, 3), 6),
+        (IVec3::new(edge, y, 4), 6),
+        (IVec3::new(edge, y + 1, 4), 10),
+    ];
+    for (spot, block) in build {
+        csys.set_block_no_sound(spot, block, true);
+    }
+
+    let corner_a = IVec3::new(edge + 1, y + 2, 5);
+    let corner_b = IVec3::new(edge - 2, y, 2);
+    let model = csys.export_region(corner_a, corner_b);
+    assert_eq!((model.size.x, model.size.y, model.size.z), (4, 4, 3));
+    assert_eq!(model.voxels.len(), build.len());
+
+    let mut vox = vox_format::data::VoxModels::default();
+    vox.models.push(model);
+    let bytes = vox_format::to_vec(&vox).unwrap();
+    let imported = JVoxModel { name: "export".to_string(), model: vox_format::from_slice(&bytes).unwrap() };
+
+    //Stamping puts the model's x/z centre on the spot, so stamp at the region's centre to land where it was
+    let base = IVec3::new(edge - 2 + 2, y, 2 + 2);
+    let mut stamped: Vec<(IVec3, u32)> = imported.prefab_blocks(&HashMap::new()).into_iter().map(|(o, b)| (base + o, b)).collect();
+    let mut original = build.to_vec();
+    stamped.sort_by_key(|(s, _)| (s.x, s.y, s.z));
+    original.sort_by_key(|(s, _)| (s.x, s.y, s.z));
+    assert_eq!(stamped, original);
+}
+
+#[test]
+fn test_save_world_has_its_own_default_key() {
+    let binds = &voxelland::statics::DEFAULT_KEYBINDS;
+    let save: Vec<_> = binds.iter().filter(|(_, action)| *action == "Save World").collect();
+    assert_eq!(save.len(), 1);
+    let key = save[0].0;
+    //L used to be both save and menu, the new key mustn't fight anything
+    assert_eq!(binds.iter().filter(|(k, _)| *k == key).count(), 1);
+    assert_eq!(hotbar_slot_for_key(key), None);
+}
+
+#[test]
+fn test_old_misc_files_pick_up_new_default_binds() {
+    use voxelland::statics::merge_default_keybinds;
+    //What an older misc file had, with jump moved off its default key
+    let mut keybinds: HashMap<i32, String> = HashMap::from([(17, "Forward".to_string()), (40, "Jump/Swim/Climb Up".to_string())]);
+    let defaults = [(17, "Forward"), (57, "Jump/Swim/Climb Up"), (34, "Toggle Game Mode"), (63, "Save World"), (40, "Noclip")];
+    merge_default_keybinds(&mut keybinds, &defaults);
+
+    assert_eq!(keybinds.get(&34).map(String::as_str), Some("Toggle Game Mode"));
+    assert_eq!(keybinds.get(&63).map(String::as_str), Some("Save World"));
+    //Rebinds stay as they are, and a key already in use isn't taken over
+    assert_eq!(keybinds.get(&40).map(String::as_str), Some("Jump/Swim/Climb Up"));
+    assert!(!keybinds.contains_key(&57));
+    assert_eq!(keybinds.len(), 4);
+}
+
+#[test]
+fn test_loaded_fraction_counts_the_square_around_the_player() {
+    let csys = ChunkSystem::new(2, 1234, 0, true);
+    let center = IVec2::new(10, -3);
+    assert_eq!(csys.loaded_fraction(center), 0.0);
+
+    //The chunk thread keeps -radius..radius on both axes, 16 spots at radius 2
+    let mut added = 0;
+    for i in -2..2 {
+        for k in -2..0 {
+            let pos = IVec2::new(center.x + i, center.y + k);
+            csys.takencare.insert(pos, ChunkFacade { geo_index: added, used: true, pos, lod: 1 });
+            added += 1;
+        }
+    }
+    //Spots outside the square don't count toward it
+    let outside = IVec2::new(center.x + 2, center.y);
+    csys.takencare.insert(outside, ChunkFacade { geo_index: added, used: true, pos: outside, lod: 1 });
+    assert_eq!(csys.loaded_fraction(center), 0.5);
+
+    for i in -2..2 {
+        for k in 0..2 {
+            let pos = IVec2::new(center.x + i, center.y + k);
+            csys.takencare.insert(pos, ChunkFacade { geo_index: 0, used: true, pos, lod: 1 });
+        }
+    }
+    assert_eq!(csys.loaded_fraction(center), 1.0);
+}
+
+#[test]
+fn test_blockat_is_air_above_the_world_and_bedrock_below() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let top = csys.chunk_height;
+    assert_eq!(top, MAX_CHUNK_HEIGHT);
+    //The highest block's top face sits one above it and still has to fit PackedVertex's 8 bits of y
+    assert!(MAX_BLOCK_Y + 1 <= 255);
+
+    for (x, z) in [(0, 0), (37, -12), (-300, 90)] {
+        assert_eq!(csys.blockat(IVec3::new(x, top, z)), 0);
+        assert_eq!(csys.blockat(IVec3::new(x, MAX_BLOCK_Y + 1, z)), 0);
+        assert_eq!(csys.blockat(IVec3::new(x, top + 500, z)), 0);
+        assert_eq!(csys.blockat(IVec3::new(x, 0, z)), 15);
+        assert_eq!(csys.blockat(IVec3::new(x, -40, z)), 15);
+    }
+
+    //Edits past the ceiling or into the floor are turned away, ones just under the ceiling go in
+    csys.set_block_no_sound(IVec3::new(3, top, 3), 5, true);
+    csys.set_block_no_sound(IVec3::new(3, MAX_BLOCK_Y + 1, 3), 5, true);
+    csys.set_block(IVec3::new(3, top + 10, 3), 5, true);
+    csys.set_block_no_queue(IVec3::new(3, 0, 3), 5, true);
+    assert!(!csys.is_in_world(IVec3::new(3, top, 3)) && !csys.is_in_world(IVec3::new(3, 0, 3)));
+    assert_eq!(csys.userdatamap.len(), 0);
+    assert_eq!(csys.user_edits.load(Ordering::Relaxed), 0);
+    csys.set_block_no_sound(IVec3::new(3, MAX_BLOCK_Y, 3), 5, true);
+    assert_eq!(csys.userdatamap.len(), 1);
+    assert_eq!(csys.blockat(IVec3::new(3, MAX_BLOCK_Y, 3)) & Blocks::block_id_bits(), 5);
+
+    //A shorter world gets its own ceiling
+    let short = ChunkSystem::new_with_dimensions(1, 1234, 0, true, 15, 64);
+    short.set_block_no_sound(IVec3::new(3, 100, 3), 5, true);
+    assert_eq!(short.blockat(IVec3::new(3, 100, 3)), 0);
+    assert!(short.userdatamap.get(&IVec3::new(3, 100, 3)).is_none());
+}
+
+#[test]
+fn test_looked_at_block_follows_edits_without_the_camera_moving() {
+    let csys = RwLock::new(ChunkSystem::new(1, 1234, 0, true));
+    let y = csys.read().chunk_height - 20;
+    csys.read().set_block_no_sound(IVec3::new(4, y, 0), 5, true);
+    csys.read().set_block_no_sound(IVec3::new(7, y, 0), 5, true);
+
+    //Same spot and facing every frame, the bob lifts the eye 0.3 so start it a little lower
+    let eye = Vec3::new(0.5, y as f32 + 0.2, 0.5);
+    let facing = Vec3::new(1.0, 0.0, 0.0);
+    let (_, hit, normal) = raycast_voxel_with_bob(eye, facing, &csys, 10.0, 0.0).unwrap();
+    assert_eq!(hit, IVec3::new(4, y, 0));
+    assert_eq!(normal, IVec3::new(-1, 0, 0));
+
+    //Breaking the front block moves the outline onto the one behind it
+    csys.read().set_block_no_sound(IVec3::new(4, y, 0), 0, true);
+    let (_, hit, _) = raycast_voxel_with_bob(eye, facing, &csys, 10.0, 0.0).unwrap();
+    assert_eq!(hit, IVec3::new(7, y, 0));
+
+    csys.read().set_block_no_sound(IVec3::new(7, y, 0), 0, true);
+    assert!(raycast_voxel_with_bob(eye, facing, &csys, 10.0, 0.0).is_none());
+}
+
+#[test]
+fn test_creative_reach_spans_a_canyon() {
+    assert_eq!(GameMode::Survival.reach(), SURVIVAL_REACH);
+    assert_eq!(GameMode::Creative.reach(), CREATIVE_REACH);
+    assert!(CREATIVE_REACH >= 50.0 && SURVIVAL_REACH < CREATIVE_REACH);
+
+    let csys = RwLock::new(ChunkSystem::new(1, 1234, 0, true));
+    let y = csys.read().chunk_height - 20;
+    let far_wall = IVec3::new(45, y, 3);
+    csys.read().set_block_no_sound(far_wall, 5, true);
+
+    //Slightly off axis so the ray crosses plenty of x and z boundaries on the way
+    let eye = Vec3::new(0.5, y as f32 + 0.2, 0.5);
+    let facing = (Vec3::new(45.5, y as f32 + 0.5, 3.5) - (eye + Vec3::new(0.0, 0.3, 0.0))).normalize();
+
+    assert!(raycast_voxel_with_bob(eye, facing, &csys, GameMode::Survival.reach(), 0.0).is_none());
+    let (tip, hit, normal) = raycast_voxel_with_bob(eye, facing, &csys, GameMode::Creative.reach(), 0.0).unwrap();
+    assert_eq!(hit, far_wall);
+    assert_eq!(normal, IVec3::new(-1, 0, 0));
+    //Placing goes on the near face, back across the gap
+    assert_eq!(hit + normal, IVec3::new(44, y, 3));
+    assert!((tip.x - 45.0).abs() < 0.001);
+}
+
+#[test]
+fn test_blocks_cant_be_placed_inside_the_player() {
+    //Standing on the block at (0, 10, 0), the same box the collision sweep gives the player
+    let eye = Vec3::new(0.5, 11.0 + 1.45, 0.5);
+    let mut body = BoundBox::new(Vec3::ZERO);
+    body.set_center(eye + Vec3::new(0.0, -0.5, 0.0), 0.2, 0.95);
+
+    //Aiming at the floor beneath you puts the block where your feet are
+    let floor = IVec3::new(0, 10, 0);
+    assert!(body.overlaps_block(floor + IVec3::new(0, 1, 0)));
+    assert!(body.overlaps_block(IVec3::new(0, 12, 0)));
+
+    //The floor itself, the space over your head and the blocks beside you only touch
+    assert!(!body.overlaps_block(floor));
+    assert!(!body.overlaps_block(IVec3::new(0, 13, 0)));
+    assert!(!body.overlaps_block(IVec3::new(1, 11, 0)));
+    assert!(!body.overlaps_block(IVec3::new(-1, 11, 0)));
+    assert!(!body.overlaps_block(IVec3::new(0, 11, 1)));
+
+    //Leaning over an edge puts part of you in the next column
+    body.set_center(Vec3::new(0.9, 11.95, 0.5), 0.2, 0.95);
+    assert!(body.overlaps_block(IVec3::new(1, 11, 0)));
+    assert!(!body.overlaps_block(IVec3::new(-1, 11, 0)));
+}
+
+#[test]
+fn test_server_block_edits_store_without_queueing_work() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let y = csys.chunk_height - 20;
+    let pool = IVec3::new(2, y, 2);
+    let beside = IVec3::new(3, y, 2);
+
+    //Next to water a normal edit queues the water up to flow
+    csys.set_block_no_sound(pool, 2, true);
+    assert!(csys.fluid_updates.pop().is_some());
+    while csys.fluid_updates.pop().is_some() {}
+
+    let edits = csys.user_edits.load(Ordering::Relaxed);
+    csys.set_block_no_queue(beside, 5, true);
+    assert_eq!(csys.blockat(beside) & Blocks::block_id_bits(), 5);
+    assert!(csys.fluid_updates.pop().is_none());
+    //Still counts as an edit to save
+    assert_eq!(csys.user_edits.load(Ordering::Relaxed), edits + 1);
+
+    //Generated blocks aren't user edits
+    csys.set_block_no_queue(IVec3::new(4, y, 2), 5, false);
+    assert_eq!(csys.user_edits.load(Ordering::Relaxed), edits + 1);
+    assert!(csys.fluid_updates.pop().is_none());
+}
+
+#[test]
+fn test_edit_batches_keep_the_last_block_per_spot() {
+    assert!(SAVE_BATCH_INTERVAL < Duration::from_secs(1));
+
+    let mut batch = EditBatch::new();
+    assert!(batch.is_empty());
+    assert!(batch.take().is_empty());
+
+    //Someone spamming one spot only costs one write
+    for block in 1..=50 {
+        batch.push(7, IVec3::new(1, 2, 3), block);
+    }
+    batch.push(7, IVec3::new(4, 5, 6), 9);
+    //Same spot in another world is its own edit
+    batch.push(8, IVec3::new(1, 2, 3), 12);
+    assert_eq!(batch.len(), 3);
+
+    let mut taken = batch.take();
+    taken.sort_by_key(|(seed, _)| *seed);
+    assert_eq!(taken.len(), 2);
+    let (seed, edits) = &taken[0];
+    assert_eq!(*seed, 7);
+    let edits: HashMap<IVec3, u32> = edits.iter().cloned().collect();
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[&IVec3::new(1, 2, 3)], 50);
+    assert_eq!(edits[&IVec3::new(4, 5, 6)], 9);
+    assert_eq!(taken[1], (8, vec![(IVec3::new(1, 2, 3), 12)]));
+
+    //Taking clears it for the next interval
+    assert!(batch.is_empty());
+}
+
+#[test]
+fn test_region_files_live_under_the_world_folder() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let cpos = IVec2 { x: 3, y: 5 };
+
+    //No world folder (multiplayer) means no region files at all
+    assert!(csys.region_file_path(&cpos).is_none());
+    csys.save_chunk(cpos);
+    assert!(!csys.load_chunk(cpos));
+    assert!(csys.region_cache.lock().regions.is_empty());
+
+    let dir = std::env::temp_dir().join(format!("voxelland-regions-{}", std::process::id()));
+    let world = dir.join("saves").join("myworld");
+    let world = world.to_str().unwrap().to_string();
+    *csys.world_path.write() = Some(world.clone());
+
+    let path = csys.region_file_path(&cpos).unwrap();
+    assert!(path.starts_with(&format!("{}/regions/", world)));
+    assert!(path.ends_with("/r.0.0.bin"));
+    assert!(csys.region_file_path(&IVec2 { x: -1, y: 16 }).unwrap().ends_with("/r.-1.1.bin"));
+
+    //Saving keeps the region around, so loading its other chunks doesn't read the file again.
+    //Nothing hits the disk until the flush, which writes the region once
+    let edit = IVec3::new(3 * 15 + 1, 100, 5 * 15 + 1);
+    csys.set_block_no_queue(edit, 5, true);
+    csys.save_chunk(cpos);
+    csys.save_chunk(IVec2 { x: 4, y: 5 });
+    assert!(!std::path::Path::new(&path).exists());
+    assert!(csys.region_cache.lock().regions[&path].dirty);
+    csys.flush_regions();
+    assert!(std::path::Path::new(&path).exists());
+    assert!(!csys.region_cache.lock().regions[&path].dirty);
+    assert_eq!(csys.region_cache.lock().regions.len(), 1);
+    assert!(csys.region_cache.lock().regions.keys().all(|k| k.starts_with(&world)));
+
+    //Only what differs from the noise terrain is stored, and that's all loading puts back
+    let changed = csys.region_cache.lock().regions[&path].data[&(3, 5)].clone();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].1, 5);
+    let fresh = ChunkSystem::new(1, 1234, 0, true);
+    *fresh.world_path.write() = Some(world.clone());
+    assert!(fresh.load_chunk(cpos));
+    assert_eq!(fresh.nonuserdatamap.len(), 1);
+    assert_eq!(fresh.blockat(edit), 5);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_region_cache_drops_the_least_recently_used_region() {
+    let csys = ChunkSystem::new(1, 1234, 0, true);
+    let dir = std::env::temp_dir().join(format!("voxelland-region-lru-{}", std::process::id()));
+    let world = dir.to_str().unwrap().to_string();
+    *csys.world_path.write() = Some(world);
+
+    let region = |i: i32| IVec2 { x: i * REGION_SIZE, y: 0 };
+    for i in 0..REGION_CACHE_MAX as i32 {
+        csys.save_chunk(region(i));
+    }
+    //Touching the first region again makes the second the oldest
+    csys.save_chunk(region(0));
+    csys.save_chunk(region(REGION_CACHE_MAX as i32));
+
+    let cache = csys.region_cache.lock();
+    assert_eq!(cache.regions.len(), REGION_CACHE_MAX);
+    let first = csys.region_file_path(&region(0)).unwrap();
+    let second = csys.region_file_path(&region(1)).unwrap();
+    assert!(cache.regions.contains_key(&first));
+    assert!(!cache.regions.contains_key(&second));
+    //Its unsaved chunk went to disk on the way out
+    assert!(std::path::Path::new(&second).exists());
+    drop(cache);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_packed_vertex_fields_keep_to_their_own_bits() {
+    //x y z corner amb bl sky, high bit first
+    let fields: [(u32, u32); 7] = [(28, 4), (20, 8), (16, 4), (12, 4), (8, 4), (4, 4), (0, 4)];
+    let full = |f: usize| {
+        let max = |i: usize| if i == f { ((1u32 << fields[i].1) - 1) as u8 } else { 0 };
+        let (packed32, _) = PackedVertex::pack(max(0), max(1), max(2), max(3), max(4), max(5), 0, 0);
+        PackedVertex::with_sky_light(packed32, max(6))
+    };
+    let mut seen = 0u32;
+    for (f, (shift, bits)) in fields.iter().enumerate() {
+        let mask = ((1u32 << bits) - 1) << shift;
+        assert_eq!(full(f), mask, "field {} isn't where it should be", f);
+        assert_eq!(seen & mask, 0);
+        seen |= mask;
+    }
+    assert_eq!(seen, u32::MAX);
+
+    let (_, packed8) = PackedVertex::pack(0, 0, 0, 0, 0, 0, MAX_TEX_COORD, 0);
+    assert_eq!(packed8, 0b1111_0000);
+    let (_, packed8) = PackedVertex::pack(0, 0, 0, 0, 0, 0, 0, MAX_TEX_COORD);
+    assert_eq!(packed8, 0b0000_1111);
+}
+
+#[test]
+fn test_every_tile_of_a_sixteen_wide_sheet_survives_packing() {
+    let layout = AtlasLayout::new(16 * CELL_PX, 16 * CELL_PX);
+    assert_eq!(layout.tiles_per_row(), MAX_TEX_COORD as u32 + 1);
+    for u in 0..=MAX_TEX_COORD {
+        for v in 0..=MAX_TEX_COORD {
+            let (_, packed8) = PackedVertex::pack(15, 255, 15, 15, 15, 15, u, v);
+            assert_eq!(PackedVertex::unpack_tex_coords(packed8), (u, v));
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "doesn't fit 4 bits")]
+fn test_packed_vertex_catches_light_that_would_wrap() {
+    //16 would have spilled into the corner bits
+    PackedVertex::pack(0, 0, 0, 0, 16, 0, 0, 0);
+}
+
+#[test]
+fn test_chunk_thread_is_out_of_its_pass_before_every_reset() {
+    let csys = Arc::new(RwLock::new(ChunkSystem::new(1, 1234, 0, true)));
+    let mid_pass = Arc::new(AtomicBool::new(false));
+    let passes = Arc::new(AtomicU32::new(0));
+    let mut worker = ChunkThread::new();
+
+    //Taking off over and over, the way start_chunks_with_radius stops, resets and respawns
+    for round in 0..100u32 {
+        let (csys2, mid_pass2, passes2) = (csys.clone(), mid_pass.clone(), passes.clone());
+        worker.start(move |run| {
+            while run.load(Ordering::Acquire) {
+                mid_pass2.store(true, Ordering::SeqCst);
+                let cs = csys2.read();
+                for x in 0..20 {
+                    cs.blockat(IVec3::new(x, 40, round as i32));
+                }
+                cs.loaded_fraction(IVec2::new(0, 0));
+                drop(cs);
+                passes2.fetch_add(1, Ordering::SeqCst);
+                mid_pass2.store(false, Ordering::SeqCst);
+            }
+        });
+        assert!(worker.is_running());
+        if round % 3 == 0 {
+            thread::yield_now();
+        }
+        assert!(worker.stop());
+        assert!(!mid_pass.load(Ordering::SeqCst), "reset would have raced a pass in round {}", round);
+        assert!(!worker.is_running());
+        csys.write().reset(1, round, round as usize % PLANET_TYPES as usize);
+        assert_eq!(csys.read().planet_type as u32, round % PLANET_TYPES as u32);
+    }
+    //Stopping twice is fine
+    assert!(worker.stop());
+}
+
+#[test]
+fn test_fatal_gl_errors_are_kept_and_named() {
+    assert!(is_fatal_gl_error(GL_CONTEXT_LOST));
+    assert!(is_fatal_gl_error(gl::OUT_OF_MEMORY));
+    assert!(!is_fatal_gl_error(gl::INVALID_OPERATION));
+    assert_eq!(gl_error_name(GL_CONTEXT_LOST), "context lost");
+    assert_eq!(gl_error_name(gl::INVALID_VALUE), "invalid value");
+    assert_eq!(gl_error_name(0x1234), "unknown error");
+
+    //The first failure is the one worth showing, what follows is fallout
+    report_gl_failure("The graphics driver reported context lost after drawing arrays.".to_string());
+    report_gl_failure("assets/wfvert.glsl didn't compile".to_string());
+    assert_eq!(gl_failure().unwrap(), "The graphics driver reported context lost after drawing arrays.");
+
+    //Driver logs come in a fixed size buffer padded with nuls
+    let mut log = b"0:12(3): error: syntax error\n".to_vec();
+    log.resize(512, 0);
+    assert_eq!(Shader::info_log(&log), "0:12(3): error: syntax error");
+}
+
+#[test]
+fn test_startup_errors_come_together_with_a_hint_for_missing_assets() {
+    assert_eq!(startup_failure_message(&[]), None);
+
+    let missing = startup_failure_message(&[
+        "Couldn't find assets/vert.glsl".to_string(),
+        "Couldn't find assets/world.png".to_string(),
+    ])
+    .unwrap();
+    assert!(missing.contains("assets/vert.glsl") && missing.contains("assets/world.png"));
+    assert!(missing.contains("folder that has the assets folder"));
+
+    //A shader that's there but broken isn't a working directory problem
+    let broken = startup_failure_message(&["assets/frag.glsl didn't compile: 0:3(1): error".to_string()]).unwrap();
+    assert!(broken.contains("didn't compile"));
+    assert!(!broken.contains("assets folder"));
+
+    //What Texture::new hands back stands in for a missing sheet without touching GL
+    let empty = Texture::empty();
+    assert_eq!((empty.id, empty.size), (0, (1, 1)));
+}
+
+#[test]
+fn test_server_only_believes_mob_hits_from_close_by() {
+    let mob = Vec3::new(10.0, 80.0, -4.0);
+    assert!(Game::mob_hit_in_reach(mob + Vec3::new(3.0, 1.0, 0.0), mob));
+    assert!(Game::mob_hit_in_reach(mob + Vec3::new(0.0, 0.0, MOB_HIT_REACH), mob));
+    assert!(!Game::mob_hit_in_reach(mob + Vec3::new(0.0, 0.0, MOB_HIT_REACH * 3.0), mob));
+    assert!(!Game::mob_hit_in_reach(Vec3::new(500.0, 80.0, 500.0), mob));
+}